@@ -0,0 +1,141 @@
+//! Check-mode dry runs
+//!
+//! Streams a whole program with GRBL's check mode (`$C`) enabled, so the
+//! controller parses every line without moving, and records each `error:N`
+//! against the line that caused it. Controller errors are reported as
+//! `ValidationIssue`s alongside the ones `GcodeValidator` finds statically.
+
+use super::{
+    streaming, GrblAlarm, GrblController, GrblResponse, MachineState, ParserState, QueuedLine,
+    BANNER_WAIT,
+};
+use crate::designer::{GcodeValidator, Severity, ValidationIssue};
+use anyhow::{anyhow, Result};
+
+/// Outcome of a check-mode dry run
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    /// Static and controller-reported issues, ordered by line number
+    pub issues: Vec<ValidationIssue>,
+    /// Program lines sent to the controller
+    pub lines_sent: usize,
+    /// Lines the controller acknowledged
+    pub lines_checked: usize,
+    /// Alarm that ended the run early, e.g. a soft limit
+    pub alarm: Option<GrblAlarm>,
+}
+
+impl CheckReport {
+    /// Check if the program ran through without errors or alarms
+    pub fn passed(&self) -> bool {
+        self.alarm.is_none()
+            && self
+                .issues
+                .iter()
+                .all(|issue| issue.severity < Severity::Error)
+    }
+
+    /// Get the issues reported by the controller rather than the validator
+    pub fn controller_issues(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.issue_type.starts_with("Controller"))
+    }
+}
+
+impl GrblController {
+    /// Dry-run a program in check mode
+    ///
+    /// Enables `$C`, streams every line, then leaves check mode again. GRBL
+    /// soft-resets on leaving check mode, so the parser state is back to its
+    /// defaults afterwards. An alarm stops the run; the controller is reset
+    /// and stays alarmed until unlocked.
+    ///
+    /// # Arguments
+    /// * `gcode` - Program to check
+    ///
+    /// # Returns
+    /// Issues found by the validator and the controller, with line numbers
+    /// counted from 1 in `gcode`; acknowledgements of lines other callers
+    /// queued are left out
+    pub async fn check_program(&self, gcode: &str) -> Result<CheckReport> {
+        let firmware = self.get_firmware().await;
+        if !firmware.dialect.is_grbl_compatible() {
+            return Err(anyhow!("{} has no check mode", firmware.dialect.name()));
+        }
+        let mut issues = GcodeValidator::for_firmware(&firmware).validate_program(gcode);
+
+        // Tagged with their index in `gcode`, so errors are mapped by tag
+        // rather than by the order acknowledgements came back in
+        let lines: Vec<QueuedLine> = gcode
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                streaming::prepare_line(line).map(|line| QueuedLine::job(line, index))
+            })
+            .collect();
+
+        if let GrblResponse::Error(error) = self.send_command("$C").await? {
+            return Err(anyhow!("Could not enter check mode: {}", error));
+        }
+
+        self.command_queue
+            .lock()
+            .await
+            .extend(lines.iter().cloned());
+        let streamed = self.stream_queue_until(true).await;
+        let alarm = {
+            let status = self.status.lock().await;
+            status.alarm.filter(|_| status.state == MachineState::Alarm)
+        };
+        let left = self.leave_check_mode(alarm.is_some()).await;
+        let acks = streamed?;
+        left?;
+
+        let mut checked = 0;
+        let mut last_checked = None;
+        for ack in &acks {
+            let Some(index) = ack.job_line else {
+                continue;
+            };
+            checked += 1;
+            last_checked = last_checked.max(Some(index));
+            if let GrblResponse::Error(error) = &ack.response {
+                issues.push(ValidationIssue::from_grbl_error(index + 1, error));
+            }
+        }
+        if let Some(alarm) = alarm {
+            // The alarm belongs to the first line that was never acknowledged
+            let line_number = lines
+                .iter()
+                .filter_map(|line| line.job_line)
+                .find(|&index| last_checked.map_or(true, |last| index > last))
+                .map_or(0, |index| index + 1);
+            issues.push(ValidationIssue::from_grbl_alarm(line_number, &alarm));
+        }
+        issues.sort_by_key(|issue| issue.line_number);
+
+        Ok(CheckReport {
+            issues,
+            lines_sent: lines.len(),
+            lines_checked: checked,
+            alarm,
+        })
+    }
+
+    /// Leave check mode and wait for the reset that follows
+    async fn leave_check_mode(&self, alarmed: bool) -> Result<()> {
+        if alarmed {
+            // An alarm locks the controller; only a reset gets out of check mode
+            self.soft_reset().await?;
+        } else {
+            if let GrblResponse::Error(error) = self.send_command("$C").await? {
+                return Err(anyhow!("Could not leave check mode: {}", error));
+            }
+            *self.parser_state.lock().await = ParserState::default();
+            *self.offsets_stale.lock().await = true;
+        }
+        self.await_banner(BANNER_WAIT).await;
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+//! Serial port discovery
+//!
+//! Enumerates serial ports with their USB metadata, flags the USB-serial
+//! chips and boards GRBL controllers are usually built on, and probes baud
+//! rates for a firmware banner so a port can be connected to without knowing
+//! its speed up front.
+
+use super::firmware::FirmwareInfo;
+use super::realtime::RealtimeCommand;
+use super::serial::{SerialConfig, SerialConnection};
+use super::transport::Transport;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Baud rates tried by `detect_baud_rate`, most common first
+pub const COMMON_BAUD_RATES: [u32; 7] = [115200, 250000, 230400, 57600, 38400, 19200, 9600];
+
+/// How long to wait for a banner at each baud rate
+///
+/// Arduino boards reset when the port opens and stay in the bootloader for
+/// about 1.5 s before GRBL prints its banner.
+pub const PROBE_TIMEOUT: Duration = Duration::from_millis(2500);
+
+/// USB-serial chip or board commonly found on GRBL controllers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KnownBoard {
+    /// WCH CH340/CH341/CH9102 (cheap Uno clones, most 3018 controllers)
+    Ch340,
+    /// FTDI FT232/FT231X
+    Ftdi,
+    /// Genuine Arduino Uno, Mega or Nano Every
+    Arduino,
+    /// Silicon Labs CP210x (ESP32 boards running FluidNC or grblHAL)
+    Cp210x,
+    /// STM32 USB CDC (grblHAL on STM32)
+    Stm32,
+    /// PJRC Teensy (grblHAL on Teensy 4.1)
+    Teensy,
+}
+
+impl KnownBoard {
+    /// Identify a board from its USB vendor and product IDs
+    pub fn from_usb_ids(vid: u16, pid: u16) -> Option<Self> {
+        match (vid, pid) {
+            (0x1a86, 0x7523 | 0x5523 | 0x55d4) => Some(KnownBoard::Ch340),
+            (0x0403, 0x6001 | 0x6010 | 0x6011 | 0x6014 | 0x6015) => Some(KnownBoard::Ftdi),
+            (0x2341 | 0x2a03, _) => Some(KnownBoard::Arduino),
+            (0x10c4, 0xea60) => Some(KnownBoard::Cp210x),
+            (0x0483, 0x5740) => Some(KnownBoard::Stm32),
+            (0x16c0, 0x0483) => Some(KnownBoard::Teensy),
+            _ => None,
+        }
+    }
+
+    /// Get a display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            KnownBoard::Ch340 => "CH340",
+            KnownBoard::Ftdi => "FTDI",
+            KnownBoard::Arduino => "Arduino",
+            KnownBoard::Cp210x => "CP210x",
+            KnownBoard::Stm32 => "STM32",
+            KnownBoard::Teensy => "Teensy",
+        }
+    }
+}
+
+/// How a serial port is attached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PortKind {
+    Usb,
+    Pci,
+    Bluetooth,
+    #[default]
+    Unknown,
+}
+
+/// A serial port and what is known about the device behind it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PortInfo {
+    pub name: String,
+    pub kind: PortKind,
+    /// USB vendor ID
+    pub vid: Option<u16>,
+    /// USB product ID
+    pub pid: Option<u16>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial_number: Option<String>,
+    /// Recognised USB-serial chip or board
+    pub board: Option<KnownBoard>,
+}
+
+impl PortInfo {
+    /// Build from the information reported by the OS
+    pub fn from_serialport(info: &serialport::SerialPortInfo) -> Self {
+        let mut port = PortInfo {
+            name: info.port_name.clone(),
+            ..Default::default()
+        };
+        match &info.port_type {
+            serialport::SerialPortType::UsbPort(usb) => {
+                port.kind = PortKind::Usb;
+                port.vid = Some(usb.vid);
+                port.pid = Some(usb.pid);
+                port.manufacturer = usb.manufacturer.clone();
+                port.product = usb.product.clone();
+                port.serial_number = usb.serial_number.clone();
+                port.board = KnownBoard::from_usb_ids(usb.vid, usb.pid);
+            }
+            serialport::SerialPortType::PciPort => port.kind = PortKind::Pci,
+            serialport::SerialPortType::BluetoothPort => port.kind = PortKind::Bluetooth,
+            serialport::SerialPortType::Unknown => {}
+        }
+        port
+    }
+
+    /// Check if the port is likely a GRBL controller
+    pub fn is_likely_grbl(&self) -> bool {
+        self.board.is_some()
+    }
+
+    /// Get `VID:PID` as hex, e.g. `1A86:7523`
+    pub fn usb_id(&self) -> Option<String> {
+        Some(format!("{:04X}:{:04X}", self.vid?, self.pid?))
+    }
+
+    /// Get a label for the port list, e.g. `/dev/ttyUSB0 (CH340, USB Serial)`
+    pub fn label(&self) -> String {
+        let details: Vec<&str> = self
+            .board
+            .map(|board| board.name())
+            .into_iter()
+            .chain(self.product.as_deref().or(self.manufacturer.as_deref()))
+            .collect();
+        if details.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} ({})", self.name, details.join(", "))
+        }
+    }
+}
+
+/// List serial ports with USB metadata
+///
+/// Ports on recognised GRBL boards come first; the order is otherwise the
+/// one reported by the OS.
+pub fn list_ports() -> Result<Vec<PortInfo>> {
+    let ports = serialport::available_ports().context("Failed to enumerate serial ports")?;
+    let mut ports: Vec<PortInfo> = ports.iter().map(PortInfo::from_serialport).collect();
+    ports.sort_by_key(|port| !port.is_likely_grbl());
+    Ok(ports)
+}
+
+/// Baud rate and firmware found by a probe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaudProbe {
+    pub baud_rate: u32,
+    pub firmware: FirmwareInfo,
+}
+
+/// Find the baud rate a serial port answers on
+///
+/// Opens the port at each rate in turn, soft-resets the controller and waits
+/// for a banner that identifies the firmware.
+///
+/// # Arguments
+/// * `port_name` - Serial port to probe
+/// * `rates` - Baud rates to try, in order
+/// * `timeout` - How long to wait for a banner at each rate
+pub async fn detect_baud_rate(
+    port_name: &str,
+    rates: &[u32],
+    timeout: Duration,
+) -> Result<BaudProbe> {
+    probe_rates(rates, timeout, |baud_rate| async move {
+        let connection = SerialConnection::new(SerialConfig {
+            baud_rate,
+            timeout: Duration::from_millis(100),
+            ..Default::default()
+        });
+        connection.connect(port_name).await?;
+        Ok(Arc::new(connection) as Arc<dyn Transport>)
+    })
+    .await
+    .with_context(|| format!("No GRBL banner on {}", port_name))
+}
+
+/// Probe baud rates using links opened by `open`
+///
+/// Rates the link cannot be opened at are skipped.
+///
+/// # Returns
+/// The first rate at which the firmware identified itself
+pub async fn probe_rates<F, Fut>(rates: &[u32], timeout: Duration, mut open: F) -> Result<BaudProbe>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<Arc<dyn Transport>>>,
+{
+    let mut last_error = None;
+    for &baud_rate in rates {
+        let transport = match open(baud_rate).await {
+            Ok(transport) => transport,
+            Err(e) => {
+                tracing::debug!("Could not open link at {} baud: {}", baud_rate, e);
+                last_error = Some(e);
+                continue;
+            }
+        };
+        let firmware = probe_banner(transport.as_ref(), timeout).await;
+        let _ = transport.disconnect().await;
+        if let Some(firmware) = firmware {
+            return Ok(BaudProbe {
+                baud_rate,
+                firmware,
+            });
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow!("No firmware banner at any baud rate")))
+}
+
+/// Soft-reset the controller and read until a banner identifies it
+///
+/// At the wrong baud rate the reply is noise and never matches a banner.
+async fn probe_banner(transport: &dyn Transport, timeout: Duration) -> Option<FirmwareInfo> {
+    let deadline = Instant::now() + timeout;
+    transport
+        .send_bytes(&[RealtimeCommand::SoftReset.byte()])
+        .await
+        .ok()?;
+
+    let mut info = FirmwareInfo::default();
+    let mut pending = String::new();
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return None;
+        }
+        if let Ok(chunk) = transport.read_response_timeout(256, remaining).await {
+            pending.push_str(&chunk);
+        } else if !transport.is_connected().await {
+            return None;
+        }
+
+        while let Some(end) = pending.find('\n') {
+            let line: String = pending.drain(..=end).collect();
+            info.apply_line(&line);
+        }
+        if info.is_known() {
+            return Some(info);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::communication::simulator::{SimulatedGrbl, SIMULATOR_ADDRESS};
+    use crate::communication::FirmwareDialect;
+
+    #[test]
+    fn test_known_board_from_usb_ids() {
+        assert_eq!(
+            KnownBoard::from_usb_ids(0x1a86, 0x7523),
+            Some(KnownBoard::Ch340)
+        );
+        assert_eq!(
+            KnownBoard::from_usb_ids(0x0403, 0x6001),
+            Some(KnownBoard::Ftdi)
+        );
+        assert_eq!(
+            KnownBoard::from_usb_ids(0x2341, 0x0043),
+            Some(KnownBoard::Arduino)
+        );
+        assert_eq!(
+            KnownBoard::from_usb_ids(0x10c4, 0xea60),
+            Some(KnownBoard::Cp210x)
+        );
+        assert_eq!(KnownBoard::from_usb_ids(0x046d, 0xc52b), None);
+    }
+
+    #[test]
+    fn test_port_info_from_usb_port() {
+        let info = serialport::SerialPortInfo {
+            port_name: "/dev/ttyUSB0".to_string(),
+            port_type: serialport::SerialPortType::UsbPort(serialport::UsbPortInfo {
+                vid: 0x1a86,
+                pid: 0x7523,
+                serial_number: None,
+                manufacturer: Some("QinHeng Electronics".to_string()),
+                product: Some("USB Serial".to_string()),
+            }),
+        };
+        let port = PortInfo::from_serialport(&info);
+        assert_eq!(port.kind, PortKind::Usb);
+        assert!(port.is_likely_grbl());
+        assert_eq!(port.usb_id().as_deref(), Some("1A86:7523"));
+        assert_eq!(port.label(), "/dev/ttyUSB0 (CH340, USB Serial)");
+
+        let plain = PortInfo {
+            name: "/dev/ttyS0".to_string(),
+            ..Default::default()
+        };
+        assert!(!plain.is_likely_grbl());
+        assert_eq!(plain.label(), "/dev/ttyS0");
+    }
+
+    #[tokio::test]
+    async fn test_probe_rates_finds_banner() {
+        // Only the 115200 link reaches the device; 9600 cannot be opened
+        let probe = probe_rates(
+            &[9600, 115200],
+            Duration::from_millis(500),
+            |rate| async move {
+                if rate != 115200 {
+                    return Err(anyhow!("wrong rate"));
+                }
+                let simulator = SimulatedGrbl::new();
+                simulator.connect(SIMULATOR_ADDRESS).await?;
+                Ok(Arc::new(simulator) as Arc<dyn Transport>)
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(probe.baud_rate, 115200);
+        assert_eq!(probe.firmware.dialect, FirmwareDialect::Grbl);
+        assert_eq!(probe.firmware.version, "1.1h");
+    }
+
+    #[tokio::test]
+    async fn test_probe_rates_without_banner() {
+        let result = probe_rates(&[115200], Duration::from_millis(50), |_| async {
+            Ok(Arc::new(SimulatedGrbl::new()) as Arc<dyn Transport>)
+        })
+        .await;
+        assert!(result.is_err());
+    }
+}
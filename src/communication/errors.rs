@@ -0,0 +1,442 @@
+//! GRBL 1.1 error and alarm code decoding
+//!
+//! Maps `error:N` and `ALARM:N` responses to typed codes with a human-readable
+//! description and a suggested recovery step.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// GRBL 1.1 `error:N` codes
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GrblError {
+    /// 1: G-code words consist of a letter and a value. Letter was not found.
+    ExpectedCommandLetter,
+    /// 2: Missing the expected G-code word value or numeric value format is not valid.
+    BadNumberFormat,
+    /// 3: Grbl '$' system command was not recognized or supported.
+    InvalidStatement,
+    /// 4: Negative value received for an expected positive value.
+    NegativeValue,
+    /// 5: Homing cycle failure. Homing is not enabled via settings.
+    SettingDisabled,
+    /// 6: Minimum step pulse time must be greater than 3usec.
+    SettingStepPulseMin,
+    /// 7: An EEPROM read failed. Auto-restoring affected EEPROM to default values.
+    SettingReadFail,
+    /// 8: Grbl '$' command cannot be used unless Grbl is IDLE.
+    IdleError,
+    /// 9: G-code commands are locked out during alarm or jog state.
+    SystemGcLock,
+    /// 10: Soft limits cannot be enabled without homing also enabled.
+    SoftLimitError,
+    /// 11: Max characters per line exceeded. Received command line was not executed.
+    Overflow,
+    /// 12: Grbl '$' setting value cause the step rate to exceed the maximum supported.
+    MaxStepRateExceeded,
+    /// 13: Safety door detected as opened and door state initiated.
+    CheckDoor,
+    /// 14: Build info or startup line exceeded EEPROM line length limit.
+    LineLengthExceeded,
+    /// 15: Jog target exceeds machine travel. Jog command has been ignored.
+    TravelExceeded,
+    /// 16: Jog command has no '=' or contains prohibited g-code.
+    InvalidJogCommand,
+    /// 17: Laser mode requires PWM output.
+    SettingDisabledLaser,
+    /// 20: Unsupported or invalid g-code command found in block.
+    UnsupportedCommand,
+    /// 21: More than one g-code command from same modal group found in block.
+    ModalGroupViolation,
+    /// 22: Feed rate has not yet been set or is undefined.
+    UndefinedFeedRate,
+    /// 23: G-code command in block requires an integer value.
+    CommandValueNotInteger,
+    /// 24: More than one g-code command that requires axis words found in block.
+    AxisCommandConflict,
+    /// 25: Repeated g-code word found in block.
+    WordRepeated,
+    /// 26: No axis words found in block for g-code command or current modal state which requires them.
+    NoAxisWords,
+    /// 27: Line number value is invalid.
+    InvalidLineNumber,
+    /// 28: G-code command is missing a required value word.
+    ValueWordMissing,
+    /// 29: G59.x work coordinate systems are not supported.
+    UnsupportedCoordSys,
+    /// 30: G53 only allowed with G0 and G1 motion modes.
+    G53InvalidMotionMode,
+    /// 31: Axis words found in block when no command or current modal state uses them.
+    AxisWordsExist,
+    /// 32: G2 and G3 arcs require at least one in-plane axis word.
+    NoAxisWordsInPlane,
+    /// 33: Motion command target is invalid.
+    InvalidTarget,
+    /// 34: Arc radius value is invalid.
+    ArcRadiusError,
+    /// 35: G2 and G3 arcs require at least one in-plane offset word.
+    NoOffsetsInPlane,
+    /// 36: Unused value words found in block.
+    UnusedWords,
+    /// 37: G43.1 dynamic tool length offset is not assigned to configured tool length axis.
+    G43DynamicAxisError,
+    /// 38: Tool number greater than max supported value.
+    MaxValueExceeded,
+    /// Numeric code not defined by GRBL 1.1
+    Unknown(u16),
+    /// Non-numeric error text (GRBL 0.9 and older)
+    Other(String),
+}
+
+impl GrblError {
+    /// Decode an error from its numeric code
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            1 => GrblError::ExpectedCommandLetter,
+            2 => GrblError::BadNumberFormat,
+            3 => GrblError::InvalidStatement,
+            4 => GrblError::NegativeValue,
+            5 => GrblError::SettingDisabled,
+            6 => GrblError::SettingStepPulseMin,
+            7 => GrblError::SettingReadFail,
+            8 => GrblError::IdleError,
+            9 => GrblError::SystemGcLock,
+            10 => GrblError::SoftLimitError,
+            11 => GrblError::Overflow,
+            12 => GrblError::MaxStepRateExceeded,
+            13 => GrblError::CheckDoor,
+            14 => GrblError::LineLengthExceeded,
+            15 => GrblError::TravelExceeded,
+            16 => GrblError::InvalidJogCommand,
+            17 => GrblError::SettingDisabledLaser,
+            20 => GrblError::UnsupportedCommand,
+            21 => GrblError::ModalGroupViolation,
+            22 => GrblError::UndefinedFeedRate,
+            23 => GrblError::CommandValueNotInteger,
+            24 => GrblError::AxisCommandConflict,
+            25 => GrblError::WordRepeated,
+            26 => GrblError::NoAxisWords,
+            27 => GrblError::InvalidLineNumber,
+            28 => GrblError::ValueWordMissing,
+            29 => GrblError::UnsupportedCoordSys,
+            30 => GrblError::G53InvalidMotionMode,
+            31 => GrblError::AxisWordsExist,
+            32 => GrblError::NoAxisWordsInPlane,
+            33 => GrblError::InvalidTarget,
+            34 => GrblError::ArcRadiusError,
+            35 => GrblError::NoOffsetsInPlane,
+            36 => GrblError::UnusedWords,
+            37 => GrblError::G43DynamicAxisError,
+            38 => GrblError::MaxValueExceeded,
+            other => GrblError::Unknown(other),
+        }
+    }
+
+    /// Parse an `error:N` response line
+    pub fn parse(line: &str) -> Option<Self> {
+        let value = line.trim().strip_prefix("error:")?.trim();
+        Some(match value.parse::<u16>() {
+            Ok(code) => Self::from_code(code),
+            Err(_) => GrblError::Other(value.to_string()),
+        })
+    }
+
+    /// Get the numeric code, if any
+    pub fn code(&self) -> Option<u16> {
+        let code = match self {
+            GrblError::ExpectedCommandLetter => 1,
+            GrblError::BadNumberFormat => 2,
+            GrblError::InvalidStatement => 3,
+            GrblError::NegativeValue => 4,
+            GrblError::SettingDisabled => 5,
+            GrblError::SettingStepPulseMin => 6,
+            GrblError::SettingReadFail => 7,
+            GrblError::IdleError => 8,
+            GrblError::SystemGcLock => 9,
+            GrblError::SoftLimitError => 10,
+            GrblError::Overflow => 11,
+            GrblError::MaxStepRateExceeded => 12,
+            GrblError::CheckDoor => 13,
+            GrblError::LineLengthExceeded => 14,
+            GrblError::TravelExceeded => 15,
+            GrblError::InvalidJogCommand => 16,
+            GrblError::SettingDisabledLaser => 17,
+            GrblError::UnsupportedCommand => 20,
+            GrblError::ModalGroupViolation => 21,
+            GrblError::UndefinedFeedRate => 22,
+            GrblError::CommandValueNotInteger => 23,
+            GrblError::AxisCommandConflict => 24,
+            GrblError::WordRepeated => 25,
+            GrblError::NoAxisWords => 26,
+            GrblError::InvalidLineNumber => 27,
+            GrblError::ValueWordMissing => 28,
+            GrblError::UnsupportedCoordSys => 29,
+            GrblError::G53InvalidMotionMode => 30,
+            GrblError::AxisWordsExist => 31,
+            GrblError::NoAxisWordsInPlane => 32,
+            GrblError::InvalidTarget => 33,
+            GrblError::ArcRadiusError => 34,
+            GrblError::NoOffsetsInPlane => 35,
+            GrblError::UnusedWords => 36,
+            GrblError::G43DynamicAxisError => 37,
+            GrblError::MaxValueExceeded => 38,
+            GrblError::Unknown(code) => *code,
+            GrblError::Other(_) => return None,
+        };
+        Some(code)
+    }
+
+    /// Get a human-readable description
+    pub fn description(&self) -> &str {
+        match self {
+            GrblError::ExpectedCommandLetter => "G-code words consist of a letter and a value. Letter was not found.",
+            GrblError::BadNumberFormat => "Missing the expected G-code word value or numeric value format is not valid.",
+            GrblError::InvalidStatement => "Grbl '$' system command was not recognized or supported.",
+            GrblError::NegativeValue => "Negative value received for an expected positive value.",
+            GrblError::SettingDisabled => "Homing cycle failure. Homing is not enabled via settings.",
+            GrblError::SettingStepPulseMin => "Minimum step pulse time must be greater than 3usec.",
+            GrblError::SettingReadFail => "An EEPROM read failed. Auto-restoring affected EEPROM to default values.",
+            GrblError::IdleError => "Grbl '$' command cannot be used unless Grbl is IDLE.",
+            GrblError::SystemGcLock => "G-code commands are locked out during alarm or jog state.",
+            GrblError::SoftLimitError => "Soft limits cannot be enabled without homing also enabled.",
+            GrblError::Overflow => "Max characters per line exceeded. Received command line was not executed.",
+            GrblError::MaxStepRateExceeded => "Grbl '$' setting value cause the step rate to exceed the maximum supported.",
+            GrblError::CheckDoor => "Safety door detected as opened and door state initiated.",
+            GrblError::LineLengthExceeded => "Build info or startup line exceeded EEPROM line length limit.",
+            GrblError::TravelExceeded => "Jog target exceeds machine travel. Jog command has been ignored.",
+            GrblError::InvalidJogCommand => "Jog command has no '=' or contains prohibited g-code.",
+            GrblError::SettingDisabledLaser => "Laser mode requires PWM output.",
+            GrblError::UnsupportedCommand => "Unsupported or invalid g-code command found in block.",
+            GrblError::ModalGroupViolation => "More than one g-code command from same modal group found in block.",
+            GrblError::UndefinedFeedRate => "Feed rate has not yet been set or is undefined.",
+            GrblError::CommandValueNotInteger => "G-code command in block requires an integer value.",
+            GrblError::AxisCommandConflict => "More than one g-code command that requires axis words found in block.",
+            GrblError::WordRepeated => "Repeated g-code word found in block.",
+            GrblError::NoAxisWords => "No axis words found in block for g-code command or current modal state which requires them.",
+            GrblError::InvalidLineNumber => "Line number value is invalid.",
+            GrblError::ValueWordMissing => "G-code command is missing a required value word.",
+            GrblError::UnsupportedCoordSys => "G59.x work coordinate systems are not supported.",
+            GrblError::G53InvalidMotionMode => "G53 only allowed with G0 and G1 motion modes.",
+            GrblError::AxisWordsExist => "Axis words found in block when no command or current modal state uses them.",
+            GrblError::NoAxisWordsInPlane => "G2 and G3 arcs require at least one in-plane axis word.",
+            GrblError::InvalidTarget => "Motion command target is invalid.",
+            GrblError::ArcRadiusError => "Arc radius value is invalid.",
+            GrblError::NoOffsetsInPlane => "G2 and G3 arcs require at least one in-plane offset word.",
+            GrblError::UnusedWords => "Unused value words found in block.",
+            GrblError::G43DynamicAxisError => "G43.1 dynamic tool length offset is not assigned to configured tool length axis.",
+            GrblError::MaxValueExceeded => "Tool number greater than max supported value.",
+            GrblError::Unknown(_) => "Unknown error code.",
+            GrblError::Other(text) => text,
+        }
+    }
+
+    /// Get a suggested recovery step
+    pub fn recovery(&self) -> &'static str {
+        match self {
+            GrblError::ExpectedCommandLetter
+            | GrblError::BadNumberFormat
+            | GrblError::CommandValueNotInteger
+            | GrblError::WordRepeated
+            | GrblError::InvalidLineNumber
+            | GrblError::UnusedWords => "Check the line for typos or post-processor output GRBL cannot parse.",
+            GrblError::InvalidStatement => "Send '$' to list supported system commands.",
+            GrblError::NegativeValue => "Use a positive value for this setting or word.",
+            GrblError::SettingDisabled => "Enable homing with $22=1 before running $H.",
+            GrblError::SettingStepPulseMin => "Set $0 to 3 microseconds or more.",
+            GrblError::SettingReadFail => "Review and re-enter settings with $$; the EEPROM may be worn.",
+            GrblError::IdleError => "Wait until the machine is Idle before changing settings.",
+            GrblError::SystemGcLock => "Clear the alarm with $X or $H, or cancel the jog, then resend.",
+            GrblError::SoftLimitError => "Enable homing ($22=1) before enabling soft limits ($20=1).",
+            GrblError::Overflow | GrblError::LineLengthExceeded => "Shorten the line to 80 characters or fewer.",
+            GrblError::MaxStepRateExceeded => "Lower max rate ($110-$112) or steps/mm ($100-$102).",
+            GrblError::CheckDoor => "Close the safety door and send cycle start to resume.",
+            GrblError::TravelExceeded => "Jog a shorter distance or re-home to restore machine position.",
+            GrblError::InvalidJogCommand => "Use the $J=<G-code> form with only G20/G21, G90/G91, G53 and axis/feed words.",
+            GrblError::SettingDisabledLaser => "Laser mode ($32=1) requires a PWM-capable spindle output.",
+            GrblError::UnsupportedCommand => "Remove the command or replace it with a GRBL-supported equivalent.",
+            GrblError::ModalGroupViolation | GrblError::AxisCommandConflict => "Split conflicting commands onto separate lines.",
+            GrblError::UndefinedFeedRate => "Add an F word to the first feed move (or the G93 block).",
+            GrblError::NoAxisWords | GrblError::NoAxisWordsInPlane => "Add the missing axis words for the active plane.",
+            GrblError::ValueWordMissing => "Add the value word required by the command (e.g. P for G4/G10).",
+            GrblError::UnsupportedCoordSys => "Use G54-G59 work coordinate systems only.",
+            GrblError::G53InvalidMotionMode => "Put G53 on a line with G0 or G1.",
+            GrblError::AxisWordsExist => "Add a motion command or remove stray axis words.",
+            GrblError::InvalidTarget => "Check arc endpoints and offsets; the target may equal the start point.",
+            GrblError::ArcRadiusError => "Regenerate the arc with I/J/K offsets or a larger radius.",
+            GrblError::NoOffsetsInPlane => "Add I/J/K offsets for the active plane.",
+            GrblError::G43DynamicAxisError => "Apply G43.1 to the Z axis only.",
+            GrblError::MaxValueExceeded => "Use a smaller tool number.",
+            GrblError::Unknown(_) | GrblError::Other(_) => "Consult the controller firmware documentation.",
+        }
+    }
+}
+
+impl fmt::Display for GrblError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code() {
+            Some(code) => write!(f, "error:{} - {}", code, self.description()),
+            None => write!(f, "error: {}", self.description()),
+        }
+    }
+}
+
+impl std::error::Error for GrblError {}
+
+/// GRBL 1.1 `ALARM:N` codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GrblAlarm {
+    /// 1: Hard limit triggered.
+    HardLimit,
+    /// 2: G-code motion target exceeds machine travel.
+    SoftLimit,
+    /// 3: Reset while in motion.
+    AbortCycle,
+    /// 4: Probe fail. The probe is not in the expected initial state.
+    ProbeFailInitial,
+    /// 5: Probe fail. Probe did not contact the workpiece.
+    ProbeFailContact,
+    /// 6: Homing fail. Reset during active homing cycle.
+    HomingFailReset,
+    /// 7: Homing fail. Safety door was opened during active homing cycle.
+    HomingFailDoor,
+    /// 8: Homing fail. Cycle failed to clear limit switch when pulling off.
+    HomingFailPulloff,
+    /// 9: Homing fail. Could not find limit switch within search distance.
+    HomingFailApproach,
+    /// 10: Homing fail. On dual axis machines, could not find the second limit switch.
+    HomingFailDualApproach,
+    /// Code not defined by GRBL 1.1
+    Unknown(u16),
+}
+
+impl GrblAlarm {
+    /// Decode an alarm from its numeric code
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            1 => GrblAlarm::HardLimit,
+            2 => GrblAlarm::SoftLimit,
+            3 => GrblAlarm::AbortCycle,
+            4 => GrblAlarm::ProbeFailInitial,
+            5 => GrblAlarm::ProbeFailContact,
+            6 => GrblAlarm::HomingFailReset,
+            7 => GrblAlarm::HomingFailDoor,
+            8 => GrblAlarm::HomingFailPulloff,
+            9 => GrblAlarm::HomingFailApproach,
+            10 => GrblAlarm::HomingFailDualApproach,
+            other => GrblAlarm::Unknown(other),
+        }
+    }
+
+    /// Parse an `ALARM:N` response line
+    pub fn parse(line: &str) -> Option<Self> {
+        let code = line.trim().strip_prefix("ALARM:")?.trim().parse().ok()?;
+        Some(Self::from_code(code))
+    }
+
+    /// Get the numeric code
+    pub fn code(&self) -> u16 {
+        match self {
+            GrblAlarm::HardLimit => 1,
+            GrblAlarm::SoftLimit => 2,
+            GrblAlarm::AbortCycle => 3,
+            GrblAlarm::ProbeFailInitial => 4,
+            GrblAlarm::ProbeFailContact => 5,
+            GrblAlarm::HomingFailReset => 6,
+            GrblAlarm::HomingFailDoor => 7,
+            GrblAlarm::HomingFailPulloff => 8,
+            GrblAlarm::HomingFailApproach => 9,
+            GrblAlarm::HomingFailDualApproach => 10,
+            GrblAlarm::Unknown(code) => *code,
+        }
+    }
+
+    /// Get a human-readable description
+    pub fn description(&self) -> &'static str {
+        match self {
+            GrblAlarm::HardLimit => "Hard limit triggered. Machine position is likely lost due to sudden and immediate halt.",
+            GrblAlarm::SoftLimit => "G-code motion target exceeds machine travel. Machine position safely retained.",
+            GrblAlarm::AbortCycle => "Reset while in motion. Machine position is likely lost due to sudden and immediate halt.",
+            GrblAlarm::ProbeFailInitial => "Probe fail. The probe is not in the expected initial state before starting probe cycle.",
+            GrblAlarm::ProbeFailContact => "Probe fail. Probe did not contact the workpiece within the programmed travel.",
+            GrblAlarm::HomingFailReset => "Homing fail. Reset during active homing cycle.",
+            GrblAlarm::HomingFailDoor => "Homing fail. Safety door was opened during active homing cycle.",
+            GrblAlarm::HomingFailPulloff => "Homing fail. Cycle failed to clear limit switch when pulling off.",
+            GrblAlarm::HomingFailApproach => "Homing fail. Could not find limit switch within search distance.",
+            GrblAlarm::HomingFailDualApproach => "Homing fail. On dual axis machines, could not find the second limit switch.",
+            GrblAlarm::Unknown(_) => "Unknown alarm code.",
+        }
+    }
+
+    /// Get a suggested recovery step
+    pub fn recovery(&self) -> &'static str {
+        match self {
+            GrblAlarm::HardLimit => "Jog off the limit switch, then re-home with $H.",
+            GrblAlarm::SoftLimit => "Unlock with $X and check the job fits inside the machine travel.",
+            GrblAlarm::AbortCycle => "Re-home with $H before continuing; position was lost.",
+            GrblAlarm::ProbeFailInitial => "Check probe wiring and that it is not already touching, then unlock with $X.",
+            GrblAlarm::ProbeFailContact => "Move the probe closer or increase probe distance, then unlock with $X.",
+            GrblAlarm::HomingFailReset | GrblAlarm::HomingFailDoor => "Close the door and run $H again.",
+            GrblAlarm::HomingFailPulloff => "Increase homing pull-off ($27) or check the limit switch wiring.",
+            GrblAlarm::HomingFailApproach | GrblAlarm::HomingFailDualApproach => {
+                "Check limit switches and increase max travel ($130-$132) if needed."
+            }
+            GrblAlarm::Unknown(_) => "Consult the controller firmware documentation.",
+        }
+    }
+
+    /// Check whether the alarm means machine position can no longer be trusted
+    pub fn position_lost(&self) -> bool {
+        matches!(self, GrblAlarm::HardLimit | GrblAlarm::AbortCycle)
+    }
+}
+
+impl fmt::Display for GrblAlarm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ALARM:{} - {}", self.code(), self.description())
+    }
+}
+
+impl std::error::Error for GrblAlarm {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_round_trip() {
+        for code in (1..=17).chain(20..=38) {
+            let error = GrblError::from_code(code);
+            assert!(!matches!(error, GrblError::Unknown(_)), "code {}", code);
+            assert_eq!(error.code(), Some(code));
+        }
+        assert_eq!(GrblError::from_code(18), GrblError::Unknown(18));
+    }
+
+    #[test]
+    fn test_error_parse_and_display() {
+        let error = GrblError::parse("error:22").unwrap();
+        assert_eq!(error, GrblError::UndefinedFeedRate);
+        assert!(error.to_string().starts_with("error:22 - Feed rate"));
+
+        let legacy = GrblError::parse("error:Bad number format").unwrap();
+        assert_eq!(legacy.code(), None);
+        assert!(GrblError::parse("ok").is_none());
+    }
+
+    #[test]
+    fn test_alarm_round_trip() {
+        for code in 1..=10 {
+            let alarm = GrblAlarm::from_code(code);
+            assert_eq!(alarm.code(), code);
+            assert!(!matches!(alarm, GrblAlarm::Unknown(_)));
+        }
+    }
+
+    #[test]
+    fn test_alarm_parse() {
+        let alarm = GrblAlarm::parse("ALARM:1").unwrap();
+        assert_eq!(alarm, GrblAlarm::HardLimit);
+        assert!(alarm.position_lost());
+        assert!(!alarm.recovery().is_empty());
+        assert!(GrblAlarm::parse("ALARM:x").is_none());
+    }
+}
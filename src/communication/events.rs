@@ -0,0 +1,292 @@
+//! Machine event bus
+//!
+//! `GrblController` publishes typed `MachineEvent`s for the transitions
+//! consumers care about (state changes, alarms, the safety door, probe
+//! contacts, acknowledged lines, planner underruns and connection changes),
+//! so the UI, the pendant, the job manager and the logger can react to them
+//! instead of polling `get_status`.
+
+use super::offsets::ProbeResult;
+use super::status::BufferState;
+use super::{GrblAlarm, GrblStatus, LineAck, MachineState};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Events buffered per subscriber before the slowest one starts lagging
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// A machine transition published by the controller
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum MachineEvent {
+    /// The reported machine state changed, e.g. Idle to Run
+    StateChanged {
+        from: MachineState,
+        to: MachineState,
+    },
+    /// The machine entered the Alarm state; the code is known when GRBL
+    /// printed `ALARM:N`
+    AlarmRaised { alarm: Option<GrblAlarm> },
+    /// The machine left the Alarm state (unlock or homing)
+    AlarmCleared,
+    /// The safety door was opened
+    DoorOpened,
+    /// The machine left the Door state
+    DoorClosed,
+    /// A probe cycle made contact
+    ProbeTriggered { probe: ProbeResult },
+    /// The controller answered `ok` or `error:N` to a line
+    LineAcknowledged { ack: LineAck },
+    /// The planner ran dry while lines were still waiting to be sent
+    BufferUnderrun { queued_lines: usize },
+    /// The link to the controller was opened
+    Connected,
+    /// The link to the controller was closed
+    Disconnected,
+    /// The link dropped without the user disconnecting
+    ConnectionLost { reason: String },
+}
+
+impl fmt::Display for MachineEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MachineEvent::StateChanged { from, to } => {
+                write!(f, "State changed: {:?} -> {:?}", from, to)
+            }
+            MachineEvent::AlarmRaised { alarm: Some(alarm) } => {
+                write!(f, "Alarm raised: {}", alarm)
+            }
+            MachineEvent::AlarmRaised { alarm: None } => write!(f, "Alarm raised"),
+            MachineEvent::AlarmCleared => write!(f, "Alarm cleared"),
+            MachineEvent::DoorOpened => write!(f, "Safety door opened"),
+            MachineEvent::DoorClosed => write!(f, "Safety door closed"),
+            MachineEvent::ProbeTriggered { probe } => write!(
+                f,
+                "Probe triggered at X{:.3} Y{:.3} Z{:.3}",
+                probe.position.x, probe.position.y, probe.position.z
+            ),
+            MachineEvent::LineAcknowledged { ack } => {
+                write!(f, "Line {} acknowledged: {}", ack.sequence, ack.command)
+            }
+            MachineEvent::BufferUnderrun { queued_lines } => {
+                write!(f, "Planner underrun with {} lines queued", queued_lines)
+            }
+            MachineEvent::Connected => write!(f, "Connected"),
+            MachineEvent::Disconnected => write!(f, "Disconnected"),
+            MachineEvent::ConnectionLost { reason } => write!(f, "Connection lost: {}", reason),
+        }
+    }
+}
+
+/// Work out the events implied by a status update
+///
+/// # Arguments
+/// * `previous` - Status before the update
+/// * `current` - Status after the update
+///
+/// # Returns
+/// Events in the order they should be published
+pub fn status_events(previous: &GrblStatus, current: &GrblStatus) -> Vec<MachineEvent> {
+    let mut events = Vec::new();
+    if !previous.connected && current.connected {
+        events.push(MachineEvent::Connected);
+    }
+
+    let (from, to) = (previous.state, current.state);
+    if from != to {
+        events.push(MachineEvent::StateChanged { from, to });
+    }
+    // Unknown only means the state is not known yet, e.g. after a link drop
+    let settled = to != MachineState::Unknown;
+
+    let new_alarm = current.alarm.is_some() && current.alarm != previous.alarm;
+    if to == MachineState::Alarm && (from != MachineState::Alarm || new_alarm) {
+        events.push(MachineEvent::AlarmRaised {
+            alarm: current.alarm,
+        });
+    } else if from == MachineState::Alarm && to != MachineState::Alarm && settled {
+        events.push(MachineEvent::AlarmCleared);
+    }
+
+    if to == MachineState::Door && from != MachineState::Door {
+        events.push(MachineEvent::DoorOpened);
+    } else if from == MachineState::Door && to != MachineState::Door && settled {
+        events.push(MachineEvent::DoorClosed);
+    }
+
+    if previous.connected && !current.connected {
+        events.push(MachineEvent::Disconnected);
+    }
+    events
+}
+
+/// Spots the planner running dry while a stream is in progress
+///
+/// GRBL reports free planner blocks in `Bf:`; the buffer size is taken from
+/// the highest count seen, which is reached whenever the machine is idle. An
+/// underrun is reported once when at most one block is left while the
+/// machine runs and lines are still queued on the host, and again only after
+/// the planner has refilled.
+#[derive(Debug, Clone, Default)]
+pub struct UnderrunDetector {
+    planner_size: u32,
+    starved: bool,
+}
+
+impl UnderrunDetector {
+    /// Feed a status report's buffer state
+    ///
+    /// # Arguments
+    /// * `state` - Reported machine state
+    /// * `buffer` - Reported buffer state
+    /// * `queued_lines` - Lines waiting on the host to be sent
+    ///
+    /// # Returns
+    /// true if this report starts an underrun
+    pub fn observe(
+        &mut self,
+        state: MachineState,
+        buffer: BufferState,
+        queued_lines: usize,
+    ) -> bool {
+        self.planner_size = self.planner_size.max(buffer.planner_blocks);
+        let nearly_empty = self.planner_size > 1 && buffer.planner_blocks + 1 >= self.planner_size;
+        if !nearly_empty {
+            self.starved = false;
+            return false;
+        }
+        if state == MachineState::Run && queued_lines > 0 && !self.starved {
+            self.starved = true;
+            return true;
+        }
+        false
+    }
+}
+
+/// Run `handler` for every event received on `events`
+///
+/// A subscriber that falls behind skips the events it missed rather than
+/// stopping. The task ends when the controller is dropped.
+pub fn spawn_listener<F>(
+    mut events: broadcast::Receiver<MachineEvent>,
+    mut handler: F,
+) -> JoinHandle<()>
+where
+    F: FnMut(MachineEvent) + Send + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => handler(event),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Event listener missed {} events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+/// Log every event through `tracing`
+///
+/// Acknowledged lines are logged at trace level since there is one per line
+/// streamed; alarms, underruns and connection losses are warnings.
+pub fn spawn_event_logger(events: broadcast::Receiver<MachineEvent>) -> JoinHandle<()> {
+    spawn_listener(events, |event| match &event {
+        MachineEvent::LineAcknowledged { .. } => tracing::trace!("{}", event),
+        MachineEvent::AlarmRaised { .. }
+        | MachineEvent::BufferUnderrun { .. }
+        | MachineEvent::ConnectionLost { .. } => tracing::warn!("{}", event),
+        _ => tracing::info!("{}", event),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(state: MachineState) -> GrblStatus {
+        GrblStatus {
+            state,
+            connected: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_status_events_for_alarm() {
+        let idle = status(MachineState::Idle);
+        let mut alarm = status(MachineState::Alarm);
+        alarm.alarm = Some(GrblAlarm::SoftLimit);
+
+        let events = status_events(&idle, &alarm);
+        assert!(matches!(
+            events[..],
+            [
+                MachineEvent::StateChanged {
+                    from: MachineState::Idle,
+                    to: MachineState::Alarm
+                },
+                MachineEvent::AlarmRaised {
+                    alarm: Some(GrblAlarm::SoftLimit)
+                }
+            ]
+        ));
+        assert!(status_events(&alarm, &alarm.clone()).is_empty());
+        assert!(matches!(
+            status_events(&alarm, &idle)[..],
+            [
+                MachineEvent::StateChanged { .. },
+                MachineEvent::AlarmCleared
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_status_events_for_door_and_link() {
+        let run = status(MachineState::Run);
+        let door = status(MachineState::Door);
+        assert!(matches!(
+            status_events(&run, &door)[..],
+            [MachineEvent::StateChanged { .. }, MachineEvent::DoorOpened]
+        ));
+
+        // A dropped link is not a closed door
+        let lost = GrblStatus {
+            connected: false,
+            ..Default::default()
+        };
+        assert!(matches!(
+            status_events(&door, &lost)[..],
+            [
+                MachineEvent::StateChanged { .. },
+                MachineEvent::Disconnected
+            ]
+        ));
+        assert!(matches!(
+            status_events(&lost, &status(MachineState::Unknown))[..],
+            [MachineEvent::Connected]
+        ));
+    }
+
+    #[test]
+    fn test_underrun_detector() {
+        let mut detector = UnderrunDetector::default();
+        let buffer = |planner_blocks| BufferState {
+            planner_blocks,
+            rx_bytes: 128,
+        };
+        assert!(!detector.observe(MachineState::Idle, buffer(15), 0));
+        assert!(!detector.observe(MachineState::Run, buffer(2), 20));
+        assert!(detector.observe(MachineState::Run, buffer(14), 20));
+        // Reported once per starvation
+        assert!(!detector.observe(MachineState::Run, buffer(15), 20));
+        assert!(!detector.observe(MachineState::Run, buffer(5), 20));
+        assert!(detector.observe(MachineState::Run, buffer(14), 20));
+        // Draining at the end of a program is not an underrun
+        assert!(!detector.observe(MachineState::Run, buffer(5), 0));
+        assert!(!detector.observe(MachineState::Run, buffer(14), 0));
+    }
+}
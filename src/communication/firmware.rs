@@ -0,0 +1,649 @@
+//! Firmware dialect detection and protocol differences
+//!
+//! Identifies the controller firmware (GRBL, grblHAL, FluidNC, Smoothieware
+//! or Marlin) from its welcome banner, `$I`, `version` or `M115` output, and
+//! describes how each dialect differs: status reports, real-time commands,
+//! settings syntax and supported G-codes.
+
+use super::realtime::RealtimeCommand;
+use super::settings::{format_value, setting_info, GrblSettings};
+use super::status::StatusReport;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// G-codes GRBL 1.1 rejects with `error:20`
+const GRBL_UNSUPPORTED: &[&str] = &[
+    "G5", "G33", "G41", "G42", "G73", "G76", "G81", "G82", "G83", "G85", "G86", "G89", "G96",
+    "G97", "M6", "M62", "M63", "M64", "M65", "M66", "M67", "M68",
+];
+
+/// grblHAL adds canned cycles, tool change and digital/analog I/O
+const GRBLHAL_UNSUPPORTED: &[&str] = &["G41", "G42"];
+
+/// FluidNC supports tool change and I/O but no canned cycles
+const FLUIDNC_UNSUPPORTED: &[&str] = &[
+    "G5", "G33", "G41", "G42", "G73", "G76", "G81", "G82", "G83", "G85", "G86", "G89", "G96",
+    "G97",
+];
+
+const SMOOTHIE_UNSUPPORTED: &[&str] = &[
+    "G5", "G33", "G41", "G42", "G73", "G76", "G81", "G82", "G83", "G85", "G86", "G89", "G93",
+    "G96", "G97", "M62", "M63", "M64", "M65", "M66", "M67", "M68",
+];
+
+/// Codes Marlin lacks, or uses for something else (G10 retracts, G30 probes)
+const MARLIN_UNSUPPORTED: &[&str] = &[
+    "G10", "G28.1", "G30", "G30.1", "G38.4", "G38.5", "G41", "G42", "G43.1", "G49", "G73", "G76",
+    "G81", "G82", "G83", "G85", "G86", "G89", "G92.1", "G93", "M62", "M63", "M64", "M65", "M66",
+    "M67", "M68",
+];
+
+/// M-code settings shared by Marlin and Smoothieware
+///
+/// Each entry maps the X/Y/Z words of an M-code to three consecutive GRBL
+/// setting numbers. `scale` converts the M-code value to GRBL units.
+const MCODE_SETTINGS: &[(&str, u16, f64)] = &[
+    // Steps per mm
+    ("M92", 100, 1.0),
+    // Max feed rate in mm/s
+    ("M203", 110, 60.0),
+    // Max acceleration in mm/s^2
+    ("M201", 120, 1.0),
+];
+
+/// FluidNC configuration paths for the per-axis GRBL settings
+const FLUIDNC_AXIS_SETTINGS: &[(u16, &str)] = &[
+    (100, "steps_per_mm"),
+    (110, "max_rate_mm_per_min"),
+    (120, "acceleration_mm_per_sec2"),
+    (130, "max_travel_mm"),
+];
+
+/// Controller firmware family
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum FirmwareDialect {
+    Grbl,
+    GrblHal,
+    FluidNc,
+    Smoothieware,
+    Marlin,
+    /// Not detected yet; treated like GRBL
+    #[default]
+    Unknown,
+}
+
+impl FirmwareDialect {
+    /// Get the display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            FirmwareDialect::Grbl => "GRBL",
+            FirmwareDialect::GrblHal => "grblHAL",
+            FirmwareDialect::FluidNc => "FluidNC",
+            FirmwareDialect::Smoothieware => "Smoothieware",
+            FirmwareDialect::Marlin => "Marlin",
+            FirmwareDialect::Unknown => "Unknown",
+        }
+    }
+
+    /// Check if the dialect speaks the GRBL 1.1 protocol
+    pub fn is_grbl_compatible(&self) -> bool {
+        matches!(
+            self,
+            FirmwareDialect::Grbl
+                | FirmwareDialect::GrblHal
+                | FirmwareDialect::FluidNc
+                | FirmwareDialect::Unknown
+        )
+    }
+
+    /// Commands that make the firmware identify itself, in the order to try
+    pub fn identify_commands(&self) -> &'static [&'static str] {
+        match self {
+            FirmwareDialect::Marlin => &["M115"],
+            FirmwareDialect::Smoothieware => &["version"],
+            FirmwareDialect::Unknown => &["$I", "M115", "version"],
+            _ => &["$I"],
+        }
+    }
+
+    /// Parse a status report in this dialect's format
+    ///
+    /// Marlin answers `M114` (or `M154` auto-reports) with
+    /// `X:10.00 Y:0.00 Z:0.00 E:0.00 Count ...`, which carries no machine
+    /// state; the returned report has `MachineState::Unknown`.
+    pub fn parse_status(&self, line: &str) -> Option<StatusReport> {
+        match self {
+            FirmwareDialect::Marlin => parse_marlin_position(line),
+            _ => StatusReport::parse_legacy(line).or_else(|| StatusReport::parse(line)),
+        }
+    }
+
+    /// Get the bytes that carry a real-time command
+    ///
+    /// Marlin has no real-time bytes; its emergency parser acts on `M410`
+    /// (quick stop) and `M108` (continue) as soon as they arrive. Both are
+    /// still answered with `ok` like any other line.
+    ///
+    /// # Returns
+    /// None if the dialect cannot perform the command
+    pub fn realtime_bytes(&self, command: RealtimeCommand) -> Option<Vec<u8>> {
+        match self {
+            FirmwareDialect::Marlin => match command {
+                RealtimeCommand::SoftReset => Some(b"M410\n".to_vec()),
+                RealtimeCommand::CycleStart => Some(b"M108\n".to_vec()),
+                _ => None,
+            },
+            FirmwareDialect::Smoothieware => matches!(
+                command,
+                RealtimeCommand::StatusQuery
+                    | RealtimeCommand::FeedHold
+                    | RealtimeCommand::CycleStart
+                    | RealtimeCommand::SoftReset
+            )
+            .then(|| vec![command.byte()]),
+            _ => Some(vec![command.byte()]),
+        }
+    }
+
+    /// Command that makes the firmware report position on its own
+    ///
+    /// Used when the dialect has no real-time status query.
+    pub fn auto_report_command(&self) -> Option<&'static str> {
+        match self {
+            FirmwareDialect::Marlin => Some("M154 S1"),
+            _ => None,
+        }
+    }
+
+    /// Line command that sets the feed override, for dialects without
+    /// real-time override bytes
+    pub fn feed_override_command(&self, percent: u32) -> Option<String> {
+        match self {
+            FirmwareDialect::Marlin | FirmwareDialect::Smoothieware => {
+                Some(format!("M220 S{}", percent))
+            }
+            _ => None,
+        }
+    }
+
+    /// Command that lists the machine settings
+    pub fn settings_query(&self) -> &'static str {
+        match self {
+            FirmwareDialect::Marlin | FirmwareDialect::Smoothieware => "M503",
+            _ => "$$",
+        }
+    }
+
+    /// Parse one line of the settings listing into GRBL-numbered values
+    ///
+    /// Marlin and Smoothieware settings are translated to the matching
+    /// `$100`-`$122` numbers so they can be edited like GRBL settings.
+    pub fn parse_setting_line(&self, line: &str) -> Vec<(u16, f64)> {
+        if self.is_grbl_compatible() {
+            return GrblSettings::parse_line(line).into_iter().collect();
+        }
+
+        let line = line.trim();
+        let line = line.strip_prefix("echo:").unwrap_or(line);
+        let mut words = line.split_whitespace();
+        let code = match words.next() {
+            Some(code) => code.to_ascii_uppercase(),
+            None => return Vec::new(),
+        };
+
+        let mut values = Vec::new();
+        for word in words {
+            let letter = match word.chars().next() {
+                Some(letter) if letter.is_ascii_alphabetic() => letter.to_ascii_uppercase(),
+                _ => continue,
+            };
+            let value = &word[1..];
+            let value: f64 = match value.parse() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+
+            if *self == FirmwareDialect::Smoothieware && code == "M204" && letter == 'S' {
+                values.extend((120..=122).map(|number| (number, value)));
+                continue;
+            }
+            let axis = match letter {
+                'X' => 0,
+                'Y' => 1,
+                'Z' => 2,
+                _ => continue,
+            };
+            if let Some((_, base, scale)) = MCODE_SETTINGS.iter().find(|(c, _, _)| *c == code) {
+                values.push((base + axis, value * scale));
+            }
+        }
+        values
+    }
+
+    /// Build the command that writes a GRBL-numbered setting
+    ///
+    /// # Returns
+    /// None if the dialect has no equivalent for the setting
+    pub fn setting_command(&self, number: u16, value: f64) -> Option<String> {
+        match self {
+            FirmwareDialect::Marlin | FirmwareDialect::Smoothieware => {
+                let axis = ["X", "Y", "Z"].get(usize::from(number % 10))?;
+                if *self == FirmwareDialect::Smoothieware && (120..=122).contains(&number) {
+                    return Some(format!("M204 S{}", format_value(number, value)));
+                }
+                let (code, _, scale) = MCODE_SETTINGS
+                    .iter()
+                    .find(|(_, base, _)| (*base..*base + 3).contains(&number))?;
+                Some(format!("{} {}{}", code, axis, format_value(number, value / scale)))
+            }
+            FirmwareDialect::FluidNc => {
+                let path = FLUIDNC_AXIS_SETTINGS
+                    .iter()
+                    .find(|(base, _)| (*base..*base + 3).contains(&number))
+                    .map(|(base, field)| {
+                        let axis = ["x", "y", "z"][usize::from(number - base)];
+                        format!("/axes/{}/{}", axis, field)
+                    });
+                match path {
+                    Some(path) => Some(format!("${}={}", path, format_value(number, value))),
+                    None => Some(format!("${}={}", number, format_value(number, value))),
+                }
+            }
+            _ => Some(format!("${}={}", number, format_value(number, value))),
+        }
+    }
+
+    /// Check whether the firmware has a setting with this GRBL number
+    ///
+    /// grblHAL and FluidNC add their own `$` settings (e.g. `$14`, `$28`)
+    /// beyond GRBL 1.1's table; those are accepted as-is and checked by the
+    /// controller when written.
+    pub fn defines_setting(&self, number: u16) -> bool {
+        match self {
+            FirmwareDialect::GrblHal | FirmwareDialect::FluidNc => true,
+            FirmwareDialect::Marlin | FirmwareDialect::Smoothieware => {
+                self.setting_command(number, 0.0).is_some()
+            }
+            FirmwareDialect::Grbl | FirmwareDialect::Unknown => setting_info(number).is_some(),
+        }
+    }
+
+    /// GRBL-numbered settings the firmware keeps as a single value
+    ///
+    /// Smoothieware has one acceleration (`M204 S`) for every axis, so
+    /// `$120`-`$122` can only be written together with the same value.
+    pub fn shared_settings(&self) -> &'static [u16] {
+        match self {
+            FirmwareDialect::Smoothieware => &[120, 121, 122],
+            _ => &[],
+        }
+    }
+
+    /// Command that stores written settings in non-volatile memory
+    ///
+    /// GRBL-style firmware saves each `$` write immediately.
+    pub fn persist_settings_command(&self) -> Option<&'static str> {
+        match self {
+            FirmwareDialect::Marlin | FirmwareDialect::Smoothieware => Some("M500"),
+            _ => None,
+        }
+    }
+
+    /// G- and M-codes the dialect rejects or interprets differently
+    pub fn unsupported_gcodes(&self) -> &'static [&'static str] {
+        match self {
+            FirmwareDialect::GrblHal => GRBLHAL_UNSUPPORTED,
+            FirmwareDialect::FluidNc => FLUIDNC_UNSUPPORTED,
+            FirmwareDialect::Smoothieware => SMOOTHIE_UNSUPPORTED,
+            FirmwareDialect::Marlin => MARLIN_UNSUPPORTED,
+            _ => GRBL_UNSUPPORTED,
+        }
+    }
+
+    /// Check if a G- or M-code word such as `G38.2` or `M6` is supported
+    pub fn supports_gcode(&self, word: &str) -> bool {
+        let word = word.trim().to_ascii_uppercase();
+        !self.unsupported_gcodes().contains(&word.as_str())
+    }
+}
+
+impl fmt::Display for FirmwareDialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Detected firmware and version
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirmwareInfo {
+    pub dialect: FirmwareDialect,
+    /// Version as printed, e.g. `1.1h` or `3.7.8`
+    pub version: String,
+    pub major: u32,
+    pub minor: u32,
+    /// Letter or patch number after the minor version (`h` in `1.1h`)
+    pub revision: String,
+    /// Build date from `[VER:...]`
+    pub build: Option<String>,
+}
+
+impl FirmwareInfo {
+    /// Identify the firmware from a single banner or identification line
+    pub fn detect(line: &str) -> Option<Self> {
+        let mut info = FirmwareInfo::default();
+        info.apply_line(line).then_some(info)
+    }
+
+    /// Identify the firmware from the lines printed for `$I`, `version` or `M115`
+    pub fn from_lines<'a>(lines: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        let mut info = FirmwareInfo::default();
+        let mut found = false;
+        for line in lines {
+            found |= info.apply_line(line);
+        }
+        found.then_some(info)
+    }
+
+    /// Update from a banner or identification line
+    ///
+    /// grblHAL prints `[VER:...]` before `[FIRMWARE:grblHAL]`, so the dialect
+    /// found by an earlier line is kept when a later one only adds the version.
+    ///
+    /// # Returns
+    /// true if the line identified the firmware or its version
+    pub fn apply_line(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let line = line.strip_prefix("echo:").unwrap_or(line).trim();
+
+        if let Some(rest) = line.strip_prefix("GrblHAL ") {
+            self.dialect = FirmwareDialect::GrblHal;
+            self.set_version(first_word(rest));
+        } else if let Some(rest) = line.strip_prefix("Grbl ") {
+            match line.find("FluidNC v") {
+                Some(idx) => {
+                    self.dialect = FirmwareDialect::FluidNc;
+                    self.set_version(first_word(&line[idx + "FluidNC v".len()..]));
+                }
+                None => {
+                    self.dialect = FirmwareDialect::Grbl;
+                    self.set_version(first_word(rest));
+                }
+            }
+        } else if let Some(body) = line.strip_prefix("[VER:").and_then(|l| l.strip_suffix(']')) {
+            let body = body.split(':').next().unwrap_or_default();
+            if let Some(idx) = body.find("FluidNC v") {
+                self.dialect = FirmwareDialect::FluidNc;
+                self.set_version(first_word(&body[idx + "FluidNC v".len()..]));
+            } else {
+                if !matches!(self.dialect, FirmwareDialect::GrblHal | FirmwareDialect::Smoothieware) {
+                    self.dialect = FirmwareDialect::Grbl;
+                }
+                // `1.1h.20190830` is version then build date
+                let mut parts = first_word(body).splitn(3, '.');
+                let version = match (parts.next(), parts.next()) {
+                    (Some(major), Some(minor)) => format!("{}.{}", major, minor),
+                    _ => return false,
+                };
+                self.set_version(&version);
+                self.build = parts.next().map(str::to_string);
+            }
+        } else if let Some(name) = line.strip_prefix("[FIRMWARE:").and_then(|l| l.strip_suffix(']')) {
+            if !name.eq_ignore_ascii_case("grblHAL") {
+                return false;
+            }
+            self.dialect = FirmwareDialect::GrblHal;
+        } else if let Some(rest) = line.strip_prefix("FIRMWARE_NAME:") {
+            if !rest.starts_with("Marlin") {
+                return false;
+            }
+            self.dialect = FirmwareDialect::Marlin;
+            self.set_version(first_word(rest.trim_start_matches("Marlin").trim_start()));
+        } else if let Some(rest) = line.strip_prefix("Marlin ") {
+            self.dialect = FirmwareDialect::Marlin;
+            self.set_version(first_word(rest));
+        } else if let Some(rest) = line.strip_prefix("Build version:") {
+            self.dialect = FirmwareDialect::Smoothieware;
+            self.set_version(rest.split(',').next().unwrap_or_default().trim());
+        } else if line.starts_with("Smoothie") {
+            self.dialect = FirmwareDialect::Smoothieware;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Store a version string and split out its numeric parts
+    fn set_version(&mut self, version: &str) {
+        let version = version.trim().trim_start_matches('v');
+        self.version = version.to_string();
+
+        let mut parts = version.splitn(3, '.');
+        self.major = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+        let minor = parts.next().unwrap_or_default();
+        let digits = minor.chars().take_while(char::is_ascii_digit).count();
+        self.minor = minor[..digits].parse().unwrap_or(0);
+        self.revision = match parts.next() {
+            Some(patch) => patch.to_string(),
+            None => minor[digits..].to_string(),
+        };
+    }
+
+    /// Check if the firmware has been identified
+    pub fn is_known(&self) -> bool {
+        self.dialect != FirmwareDialect::Unknown
+    }
+
+    /// Get a short description such as `grblHAL 1.1f`
+    pub fn describe(&self) -> String {
+        if self.version.is_empty() {
+            self.dialect.name().to_string()
+        } else {
+            format!("{} {}", self.dialect, self.version)
+        }
+    }
+}
+
+/// GRBL version information
+#[deprecated(note = "use FirmwareInfo, which also identifies the dialect")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: char,
+}
+
+#[allow(deprecated)]
+impl VersionInfo {
+    /// Parse a version from a banner such as `Grbl 1.1h ['$' for help]`
+    pub fn parse(response: &str) -> Option<Self> {
+        let response = response.trim();
+        // Older callers passed `GRBL v1.1h`
+        let line = match response.strip_prefix("GRBL ") {
+            Some(rest) => format!("Grbl {}", rest),
+            None => response.to_string(),
+        };
+        FirmwareInfo::detect(&line).map(|info| Self::from(&info))
+    }
+}
+
+#[allow(deprecated)]
+impl From<&FirmwareInfo> for VersionInfo {
+    fn from(info: &FirmwareInfo) -> Self {
+        VersionInfo {
+            major: info.major,
+            minor: info.minor,
+            patch: info.revision.chars().next().unwrap_or('0'),
+        }
+    }
+}
+
+/// Get the first whitespace-separated word
+fn first_word(text: &str) -> &str {
+    text.split_whitespace().next().unwrap_or_default()
+}
+
+/// Parse Marlin's `X:10.00 Y:0.00 Z:0.00 E:0.00 Count X:800 Y:0 Z:0`
+fn parse_marlin_position(line: &str) -> Option<StatusReport> {
+    let line = line.trim();
+    let line = line.strip_prefix("ok").unwrap_or(line);
+    let logical = line.split(" Count").next()?;
+
+    let mut position = [None; 3];
+    for word in logical.split_whitespace() {
+        let (axis, value) = word.split_once(':')?;
+        let index = match axis {
+            "X" => 0,
+            "Y" => 1,
+            "Z" => 2,
+            _ => continue,
+        };
+        position[index] = value.parse::<f64>().ok();
+    }
+
+    Some(StatusReport {
+        wpos: Some(super::Position {
+            x: position[0]?,
+            y: position[1]?,
+            z: position[2]?,
+        }),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::communication::MachineState;
+
+    fn detect(text: &str) -> FirmwareInfo {
+        FirmwareInfo::from_lines(text.lines()).unwrap()
+    }
+
+    #[test]
+    fn test_detect_grbl() {
+        let info = detect("Grbl 1.1h ['$' for help]");
+        assert_eq!(info.dialect, FirmwareDialect::Grbl);
+        assert_eq!((info.major, info.minor, info.revision.as_str()), (1, 1, "h"));
+
+        let info = detect("[VER:1.1h.20190830:]\n[OPT:V,15,128]\nok");
+        assert_eq!(info.dialect, FirmwareDialect::Grbl);
+        assert_eq!(info.version, "1.1h");
+        assert_eq!(info.build.as_deref(), Some("20190830"));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_version_info_from_banner() {
+        let version = VersionInfo::parse("GRBL v1.1h").unwrap();
+        assert_eq!((version.major, version.minor, version.patch), (1, 1, 'h'));
+        let version = VersionInfo::parse("Grbl 0.9j ['$' for help]").unwrap();
+        assert_eq!((version.major, version.minor, version.patch), (0, 9, 'j'));
+        assert!(VersionInfo::parse("ok").is_none());
+    }
+
+    #[test]
+    fn test_detect_grblhal() {
+        assert_eq!(
+            detect("GrblHAL 1.1f ['$' or '$HELP' for help]").dialect,
+            FirmwareDialect::GrblHal
+        );
+        let info = detect("[VER:1.1f.20230101:]\n[OPT:VNMSL,35,1024,3,0]\n[FIRMWARE:grblHAL]\nok");
+        assert_eq!(info.dialect, FirmwareDialect::GrblHal);
+        assert_eq!(info.describe(), "grblHAL 1.1f");
+    }
+
+    #[test]
+    fn test_detect_fluidnc() {
+        let info = detect("Grbl 3.7 [FluidNC v3.7.8 (wifi) '$' for help]");
+        assert_eq!(info.dialect, FirmwareDialect::FluidNc);
+        assert_eq!((info.major, info.minor, info.revision.as_str()), (3, 7, "8"));
+        assert_eq!(
+            detect("[VER:3.7 FluidNC v3.7.8:]").dialect,
+            FirmwareDialect::FluidNc
+        );
+    }
+
+    #[test]
+    fn test_detect_smoothie_and_marlin() {
+        let info = detect("Build version: edge-3332442, Build date: Feb 10 2021, MCU: LPC1769");
+        assert_eq!(info.dialect, FirmwareDialect::Smoothieware);
+        assert_eq!(info.version, "edge-3332442");
+
+        let info = detect("FIRMWARE_NAME:Marlin 2.1.2 (Jun  1 2023) SOURCE_CODE_URL:github.com/MarlinFirmware/Marlin");
+        assert_eq!(info.dialect, FirmwareDialect::Marlin);
+        assert_eq!(info.version, "2.1.2");
+        assert_eq!(detect("echo:Marlin 2.0.9.3").dialect, FirmwareDialect::Marlin);
+
+        assert!(FirmwareInfo::detect("ok").is_none());
+    }
+
+    #[test]
+    fn test_parse_status_per_dialect() {
+        let report = FirmwareDialect::Marlin
+            .parse_status("X:10.00 Y:2.50 Z:-1.00 E:0.00 Count X:800 Y:200 Z:-400")
+            .unwrap();
+        assert_eq!(report.state, MachineState::Unknown);
+        assert_eq!(report.wpos.unwrap().y, 2.5);
+
+        let report = FirmwareDialect::Smoothieware
+            .parse_status("<Idle,MPos:1.0000,2.0000,3.0000,WPos:1.0000,2.0000,3.0000>")
+            .unwrap();
+        assert_eq!(report.state, MachineState::Idle);
+        assert!(FirmwareDialect::Grbl
+            .parse_status("<Run|MPos:0.000,0.000,0.000|FS:0,0>")
+            .is_some());
+    }
+
+    #[test]
+    fn test_realtime_bytes() {
+        assert_eq!(
+            FirmwareDialect::FluidNc.realtime_bytes(RealtimeCommand::JogCancel),
+            Some(vec![0x85])
+        );
+        assert!(FirmwareDialect::Smoothieware
+            .realtime_bytes(RealtimeCommand::FeedOverrideReset)
+            .is_none());
+        assert_eq!(
+            FirmwareDialect::Marlin.realtime_bytes(RealtimeCommand::SoftReset),
+            Some(b"M410\n".to_vec())
+        );
+        assert!(FirmwareDialect::Marlin
+            .realtime_bytes(RealtimeCommand::StatusQuery)
+            .is_none());
+    }
+
+    #[test]
+    fn test_settings_syntax() {
+        assert_eq!(
+            FirmwareDialect::GrblHal.setting_command(110, 1000.0).unwrap(),
+            "$110=1000.000"
+        );
+        assert_eq!(
+            FirmwareDialect::FluidNc.setting_command(121, 200.0).unwrap(),
+            "$/axes/y/acceleration_mm_per_sec2=200.000"
+        );
+        assert_eq!(
+            FirmwareDialect::Marlin.setting_command(111, 6000.0).unwrap(),
+            "M203 Y100.000"
+        );
+        assert!(FirmwareDialect::Marlin.setting_command(22, 1.0).is_none());
+        assert!(FirmwareDialect::GrblHal.defines_setting(28));
+        assert!(!FirmwareDialect::Grbl.defines_setting(28));
+        assert!(FirmwareDialect::Marlin.defines_setting(110));
+        assert!(!FirmwareDialect::Marlin.defines_setting(22));
+        assert_eq!(FirmwareDialect::Marlin.persist_settings_command(), Some("M500"));
+
+        let values = FirmwareDialect::Marlin.parse_setting_line("echo:  M203 X300.00 Y300.00 Z5.00 E25.00");
+        assert_eq!(values, vec![(110, 18000.0), (111, 18000.0), (112, 300.0)]);
+        assert_eq!(FirmwareDialect::Grbl.parse_setting_line("$22=1"), vec![(22, 1.0)]);
+    }
+
+    #[test]
+    fn test_supported_gcodes() {
+        assert!(!FirmwareDialect::Grbl.supports_gcode("G81"));
+        assert!(FirmwareDialect::GrblHal.supports_gcode("G81"));
+        assert!(FirmwareDialect::FluidNc.supports_gcode("m6"));
+        assert!(!FirmwareDialect::Marlin.supports_gcode("G10"));
+        assert!(FirmwareDialect::Marlin.supports_gcode("G1"));
+    }
+}
@@ -1,22 +1,61 @@
 //! GRBL Protocol Communication Module
 //!
-//! Handles GRBL firmware communication including serial port management,
+//! Handles GRBL firmware communication including serial and network transports,
 //! command sending, response parsing, version detection, and real-time status monitoring.
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::TcpStream;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, oneshot, Mutex, RwLock};
+use tokio::task::JoinHandle;
 use tokio::time::sleep;
 
-mod grbl;
+use crate::jobs::{Job, ResumeOptions};
+use events::{UnderrunDetector, EVENT_CHANNEL_CAPACITY};
+use offsets::ProbeResult;
+
+/// How long to wait for a startup banner after reconnecting
+const BANNER_WAIT: Duration = Duration::from_secs(1);
+
+mod errors;
+pub mod check_mode;
+pub mod discovery;
+pub mod events;
+pub mod firmware;
+pub mod offsets;
+pub mod parser_state;
+pub mod probing;
 mod serial;
-pub use grbl::*;
+mod tcp;
+mod websocket;
+pub mod realtime;
+pub mod settings;
+pub mod simulator;
+pub mod status;
+pub mod streaming;
+pub mod supervisor;
+pub mod trace;
+pub mod transport;
+pub use discovery::PortInfo;
+pub use events::MachineEvent;
+pub use errors::{GrblAlarm, GrblError};
+#[allow(deprecated)]
+pub use firmware::{FirmwareDialect, FirmwareInfo, VersionInfo};
+pub use offsets::{Axis, CoordinateSystem, WorkOffsets};
+pub use parser_state::ParserState;
+pub use realtime::RealtimeCommand;
 pub use serial::{SerialConfig, SerialConnection};
+pub use settings::{GrblSettings, SettingDiff};
+pub use simulator::SimulatedGrbl;
+pub use tcp::TcpConnection;
+pub use transport::{Transport, TransportKind};
+pub use websocket::WebSocketConnection;
+pub use status::{AccessoryState, BufferState, OverrideValues, PinState, StatusReport};
+pub use streaming::{CharacterCounter, LineAck, QueuedLine, StreamingConfig, StreamingMode};
+pub use supervisor::{ConnectionEvent, RestorePoint};
+pub use trace::{ReplayTransport, TraceRecord, TraceRecorder};
 
 /// GRBL machine state enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash, Default)]
@@ -75,25 +114,114 @@ pub struct Position {
 }
 
 /// GRBL status response
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct GrblStatus {
     pub state: MachineState,
+    pub sub_state: Option<u8>,
     pub mpos: Position,
     pub wpos: Position,
+    pub wco: Position,
     pub feed_rate: u32,
     pub spindle_speed: u32,
+    pub buffer: Option<BufferState>,
+    pub overrides: OverrideValues,
+    pub pins: PinState,
+    pub line_number: Option<u32>,
+    pub accessories: AccessoryState,
+    /// Most recent alarm, cleared once the machine leaves the Alarm state
+    pub alarm: Option<GrblAlarm>,
     pub version: String,
     pub connected: bool,
 }
 
+impl GrblStatus {
+    /// Merge a parsed status report into this status
+    ///
+    /// GRBL reports either MPos or WPos depending on `$10`; the other is derived
+    /// from the last known work coordinate offset.
+    pub fn apply_report(&mut self, report: &StatusReport) {
+        self.state = report.state;
+        self.sub_state = report.sub_state;
+        if self.state != MachineState::Alarm {
+            self.alarm = None;
+        }
+
+        if let Some(wco) = report.wco {
+            self.wco = wco;
+        }
+
+        if let Some(mpos) = report.mpos {
+            self.mpos = mpos;
+            self.wpos = Position {
+                x: mpos.x - self.wco.x,
+                y: mpos.y - self.wco.y,
+                z: mpos.z - self.wco.z,
+            };
+        } else if let Some(wpos) = report.wpos {
+            self.wpos = wpos;
+            self.mpos = Position {
+                x: wpos.x + self.wco.x,
+                y: wpos.y + self.wco.y,
+                z: wpos.z + self.wco.z,
+            };
+        }
+
+        if let Some(feed_rate) = report.feed_rate {
+            self.feed_rate = feed_rate.round() as u32;
+        }
+        if let Some(spindle_speed) = report.spindle_speed {
+            self.spindle_speed = spindle_speed.round() as u32;
+        }
+
+        self.buffer = report.buffer;
+        self.line_number = report.line_number;
+
+        // Pins are only listed while triggered
+        self.pins = report.pins.unwrap_or_default();
+
+        // Accessory state is sent together with overrides; absent means all off
+        if let Some(overrides) = report.overrides {
+            self.overrides = overrides;
+            self.accessories = report.accessories.unwrap_or_default();
+        }
+    }
+}
+
 /// Response type from GRBL device
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GrblResponse {
     Ok,
-    Error(String),
+    Error(GrblError),
+    Alarm(GrblAlarm),
     Status(GrblStatus),
     Version(String),
     Settings(String),
+    Message(String),
+}
+
+impl GrblResponse {
+    /// Classify a single line received from the device
+    pub fn parse(line: &str) -> Self {
+        let line = line.trim();
+        // Marlin may append details to its acknowledgements (`ok T:21.3`)
+        if line.eq_ignore_ascii_case("ok") || line.starts_with("ok ") {
+            GrblResponse::Ok
+        } else if let Some(error) = GrblError::parse(line) {
+            GrblResponse::Error(error)
+        } else if let Some(alarm) = GrblAlarm::parse(line) {
+            GrblResponse::Alarm(alarm)
+        } else if FirmwareInfo::detect(line).is_some() {
+            GrblResponse::Version(line.to_string())
+        } else if let Some(report) = StatusReport::parse(line) {
+            let mut status = GrblStatus::default();
+            status.apply_report(&report);
+            GrblResponse::Status(status)
+        } else if line.starts_with('$') && line.contains('=') {
+            GrblResponse::Settings(line.to_string())
+        } else {
+            GrblResponse::Message(line.to_string())
+        }
+    }
 }
 
 /// Error recovery configuration
@@ -118,36 +246,57 @@ impl Default for RecoveryConfig {
 
 /// GRBL Controller for managing device communication
 pub struct GrblController {
-    serial: Arc<SerialConnection>,
+    transport: Arc<RwLock<Arc<dyn Transport>>>,
+    serial_config: Arc<Mutex<SerialConfig>>,
     port: Arc<Mutex<Option<String>>>,
     version: Arc<Mutex<String>>,
     status: Arc<Mutex<GrblStatus>>,
     recovery_config: Arc<Mutex<RecoveryConfig>>,
-    pub command_queue: Arc<Mutex<VecDeque<String>>>,
+    pub command_queue: Arc<Mutex<VecDeque<QueuedLine>>>,
     response_log: Arc<Mutex<VecDeque<String>>>,
+    /// Timestamped record of all traffic with the device
+    trace: Arc<Mutex<TraceRecorder>>,
+    streaming_config: Arc<Mutex<StreamingConfig>>,
+    counter: Arc<Mutex<CharacterCounter>>,
+    rx_buffer: Arc<Mutex<String>>,
+    status_tx: broadcast::Sender<GrblStatus>,
+    event_tx: broadcast::Sender<MachineEvent>,
+    underrun: Arc<Mutex<UnderrunDetector>>,
+    poll_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// Informational lines collected for the running `query`
+    capture: Arc<Mutex<Option<Vec<String>>>>,
+    parser_state: Arc<Mutex<ParserState>>,
+    offsets: Arc<Mutex<WorkOffsets>>,
+    /// Set when an acknowledged line may have changed the offsets
+    offsets_stale: Arc<Mutex<bool>>,
+    firmware: Arc<Mutex<FirmwareInfo>>,
+    connection_tx: broadcast::Sender<ConnectionEvent>,
+    supervisor_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// State captured when the link dropped, until it has been replayed
+    restore_point: Arc<Mutex<Option<RestorePoint>>>,
+    /// Set when the controller reset on reconnect, cleared by homing
+    position_lost: Arc<Mutex<bool>>,
 }
 
 impl GrblController {
     /// Create a new GRBL controller
     pub fn new() -> Self {
-        GrblController {
-            serial: Arc::new(SerialConnection::default_config()),
-            port: Arc::new(Mutex::new(None)),
-            version: Arc::new(Mutex::new(String::new())),
-            status: Arc::new(Mutex::new(GrblStatus {
-                connected: false,
-                ..Default::default()
-            })),
-            recovery_config: Arc::new(Mutex::new(RecoveryConfig::default())),
-            command_queue: Arc::new(Mutex::new(VecDeque::new())),
-            response_log: Arc::new(Mutex::new(VecDeque::new())),
-        }
+        Self::with_config(SerialConfig::default())
     }
 
     /// Create with custom serial configuration
     pub fn with_config(config: SerialConfig) -> Self {
+        let serial: Arc<dyn Transport> = Arc::new(SerialConnection::new(config.clone()));
+        let mut controller = Self::with_transport(serial);
+        controller.serial_config = Arc::new(Mutex::new(config));
+        controller
+    }
+
+    /// Create with a specific transport (serial, TCP or a test double)
+    pub fn with_transport(transport: Arc<dyn Transport>) -> Self {
         GrblController {
-            serial: Arc::new(SerialConnection::new(config)),
+            transport: Arc::new(RwLock::new(transport)),
+            serial_config: Arc::new(Mutex::new(SerialConfig::default())),
             port: Arc::new(Mutex::new(None)),
             version: Arc::new(Mutex::new(String::new())),
             status: Arc::new(Mutex::new(GrblStatus {
@@ -157,11 +306,98 @@ impl GrblController {
             recovery_config: Arc::new(Mutex::new(RecoveryConfig::default())),
             command_queue: Arc::new(Mutex::new(VecDeque::new())),
             response_log: Arc::new(Mutex::new(VecDeque::new())),
+            trace: Arc::new(Mutex::new(TraceRecorder::default())),
+            streaming_config: Arc::new(Mutex::new(StreamingConfig::default())),
+            counter: Arc::new(Mutex::new(CharacterCounter::default())),
+            rx_buffer: Arc::new(Mutex::new(String::new())),
+            status_tx: broadcast::channel(64).0,
+            event_tx: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            underrun: Arc::new(Mutex::new(UnderrunDetector::default())),
+            poll_task: Arc::new(Mutex::new(None)),
+            capture: Arc::new(Mutex::new(None)),
+            parser_state: Arc::new(Mutex::new(ParserState::default())),
+            offsets: Arc::new(Mutex::new(WorkOffsets::default())),
+            offsets_stale: Arc::new(Mutex::new(true)),
+            firmware: Arc::new(Mutex::new(FirmwareInfo::default())),
+            connection_tx: broadcast::channel(16).0,
+            supervisor_task: Arc::new(Mutex::new(None)),
+            restore_point: Arc::new(Mutex::new(None)),
+            position_lost: Arc::new(Mutex::new(false)),
+        }
+    }
+
+    /// Get the active transport
+    pub async fn transport(&self) -> Arc<dyn Transport> {
+        let transport = self.transport.read().await;
+        Arc::clone(&transport)
+    }
+
+    /// Get the kind of link currently in use
+    pub async fn transport_kind(&self) -> TransportKind {
+        self.transport().await.kind()
+    }
+
+    /// Change the baud rate used for serial connections
+    ///
+    /// A connected serial link is closed first, and the serial transport is
+    /// replaced so the new rate takes effect on the next `connect`.
+    pub async fn set_baud_rate(&self, baud_rate: u32) -> Result<()> {
+        if self.serial_config.lock().await.baud_rate == baud_rate {
+            return Ok(());
+        }
+
+        let current = self.transport().await;
+        let serial = current.kind() == TransportKind::Serial;
+        if serial && current.is_connected().await {
+            self.disconnect().await?;
+        }
+
+        let config = {
+            let mut config = self.serial_config.lock().await;
+            config.baud_rate = baud_rate;
+            config.clone()
+        };
+        if serial {
+            let mut slot = self.transport.write().await;
+            *slot = Arc::new(SerialConnection::new(config));
+        }
+        Ok(())
+    }
+
+    /// Pick the transport for an address, replacing the current one if the
+    /// address needs a different kind of link
+    async fn transport_for(&self, address: &str) -> Result<Arc<dyn Transport>> {
+        let kind = TransportKind::from_address(address);
+        let current = self.transport().await;
+        if current.kind() == kind {
+            return Ok(current);
+        }
+
+        if current.is_connected().await {
+            current.disconnect().await?;
         }
+
+        let transport: Arc<dyn Transport> = match kind {
+            TransportKind::Serial => {
+                Arc::new(SerialConnection::new(self.serial_config.lock().await.clone()))
+            }
+            TransportKind::Tcp => Arc::new(TcpConnection::new()),
+            TransportKind::WebSocket => Arc::new(WebSocketConnection::new()),
+            TransportKind::Simulator => Arc::new(SimulatedGrbl::new()),
+            TransportKind::Replay => Arc::new(ReplayTransport::new()),
+        };
+        let mut slot = self.transport.write().await;
+        *slot = Arc::clone(&transport);
+        Ok(transport)
     }
 
     /// Connect to a GRBL device on the specified port
+    ///
+    /// Accepts a serial port name (`/dev/ttyUSB0`, `COM3`) or a network
+    /// address such as `tcp://192.168.1.50:23` or `ws://fluidnc.local:81`;
+    /// `sim://grbl` connects to the built-in simulator.
     pub async fn connect(&self, port_name: &str) -> Result<()> {
+        let transport = self.transport_for(port_name).await?;
 
         // Attempt to connect with retries
         let config = self.recovery_config.lock().await;
@@ -169,14 +405,19 @@ impl GrblController {
         let max_attempts = config.max_retries as usize;
 
         loop {
-            match self.serial.connect(port_name).await {
+            match transport.connect(port_name).await {
                 Ok(_) => {
                     let mut port = self.port.lock().await;
                     *port = Some(port_name.to_string());
 
+                    // The banner identifies the firmware again
+                    *self.firmware.lock().await = FirmwareInfo::default();
+
                     let mut status = self.status.lock().await;
+                    let previous = status.clone();
                     status.connected = true;
                     status.state = MachineState::Idle;
+                    self.publish_status(&previous, &status);
 
                     return Ok(());
                 }
@@ -193,68 +434,568 @@ impl GrblController {
 
     /// Disconnect from the device
     pub async fn disconnect(&self) -> Result<()> {
-        self.serial.disconnect().await?;
+        self.stop_status_polling().await;
+        self.transport().await.disconnect().await?;
 
         let mut port = self.port.lock().await;
         *port = None;
 
         let mut status = self.status.lock().await;
+        let previous = status.clone();
         status.connected = false;
+        self.publish_status(&previous, &status);
+        drop(status);
+
+        self.counter.lock().await.reset();
+        self.rx_buffer.lock().await.clear();
 
         Ok(())
     }
 
-    /// Detect GRBL firmware version
-    pub async fn detect_version(&self) -> Result<String> {
+    /// Identify the controller firmware and version
+    ///
+    /// Sends the dialect's identification commands (`$I`, `M115`, `version`)
+    /// until one is recognised. Dialects without a real-time status query are
+    /// switched to automatic position reports.
+    pub async fn detect_version(&self) -> Result<FirmwareInfo> {
+        let dialect = self.get_firmware().await.dialect;
+        for command in dialect.identify_commands() {
+            let lines = match self.query(command).await {
+                Ok(lines) => lines,
+                Err(e) => {
+                    tracing::debug!("Firmware identification with {} failed: {}", command, e);
+                    continue;
+                }
+            };
+            if FirmwareInfo::from_lines(lines.iter().map(String::as_str)).is_none() {
+                continue;
+            }
+
+            // The tracked info merges the banner with the identification lines
+            let info = self.get_firmware().await;
+            if let Some(command) = info.dialect.auto_report_command() {
+                self.send_command(command).await?;
+            }
+            return Ok(info);
+        }
+        Err(anyhow!("Could not identify the controller firmware"))
+    }
+
+    /// Get the detected firmware
+    pub async fn get_firmware(&self) -> FirmwareInfo {
+        let firmware = self.firmware.lock().await;
+        firmware.clone()
+    }
 
-        // Send version request
-        self.serial.send_command("$I").await?;
+    /// Override the detected firmware, e.g. when the user picks the dialect
+    pub async fn set_firmware(&self, info: FirmwareInfo) {
+        let version = info.describe();
+        *self.firmware.lock().await = info;
+        *self.version.lock().await = version.clone();
 
-        // Read response
-        match self.serial.read_response_timeout(256, Duration::from_secs(2)).await {
-            Ok(response) => {
-                let version = response.trim().to_string();
-                let mut ver = self.version.lock().await;
-                *ver = version.clone();
+        let mut status = self.status.lock().await;
+        let previous = status.clone();
+        status.version = version;
+        self.publish_status(&previous, &status);
+    }
 
-                let mut status = self.status.lock().await;
-                status.version = version.clone();
+    /// Send a command to GRBL and wait for its acknowledgement
+    ///
+    /// Waits up to the streaming response timeout; see `send_command_timeout`.
+    ///
+    /// # Returns
+    /// The `ok` or decoded `error:N` response for the command
+    pub async fn send_command(&self, command: &str) -> Result<GrblResponse> {
+        let timeout = self.streaming_config.lock().await.response_timeout();
+        self.send_command_timeout(command, timeout).await
+    }
 
-                Ok(version)
+    /// Send a command to GRBL and wait up to `timeout` for its acknowledgement
+    ///
+    /// The command bypasses the command queue: it is sent as soon as it fits
+    /// in the RX buffer, between the lines of a running stream. Its response
+    /// is matched by sequence number, so neither a stream nor another caller
+    /// can take it.
+    ///
+    /// # Returns
+    /// The `ok` or decoded `error:N` response for the command, or an error if
+    /// none arrived in time. A command that timed out stays in flight, so its
+    /// late response is not matched to the next line.
+    pub async fn send_command_timeout(
+        &self,
+        command: &str,
+        timeout: Duration,
+    ) -> Result<GrblResponse> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut ack = loop {
+            {
+                let mut counter = self.counter.lock().await;
+                if counter.can_send(command) {
+                    // Send while holding the counter, so lines reach the device
+                    // in the order they were registered
+                    let (_, ack) = counter.register_awaited(command);
+                    self.trace.lock().await.record_tx(command);
+                    if let Err(e) = self.transport().await.send_command(command).await {
+                        counter.reset();
+                        return Err(e);
+                    }
+                    break ack;
+                }
             }
-            Err(e) => {
-                Err(e)
+            self.await_device(command, deadline, timeout).await?;
+        };
+
+        loop {
+            match ack.try_recv() {
+                Ok(ack) => return Ok(ack.response),
+                Err(oneshot::error::TryRecvError::Closed) => {
+                    return Err(anyhow!(
+                        "{} was dropped by a reset before it was acknowledged",
+                        command
+                    ));
+                }
+                Err(oneshot::error::TryRecvError::Empty) => {}
             }
+            self.await_device(command, deadline, timeout).await?;
         }
     }
 
-    /// Send a command to GRBL
-    pub async fn send_command(&self, command: &str) -> Result<()> {
-        // Queue the command
+    /// Read responses once while `send_command_timeout` waits
+    async fn await_device(
+        &self,
+        command: &str,
+        deadline: tokio::time::Instant,
+        timeout: Duration,
+    ) -> Result<()> {
+        if !self.transport().await.is_connected().await {
+            return Err(anyhow!("Connection lost waiting for {}", command));
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(anyhow!(
+                "No response to {} within {} ms",
+                command,
+                timeout.as_millis()
+            ));
+        }
+        self.poll_responses(Duration::from_millis(100)).await;
+        Ok(())
+    }
+
+    /// Add a command to the streaming queue without sending it
+    pub async fn queue_command(&self, command: &str) {
         let mut queue = self.command_queue.lock().await;
-        queue.push_back(command.to_string());
+        queue.push_back(QueuedLine::new(command));
+    }
+
+    /// Queue every line of a G-code program and stream it to the device
+    ///
+    /// # Returns
+    /// Acknowledgements for each line in the order they were received
+    pub async fn stream_program(&self, gcode: &str) -> Result<Vec<LineAck>> {
+        {
+            let mut queue = self.command_queue.lock().await;
+            queue.extend(
+                gcode
+                    .lines()
+                    .filter_map(streaming::prepare_line)
+                    .map(QueuedLine::new),
+            );
+        }
+        self.stream_queue().await
+    }
+
+    /// Stream a job's program from its `current_line`
+    ///
+    /// Each line is tagged with its index in the program, so its
+    /// acknowledgement advances the job in `JobManager::handle_event`.
+    ///
+    /// # Returns
+    /// Acknowledgements for each line in the order they were received
+    pub async fn stream_job(&self, job: &Job) -> Result<Vec<LineAck>> {
+        self.command_queue
+            .lock()
+            .await
+            .extend(job.queued_lines(job.current_line));
+        self.stream_queue().await
+    }
+
+    /// Stream the command queue until it is empty and every line is acknowledged
+    ///
+    /// Lines are sent as long as they fit in GRBL's RX buffer (or one at a time in
+    /// send-response mode), and each `ok`/`error:N` is matched to its line.
+    pub async fn stream_queue(&self) -> Result<Vec<LineAck>> {
+        self.stream_queue_until(false).await
+    }
+
+    /// Stream the command queue, optionally giving up when the machine alarms
+    ///
+    /// GRBL never acknowledges the line that raised an alarm, so with
+    /// `stop_on_alarm` the queue is dropped and the acknowledgements received
+    /// so far are returned instead of waiting for the response timeout.
+    async fn stream_queue_until(&self, stop_on_alarm: bool) -> Result<Vec<LineAck>> {
+        let timeout = self.streaming_config.lock().await.response_timeout();
+        let mut acks = Vec::new();
+        let mut last_activity = std::time::Instant::now();
+
+        loop {
+            // Fill the RX buffer with as many queued lines as fit
+            loop {
+                let mut queue = self.command_queue.lock().await;
+                let mut counter = self.counter.lock().await;
+                match queue.front() {
+                    Some(next) if counter.can_send(&next.command) => {}
+                    _ => break,
+                }
+                let Some(line) = queue.pop_front() else {
+                    break;
+                };
+                drop(queue);
+
+                // Send while holding the counter, so lines reach the device in
+                // the order they were registered
+                counter.register_line(&line);
+                self.trace.lock().await.record_tx(&line.command);
+                if let Err(e) = self.transport().await.send_command(&line.command).await {
+                    counter.reset();
+                    return Err(e);
+                }
+            }
+
+            let (completed, idle) = {
+                let mut counter = self.counter.lock().await;
+                (counter.take_completed(), counter.is_idle())
+            };
+            if !completed.is_empty() {
+                last_activity = std::time::Instant::now();
+                acks.extend(completed);
+            }
+
+            if idle && self.command_queue.lock().await.is_empty() {
+                return Ok(acks);
+            }
+
+            if stop_on_alarm && self.status.lock().await.state == MachineState::Alarm {
+                self.command_queue.lock().await.clear();
+                self.counter.lock().await.reset();
+                return Ok(acks);
+            }
+
+            if !self.transport().await.is_connected().await {
+                self.counter.lock().await.reset();
+                return Err(anyhow!("Connection lost while streaming"));
+            }
+
+            if last_activity.elapsed() > timeout {
+                self.counter.lock().await.reset();
+                return Err(anyhow!(
+                    "No response from device within {} ms",
+                    timeout.as_millis()
+                ));
+            }
+
+            self.poll_responses(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Read any complete lines from the device and dispatch them
+    ///
+    /// # Returns
+    /// Acknowledgements matched during this poll
+    pub async fn poll_responses(&self, timeout: Duration) -> Vec<LineAck> {
+        self.read_lines(timeout)
+            .await
+            .into_iter()
+            .filter_map(|(_, ack)| ack)
+            .collect()
+    }
+
+    /// Read a chunk from the device, split it into complete lines and
+    /// dispatch them
+    ///
+    /// The status poller, the streamer and connection recovery all read from
+    /// the device, so the receive buffer stays locked from the read until the
+    /// last line is dispatched. Chunks are appended in the order they arrived
+    /// and lines are handled in that order.
+    ///
+    /// # Returns
+    /// Each complete line with the acknowledgement it matched, if any
+    async fn read_lines(&self, timeout: Duration) -> Vec<(String, Option<LineAck>)> {
+        let mut buffer = self.rx_buffer.lock().await;
+        let chunk = match self.transport().await.read_response_timeout(256, timeout).await {
+            Ok(chunk) => chunk,
+            Err(_) => return Vec::new(),
+        };
+        buffer.push_str(&chunk);
+
+        let mut lines = Vec::new();
+        while let Some(idx) = buffer.find('\n') {
+            let line: String = buffer.drain(..=idx).collect();
+            let line = line.trim();
+            if !line.is_empty() {
+                lines.push(line.to_string());
+            }
+        }
+        if !lines.is_empty() {
+            let mut trace = self.trace.lock().await;
+            for line in &lines {
+                trace.record_rx(line);
+            }
+        }
+
+        let mut received = Vec::with_capacity(lines.len());
+        for line in lines {
+            let ack = self.dispatch_line(&line).await;
+            received.push((line, ack));
+        }
+        received
+    }
+
+    /// Handle a single line received from the device
+    ///
+    /// Logs the line and matches acknowledgements to the oldest line in flight.
+    pub async fn dispatch_line(&self, line: &str) -> Option<LineAck> {
+        let dialect = self.get_firmware().await.dialect;
+        if let Some(mut report) = dialect.parse_status(line) {
+            tracing::trace!("Status report: {}", line);
+            if dialect == FirmwareDialect::Marlin {
+                // Marlin position reports carry no state
+                report.state = if self.lines_in_flight().await > 0 {
+                    MachineState::Run
+                } else {
+                    MachineState::Idle
+                };
+            }
+            self.apply_status_report(&report).await;
+            return None;
+        }
+
+        let response = GrblResponse::parse(line);
+        match &response {
+            GrblResponse::Error(error) => self.log_response(error.to_string()).await,
+            GrblResponse::Alarm(alarm) => {
+                self.log_response(alarm.to_string()).await;
+
+                let mut status = self.status.lock().await;
+                let previous = status.clone();
+                status.state = MachineState::Alarm;
+                status.alarm = Some(*alarm);
+                self.publish_status(&previous, &status);
+            }
+            GrblResponse::Ok => self.log_response(line.to_string()).await,
+            _ => {
+                self.log_response(line.to_string()).await;
+                self.track_report_line(line).await;
+                if let Some(lines) = self.capture.lock().await.as_mut() {
+                    lines.push(line.to_string());
+                }
+            }
+        }
+
+        let ack = self.counter.lock().await.acknowledge(&response);
+        if let Some(ack) = &ack {
+            self.publish_event(MachineEvent::LineAcknowledged { ack: ack.clone() });
+        }
+        if let Some(ack) = ack.as_ref().filter(|ack| ack.is_ok()) {
+            self.parser_state.lock().await.apply_line(&ack.command);
+            if WorkOffsets::affected_by(&ack.command) {
+                *self.offsets_stale.lock().await = true;
+            }
+            if ack.command.trim().to_ascii_uppercase().starts_with("$H") {
+                *self.position_lost.lock().await = false;
+            }
+        }
+        ack
+    }
+
+    /// Update cached parser state, offsets and firmware from `$G`/`$#`/`$I`
+    /// output and the welcome banner
+    async fn track_report_line(&self, line: &str) {
+        if let Some(state) = ParserState::parse(line) {
+            *self.parser_state.lock().await = state;
+        } else if let Some(probe) = ProbeResult::parse(line) {
+            self.offsets.lock().await.probe = Some(probe);
+            if probe.success {
+                self.publish_event(MachineEvent::ProbeTriggered { probe });
+            }
+        } else if !self.offsets.lock().await.apply_line(line) {
+            let mut firmware = self.firmware.lock().await;
+            if firmware.apply_line(line) {
+                let version = firmware.describe();
+                drop(firmware);
+                *self.version.lock().await = version.clone();
+                self.status.lock().await.version = version;
+            }
+        }
+    }
+
+    /// Get the last known parser state
+    pub async fn get_parser_state(&self) -> ParserState {
+        let state = self.parser_state.lock().await;
+        state.clone()
+    }
+
+    /// Query `$G` and return the parser state
+    pub async fn refresh_parser_state(&self) -> Result<ParserState> {
+        self.query("$G").await?;
+        Ok(self.get_parser_state().await)
+    }
+
+    /// Get the last known work coordinate offsets
+    pub async fn get_offsets(&self) -> WorkOffsets {
+        let offsets = self.offsets.lock().await;
+        offsets.clone()
+    }
+
+    /// Check if a command may have changed the offsets since the last `$#`
+    pub async fn offsets_stale(&self) -> bool {
+        *self.offsets_stale.lock().await
+    }
+
+    /// Query `$#` and return the work coordinate offsets
+    pub async fn refresh_offsets(&self) -> Result<WorkOffsets> {
+        self.query("$#").await?;
+        *self.offsets_stale.lock().await = false;
+        Ok(self.get_offsets().await)
+    }
+
+    /// Set the work position of the given axes in a coordinate system (`G10 L20`)
+    ///
+    /// # Arguments
+    /// * `wcs` - Coordinate system to change (need not be the active one)
+    /// * `values` - Work coordinate each axis should read at the current position
+    ///
+    /// # Returns
+    /// The offsets after the change
+    pub async fn set_work_position(
+        &self,
+        wcs: CoordinateSystem,
+        values: &[(Axis, f64)],
+    ) -> Result<WorkOffsets> {
+        if values.is_empty() {
+            return Err(anyhow!("No axes given"));
+        }
+        let command = offsets::set_work_position_command(wcs, values);
+        if let GrblResponse::Error(error) = self.send_command(&command).await? {
+            return Err(anyhow!("{} rejected: {} {}", command, error, error.recovery()));
+        }
+        self.refresh_offsets().await
+    }
+
+    /// Make the current position the work zero of the given axes
+    pub async fn set_work_zero(&self, wcs: CoordinateSystem, axes: &[Axis]) -> Result<WorkOffsets> {
+        let values: Vec<(Axis, f64)> = axes.iter().map(|axis| (*axis, 0.0)).collect();
+        self.set_work_position(wcs, &values).await
+    }
+
+    /// Make a coordinate system active (`G54`-`G59`)
+    pub async fn select_coordinate_system(&self, wcs: CoordinateSystem) -> Result<()> {
+        match self.send_command(wcs.code()).await? {
+            GrblResponse::Error(error) => Err(anyhow!("{}: {}", wcs.code(), error)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Send a command and collect the lines it prints before its `ok`
+    ///
+    /// Used for `$$`, `$#`, `$G` and `$I`. Only one query should run at a time.
+    ///
+    /// # Returns
+    /// The informational lines, or the decoded error if GRBL rejected the command
+    pub async fn query(&self, command: &str) -> Result<Vec<String>> {
+        *self.capture.lock().await = Some(Vec::new());
+        let response = self.send_command(command).await;
+        let lines = self.capture.lock().await.take().unwrap_or_default();
+
+        match response? {
+            GrblResponse::Error(error) => Err(anyhow!("{}: {}", command, error)),
+            _ => Ok(lines),
+        }
+    }
+
+    /// Read all settings from the device
+    ///
+    /// Marlin and Smoothieware settings listed by `M503` are returned under
+    /// their GRBL numbers.
+    pub async fn read_settings(&self) -> Result<GrblSettings> {
+        let dialect = self.get_firmware().await.dialect;
+        let lines = self.query(dialect.settings_query()).await?;
+        Ok(lines
+            .iter()
+            .flat_map(|line| dialect.parse_setting_line(line))
+            .collect())
+    }
 
-        // Send to device
-        self.serial.send_command(command).await?;
+    /// Write the settings that differ between `original` and `edited`
+    ///
+    /// Every changed value is validated and translated to the firmware's
+    /// syntax before anything is sent. Writing stops at the first value the
+    /// controller rejects.
+    ///
+    /// # Returns
+    /// The commands that were written
+    pub async fn write_settings(
+        &self,
+        original: &GrblSettings,
+        edited: &GrblSettings,
+    ) -> Result<Vec<String>> {
+        let dialect = self.get_firmware().await.dialect;
+        if let Some(error) = edited.validate_changes(original, dialect).into_iter().next() {
+            return Err(anyhow!("{}", error));
+        }
 
-        // Try to read response
-        match self.serial.read_response_timeout(256, Duration::from_secs(1)).await {
-            Ok(response) => {
-                self.log_response(response).await;
-                Ok(())
+        let mut commands = Vec::new();
+        for number in edited.write_order(original) {
+            let value = edited.get(number).unwrap_or_default();
+            let command = dialect
+                .setting_command(number, value)
+                .ok_or_else(|| anyhow!("${} cannot be set on {}", number, dialect))?;
+            // Shared settings map to the same command; write it once
+            if !commands.contains(&command) {
+                commands.push(command);
             }
-            Err(e) => {
-                // Command was sent, but we might not get immediate response
-                // This is not necessarily an error
-                Ok(())
+        }
+        if let Some(persist) = dialect.persist_settings_command() {
+            if !commands.is_empty() {
+                commands.push(persist.to_string());
             }
         }
+
+        for command in &commands {
+            if let GrblResponse::Error(error) = self.send_command(command).await? {
+                return Err(anyhow!("{} rejected: {} {}", command, error, error.recovery()));
+            }
+        }
+        Ok(commands)
+    }
+
+    /// Compare the live machine settings against a saved snapshot
+    pub async fn diff_settings(&self, snapshot: &GrblSettings) -> Result<Vec<SettingDiff>> {
+        let live = self.read_settings().await?;
+        Ok(live.diff(snapshot))
+    }
+
+    /// Set streaming configuration
+    pub async fn set_streaming_config(&self, config: StreamingConfig) {
+        self.counter.lock().await.configure(&config);
+        let mut cfg = self.streaming_config.lock().await;
+        *cfg = config;
+    }
+
+    /// Get streaming configuration
+    pub async fn get_streaming_config(&self) -> StreamingConfig {
+        let cfg = self.streaming_config.lock().await;
+        cfg.clone()
+    }
+
+    /// Get number of lines sent but not yet acknowledged
+    pub async fn lines_in_flight(&self) -> usize {
+        self.counter.lock().await.in_flight_count()
     }
 
     /// Get the next queued command
     pub async fn get_next_command(&self) -> Option<String> {
         let mut queue = self.command_queue.lock().await;
-        queue.pop_front()
+        queue.pop_front().map(|line| line.command)
     }
 
     /// Get current status from GRBL
@@ -273,11 +1014,358 @@ impl GrblController {
         spindle_speed: u32,
     ) {
         let mut status = self.status.lock().await;
+        let previous = status.clone();
         status.state = state;
         status.mpos = mpos;
         status.wpos = wpos;
         status.feed_rate = feed_rate;
         status.spindle_speed = spindle_speed;
+        self.publish_status(&previous, &status);
+    }
+
+    /// Merge a parsed status report into the current status
+    ///
+    /// Subscribers are only notified when the status actually changed.
+    pub async fn apply_status_report(&self, report: &StatusReport) {
+        let mut status = self.status.lock().await;
+        let previous = status.clone();
+        status.apply_report(report);
+        if *status != previous {
+            self.publish_status(&previous, &status);
+        }
+        drop(status);
+
+        if let Some(buffer) = report.buffer {
+            let queued_lines = self.command_queue.lock().await.len();
+            if self.underrun.lock().await.observe(report.state, buffer, queued_lines) {
+                self.publish_event(MachineEvent::BufferUnderrun { queued_lines });
+            }
+        }
+    }
+
+    /// Subscribe to status changes
+    pub fn subscribe_status(&self) -> broadcast::Receiver<GrblStatus> {
+        self.status_tx.subscribe()
+    }
+
+    /// Subscribe to machine events
+    pub fn subscribe_events(&self) -> broadcast::Receiver<MachineEvent> {
+        self.event_tx.subscribe()
+    }
+
+    /// Publish a status update and the events it implies
+    fn publish_status(&self, previous: &GrblStatus, status: &GrblStatus) {
+        let _ = self.status_tx.send(status.clone());
+        for event in events::status_events(previous, status) {
+            self.publish_event(event);
+        }
+    }
+
+    /// Publish a machine event to every subscriber
+    fn publish_event(&self, event: MachineEvent) {
+        let _ = self.event_tx.send(event);
+    }
+
+    /// Start a background task that polls the device with `?`
+    ///
+    /// Any previously running poll task is stopped first. Reports are parsed
+    /// and published to status subscribers.
+    pub async fn start_status_polling(self: &Arc<Self>, interval: Duration) {
+        self.stop_status_polling().await;
+
+        let controller = Arc::clone(self);
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                if !controller.transport().await.is_connected().await {
+                    continue;
+                }
+                // Dialects without a status query report on their own
+                let dialect = controller.get_firmware().await.dialect;
+                if dialect.realtime_bytes(RealtimeCommand::StatusQuery).is_some() {
+                    if let Err(e) = controller.send_realtime(RealtimeCommand::StatusQuery).await {
+                        tracing::warn!("Status poll failed: {}", e);
+                        continue;
+                    }
+                }
+                controller.poll_responses(interval / 2).await;
+            }
+        });
+
+        let mut task = self.poll_task.lock().await;
+        *task = Some(handle);
+    }
+
+    /// Stop the background status polling task
+    pub async fn stop_status_polling(&self) {
+        let mut task = self.poll_task.lock().await;
+        if let Some(handle) = task.take() {
+            handle.abort();
+        }
+    }
+
+    /// Check if the background status polling task is running
+    pub async fn is_polling_status(&self) -> bool {
+        let task = self.poll_task.lock().await;
+        task.as_ref().is_some_and(|h| !h.is_finished())
+    }
+
+    /// Subscribe to connection loss, reconnect and restore events
+    pub fn subscribe_connection(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.connection_tx.subscribe()
+    }
+
+    /// Start a background task that reconnects when the link drops
+    ///
+    /// Every `interval` the task checks the transport. If it closed while a
+    /// connection was wanted (the user did not call `disconnect`), it
+    /// reconnects according to the `RecoveryConfig` and replays the offsets
+    /// and modal state captured before the drop.
+    pub async fn start_supervisor(self: &Arc<Self>, interval: Duration) {
+        self.stop_supervisor().await;
+
+        let controller = Arc::clone(self);
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                let address = match controller.port.lock().await.clone() {
+                    Some(address) => address,
+                    None => continue,
+                };
+                if controller.transport().await.is_connected().await {
+                    continue;
+                }
+                controller.recover_connection(&address).await;
+            }
+        });
+
+        let mut task = self.supervisor_task.lock().await;
+        *task = Some(handle);
+    }
+
+    /// Stop the connection supervisor
+    pub async fn stop_supervisor(&self) {
+        let mut task = self.supervisor_task.lock().await;
+        if let Some(handle) = task.take() {
+            handle.abort();
+        }
+    }
+
+    /// Check if the connection supervisor is running
+    pub async fn is_supervising(&self) -> bool {
+        let task = self.supervisor_task.lock().await;
+        task.as_ref().is_some_and(|h| !h.is_finished())
+    }
+
+    /// Handle a dropped link: reconnect with backoff, then restore state
+    async fn recover_connection(&self, address: &str) {
+        let point = RestorePoint {
+            parser_state: self.get_parser_state().await,
+            offsets: self.get_offsets().await,
+        };
+        *self.restore_point.lock().await = Some(point);
+
+        {
+            let mut status = self.status.lock().await;
+            let previous = status.clone();
+            status.connected = false;
+            status.state = MachineState::Unknown;
+            self.publish_status(&previous, &status);
+        }
+        // Lines in flight and queued were lost with the link
+        self.command_queue.lock().await.clear();
+        self.counter.lock().await.reset();
+        self.rx_buffer.lock().await.clear();
+        tracing::warn!("Connection to {} lost", address);
+        let reason = format!("Connection to {} lost", address);
+        self.publish_event(MachineEvent::ConnectionLost {
+            reason: reason.clone(),
+        });
+        let _ = self.connection_tx.send(ConnectionEvent::Lost { reason });
+
+        let config = self.get_recovery_config().await;
+        if !config.auto_reconnect {
+            *self.port.lock().await = None;
+            return;
+        }
+
+        let transport = self.transport().await;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let delay = supervisor::reconnect_delay(&config, attempt);
+            let _ = self.connection_tx.send(ConnectionEvent::Reconnecting {
+                attempt,
+                delay_ms: delay.as_millis() as u64,
+            });
+            sleep(delay).await;
+
+            // The user may have disconnected or picked another port meanwhile
+            if self.port.lock().await.as_deref() != Some(address) {
+                return;
+            }
+            match transport.connect(address).await {
+                Ok(()) => break,
+                Err(e) => tracing::debug!("Reconnect attempt {} failed: {}", attempt, e),
+            }
+        }
+
+        {
+            let mut status = self.status.lock().await;
+            let previous = status.clone();
+            status.connected = true;
+            self.publish_status(&previous, &status);
+        }
+        let reset = self.await_banner(BANNER_WAIT).await;
+        if reset {
+            *self.position_lost.lock().await = true;
+            *self.parser_state.lock().await = ParserState::default();
+            *self.offsets_stale.lock().await = true;
+        }
+        tracing::info!("Reconnected to {} after {} attempt(s)", address, attempt);
+        let _ = self
+            .connection_tx
+            .send(ConnectionEvent::Reconnected { attempts: attempt, reset });
+
+        let event = match self.restore_state().await {
+            Ok(commands) => ConnectionEvent::Restored { commands },
+            Err(e) => ConnectionEvent::RestoreFailed {
+                reason: e.to_string(),
+            },
+        };
+        let _ = self.connection_tx.send(event);
+    }
+
+    /// Read lines until a startup banner arrives or `timeout` passes
+    ///
+    /// # Returns
+    /// true if the controller printed its banner, i.e. it was reset
+    async fn await_banner(&self, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while tokio::time::Instant::now() < deadline {
+            let mut reset = false;
+            for (line, _) in self.read_lines(Duration::from_millis(100)).await {
+                reset |= matches!(GrblResponse::parse(&line), GrblResponse::Version(_));
+            }
+            if reset {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Ask for a status report and wait for it
+    ///
+    /// # Returns
+    /// None if the dialect has no status query or no report arrived in time
+    async fn request_status_report(&self, timeout: Duration) -> Option<GrblStatus> {
+        let dialect = self.get_firmware().await.dialect;
+        dialect.realtime_bytes(RealtimeCommand::StatusQuery)?;
+        self.send_realtime(RealtimeCommand::StatusQuery).await.ok()?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        while tokio::time::Instant::now() < deadline {
+            let lines = self.read_lines(Duration::from_millis(50)).await;
+            if lines.iter().any(|(line, _)| dialect.parse_status(line).is_some()) {
+                return self.get_status().await.ok();
+            }
+        }
+        None
+    }
+
+    /// Get the state captured at the last connection drop that has not been
+    /// replayed yet
+    pub async fn pending_restore(&self) -> Option<RestorePoint> {
+        let point = self.restore_point.lock().await;
+        point.clone()
+    }
+
+    /// Replay the offsets and modal state captured when the link dropped
+    ///
+    /// Fails while the controller is alarmed (e.g. waiting for homing after
+    /// a reset); call again once it is unlocked.
+    ///
+    /// # Returns
+    /// The commands that were sent
+    pub async fn restore_state(&self) -> Result<Vec<String>> {
+        let point = self
+            .pending_restore()
+            .await
+            .ok_or_else(|| anyhow!("No connection drop to restore from"))?;
+
+        let status = self.request_status_report(Duration::from_secs(1)).await;
+        if status.as_ref().is_some_and(|s| s.state == MachineState::Alarm) {
+            return Err(anyhow!(
+                "Controller is in alarm; home or unlock it, then restore the state"
+            ));
+        }
+
+        let current = if self.get_firmware().await.dialect.is_grbl_compatible() {
+            self.refresh_offsets().await?
+        } else {
+            point.offsets.clone()
+        };
+        let commands = point.restore_commands(&current, status.map(|s| s.mpos));
+        for command in &commands {
+            if let GrblResponse::Error(error) = self.send_command(command).await? {
+                return Err(anyhow!("{} rejected: {}", command, error));
+            }
+        }
+
+        *self.restore_point.lock().await = None;
+        Ok(commands)
+    }
+
+    /// Check if the machine position was lost when the controller reset
+    pub async fn position_lost(&self) -> bool {
+        *self.position_lost.lock().await
+    }
+
+    /// Confirm the machine position is valid again without homing, e.g.
+    /// after re-zeroing on a machine without homing switches
+    pub async fn confirm_position(&self) {
+        *self.position_lost.lock().await = false;
+    }
+
+    /// Resume a job from its `current_line` after a safe retract
+    ///
+    /// Refuses while the position is lost, the machine is alarmed or state
+    /// from a connection drop has not been restored.
+    ///
+    /// # Returns
+    /// Acknowledgements for the preamble and the remaining lines
+    pub async fn resume_job(&self, job: &Job, options: &ResumeOptions) -> Result<Vec<LineAck>> {
+        if self.position_lost().await {
+            return Err(anyhow!(
+                "Machine position was lost when the controller reset; home before resuming"
+            ));
+        }
+        if self.pending_restore().await.is_some() {
+            return Err(anyhow!("Restore the machine state before resuming"));
+        }
+        if self.status.lock().await.state == MachineState::Alarm {
+            return Err(anyhow!("Machine is in alarm"));
+        }
+
+        let plan = job.resume_plan(options);
+        tracing::info!("Resuming {} at line {}", job.name, plan.start_line + 1);
+        {
+            // Only the program lines are tagged, so the preamble does not
+            // advance the job
+            let mut queue = self.command_queue.lock().await;
+            queue.extend(
+                plan.preamble
+                    .iter()
+                    .filter_map(|line| streaming::prepare_line(line))
+                    .map(QueuedLine::new),
+            );
+            queue.extend(job.queued_lines(plan.start_line));
+        }
+        self.stream_queue().await
     }
 
     /// Add response to log
@@ -302,6 +1390,28 @@ impl GrblController {
         log.clear();
     }
 
+    /// Get the communication trace, oldest record first
+    pub async fn get_trace(&self) -> Vec<TraceRecord> {
+        self.trace.lock().await.records()
+    }
+
+    /// Clear the communication trace and restart its clock
+    pub async fn clear_trace(&self) {
+        self.trace.lock().await.clear();
+    }
+
+    /// Enable or disable trace recording
+    pub async fn set_trace_enabled(&self, enabled: bool) {
+        self.trace.lock().await.set_enabled(enabled);
+    }
+
+    /// Write the communication trace to a JSON Lines file
+    ///
+    /// The file can be replayed by connecting to `replay://<path>`.
+    pub async fn export_trace(&self, path: &std::path::Path) -> Result<()> {
+        self.trace.lock().await.save_jsonl(path)
+    }
+
     /// Get is connected status
     pub async fn is_connected(&self) -> bool {
         let status = self.status.lock().await;
@@ -320,13 +1430,144 @@ impl GrblController {
         cfg.clone()
     }
 
+    /// Send a real-time command byte directly to the device
+    ///
+    /// Bypasses the command queue and RX buffer accounting; GRBL acts on
+    /// real-time bytes as soon as they arrive. Dialects that carry the command
+    /// as a line (Marlin's `M410`/`M108`) answer it with `ok`, so that line is
+    /// counted as in flight to keep later acknowledgements matched. Fails if
+    /// the detected firmware has no equivalent for the command.
+    pub async fn send_realtime(&self, command: RealtimeCommand) -> Result<()> {
+        let dialect = self.get_firmware().await.dialect;
+        let bytes = dialect
+            .realtime_bytes(command)
+            .ok_or_else(|| anyhow!("{:?} is not supported by {}", command, dialect))?;
+        tracing::debug!("Real-time command: {:?} ({:02X?})", command, bytes);
+
+        let line = bytes
+            .strip_suffix(b"\n")
+            .map(|line| String::from_utf8_lossy(line).into_owned());
+        // Hold the counter until the line is registered so its `ok` cannot be
+        // matched to another line
+        let mut counter = if line.is_some() || command == RealtimeCommand::SoftReset {
+            Some(self.counter.lock().await)
+        } else {
+            None
+        };
+
+        self.trace.lock().await.record_realtime(&bytes);
+        self.transport().await.send_bytes(&bytes).await?;
+
+        if let Some(counter) = counter.as_mut() {
+            if command == RealtimeCommand::SoftReset {
+                // The controller discards its buffers, so lines in flight are dropped
+                counter.reset();
+            }
+            if let Some(line) = line {
+                counter.register(&line);
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a sequence of real-time commands
+    pub async fn send_realtime_sequence(&self, commands: &[RealtimeCommand]) -> Result<()> {
+        for command in commands {
+            self.send_realtime(*command).await?;
+        }
+        Ok(())
+    }
+
+    /// Pause motion (feed hold)
+    pub async fn feed_hold(&self) -> Result<()> {
+        self.send_realtime(RealtimeCommand::FeedHold).await
+    }
+
+    /// Resume motion (cycle start)
+    pub async fn cycle_start(&self) -> Result<()> {
+        self.send_realtime(RealtimeCommand::CycleStart).await
+    }
+
+    /// Soft reset the controller
+    ///
+    /// GRBL discards its buffers on reset, so queued and in-flight lines are
+    /// dropped as well.
+    pub async fn soft_reset(&self) -> Result<()> {
+        self.send_realtime(RealtimeCommand::SoftReset).await?;
+        self.command_queue.lock().await.clear();
+
+        // A reset restores the parser defaults and clears G92
+        *self.parser_state.lock().await = ParserState::default();
+        *self.offsets_stale.lock().await = true;
+        Ok(())
+    }
+
+    /// Cancel the active jog
+    pub async fn jog_cancel(&self) -> Result<()> {
+        self.send_realtime(RealtimeCommand::JogCancel).await
+    }
+
+    /// Trigger the safety door state
+    pub async fn safety_door(&self) -> Result<()> {
+        self.send_realtime(RealtimeCommand::SafetyDoor).await
+    }
+
+    /// Set feed override percentage (10-200%)
+    ///
+    /// Dialects without override bytes take an `M220` line. Like Marlin's
+    /// emergency lines in `send_realtime`, it goes straight to the device
+    /// ahead of any queued job lines and is counted as in flight, so its
+    /// `ok` does not upset the matching of later acknowledgements.
+    pub async fn set_feed_override(&self, percent: u32) -> Result<()> {
+        let dialect = self.get_firmware().await.dialect;
+        if let Some(command) = dialect.feed_override_command(percent) {
+            let mut counter = self.counter.lock().await;
+            self.trace.lock().await.record_tx(&command);
+            self.transport().await.send_command(&command).await?;
+            counter.register(&command);
+            return Ok(());
+        }
+        self.send_realtime_sequence(&RealtimeCommand::feed_override_sequence(percent))
+            .await
+    }
+
+    /// Set spindle override percentage (10-200%)
+    pub async fn set_spindle_override(&self, percent: u32) -> Result<()> {
+        self.send_realtime_sequence(&RealtimeCommand::spindle_override_sequence(percent))
+            .await
+    }
+
+    /// Set rapid override percentage (25, 50 or 100%)
+    pub async fn set_rapid_override(&self, percent: u32) -> Result<()> {
+        self.send_realtime(RealtimeCommand::rapid_override(percent)).await
+    }
+
     /// Emergency stop
+    ///
+    /// Sends a feed hold followed by a soft reset when connected, then marks the
+    /// machine as alarmed since position is no longer guaranteed.
     pub async fn emergency_stop(&self) -> Result<()> {
+        if self.transport().await.is_connected().await {
+            let dialect = self.get_firmware().await.dialect;
+            if dialect.realtime_bytes(RealtimeCommand::FeedHold).is_some() {
+                self.feed_hold().await?;
+            }
+            self.soft_reset().await?;
+        }
+
         let mut status = self.status.lock().await;
+        let previous = status.clone();
         status.state = MachineState::Alarm;
+        self.publish_status(&previous, &status);
         Ok(())
     }
 
+    /// Get the most recent alarm, if the machine is alarmed
+    pub async fn get_alarm(&self) -> Option<GrblAlarm> {
+        let status = self.status.lock().await;
+        status.alarm
+    }
+
     /// Reset machine alarm
     pub async fn reset_alarm(&self) -> Result<()> {
         self.send_command("$X").await?;
@@ -0,0 +1,306 @@
+//! Work coordinate offsets (`$#`)
+//!
+//! Parses the `[G54:...]` through `[TLO:...]` and `[PRB:...]` lines GRBL prints
+//! for `$#`, and builds the `G10 L20` commands that set work zero.
+
+use super::Position;
+use serde::{Deserialize, Serialize};
+
+/// Machine axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    /// Get the G-code letter for this axis
+    pub fn letter(&self) -> char {
+        match self {
+            Axis::X => 'X',
+            Axis::Y => 'Y',
+            Axis::Z => 'Z',
+        }
+    }
+
+    /// Get all axes in order
+    pub fn all() -> [Axis; 3] {
+        [Axis::X, Axis::Y, Axis::Z]
+    }
+
+    /// Read this axis from a position
+    pub fn of(&self, position: &Position) -> f64 {
+        match self {
+            Axis::X => position.x,
+            Axis::Y => position.y,
+            Axis::Z => position.z,
+        }
+    }
+
+    /// Write this axis into a position
+    pub fn set(&self, position: &mut Position, value: f64) {
+        match self {
+            Axis::X => position.x = value,
+            Axis::Y => position.y = value,
+            Axis::Z => position.z = value,
+        }
+    }
+}
+
+/// Work coordinate system (G54-G59)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum CoordinateSystem {
+    #[default]
+    G54,
+    G55,
+    G56,
+    G57,
+    G58,
+    G59,
+}
+
+impl CoordinateSystem {
+    /// Get all coordinate systems in order
+    pub fn all() -> [CoordinateSystem; 6] {
+        [
+            CoordinateSystem::G54,
+            CoordinateSystem::G55,
+            CoordinateSystem::G56,
+            CoordinateSystem::G57,
+            CoordinateSystem::G58,
+            CoordinateSystem::G59,
+        ]
+    }
+
+    /// Get the zero-based index (G54 = 0)
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Get the `P` number used by `G10` (G54 = 1)
+    pub fn p_number(&self) -> u8 {
+        self.index() as u8 + 1
+    }
+
+    /// Get the G-code word, e.g. `G54`
+    pub fn code(&self) -> &'static str {
+        match self {
+            CoordinateSystem::G54 => "G54",
+            CoordinateSystem::G55 => "G55",
+            CoordinateSystem::G56 => "G56",
+            CoordinateSystem::G57 => "G57",
+            CoordinateSystem::G58 => "G58",
+            CoordinateSystem::G59 => "G59",
+        }
+    }
+
+    /// Look up a coordinate system by its G-code word
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .find(|wcs| wcs.code().eq_ignore_ascii_case(code.trim()))
+    }
+}
+
+/// Result of the last probe cycle (`[PRB:x,y,z:success]`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProbeResult {
+    /// Machine position where the probe triggered
+    pub position: Position,
+    pub success: bool,
+}
+
+impl ProbeResult {
+    /// Parse a `[PRB:...]` line
+    pub fn parse(line: &str) -> Option<Self> {
+        let body = line.trim().strip_prefix("[PRB:")?.strip_suffix(']')?;
+        let (coords, success) = body.rsplit_once(':')?;
+        Some(ProbeResult {
+            position: parse_xyz(coords)?,
+            success: success.trim() == "1",
+        })
+    }
+}
+
+/// Offsets reported by `$#`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkOffsets {
+    /// G54-G59 in order
+    pub coordinate_systems: [Position; 6],
+    pub g28: Position,
+    pub g30: Position,
+    pub g92: Position,
+    /// Tool length offset applied to Z (G43.1)
+    pub tool_length: f64,
+    pub probe: Option<ProbeResult>,
+}
+
+impl WorkOffsets {
+    /// Parse the lines GRBL prints for `$#`
+    pub fn parse(output: &str) -> Self {
+        let mut offsets = WorkOffsets::default();
+        for line in output.lines() {
+            offsets.apply_line(line);
+        }
+        offsets
+    }
+
+    /// Apply a single `$#` line
+    ///
+    /// # Returns
+    /// true if the line was an offset or probe report
+    pub fn apply_line(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        if let Some(probe) = ProbeResult::parse(line) {
+            self.probe = Some(probe);
+            return true;
+        }
+
+        let body = match line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            Some(body) => body,
+            None => return false,
+        };
+        let (name, value) = match body.split_once(':') {
+            Some(pair) => pair,
+            None => return false,
+        };
+
+        if name == "TLO" {
+            return match value.trim().parse() {
+                Ok(tlo) => {
+                    self.tool_length = tlo;
+                    true
+                }
+                Err(_) => false,
+            };
+        }
+
+        let position = match parse_xyz(value) {
+            Some(position) => position,
+            None => return false,
+        };
+        match name {
+            "G28" => self.g28 = position,
+            "G30" => self.g30 = position,
+            "G92" => self.g92 = position,
+            _ => match CoordinateSystem::from_code(name) {
+                Some(wcs) => self.coordinate_systems[wcs.index()] = position,
+                None => return false,
+            },
+        }
+        true
+    }
+
+    /// Get the offset of a coordinate system
+    pub fn get(&self, wcs: CoordinateSystem) -> Position {
+        self.coordinate_systems[wcs.index()]
+    }
+
+    /// Get the total work coordinate offset for a coordinate system
+    ///
+    /// This is what GRBL reports as `WCO`: the WCS offset plus G92 plus the
+    /// tool length offset on Z.
+    pub fn work_offset(&self, wcs: CoordinateSystem) -> Position {
+        let base = self.get(wcs);
+        Position {
+            x: base.x + self.g92.x,
+            y: base.y + self.g92.y,
+            z: base.z + self.g92.z + self.tool_length,
+        }
+    }
+
+    /// Check if a sent line changes any offset reported by `$#`
+    pub fn affected_by(line: &str) -> bool {
+        let upper: String = line
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase();
+        if upper.starts_with('$') {
+            return upper.starts_with("$RST");
+        }
+        ["G10", "G92", "G28.1", "G30.1", "G43.1", "G49", "G38"]
+            .iter()
+            .any(|code| upper.contains(code))
+    }
+}
+
+/// Build a `G10 L20` command that makes the current position read `values`
+///
+/// # Arguments
+/// * `wcs` - Coordinate system to change (need not be the active one)
+/// * `values` - Work coordinate each axis should read at the current position
+pub fn set_work_position_command(wcs: CoordinateSystem, values: &[(Axis, f64)]) -> String {
+    let mut command = format!("G10 L20 P{}", wcs.p_number());
+    for (axis, value) in values {
+        command.push_str(&format!(" {}{}", axis.letter(), format_coordinate(*value)));
+    }
+    command
+}
+
+/// Format a coordinate with up to three decimals
+pub(crate) fn format_coordinate(value: f64) -> String {
+    let text = format!("{:.3}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Parse `x,y,z` (extra axes are ignored)
+fn parse_xyz(value: &str) -> Option<Position> {
+    let mut values = value.split(',').map(|v| v.trim().parse::<f64>());
+    Some(Position {
+        x: values.next()?.ok()?,
+        y: values.next()?.ok()?,
+        z: values.next()?.ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "[G54:10.000,5.000,-2.000]\r\n[G55:0.000,0.000,0.000]\r\n\
+        [G56:0.000,0.000,0.000]\r\n[G57:0.000,0.000,0.000]\r\n[G58:0.000,0.000,0.000]\r\n\
+        [G59:1.000,2.000,3.000]\r\n[G28:0.000,0.000,0.000]\r\n[G30:-5.000,-5.000,0.000]\r\n\
+        [G92:0.000,0.000,1.500]\r\n[TLO:0.250]\r\n[PRB:1.000,2.000,-3.000:1]\r\nok\r\n";
+
+    #[test]
+    fn test_parse_offsets() {
+        let offsets = WorkOffsets::parse(SAMPLE);
+        assert_eq!(offsets.get(CoordinateSystem::G54), Position { x: 10.0, y: 5.0, z: -2.0 });
+        assert_eq!(offsets.get(CoordinateSystem::G59).z, 3.0);
+        assert_eq!(offsets.g30.x, -5.0);
+        assert_eq!(offsets.tool_length, 0.25);
+        let probe = offsets.probe.unwrap();
+        assert!(probe.success);
+        assert_eq!(probe.position.z, -3.0);
+    }
+
+    #[test]
+    fn test_work_offset_combines_g92_and_tlo() {
+        let offsets = WorkOffsets::parse(SAMPLE);
+        let wco = offsets.work_offset(CoordinateSystem::G54);
+        assert_eq!(wco, Position { x: 10.0, y: 5.0, z: -0.25 });
+    }
+
+    #[test]
+    fn test_set_work_position_command() {
+        assert_eq!(
+            set_work_position_command(CoordinateSystem::G55, &[(Axis::X, 0.0), (Axis::Z, -0.5)]),
+            "G10 L20 P2 X0 Z-0.5"
+        );
+    }
+
+    #[test]
+    fn test_affected_by() {
+        assert!(WorkOffsets::affected_by("G10 L20 P1 X0"));
+        assert!(WorkOffsets::affected_by("g92 z0"));
+        assert!(!WorkOffsets::affected_by("G1 X10 F100"));
+        assert!(!WorkOffsets::affected_by("$10=1"));
+    }
+}
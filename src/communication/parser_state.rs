@@ -0,0 +1,304 @@
+//! G-code parser state (`$G`)
+//!
+//! Parses the `[GC:G0 G54 G17 G21 G90 G94 M5 M9 T0 F0 S0]` line GRBL prints
+//! for `$G` into typed modal groups, and tracks acknowledged lines so the
+//! state stays current between queries.
+
+use super::offsets::CoordinateSystem;
+use serde::{Deserialize, Serialize};
+
+/// Motion mode (modal group 1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MotionMode {
+    #[default]
+    Rapid,
+    Linear,
+    ArcCw,
+    ArcCcw,
+    /// G38.2
+    ProbeToward,
+    /// G38.3
+    ProbeTowardNoError,
+    /// G38.4
+    ProbeAway,
+    /// G38.5
+    ProbeAwayNoError,
+    /// G80
+    Cancel,
+}
+
+impl MotionMode {
+    /// Get the G-code word
+    pub fn code(&self) -> &'static str {
+        match self {
+            MotionMode::Rapid => "G0",
+            MotionMode::Linear => "G1",
+            MotionMode::ArcCw => "G2",
+            MotionMode::ArcCcw => "G3",
+            MotionMode::ProbeToward => "G38.2",
+            MotionMode::ProbeTowardNoError => "G38.3",
+            MotionMode::ProbeAway => "G38.4",
+            MotionMode::ProbeAwayNoError => "G38.5",
+            MotionMode::Cancel => "G80",
+        }
+    }
+}
+
+/// Arc plane (modal group 2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Plane {
+    /// G17
+    #[default]
+    XY,
+    /// G18
+    ZX,
+    /// G19
+    YZ,
+}
+
+/// Units (modal group 6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Units {
+    /// G21
+    #[default]
+    Millimeters,
+    /// G20
+    Inches,
+}
+
+/// Distance mode (modal group 3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DistanceMode {
+    /// G90
+    #[default]
+    Absolute,
+    /// G91
+    Incremental,
+}
+
+/// Feed rate mode (modal group 5)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FeedRateMode {
+    /// G94
+    #[default]
+    UnitsPerMinute,
+    /// G93
+    InverseTime,
+}
+
+/// Spindle state (modal group 7)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SpindleState {
+    /// M5
+    #[default]
+    Off,
+    /// M3
+    Clockwise,
+    /// M4
+    CounterClockwise,
+}
+
+/// Active modal state of the GRBL G-code parser
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParserState {
+    pub motion: MotionMode,
+    pub coordinate_system: CoordinateSystem,
+    pub plane: Plane,
+    pub units: Units,
+    pub distance: DistanceMode,
+    pub feed_mode: FeedRateMode,
+    pub spindle: SpindleState,
+    pub flood: bool,
+    pub mist: bool,
+    pub tool: u32,
+    /// Feed rate in the active units
+    pub feed_rate: f64,
+    pub spindle_speed: f64,
+}
+
+impl ParserState {
+    /// Parse a `[GC:...]` line
+    pub fn parse(line: &str) -> Option<Self> {
+        let body = line.trim().strip_prefix("[GC:")?.strip_suffix(']')?;
+        let mut state = ParserState::default();
+        // `$G` lists every modal group, so coolant is off unless M7/M8 appear
+        for word in body.split_whitespace() {
+            state.apply_word(word);
+        }
+        Some(state)
+    }
+
+    /// Update the state from a line GRBL acknowledged with `ok`
+    ///
+    /// System commands and jogs (`$J=`) leave the parser state unchanged.
+    pub fn apply_line(&mut self, line: &str) {
+        let line = line.trim();
+        if line.starts_with('$') {
+            return;
+        }
+        for word in split_words(line) {
+            self.apply_word(&word);
+        }
+    }
+
+    /// Apply a single word such as `G91`, `M8` or `F500`
+    fn apply_word(&mut self, word: &str) {
+        let word = word.to_ascii_uppercase();
+        let (letter, value) = match word.chars().next() {
+            Some(letter) => (letter, &word[1..]),
+            None => return,
+        };
+        let number: f64 = match value.parse() {
+            Ok(number) => number,
+            Err(_) => return,
+        };
+
+        match letter {
+            'G' => match (number * 10.0).round() as i32 {
+                0 => self.motion = MotionMode::Rapid,
+                10 => self.motion = MotionMode::Linear,
+                20 => self.motion = MotionMode::ArcCw,
+                30 => self.motion = MotionMode::ArcCcw,
+                382 => self.motion = MotionMode::ProbeToward,
+                383 => self.motion = MotionMode::ProbeTowardNoError,
+                384 => self.motion = MotionMode::ProbeAway,
+                385 => self.motion = MotionMode::ProbeAwayNoError,
+                800 => self.motion = MotionMode::Cancel,
+                170 => self.plane = Plane::XY,
+                180 => self.plane = Plane::ZX,
+                190 => self.plane = Plane::YZ,
+                200 => self.units = Units::Inches,
+                210 => self.units = Units::Millimeters,
+                900 => self.distance = DistanceMode::Absolute,
+                910 => self.distance = DistanceMode::Incremental,
+                930 => self.feed_mode = FeedRateMode::InverseTime,
+                940 => self.feed_mode = FeedRateMode::UnitsPerMinute,
+                code @ (540 | 550 | 560 | 570 | 580 | 590) => {
+                    self.coordinate_system = CoordinateSystem::all()[((code - 540) / 10) as usize]
+                }
+                _ => {}
+            },
+            'M' => match number as u32 {
+                2 | 30 => self.program_end(),
+                3 => self.spindle = SpindleState::Clockwise,
+                4 => self.spindle = SpindleState::CounterClockwise,
+                5 => self.spindle = SpindleState::Off,
+                7 => self.mist = true,
+                8 => self.flood = true,
+                9 => {
+                    self.mist = false;
+                    self.flood = false;
+                }
+                _ => {}
+            },
+            'T' => self.tool = number as u32,
+            'F' => self.feed_rate = number,
+            'S' => self.spindle_speed = number,
+            _ => {}
+        }
+    }
+
+    /// Modal defaults GRBL restores on M2/M30
+    fn program_end(&mut self) {
+        self.motion = MotionMode::Linear;
+        self.coordinate_system = CoordinateSystem::G54;
+        self.plane = Plane::XY;
+        self.distance = DistanceMode::Absolute;
+        self.feed_mode = FeedRateMode::UnitsPerMinute;
+        self.spindle = SpindleState::Off;
+        self.flood = false;
+        self.mist = false;
+    }
+
+    /// Build a line that restores the modal groups that are safe to replay
+    ///
+    /// Motion, spindle and coolant are left out so restoring state after a
+    /// reconnect never moves the machine or starts the spindle.
+    pub fn to_gcode(&self) -> String {
+        format!(
+            "{} {} {} {} {}",
+            match self.units {
+                Units::Millimeters => "G21",
+                Units::Inches => "G20",
+            },
+            match self.distance {
+                DistanceMode::Absolute => "G90",
+                DistanceMode::Incremental => "G91",
+            },
+            match self.plane {
+                Plane::XY => "G17",
+                Plane::ZX => "G18",
+                Plane::YZ => "G19",
+            },
+            self.coordinate_system.code(),
+            match self.feed_mode {
+                FeedRateMode::UnitsPerMinute => "G94",
+                FeedRateMode::InverseTime => "G93",
+            },
+        )
+    }
+}
+
+/// Split a G-code line into words, dropping comments and spaces
+pub(crate) fn split_words(line: &str) -> Vec<String> {
+    let cleaned = match super::streaming::prepare_line(line) {
+        Some(cleaned) => cleaned,
+        None => return Vec::new(),
+    };
+
+    let mut words: Vec<String> = Vec::new();
+    for ch in cleaned.chars().filter(|c| !c.is_whitespace()) {
+        if ch.is_ascii_alphabetic() {
+            words.push(ch.to_string());
+        } else if let Some(word) = words.last_mut() {
+            word.push(ch);
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gc_line() {
+        let state = ParserState::parse("[GC:G1 G55 G18 G20 G91 G94 M3 M7 M8 T2 F250 S12000]").unwrap();
+        assert_eq!(state.motion, MotionMode::Linear);
+        assert_eq!(state.coordinate_system, CoordinateSystem::G55);
+        assert_eq!(state.plane, Plane::ZX);
+        assert_eq!(state.units, Units::Inches);
+        assert_eq!(state.distance, DistanceMode::Incremental);
+        assert_eq!(state.spindle, SpindleState::Clockwise);
+        assert!(state.mist && state.flood);
+        assert_eq!(state.tool, 2);
+        assert_eq!(state.feed_rate, 250.0);
+        assert_eq!(state.spindle_speed, 12000.0);
+        assert!(ParserState::parse("ok").is_none());
+    }
+
+    #[test]
+    fn test_apply_line() {
+        let mut state = ParserState::default();
+        state.apply_line("G91 G1 X10 F300 (feed)");
+        state.apply_line("g56m3s1000");
+        assert_eq!(state.distance, DistanceMode::Incremental);
+        assert_eq!(state.motion, MotionMode::Linear);
+        assert_eq!(state.coordinate_system, CoordinateSystem::G56);
+        assert_eq!(state.spindle, SpindleState::Clockwise);
+        assert_eq!(state.feed_rate, 300.0);
+
+        state.apply_line("$J=G90 X0 F100");
+        assert_eq!(state.distance, DistanceMode::Incremental);
+
+        state.apply_line("M30");
+        assert_eq!(state.coordinate_system, CoordinateSystem::G54);
+        assert_eq!(state.spindle, SpindleState::Off);
+    }
+
+    #[test]
+    fn test_to_gcode() {
+        let state = ParserState::parse("[GC:G0 G57 G17 G20 G91 G94 M5 M9 T0 F0 S0]").unwrap();
+        assert_eq!(state.to_gcode(), "G20 G91 G17 G57 G94");
+    }
+}
@@ -0,0 +1,746 @@
+//! Homing, probing and tool length measurement
+//!
+//! High-level routines on top of `GrblController`: `$H` with per-axis
+//! progress, Z touch plates, XY edge and corner finding, and tool length
+//! measurement on a fixed tool setter. Each probe move gets a deadline derived
+//! from its travel and feed; when it passes, motion is stopped with a feed hold
+//! and soft reset rather than left running.
+
+use super::offsets::{format_coordinate, Axis, CoordinateSystem, ProbeResult};
+use super::parser_state::{DistanceMode, Units};
+use super::realtime::RealtimeCommand;
+use super::{GrblController, GrblResponse, Position};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often homing asks for status reports to follow the axes
+const HOMING_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Slack added to the time a probe move should take before it is aborted
+const PROBE_TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+
+/// Movement below this is treated as standing still (GRBL reports 3 decimals)
+const MOTION_THRESHOLD: f64 = 0.0005;
+
+/// Homing state of one axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HomingPhase {
+    #[default]
+    Pending,
+    /// Moving toward its limit switch or pulling off
+    Seeking,
+    Homed,
+}
+
+/// Per-axis progress of a homing cycle
+///
+/// GRBL does not report homing per axis, so progress is inferred from status
+/// reports: an axis is seeking once it moves and homed once it stays put while
+/// another axis moves (GRBL homes Z first, then X and Y).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HomingProgress {
+    /// X, Y and Z in order
+    pub phases: [HomingPhase; 3],
+}
+
+impl HomingProgress {
+    /// Get the phase of an axis
+    pub fn phase(&self, axis: Axis) -> HomingPhase {
+        self.phases[axis as usize]
+    }
+
+    /// Check if every axis is homed
+    pub fn is_complete(&self) -> bool {
+        self.phases.iter().all(|phase| *phase == HomingPhase::Homed)
+    }
+
+    /// Update from two consecutive machine positions
+    ///
+    /// # Returns
+    /// true if any axis changed phase
+    pub fn update(&mut self, previous: &Position, current: &Position) -> bool {
+        let moving =
+            Axis::all().map(|axis| (axis.of(current) - axis.of(previous)).abs() > MOTION_THRESHOLD);
+        if !moving.iter().any(|m| *m) {
+            return false;
+        }
+
+        let mut changed = false;
+        for (phase, moving) in self.phases.iter_mut().zip(moving) {
+            let next = match (*phase, moving) {
+                (HomingPhase::Pending, true) => HomingPhase::Seeking,
+                (HomingPhase::Seeking, false) => HomingPhase::Homed,
+                (phase, _) => phase,
+            };
+            changed |= next != *phase;
+            *phase = next;
+        }
+        changed
+    }
+
+    /// Mark every axis homed once GRBL acknowledged `$H`
+    pub fn finish(&mut self) {
+        self.phases = [HomingPhase::Homed; 3];
+    }
+}
+
+/// Z probing against a touch plate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TouchPlateOptions {
+    /// Plate thickness in mm (0 to probe the stock directly)
+    pub plate_thickness: f64,
+    /// Furthest the probe may travel down, in mm
+    pub max_travel: f64,
+    /// Feed of the first, fast probe in mm/min
+    pub seek_feed: f64,
+    /// Feed of a second, slow probe after backing off; None probes once
+    pub latch_feed: Option<f64>,
+    /// Distance to back off after contact, in mm
+    pub retract: f64,
+    /// Coordinate system to set; None uses the active one
+    pub wcs: Option<CoordinateSystem>,
+    /// Overrides the deadline derived from travel and feed
+    pub timeout: Option<Duration>,
+}
+
+impl Default for TouchPlateOptions {
+    fn default() -> Self {
+        TouchPlateOptions {
+            plate_thickness: 0.0,
+            max_travel: 25.0,
+            seek_feed: 100.0,
+            latch_feed: Some(25.0),
+            retract: 2.0,
+            wcs: None,
+            timeout: None,
+        }
+    }
+}
+
+/// Stock edge to find, named by the side of the stock it is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Edge {
+    /// Minimum X; probed moving +X
+    Left,
+    /// Maximum X; probed moving -X
+    Right,
+    /// Minimum Y; probed moving +Y
+    Front,
+    /// Maximum Y; probed moving -Y
+    Back,
+}
+
+impl Edge {
+    /// Get the axis the edge is found on
+    pub fn axis(&self) -> Axis {
+        match self {
+            Edge::Left | Edge::Right => Axis::X,
+            Edge::Front | Edge::Back => Axis::Y,
+        }
+    }
+
+    /// Get the direction of the probe move (+1 or -1)
+    pub fn direction(&self) -> f64 {
+        match self {
+            Edge::Left | Edge::Front => 1.0,
+            Edge::Right | Edge::Back => -1.0,
+        }
+    }
+}
+
+/// Stock corner to find
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Corner {
+    FrontLeft,
+    FrontRight,
+    BackLeft,
+    BackRight,
+}
+
+impl Corner {
+    /// Get the X and Y edges that meet at this corner
+    pub fn edges(&self) -> (Edge, Edge) {
+        match self {
+            Corner::FrontLeft => (Edge::Left, Edge::Front),
+            Corner::FrontRight => (Edge::Right, Edge::Front),
+            Corner::BackLeft => (Edge::Left, Edge::Back),
+            Corner::BackRight => (Edge::Right, Edge::Back),
+        }
+    }
+}
+
+/// XY edge and corner probing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EdgeProbeOptions {
+    /// Diameter of the probe tip or tool touching the edge, in mm
+    pub tool_diameter: f64,
+    /// Furthest a probe move may travel, in mm
+    pub max_travel: f64,
+    /// Probe feed in mm/min
+    pub feed: f64,
+    /// Distance to back off after contact, in mm
+    pub retract: f64,
+    /// Corner probing: how far to step outside each edge before lowering
+    pub clearance: f64,
+    /// Corner probing: how far to lower beside the stock
+    pub depth: f64,
+    /// Coordinate system to set; None uses the active one
+    pub wcs: Option<CoordinateSystem>,
+    /// Overrides the deadline derived from travel and feed
+    pub timeout: Option<Duration>,
+}
+
+impl Default for EdgeProbeOptions {
+    fn default() -> Self {
+        EdgeProbeOptions {
+            tool_diameter: 6.0,
+            max_travel: 20.0,
+            feed: 100.0,
+            retract: 2.0,
+            clearance: 10.0,
+            depth: 5.0,
+            wcs: None,
+            timeout: None,
+        }
+    }
+}
+
+/// Tool length measurement on a fixed tool setter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolSetterOptions {
+    /// Machine X and Y of the setter (Z is ignored)
+    pub position: Position,
+    /// Machine Z to travel at
+    pub safe_z: f64,
+    /// Furthest the probe may travel down from `safe_z`, in mm
+    pub max_travel: f64,
+    /// Feed of the first, fast probe in mm/min
+    pub seek_feed: f64,
+    /// Feed of a second, slow probe after backing off; None probes once
+    pub latch_feed: Option<f64>,
+    /// Distance to back off before the slow probe, in mm
+    pub retract: f64,
+    /// Overrides the deadline derived from travel and feed
+    pub timeout: Option<Duration>,
+}
+
+impl Default for ToolSetterOptions {
+    fn default() -> Self {
+        ToolSetterOptions {
+            position: Position::default(),
+            safe_z: -1.0,
+            max_travel: 50.0,
+            seek_feed: 200.0,
+            latch_feed: Some(25.0),
+            retract: 2.0,
+            timeout: None,
+        }
+    }
+}
+
+/// Result of a tool length measurement
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ToolMeasurement {
+    /// Where the tool touched the setter
+    pub probe: ProbeResult,
+    /// Tool length offset applied with `G43.1`, relative to the reference tool
+    pub offset: f64,
+}
+
+/// One probe move along an axis, with an optional slow second touch
+struct ProbeMove {
+    axis: Axis,
+    /// +1 or -1
+    direction: f64,
+    max_travel: f64,
+    seek_feed: f64,
+    latch_feed: Option<f64>,
+    retract: f64,
+    timeout: Option<Duration>,
+}
+
+impl GrblController {
+    /// Run a homing cycle (`$H`), reporting per-axis progress
+    ///
+    /// If no response arrives within `timeout` or the link fails, the cycle
+    /// is aborted with a soft reset, which leaves GRBL in a homing alarm.
+    ///
+    /// # Arguments
+    /// * `timeout` - Longest the cycle may take
+    /// * `on_progress` - Called whenever an axis changes phase
+    pub async fn home<F>(&self, timeout: Duration, mut on_progress: F) -> Result<HomingProgress>
+    where
+        F: FnMut(&HomingProgress),
+    {
+        let mut progress = HomingProgress::default();
+        let mut updates = self.subscribe_status();
+        let mut last = self.get_status().await?.mpos;
+
+        let response = {
+            let homing = self.send_command_timeout("$H", timeout);
+            tokio::pin!(homing);
+            let mut ticker = tokio::time::interval(HOMING_POLL_INTERVAL);
+            loop {
+                tokio::select! {
+                    response = &mut homing => break response,
+                    _ = ticker.tick() => {
+                        let _ = self.send_realtime(RealtimeCommand::StatusQuery).await;
+                    }
+                    Ok(status) = updates.recv() => {
+                        if progress.update(&last, &status.mpos) {
+                            on_progress(&progress);
+                        }
+                        last = status.mpos;
+                    }
+                }
+            }
+        };
+
+        match response {
+            Err(e) => {
+                self.abort_motion().await;
+                Err(anyhow!("Homing failed: {}; cycle aborted", e))
+            }
+            Ok(GrblResponse::Error(error)) => {
+                Err(anyhow!("$H rejected: {} {}", error, error.recovery()))
+            }
+            Ok(_) => {
+                progress.finish();
+                on_progress(&progress);
+                Ok(progress)
+            }
+        }
+    }
+
+    /// Probe down onto a touch plate and set work Z
+    ///
+    /// Work Z is set so the plate surface reads `plate_thickness`, i.e. the
+    /// stock top under the plate becomes Z0. The tool ends `retract` above
+    /// the plate.
+    ///
+    /// # Returns
+    /// Where the probe triggered
+    pub async fn probe_touch_plate(&self, options: &TouchPlateOptions) -> Result<ProbeResult> {
+        let restore = self.modal_restore_line().await;
+        let result = self.touch_plate_cycle(options).await;
+        self.restore_modes(&restore).await;
+        result
+    }
+
+    async fn touch_plate_cycle(&self, options: &TouchPlateOptions) -> Result<ProbeResult> {
+        let wcs = self.probe_wcs(options.wcs).await;
+        self.run_line("G21 G91").await?;
+        let contact = self
+            .probe_move(ProbeMove {
+                axis: Axis::Z,
+                direction: -1.0,
+                max_travel: options.max_travel,
+                seek_feed: options.seek_feed,
+                latch_feed: options.latch_feed,
+                retract: options.retract,
+                timeout: options.timeout,
+            })
+            .await?;
+
+        self.set_work_coordinate(wcs, Axis::Z, contact.position.z, options.plate_thickness)
+            .await?;
+        self.run_line(&format!("G0 Z{}", format_coordinate(options.retract)))
+            .await?;
+        self.wait_for_motion().await?;
+        Ok(contact)
+    }
+
+    /// Probe toward an edge from beside it and make the edge work zero
+    ///
+    /// The tool must already be at probing depth, beside the edge. The probe
+    /// tip radius is added to the contact position.
+    ///
+    /// # Returns
+    /// Machine coordinate of the edge
+    pub async fn find_edge(&self, edge: Edge, options: &EdgeProbeOptions) -> Result<f64> {
+        let restore = self.modal_restore_line().await;
+        let result = async {
+            let wcs = self.probe_wcs(options.wcs).await;
+            self.run_line("G21 G91").await?;
+            let position = self.probe_edge(edge, wcs, options).await?;
+            self.wait_for_motion().await?;
+            Ok(position)
+        }
+        .await;
+        self.restore_modes(&restore).await;
+        result
+    }
+
+    /// Find a stock corner and make it work zero in X and Y
+    ///
+    /// Start above the stock near the corner, no more than `depth` above its
+    /// top and at least `tool_diameter` in from both edges. For each edge the
+    /// tool steps `clearance` outside it, lowers by `depth`, probes back, then
+    /// returns to the start; the path beside the stock must be clear.
+    ///
+    /// # Returns
+    /// Machine X and Y of the corner, with the start Z
+    pub async fn find_corner(
+        &self,
+        corner: Corner,
+        options: &EdgeProbeOptions,
+    ) -> Result<Position> {
+        let restore = self.modal_restore_line().await;
+        let result = self.corner_cycle(corner, options).await;
+        self.restore_modes(&restore).await;
+        result
+    }
+
+    async fn corner_cycle(&self, corner: Corner, options: &EdgeProbeOptions) -> Result<Position> {
+        let wcs = self.probe_wcs(options.wcs).await;
+        self.wait_for_motion().await?;
+        let start = self
+            .request_status_report(Duration::from_secs(1))
+            .await
+            .ok_or_else(|| anyhow!("No status report; machine position unknown"))?
+            .mpos;
+        let return_to_start = [
+            format!("G53 G0 Z{}", format_coordinate(start.z)),
+            format!(
+                "G53 G0 X{} Y{}",
+                format_coordinate(start.x),
+                format_coordinate(start.y)
+            ),
+        ];
+
+        let mut corner_position = start;
+        let (x_edge, y_edge) = corner.edges();
+        for edge in [x_edge, y_edge] {
+            let axis = edge.axis();
+            self.run_line("G21 G91").await?;
+            self.run_line(&format!(
+                "G0 {}{}",
+                axis.letter(),
+                format_coordinate(-edge.direction() * options.clearance)
+            ))
+            .await?;
+            self.run_line(&format!("G0 Z{}", format_coordinate(-options.depth)))
+                .await?;
+            let position = self.probe_edge(edge, wcs, options).await?;
+            axis.set(&mut corner_position, position);
+            for line in &return_to_start {
+                self.run_line(line).await?;
+            }
+        }
+        self.wait_for_motion().await?;
+        Ok(corner_position)
+    }
+
+    /// Measure the tool on a fixed tool setter and apply its length offset
+    ///
+    /// Measure the reference tool (the one work Z was set with) first with
+    /// `reference: None`, which clears the offset, and keep its
+    /// `probe.position.z`. Later tools are measured against it and get a
+    /// `G43.1` offset.
+    ///
+    /// # Arguments
+    /// * `options` - Tool setter location and probing parameters
+    /// * `reference` - Machine Z at which the reference tool triggered
+    pub async fn measure_tool_length(
+        &self,
+        options: &ToolSetterOptions,
+        reference: Option<f64>,
+    ) -> Result<ToolMeasurement> {
+        let restore = self.modal_restore_line().await;
+        let result = self.tool_length_cycle(options, reference).await;
+        self.restore_modes(&restore).await;
+        result
+    }
+
+    async fn tool_length_cycle(
+        &self,
+        options: &ToolSetterOptions,
+        reference: Option<f64>,
+    ) -> Result<ToolMeasurement> {
+        let safe_z = format!("G53 G0 Z{}", format_coordinate(options.safe_z));
+        self.run_line("G21").await?;
+        self.run_line(&safe_z).await?;
+        self.run_line(&format!(
+            "G53 G0 X{} Y{}",
+            format_coordinate(options.position.x),
+            format_coordinate(options.position.y)
+        ))
+        .await?;
+        self.run_line("G91").await?;
+        let probe = self
+            .probe_move(ProbeMove {
+                axis: Axis::Z,
+                direction: -1.0,
+                max_travel: options.max_travel,
+                seek_feed: options.seek_feed,
+                latch_feed: options.latch_feed,
+                retract: options.retract,
+                timeout: options.timeout,
+            })
+            .await?;
+        self.run_line(&safe_z).await?;
+        self.wait_for_motion().await?;
+
+        let offset = reference.map_or(0.0, |reference| probe.position.z - reference);
+        let command = if offset.abs() < MOTION_THRESHOLD {
+            "G49".to_string()
+        } else {
+            format!("G43.1 Z{}", format_coordinate(offset))
+        };
+        self.run_line(&command).await?;
+        self.refresh_offsets().await?;
+        Ok(ToolMeasurement { probe, offset })
+    }
+
+    /// Probe an edge in incremental mode, set its work zero and back off
+    async fn probe_edge(
+        &self,
+        edge: Edge,
+        wcs: CoordinateSystem,
+        options: &EdgeProbeOptions,
+    ) -> Result<f64> {
+        let axis = edge.axis();
+        let direction = edge.direction();
+        let contact = self
+            .probe_move(ProbeMove {
+                axis,
+                direction,
+                max_travel: options.max_travel,
+                seek_feed: options.feed,
+                latch_feed: None,
+                retract: options.retract,
+                timeout: options.timeout,
+            })
+            .await?;
+
+        // The tool centre stops one radius short of the edge
+        let position = axis.of(&contact.position) + direction * options.tool_diameter / 2.0;
+        self.set_work_coordinate(wcs, axis, position, 0.0).await?;
+        self.run_line(&format!(
+            "G0 {}{}",
+            axis.letter(),
+            format_coordinate(-direction * options.retract)
+        ))
+        .await?;
+        Ok(position)
+    }
+
+    /// Probe along an axis, optionally backing off and probing again slowly
+    ///
+    /// Expects incremental distance mode.
+    async fn probe_move(&self, probe: ProbeMove) -> Result<ProbeResult> {
+        let axis = probe.axis;
+        let mut contact = self
+            .run_probe(
+                axis,
+                probe.direction * probe.max_travel,
+                probe.seek_feed,
+                probe.timeout,
+            )
+            .await?;
+        if let Some(latch_feed) = probe.latch_feed {
+            self.run_line(&format!(
+                "G0 {}{}",
+                axis.letter(),
+                format_coordinate(-probe.direction * probe.retract)
+            ))
+            .await?;
+            contact = self
+                .run_probe(
+                    axis,
+                    probe.direction * probe.retract * 2.0,
+                    latch_feed,
+                    probe.timeout,
+                )
+                .await?;
+        }
+        Ok(contact)
+    }
+
+    /// Run one `G38.2` move and return where the probe triggered
+    ///
+    /// If the move outlives its deadline or the link fails, motion is stopped.
+    async fn run_probe(
+        &self,
+        axis: Axis,
+        distance: f64,
+        feed: f64,
+        timeout: Option<Duration>,
+    ) -> Result<ProbeResult> {
+        if feed <= 0.0 {
+            return Err(anyhow!("Probe feed must be positive"));
+        }
+        let command = format!(
+            "G38.2 {}{} F{}",
+            axis.letter(),
+            format_coordinate(distance),
+            format_coordinate(feed)
+        );
+        let timeout = timeout.unwrap_or_else(|| probe_timeout(distance, feed));
+
+        self.offsets.lock().await.probe = None;
+        let response = match self.send_command_timeout(&command, timeout).await {
+            Ok(response) => response,
+            Err(e) => {
+                self.abort_motion().await;
+                return Err(anyhow!("{} failed: {}; motion stopped", command, e));
+            }
+        };
+        if let GrblResponse::Error(error) = response {
+            return Err(anyhow!(
+                "{} rejected: {} {}",
+                command,
+                error,
+                error.recovery()
+            ));
+        }
+
+        let probe = self.offsets.lock().await.probe;
+        match probe {
+            Some(result) if result.success => Ok(result),
+            Some(_) => Err(anyhow!(
+                "Probe made no contact within {} mm",
+                format_coordinate(distance.abs())
+            )),
+            // An initial-state failure raises an alarm without a [PRB:] report
+            None => match self.get_alarm().await {
+                Some(alarm) => Err(anyhow!("{}", alarm)),
+                None => Err(anyhow!("{} reported no probe result", command)),
+            },
+        }
+    }
+
+    /// Set an axis's offset so `machine` reads `work` in a coordinate system
+    ///
+    /// Uses `G10 L2` with the reported offsets rather than `G10 L20`, so the
+    /// overshoot after a probe trigger does not shift the zero.
+    async fn set_work_coordinate(
+        &self,
+        wcs: CoordinateSystem,
+        axis: Axis,
+        machine: f64,
+        work: f64,
+    ) -> Result<()> {
+        let offsets = self.refresh_offsets().await?;
+        let tool_length = if axis == Axis::Z {
+            offsets.tool_length
+        } else {
+            0.0
+        };
+        let value = machine - work - axis.of(&offsets.g92) - tool_length;
+        self.run_line(&format!(
+            "G10 L2 P{} {}{}",
+            wcs.p_number(),
+            axis.letter(),
+            format_coordinate(value)
+        ))
+        .await?;
+        self.refresh_offsets().await?;
+        Ok(())
+    }
+
+    /// Send a line and fail if GRBL rejects it
+    async fn run_line(&self, line: &str) -> Result<()> {
+        match self.send_command(line).await? {
+            GrblResponse::Error(error) => {
+                Err(anyhow!("{} rejected: {} {}", line, error, error.recovery()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Wait until queued motion has finished
+    ///
+    /// GRBL acknowledges a dwell only once the planner is empty.
+    async fn wait_for_motion(&self) -> Result<()> {
+        self.run_line("G4 P0").await
+    }
+
+    async fn probe_wcs(&self, wcs: Option<CoordinateSystem>) -> CoordinateSystem {
+        match wcs {
+            Some(wcs) => wcs,
+            None => self.get_parser_state().await.coordinate_system,
+        }
+    }
+
+    /// Build the line that puts units and distance mode back as they were
+    async fn modal_restore_line(&self) -> String {
+        let state = self.get_parser_state().await;
+        let units = match state.units {
+            Units::Millimeters => "G21",
+            Units::Inches => "G20",
+        };
+        let distance = match state.distance {
+            DistanceMode::Absolute => "G90",
+            DistanceMode::Incremental => "G91",
+        };
+        format!("{} {}", units, distance)
+    }
+
+    async fn restore_modes(&self, line: &str) {
+        // Rejected while alarmed after a failed probe; GRBL keeps the modes
+        // until the next reset, so the caller's program sets them again
+        if let Err(e) = self.send_command(line).await {
+            tracing::warn!("Could not restore {}: {}", line, e);
+        }
+    }
+
+    /// Stop motion after a routine failed while the machine may be moving
+    async fn abort_motion(&self) {
+        let _ = self.feed_hold().await;
+        if let Err(e) = self.soft_reset().await {
+            tracing::error!("Could not reset after a failed routine: {}", e);
+        }
+    }
+}
+
+/// Time a probe move may take before it is aborted
+///
+/// Allows for a feed override down to 50% plus a fixed margin.
+pub fn probe_timeout(distance: f64, feed: f64) -> Duration {
+    Duration::from_secs_f64(distance.abs() / feed * 60.0 * 2.0) + PROBE_TIMEOUT_MARGIN
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f64, y: f64, z: f64) -> Position {
+        Position { x, y, z }
+    }
+
+    #[test]
+    fn test_homing_progress_follows_axes() {
+        let mut progress = HomingProgress::default();
+        assert!(!progress.update(&at(0.0, 0.0, 0.0), &at(0.0, 0.0, 0.0)));
+
+        // Z seeks first
+        assert!(progress.update(&at(0.0, 0.0, 0.0), &at(0.0, 0.0, 5.0)));
+        assert_eq!(progress.phase(Axis::Z), HomingPhase::Seeking);
+        assert_eq!(progress.phase(Axis::X), HomingPhase::Pending);
+
+        // Then X and Y while Z stays put
+        assert!(progress.update(&at(0.0, 0.0, 5.0), &at(-3.0, -3.0, 5.0)));
+        assert_eq!(progress.phase(Axis::Z), HomingPhase::Homed);
+        assert_eq!(progress.phase(Axis::X), HomingPhase::Seeking);
+        assert!(!progress.is_complete());
+
+        progress.finish();
+        assert!(progress.is_complete());
+    }
+
+    #[test]
+    fn test_corner_edges() {
+        let (x, y) = Corner::BackRight.edges();
+        assert_eq!((x.axis(), x.direction()), (Axis::X, -1.0));
+        assert_eq!((y.axis(), y.direction()), (Axis::Y, -1.0));
+        assert_eq!(Corner::FrontLeft.edges(), (Edge::Left, Edge::Front));
+    }
+
+    #[test]
+    fn test_probe_timeout() {
+        // 10 mm at 100 mm/min takes 6 s, doubled plus the margin
+        assert_eq!(probe_timeout(-10.0, 100.0), Duration::from_secs(17));
+    }
+}
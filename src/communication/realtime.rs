@@ -0,0 +1,238 @@
+//! GRBL real-time commands
+//!
+//! Real-time commands are single bytes that GRBL acts on the moment they arrive,
+//! bypassing the line buffer and the planner. They must never be queued behind
+//! streamed G-code.
+
+use serde::{Deserialize, Serialize};
+
+/// Lowest feed and spindle override GRBL accepts (percent)
+pub const MIN_OVERRIDE: u32 = 10;
+
+/// Highest feed and spindle override GRBL accepts (percent)
+pub const MAX_OVERRIDE: u32 = 200;
+
+/// Single-byte real-time command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RealtimeCommand {
+    /// Request a status report (`?`)
+    StatusQuery,
+    /// Pause motion with controlled deceleration (`!`)
+    FeedHold,
+    /// Resume from feed hold or start a queued cycle (`~`)
+    CycleStart,
+    /// Halt immediately and reset GRBL (Ctrl-X, 0x18)
+    SoftReset,
+    /// Trigger the safety door state (0x84)
+    SafetyDoor,
+    /// Cancel the active jog and flush jog motions (0x85)
+    JogCancel,
+    /// Set feed override to 100% (0x90)
+    FeedOverrideReset,
+    /// Increase feed override by 10% (0x91)
+    FeedOverrideCoarsePlus,
+    /// Decrease feed override by 10% (0x92)
+    FeedOverrideCoarseMinus,
+    /// Increase feed override by 1% (0x93)
+    FeedOverrideFinePlus,
+    /// Decrease feed override by 1% (0x94)
+    FeedOverrideFineMinus,
+    /// Set rapid override to 100% (0x95)
+    RapidOverrideFull,
+    /// Set rapid override to 50% (0x96)
+    RapidOverrideMedium,
+    /// Set rapid override to 25% (0x97)
+    RapidOverrideLow,
+    /// Set spindle override to 100% (0x99)
+    SpindleOverrideReset,
+    /// Increase spindle override by 10% (0x9A)
+    SpindleOverrideCoarsePlus,
+    /// Decrease spindle override by 10% (0x9B)
+    SpindleOverrideCoarseMinus,
+    /// Increase spindle override by 1% (0x9C)
+    SpindleOverrideFinePlus,
+    /// Decrease spindle override by 1% (0x9D)
+    SpindleOverrideFineMinus,
+    /// Toggle spindle stop while in feed hold (0x9E)
+    SpindleStopToggle,
+    /// Toggle flood coolant (0xA0)
+    FloodCoolantToggle,
+    /// Toggle mist coolant (0xA1)
+    MistCoolantToggle,
+}
+
+impl RealtimeCommand {
+    /// Get the byte sent to the controller
+    pub fn byte(&self) -> u8 {
+        match self {
+            RealtimeCommand::StatusQuery => b'?',
+            RealtimeCommand::FeedHold => b'!',
+            RealtimeCommand::CycleStart => b'~',
+            RealtimeCommand::SoftReset => 0x18,
+            RealtimeCommand::SafetyDoor => 0x84,
+            RealtimeCommand::JogCancel => 0x85,
+            RealtimeCommand::FeedOverrideReset => 0x90,
+            RealtimeCommand::FeedOverrideCoarsePlus => 0x91,
+            RealtimeCommand::FeedOverrideCoarseMinus => 0x92,
+            RealtimeCommand::FeedOverrideFinePlus => 0x93,
+            RealtimeCommand::FeedOverrideFineMinus => 0x94,
+            RealtimeCommand::RapidOverrideFull => 0x95,
+            RealtimeCommand::RapidOverrideMedium => 0x96,
+            RealtimeCommand::RapidOverrideLow => 0x97,
+            RealtimeCommand::SpindleOverrideReset => 0x99,
+            RealtimeCommand::SpindleOverrideCoarsePlus => 0x9A,
+            RealtimeCommand::SpindleOverrideCoarseMinus => 0x9B,
+            RealtimeCommand::SpindleOverrideFinePlus => 0x9C,
+            RealtimeCommand::SpindleOverrideFineMinus => 0x9D,
+            RealtimeCommand::SpindleStopToggle => 0x9E,
+            RealtimeCommand::FloodCoolantToggle => 0xA0,
+            RealtimeCommand::MistCoolantToggle => 0xA1,
+        }
+    }
+
+    /// Look up the command for a received byte
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        Self::all().iter().copied().find(|cmd| cmd.byte() == byte)
+    }
+
+    /// Get all real-time commands
+    pub fn all() -> &'static [RealtimeCommand] {
+        &[
+            RealtimeCommand::StatusQuery,
+            RealtimeCommand::FeedHold,
+            RealtimeCommand::CycleStart,
+            RealtimeCommand::SoftReset,
+            RealtimeCommand::SafetyDoor,
+            RealtimeCommand::JogCancel,
+            RealtimeCommand::FeedOverrideReset,
+            RealtimeCommand::FeedOverrideCoarsePlus,
+            RealtimeCommand::FeedOverrideCoarseMinus,
+            RealtimeCommand::FeedOverrideFinePlus,
+            RealtimeCommand::FeedOverrideFineMinus,
+            RealtimeCommand::RapidOverrideFull,
+            RealtimeCommand::RapidOverrideMedium,
+            RealtimeCommand::RapidOverrideLow,
+            RealtimeCommand::SpindleOverrideReset,
+            RealtimeCommand::SpindleOverrideCoarsePlus,
+            RealtimeCommand::SpindleOverrideCoarseMinus,
+            RealtimeCommand::SpindleOverrideFinePlus,
+            RealtimeCommand::SpindleOverrideFineMinus,
+            RealtimeCommand::SpindleStopToggle,
+            RealtimeCommand::FloodCoolantToggle,
+            RealtimeCommand::MistCoolantToggle,
+        ]
+    }
+
+    /// Build the command sequence that sets feed override to a percentage
+    ///
+    /// GRBL has no absolute override command, so the sequence resets to 100%
+    /// and then steps in 10% and 1% increments.
+    pub fn feed_override_sequence(percent: u32) -> Vec<RealtimeCommand> {
+        override_sequence(
+            percent,
+            RealtimeCommand::FeedOverrideReset,
+            (
+                RealtimeCommand::FeedOverrideCoarsePlus,
+                RealtimeCommand::FeedOverrideFinePlus,
+            ),
+            (
+                RealtimeCommand::FeedOverrideCoarseMinus,
+                RealtimeCommand::FeedOverrideFineMinus,
+            ),
+        )
+    }
+
+    /// Build the command sequence that sets spindle override to a percentage
+    pub fn spindle_override_sequence(percent: u32) -> Vec<RealtimeCommand> {
+        override_sequence(
+            percent,
+            RealtimeCommand::SpindleOverrideReset,
+            (
+                RealtimeCommand::SpindleOverrideCoarsePlus,
+                RealtimeCommand::SpindleOverrideFinePlus,
+            ),
+            (
+                RealtimeCommand::SpindleOverrideCoarseMinus,
+                RealtimeCommand::SpindleOverrideFineMinus,
+            ),
+        )
+    }
+
+    /// Get the rapid override command closest to a percentage (100, 50 or 25)
+    pub fn rapid_override(percent: u32) -> RealtimeCommand {
+        match percent {
+            0..=37 => RealtimeCommand::RapidOverrideLow,
+            38..=75 => RealtimeCommand::RapidOverrideMedium,
+            _ => RealtimeCommand::RapidOverrideFull,
+        }
+    }
+}
+
+/// Build reset + coarse + fine steps to reach a target override percentage
+fn override_sequence(
+    percent: u32,
+    reset: RealtimeCommand,
+    plus: (RealtimeCommand, RealtimeCommand),
+    minus: (RealtimeCommand, RealtimeCommand),
+) -> Vec<RealtimeCommand> {
+    let target = percent.clamp(MIN_OVERRIDE, MAX_OVERRIDE);
+    let mut commands = vec![reset];
+
+    let (delta, (coarse, fine)) = if target >= 100 {
+        (target - 100, plus)
+    } else {
+        (100 - target, minus)
+    };
+
+    commands.extend(std::iter::repeat_n(coarse, (delta / 10) as usize));
+    commands.extend(std::iter::repeat_n(fine, (delta % 10) as usize));
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_bytes() {
+        assert_eq!(RealtimeCommand::FeedHold.byte(), b'!');
+        assert_eq!(RealtimeCommand::SoftReset.byte(), 0x18);
+        assert_eq!(RealtimeCommand::JogCancel.byte(), 0x85);
+        assert_eq!(RealtimeCommand::SpindleOverrideFineMinus.byte(), 0x9D);
+    }
+
+    #[test]
+    fn test_from_byte_round_trip() {
+        for cmd in RealtimeCommand::all() {
+            assert_eq!(RealtimeCommand::from_byte(cmd.byte()), Some(*cmd));
+        }
+        assert_eq!(RealtimeCommand::from_byte(b'G'), None);
+    }
+
+    #[test]
+    fn test_feed_override_sequence() {
+        let seq = RealtimeCommand::feed_override_sequence(123);
+        assert_eq!(seq[0], RealtimeCommand::FeedOverrideReset);
+        assert_eq!(
+            seq.iter()
+                .filter(|c| **c == RealtimeCommand::FeedOverrideCoarsePlus)
+                .count(),
+            2
+        );
+        assert_eq!(
+            seq.iter()
+                .filter(|c| **c == RealtimeCommand::FeedOverrideFinePlus)
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_spindle_override_sequence_clamps() {
+        let seq = RealtimeCommand::spindle_override_sequence(0);
+        assert_eq!(seq.len(), 1 + 9);
+        assert!(seq[1..]
+            .iter()
+            .all(|c| *c == RealtimeCommand::SpindleOverrideCoarseMinus));
+    }
+}
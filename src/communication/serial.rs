@@ -3,13 +3,16 @@
 //! Provides asynchronous serial port management, command sending/receiving,
 //! and real-time status monitoring with error recovery.
 
+use super::transport::{Transport, TransportKind};
 use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
 use serialport::SerialPort;
 use std::io::{Read, Write};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::thread;
 use std::time::Duration;
-use tokio::sync::Mutex;
-use tokio::time::sleep;
+use tokio::sync::{mpsc, Mutex};
 
 /// Serial port configuration
 #[derive(Debug, Clone)]
@@ -36,17 +39,84 @@ impl Default for SerialConfig {
 }
 
 /// Serial port connection handler
+///
+/// Reads happen on a dedicated thread that owns a clone of the port, so a
+/// blocking read never holds up the async runtime or a write. Real-time
+/// bytes go through the write half and never wait behind a poll.
 pub struct SerialConnection {
-    port: Arc<Mutex<Option<Box<dyn SerialPort>>>>,
+    writer: Arc<StdMutex<Option<Box<dyn SerialPort>>>>,
+    reader: Mutex<Option<SerialReader>>,
     config: SerialConfig,
     port_name: Arc<Mutex<String>>,
 }
 
+/// Receiving end of the reader thread
+struct SerialReader {
+    chunks: mpsc::UnboundedReceiver<std::io::Result<Vec<u8>>>,
+    /// Bytes received but not yet handed to a caller
+    pending: Vec<u8>,
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl SerialReader {
+    /// Start a thread that reads `port` until stopped or the port fails
+    fn spawn(mut port: Box<dyn SerialPort>, port_name: &str) -> Result<Self> {
+        let (tx, chunks) = mpsc::unbounded_channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = thread::Builder::new()
+            .name(format!("serial-reader {}", port_name))
+            .spawn({
+                let stop = Arc::clone(&stop);
+                move || {
+                    let mut buffer = [0u8; 1024];
+                    // The port timeout bounds each read, so the stop flag is
+                    // checked at least that often
+                    while !stop.load(Ordering::Relaxed) {
+                        let chunk = match port.read(&mut buffer) {
+                            Ok(0) => continue,
+                            Ok(n) => Ok(buffer[..n].to_vec()),
+                            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => continue,
+                            Err(e) => Err(e),
+                        };
+                        let failed = chunk.is_err();
+                        if tx.send(chunk).is_err() || failed {
+                            break;
+                        }
+                    }
+                }
+            })
+            .context("Failed to start serial reader thread")?;
+
+        Ok(SerialReader {
+            chunks,
+            pending: Vec::new(),
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Stop the thread and wait for it to release the port
+    async fn shutdown(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = tokio::task::spawn_blocking(move || thread.join()).await;
+        }
+    }
+}
+
+impl Drop for SerialReader {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
 impl SerialConnection {
     /// Create a new serial connection handler
     pub fn new(config: SerialConfig) -> Self {
         SerialConnection {
-            port: Arc::new(Mutex::new(None)),
+            writer: Arc::new(StdMutex::new(None)),
+            reader: Mutex::new(None),
             config,
             port_name: Arc::new(Mutex::new(String::new())),
         }
@@ -59,6 +129,7 @@ impl SerialConnection {
 
     /// Connect to a serial port
     pub async fn connect(&self, port_name: &str) -> Result<()> {
+        self.disconnect().await?;
 
         // Try to open the serial port
         let serial_port = serialport::new(port_name, self.config.baud_rate)
@@ -72,9 +143,12 @@ impl SerialConnection {
                 "Failed to open serial port: {}",
                 port_name
             ))?;
+        let read_half = serial_port
+            .try_clone()
+            .context(format!("Failed to clone serial port: {}", port_name))?;
 
-        let mut port = self.port.lock().await;
-        *port = Some(serial_port);
+        *self.reader.lock().await = Some(SerialReader::spawn(read_half, port_name)?);
+        *lock_writer(&self.writer) = Some(serial_port);
 
         let mut stored_port_name = self.port_name.lock().await;
         *stored_port_name = port_name.to_string();
@@ -84,23 +158,46 @@ impl SerialConnection {
 
     /// Disconnect from the serial port
     pub async fn disconnect(&self) -> Result<()> {
-        let mut port = self.port.lock().await;
-        *port = None;
+        *lock_writer(&self.writer) = None;
+        let reader = self.reader.lock().await.take();
+        if let Some(reader) = reader {
+            reader.shutdown().await;
+        }
         Ok(())
     }
 
     /// Send raw bytes to the device
+    ///
+    /// The write runs on the blocking pool and only takes the write half,
+    /// so it goes out even while a read is waiting for data.
     pub async fn send_bytes(&self, data: &[u8]) -> Result<usize> {
-        let mut port = self.port.lock().await;
-        let port_ref = port
-            .as_mut()
-            .ok_or_else(|| anyhow!("Serial port not connected"))?;
-
-        let bytes_written = port_ref
-            .write(data)
-            .context("Failed to write to serial port")?;
+        let writer = Arc::clone(&self.writer);
+        let data = data.to_vec();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut port = lock_writer(&writer);
+            let port_ref = port
+                .as_mut()
+                .ok_or_else(|| anyhow!("Serial port not connected"))?;
+
+            match port_ref.write_all(&data) {
+                Ok(()) => Ok(data.len()),
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                    Err(anyhow!("Failed to write to serial port: {}", e))
+                }
+                Err(e) => {
+                    // The device is gone (unplugged or USB reset)
+                    *port = None;
+                    Err(anyhow!("Serial port lost: {}", e))
+                }
+            }
+        })
+        .await
+        .map_err(|e| anyhow!("Serial write task failed: {}", e))?;
 
-        Ok(bytes_written)
+        if result.is_err() && !self.is_connected().await {
+            self.disconnect().await?;
+        }
+        result
     }
 
     /// Send a string command (with newline)
@@ -110,47 +207,51 @@ impl SerialConnection {
         Ok(())
     }
 
-    /// Read response from device (with timeout)
+    /// Read response from device (with the configured timeout)
     pub async fn read_response(&self, max_size: usize) -> Result<String> {
-        let mut buffer = vec![0u8; max_size];
-
-        let mut port = self.port.lock().await;
-        let port_ref = port
-            .as_mut()
-            .ok_or_else(|| anyhow!("Serial port not connected"))?;
-
-        match port_ref.read(&mut buffer) {
-            Ok(n) if n > 0 => {
-                buffer.truncate(n);
-                let response = String::from_utf8_lossy(&buffer).to_string();
-                Ok(response)
-            }
-            Ok(_) => Err(anyhow!("No data received from serial port")),
-            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
-                Err(anyhow!("Serial port read timeout"))
-            }
-            Err(e) => Err(anyhow!("Serial port read error: {}", e)),
-        }
+        self.read_response_timeout(max_size, self.config.timeout).await
     }
 
     /// Read response with custom timeout
+    ///
+    /// Waits on data from the reader thread, so the timeout is honoured and
+    /// the wait can be cancelled.
     pub async fn read_response_timeout(
         &self,
         max_size: usize,
         timeout: Duration,
     ) -> Result<String> {
-        let result = tokio::time::timeout(timeout, self.read_response(max_size)).await;
+        let mut guard = self.reader.lock().await;
+        let reader = guard
+            .as_mut()
+            .ok_or_else(|| anyhow!("Serial port not connected"))?;
 
-        match result {
-            Ok(response_result) => response_result,
-            Err(_) => Err(anyhow!("Read operation timed out")),
+        if reader.pending.is_empty() {
+            match tokio::time::timeout(timeout, reader.chunks.recv()).await {
+                Err(_) => return Err(anyhow!("Serial port read timeout")),
+                Ok(Some(Ok(bytes))) => reader.pending = bytes,
+                Ok(Some(Err(e))) => {
+                    // The device is gone (unplugged or USB reset)
+                    drop(guard);
+                    self.disconnect().await?;
+                    return Err(anyhow!("Serial port lost: {}", e));
+                }
+                Ok(None) => {
+                    drop(guard);
+                    self.disconnect().await?;
+                    return Err(anyhow!("Serial port lost"));
+                }
+            }
         }
+
+        let n = max_size.min(reader.pending.len());
+        let bytes: Vec<u8> = reader.pending.drain(..n).collect();
+        Ok(String::from_utf8_lossy(&bytes).to_string())
     }
 
     /// Check if port is connected
     pub async fn is_connected(&self) -> bool {
-        let port = self.port.lock().await;
-        port.is_some()
+        lock_writer(&self.writer).is_some()
     }
 
     /// Get the connected port name
@@ -160,29 +261,55 @@ impl SerialConnection {
     }
 
     /// List available serial ports
+    ///
+    /// See `discovery::list_ports` for USB metadata.
     pub fn list_ports() -> Result<Vec<String>> {
-        let ports = serialport::available_ports()
-            .context("Failed to enumerate serial ports")?;
-
-        let port_names: Vec<String> = ports
-            .iter()
-            .filter_map(|port| match port {
-                serialport::SerialPortInfo {
-                    port_name,
-                    port_type: _,
-                } => Some(port_name.clone()),
-            })
-            .collect();
+        let ports = super::discovery::list_ports()?;
+        Ok(ports.into_iter().map(|port| port.name).collect())
+    }
+}
+
+#[async_trait]
+impl Transport for SerialConnection {
+    fn kind(&self) -> TransportKind {
+        TransportKind::Serial
+    }
+
+    async fn connect(&self, address: &str) -> Result<()> {
+        SerialConnection::connect(self, address).await
+    }
 
-        Ok(port_names)
+    async fn disconnect(&self) -> Result<()> {
+        SerialConnection::disconnect(self).await
+    }
+
+    async fn send_bytes(&self, data: &[u8]) -> Result<usize> {
+        SerialConnection::send_bytes(self, data).await
+    }
+
+    async fn read_response_timeout(&self, max_size: usize, timeout: Duration) -> Result<String> {
+        SerialConnection::read_response_timeout(self, max_size, timeout).await
+    }
+
+    async fn is_connected(&self) -> bool {
+        SerialConnection::is_connected(self).await
     }
 }
 
+/// Lock the write half, recovering it if a writer panicked
+fn lock_writer(
+    writer: &StdMutex<Option<Box<dyn SerialPort>>>,
+) -> std::sync::MutexGuard<'_, Option<Box<dyn SerialPort>>> {
+    writer.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
 impl Drop for SerialConnection {
     fn drop(&mut self) {
-        // Ensure port is closed when the connection is dropped
-        if let Ok(mut port_guard) = self.port.try_lock() {
-            *port_guard = None;
+        // Ensure port is closed when the connection is dropped; the reader
+        // thread stops at its next read timeout
+        *lock_writer(&self.writer) = None;
+        if let Ok(mut reader_guard) = self.reader.try_lock() {
+            *reader_guard = None;
         }
     }
 }
@@ -210,6 +337,17 @@ mod tests {
         assert_eq!(conn.get_port_name().await, "");
     }
 
+    #[tokio::test]
+    async fn test_io_without_connection_fails() {
+        let conn = SerialConnection::default_config();
+        assert!(conn.send_bytes(b"?").await.is_err());
+        assert!(conn
+            .read_response_timeout(64, Duration::from_millis(10))
+            .await
+            .is_err());
+        conn.disconnect().await.unwrap();
+    }
+
     #[test]
     fn test_list_available_ports() {
         // This test may fail if no serial ports are available
@@ -0,0 +1,598 @@
+//! GRBL `$` settings
+//!
+//! Typed model of the settings GRBL prints for `$$`. Every known setting has a
+//! description, unit and valid range so edits can be checked before they are
+//! sent, and only values that actually changed are written back. Settings can
+//! be saved as a JSON snapshot and diffed against the live machine.
+
+use super::firmware::FirmwareDialect;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// How a setting value is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SettingKind {
+    /// Whole number
+    Integer,
+    /// Number printed with three decimals
+    Decimal,
+    /// 0 or 1
+    Boolean,
+    /// Bit mask (axis mask or report options)
+    Mask,
+}
+
+/// Description of a GRBL setting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SettingInfo {
+    pub number: u16,
+    pub name: &'static str,
+    pub unit: &'static str,
+    pub kind: SettingKind,
+    pub min: f64,
+    pub max: f64,
+}
+
+const fn info(
+    number: u16,
+    name: &'static str,
+    unit: &'static str,
+    kind: SettingKind,
+    min: f64,
+    max: f64,
+) -> SettingInfo {
+    SettingInfo {
+        number,
+        name,
+        unit,
+        kind,
+        min,
+        max,
+    }
+}
+
+/// Settings defined by GRBL 1.1
+pub const SETTINGS: &[SettingInfo] = &[
+    info(0, "Step pulse time", "µs", SettingKind::Integer, 3.0, 255.0),
+    info(1, "Step idle delay", "ms", SettingKind::Integer, 0.0, 255.0),
+    info(2, "Step pulse invert", "mask", SettingKind::Mask, 0.0, 7.0),
+    info(3, "Step direction invert", "mask", SettingKind::Mask, 0.0, 7.0),
+    info(4, "Invert step enable pin", "boolean", SettingKind::Boolean, 0.0, 1.0),
+    info(5, "Invert limit pins", "boolean", SettingKind::Boolean, 0.0, 1.0),
+    info(6, "Invert probe pin", "boolean", SettingKind::Boolean, 0.0, 1.0),
+    info(10, "Status report options", "mask", SettingKind::Mask, 0.0, 3.0),
+    info(11, "Junction deviation", "mm", SettingKind::Decimal, 0.0, f64::MAX),
+    info(12, "Arc tolerance", "mm", SettingKind::Decimal, 0.0, f64::MAX),
+    info(13, "Report in inches", "boolean", SettingKind::Boolean, 0.0, 1.0),
+    info(20, "Soft limits enable", "boolean", SettingKind::Boolean, 0.0, 1.0),
+    info(21, "Hard limits enable", "boolean", SettingKind::Boolean, 0.0, 1.0),
+    info(22, "Homing cycle enable", "boolean", SettingKind::Boolean, 0.0, 1.0),
+    info(23, "Homing direction invert", "mask", SettingKind::Mask, 0.0, 7.0),
+    info(24, "Homing locate feed rate", "mm/min", SettingKind::Decimal, 0.0, f64::MAX),
+    info(25, "Homing search seek rate", "mm/min", SettingKind::Decimal, 0.0, f64::MAX),
+    info(26, "Homing switch debounce delay", "ms", SettingKind::Integer, 0.0, 65535.0),
+    info(27, "Homing switch pull-off distance", "mm", SettingKind::Decimal, 0.0, f64::MAX),
+    info(30, "Maximum spindle speed", "RPM", SettingKind::Integer, 0.0, f64::MAX),
+    info(31, "Minimum spindle speed", "RPM", SettingKind::Integer, 0.0, f64::MAX),
+    info(32, "Laser mode enable", "boolean", SettingKind::Boolean, 0.0, 1.0),
+    info(100, "X-axis travel resolution", "step/mm", SettingKind::Decimal, 0.001, f64::MAX),
+    info(101, "Y-axis travel resolution", "step/mm", SettingKind::Decimal, 0.001, f64::MAX),
+    info(102, "Z-axis travel resolution", "step/mm", SettingKind::Decimal, 0.001, f64::MAX),
+    info(110, "X-axis maximum rate", "mm/min", SettingKind::Decimal, 0.001, f64::MAX),
+    info(111, "Y-axis maximum rate", "mm/min", SettingKind::Decimal, 0.001, f64::MAX),
+    info(112, "Z-axis maximum rate", "mm/min", SettingKind::Decimal, 0.001, f64::MAX),
+    info(120, "X-axis acceleration", "mm/sec²", SettingKind::Decimal, 0.001, f64::MAX),
+    info(121, "Y-axis acceleration", "mm/sec²", SettingKind::Decimal, 0.001, f64::MAX),
+    info(122, "Z-axis acceleration", "mm/sec²", SettingKind::Decimal, 0.001, f64::MAX),
+    info(130, "X-axis maximum travel", "mm", SettingKind::Decimal, 0.0, f64::MAX),
+    info(131, "Y-axis maximum travel", "mm", SettingKind::Decimal, 0.0, f64::MAX),
+    info(132, "Z-axis maximum travel", "mm", SettingKind::Decimal, 0.0, f64::MAX),
+];
+
+/// Look up the description of a setting
+pub fn setting_info(number: u16) -> Option<&'static SettingInfo> {
+    SETTINGS.iter().find(|s| s.number == number)
+}
+
+/// Format a value the way GRBL prints it
+///
+/// Unknown settings (firmware extensions) keep up to three decimals.
+pub fn format_value(number: u16, value: f64) -> String {
+    match setting_info(number).map(|s| s.kind) {
+        Some(SettingKind::Decimal) => format!("{:.3}", value),
+        Some(_) => format!("{}", value.round() as i64),
+        None => {
+            let text = format!("{:.3}", value);
+            text.trim_end_matches('0').trim_end_matches('.').to_string()
+        }
+    }
+}
+
+/// Reason an edited setting was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingError {
+    /// Setting number is not defined by GRBL 1.1
+    Unknown(u16),
+    /// Value outside the setting's range
+    OutOfRange { number: u16, value: f64 },
+    /// Integer, boolean or mask setting given a fractional value
+    NotInteger { number: u16, value: f64 },
+    /// `$20=1` requires homing (`$22=1`)
+    SoftLimitsRequireHoming,
+    /// Settings the firmware stores as one value were given different values
+    SharedValueDiffers { numbers: Vec<u16> },
+}
+
+impl fmt::Display for SettingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingError::Unknown(number) => write!(f, "${} is not a GRBL setting", number),
+            SettingError::OutOfRange { number, value } => {
+                let info = setting_info(*number);
+                let (min, max) = info.map(|s| (s.min, s.max)).unwrap_or((0.0, 0.0));
+                if max == f64::MAX {
+                    write!(f, "${}={} must be at least {}", number, value, min)
+                } else {
+                    write!(f, "${}={} must be between {} and {}", number, value, min, max)
+                }
+            }
+            SettingError::NotInteger { number, value } => {
+                write!(f, "${}={} must be a whole number", number, value)
+            }
+            SettingError::SoftLimitsRequireHoming => {
+                write!(f, "Soft limits ($20) require homing ($22) to be enabled")
+            }
+            SettingError::SharedValueDiffers { numbers } => {
+                let numbers: Vec<String> = numbers.iter().map(|n| format!("${}", n)).collect();
+                write!(
+                    f,
+                    "{} are one setting on this firmware and must be equal",
+                    numbers.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingError {}
+
+/// Check a value against a setting's kind and range
+pub fn validate(number: u16, value: f64) -> std::result::Result<(), SettingError> {
+    let info = setting_info(number).ok_or(SettingError::Unknown(number))?;
+    if !value.is_finite() || value < info.min || value > info.max {
+        return Err(SettingError::OutOfRange { number, value });
+    }
+    if info.kind != SettingKind::Decimal && value.fract() != 0.0 {
+        return Err(SettingError::NotInteger { number, value });
+    }
+    Ok(())
+}
+
+/// A setting that differs between two sets of settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingDiff {
+    pub number: u16,
+    /// Value on the left-hand side (e.g. the live machine), None if missing
+    pub current: Option<f64>,
+    /// Value on the right-hand side (e.g. a snapshot or an edit), None if missing
+    pub target: Option<f64>,
+}
+
+impl SettingDiff {
+    /// Get a one-line description such as `$110 X-axis maximum rate: 500.000 -> 800.000 mm/min`
+    pub fn describe(&self) -> String {
+        let show = |value: Option<f64>| match value {
+            Some(v) => format_value(self.number, v),
+            None => "(missing)".to_string(),
+        };
+        match setting_info(self.number) {
+            Some(info) => format!(
+                "${} {}: {} -> {} {}",
+                self.number,
+                info.name,
+                show(self.current),
+                show(self.target),
+                info.unit
+            ),
+            None => format!("${}: {} -> {}", self.number, show(self.current), show(self.target)),
+        }
+    }
+}
+
+/// Settings read from (or to be written to) a GRBL controller
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GrblSettings {
+    values: BTreeMap<u16, f64>,
+}
+
+impl GrblSettings {
+    /// Create an empty set of settings
+    pub fn new() -> Self {
+        GrblSettings {
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Parse the lines GRBL prints for `$$`
+    ///
+    /// Lines that are not settings (`ok`, messages) are skipped.
+    pub fn parse(output: &str) -> Self {
+        Self::from_lines(output.lines())
+    }
+
+    /// Parse settings from individual response lines
+    pub fn from_lines<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+        lines.into_iter().filter_map(Self::parse_line).collect()
+    }
+
+    /// Parse a single `$n=value` line
+    ///
+    /// Accepts the GRBL 0.9 style with a trailing `(description)`.
+    pub fn parse_line(line: &str) -> Option<(u16, f64)> {
+        let (number, value) = line.trim().strip_prefix('$')?.split_once('=')?;
+        let number = number.trim().parse().ok()?;
+        let value = value.split('(').next()?.trim().parse().ok()?;
+        Some((number, value))
+    }
+
+    /// Get a setting value
+    pub fn get(&self, number: u16) -> Option<f64> {
+        self.values.get(&number).copied()
+    }
+
+    /// Set a setting after validating it
+    pub fn set(&mut self, number: u16, value: f64) -> std::result::Result<(), SettingError> {
+        validate(number, value)?;
+        if number == 20 && value != 0.0 && self.get(22).unwrap_or(0.0) == 0.0 {
+            return Err(SettingError::SoftLimitsRequireHoming);
+        }
+        self.values.insert(number, value);
+        Ok(())
+    }
+
+    /// Iterate settings in numeric order
+    pub fn iter(&self) -> impl Iterator<Item = (u16, f64)> + '_ {
+        self.values.iter().map(|(n, v)| (*n, *v))
+    }
+
+    /// Get number of settings
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Check if no settings are present
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Describe a setting with its value and unit
+    ///
+    /// # Returns
+    /// e.g. `$110=500.000 mm/min (X-axis maximum rate)`, or None if not present
+    pub fn describe(&self, number: u16) -> Option<String> {
+        let value = self.get(number)?;
+        Some(match setting_info(number) {
+            Some(info) => format!(
+                "${}={} {} ({})",
+                number,
+                format_value(number, value),
+                info.unit,
+                info.name
+            ),
+            None => format!("${}={}", number, format_value(number, value)),
+        })
+    }
+
+    /// Get the command that writes a setting, e.g. `$110=500.000`
+    pub fn command(&self, number: u16) -> Option<String> {
+        self.get(number)
+            .map(|value| format!("${}={}", number, format_value(number, value)))
+    }
+
+    /// Compare against another set of settings
+    ///
+    /// # Returns
+    /// One entry per setting whose value differs or is missing on either side
+    pub fn diff(&self, other: &GrblSettings) -> Vec<SettingDiff> {
+        let mut numbers: Vec<u16> = self.values.keys().chain(other.values.keys()).copied().collect();
+        numbers.sort_unstable();
+        numbers.dedup();
+
+        numbers
+            .into_iter()
+            .filter_map(|number| {
+                let current = self.get(number);
+                let target = other.get(number);
+                let same = match (current, target) {
+                    (Some(a), Some(b)) => format_value(number, a) == format_value(number, b),
+                    _ => false,
+                };
+                (!same).then_some(SettingDiff {
+                    number,
+                    current,
+                    target,
+                })
+            })
+            .collect()
+    }
+
+    /// Get the commands that turn `original` into these settings
+    ///
+    /// Only changed values are written. Homing is enabled before soft limits
+    /// and soft limits are disabled before homing, so GRBL accepts each step.
+    pub fn write_commands(&self, original: &GrblSettings) -> Vec<String> {
+        self.write_order(original)
+            .into_iter()
+            .filter_map(|number| self.command(number))
+            .collect()
+    }
+
+    /// Get the settings that changed relative to `original`, in write order
+    pub fn write_order(&self, original: &GrblSettings) -> Vec<u16> {
+        let mut changes: Vec<SettingDiff> = original
+            .diff(self)
+            .into_iter()
+            .filter(|d| d.target.is_some())
+            .collect();
+
+        let enabling_homing = self.get(22).unwrap_or(0.0) != 0.0;
+        changes.sort_by_key(|d| match d.number {
+            22 if enabling_homing => 19,
+            20 if !enabling_homing => 19,
+            n => n,
+        });
+
+        changes.iter().map(|d| d.number).collect()
+    }
+
+    /// Check every value and the soft limit/homing dependency
+    pub fn validate_all(&self) -> Vec<SettingError> {
+        let mut errors: Vec<SettingError> = self
+            .iter()
+            .filter_map(|(number, value)| validate(number, value).err())
+            .collect();
+        if self.soft_limits_enabled() && !self.homing_enabled() {
+            errors.push(SettingError::SoftLimitsRequireHoming);
+        }
+        errors
+    }
+
+    /// Check the settings that changed relative to `original`
+    ///
+    /// Settings outside GRBL 1.1's table pass when the dialect defines them,
+    /// so grblHAL and FluidNC snapshots with extension settings can be written.
+    /// Settings the dialect keeps as one value must all be equal once any of
+    /// them changes.
+    pub fn validate_changes(
+        &self,
+        original: &GrblSettings,
+        dialect: FirmwareDialect,
+    ) -> Vec<SettingError> {
+        let changed = self.write_order(original);
+        let mut errors: Vec<SettingError> = changed
+            .iter()
+            .copied()
+            .filter_map(|number| {
+                let value = self.get(number)?;
+                match setting_info(number) {
+                    Some(_) => validate(number, value).err(),
+                    None if dialect.defines_setting(number) => None,
+                    None => Some(SettingError::Unknown(number)),
+                }
+            })
+            .collect();
+        let shared = dialect.shared_settings();
+        if shared.iter().any(|number| changed.contains(number)) {
+            let mut values = shared.iter().filter_map(|&number| self.get(number));
+            if let Some(first) = values.next() {
+                if values.any(|value| value != first) {
+                    errors.push(SettingError::SharedValueDiffers {
+                        numbers: shared.to_vec(),
+                    });
+                }
+            }
+        }
+        if self.soft_limits_enabled() && !self.homing_enabled() {
+            errors.push(SettingError::SoftLimitsRequireHoming);
+        }
+        errors
+    }
+
+    /// Serialize to pretty JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Deserialize from JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Save a JSON snapshot to disk
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_json()?)
+            .with_context(|| format!("Failed to write settings to {}", path.display()))
+    }
+
+    /// Load a JSON snapshot from disk
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read settings from {}", path.display()))?;
+        Self::from_json(&json)
+    }
+
+    /// Get steps per mm for an axis (0 = X, 1 = Y, 2 = Z)
+    pub fn steps_per_mm(&self, axis: usize) -> Option<f64> {
+        self.get(100 + axis as u16)
+    }
+
+    /// Get maximum rate in mm/min for an axis
+    pub fn max_rate(&self, axis: usize) -> Option<f64> {
+        self.get(110 + axis as u16)
+    }
+
+    /// Get acceleration in mm/sec² for an axis
+    pub fn acceleration(&self, axis: usize) -> Option<f64> {
+        self.get(120 + axis as u16)
+    }
+
+    /// Get maximum travel in mm for an axis
+    pub fn max_travel(&self, axis: usize) -> Option<f64> {
+        self.get(130 + axis as u16)
+    }
+
+    /// Get junction deviation in mm
+    pub fn junction_deviation(&self) -> Option<f64> {
+        self.get(11)
+    }
+
+    /// Check if soft limits are enabled
+    pub fn soft_limits_enabled(&self) -> bool {
+        self.get(20).unwrap_or(0.0) != 0.0
+    }
+
+    /// Check if the homing cycle is enabled
+    pub fn homing_enabled(&self) -> bool {
+        self.get(22).unwrap_or(0.0) != 0.0
+    }
+
+    /// Check if laser mode is enabled
+    pub fn laser_mode(&self) -> bool {
+        self.get(32).unwrap_or(0.0) != 0.0
+    }
+}
+
+impl FromIterator<(u16, f64)> for GrblSettings {
+    /// Collect raw values without validation, as reported by the device
+    fn from_iter<I: IntoIterator<Item = (u16, f64)>>(iter: I) -> Self {
+        GrblSettings {
+            values: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "$0=10\r\n$11=0.010\r\n$22=0\r\n$110=500.000\r\n$111=500.000\r\nok\r\n";
+
+    #[test]
+    fn test_parse_settings() {
+        let settings = GrblSettings::parse(SAMPLE);
+        assert_eq!(settings.len(), 5);
+        assert_eq!(settings.get(110), Some(500.0));
+        assert_eq!(settings.max_rate(1), Some(500.0));
+        assert_eq!(settings.junction_deviation(), Some(0.01));
+        assert!(!settings.homing_enabled());
+    }
+
+    #[test]
+    fn test_parse_legacy_line() {
+        assert_eq!(
+            GrblSettings::parse_line("$0=10 (step pulse, usec)"),
+            Some((0, 10.0))
+        );
+        assert_eq!(GrblSettings::parse_line("[MSG:ok]"), None);
+    }
+
+    #[test]
+    fn test_describe_and_command() {
+        let settings = GrblSettings::parse(SAMPLE);
+        assert_eq!(
+            settings.describe(110).unwrap(),
+            "$110=500.000 mm/min (X-axis maximum rate)"
+        );
+        assert_eq!(settings.command(0).unwrap(), "$0=10");
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut settings = GrblSettings::parse(SAMPLE);
+        assert_eq!(settings.set(0, 2.0), Err(SettingError::OutOfRange { number: 0, value: 2.0 }));
+        assert_eq!(settings.set(22, 0.5), Err(SettingError::NotInteger { number: 22, value: 0.5 }));
+        assert_eq!(settings.set(99, 1.0), Err(SettingError::Unknown(99)));
+        assert_eq!(settings.set(20, 1.0), Err(SettingError::SoftLimitsRequireHoming));
+        assert!(settings.set(22, 1.0).is_ok());
+        assert!(settings.set(20, 1.0).is_ok());
+        assert!(settings.validate_all().is_empty());
+    }
+
+    #[test]
+    fn test_write_only_changed_values() {
+        let original = GrblSettings::parse(SAMPLE);
+        let mut edited = original.clone();
+        edited.set(110, 800.0).unwrap();
+        edited.set(22, 1.0).unwrap();
+        edited.set(20, 1.0).unwrap();
+
+        assert_eq!(
+            edited.write_commands(&original),
+            vec!["$22=1", "$20=1", "$110=800.000"]
+        );
+        assert!(original.write_commands(&original).is_empty());
+    }
+
+    #[test]
+    fn test_validate_changes_accepts_dialect_settings() {
+        // grblHAL lists extension settings alongside the GRBL ones
+        let original: GrblSettings = GrblSettings::parse(SAMPLE)
+            .iter()
+            .chain([(14, 7.0), (28, 0.1)])
+            .collect();
+        let mut edited = original.clone();
+        edited.set(110, 800.0).unwrap();
+        assert!(edited.validate_changes(&original, FirmwareDialect::GrblHal).is_empty());
+        assert!(edited.validate_changes(&original, FirmwareDialect::Grbl).is_empty());
+
+        let edited: GrblSettings = edited.iter().chain([(28, 0.2)]).collect();
+        assert!(edited.validate_changes(&original, FirmwareDialect::GrblHal).is_empty());
+        assert_eq!(
+            edited.validate_changes(&original, FirmwareDialect::Grbl),
+            vec![SettingError::Unknown(28)]
+        );
+    }
+
+    #[test]
+    fn test_validate_changes_shared_acceleration() {
+        let original: GrblSettings = GrblSettings::parse(SAMPLE)
+            .iter()
+            .chain([(120, 10.0), (121, 10.0), (122, 10.0)])
+            .collect();
+        let mut edited = original.clone();
+        edited.set(120, 500.0).unwrap();
+        assert_eq!(
+            edited.validate_changes(&original, FirmwareDialect::Smoothieware),
+            vec![SettingError::SharedValueDiffers {
+                numbers: vec![120, 121, 122]
+            }]
+        );
+        assert!(edited.validate_changes(&original, FirmwareDialect::Grbl).is_empty());
+
+        edited.set(121, 500.0).unwrap();
+        edited.set(122, 500.0).unwrap();
+        assert!(edited
+            .validate_changes(&original, FirmwareDialect::Smoothieware)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_diff_and_json_round_trip() {
+        let live = GrblSettings::parse(SAMPLE);
+        let mut snapshot = GrblSettings::from_json(&live.to_json().unwrap()).unwrap();
+        assert!(live.diff(&snapshot).is_empty());
+
+        snapshot.set(111, 750.0).unwrap();
+        let diff = live.diff(&snapshot);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].current, Some(500.0));
+        assert_eq!(diff[0].target, Some(750.0));
+        assert_eq!(
+            diff[0].describe(),
+            "$111 Y-axis maximum rate: 500.000 -> 750.000 mm/min"
+        );
+    }
+}
@@ -0,0 +1,2120 @@
+//! Simulated GRBL 1.1 device
+//!
+//! An in-process virtual machine that speaks the GRBL 1.1 protocol through the
+//! `Transport` trait, so the controller, job streaming and the pendant can be
+//! exercised end to end without hardware. Connect to it with `sim://grbl`.
+//!
+//! Lines wait in a 128-byte RX buffer until the 15-block planner has room, so
+//! `ok` responses pace the sender exactly like a real board. Moves run at their
+//! programmed feed (or the `$110`-`$112` rapid rates) scaled by the overrides and
+//! `SimulatorConfig::time_scale`; acceleration is not modelled and arcs are
+//! travelled along their chord at arc-length speed. G38 probes trigger when the
+//! tool tip enters one of the configured `ProbeZone`s.
+
+use super::realtime::{RealtimeCommand, MAX_OVERRIDE, MIN_OVERRIDE};
+use super::settings;
+use super::status::OverrideValues;
+use super::streaming::{self, GRBL_RX_BUFFER_SIZE};
+use super::transport::{Transport, TransportKind};
+use super::{GrblAlarm, GrblError, MachineState, Position};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::f64::consts::TAU;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Address that selects the simulator in `GrblController::connect`
+pub const SIMULATOR_ADDRESS: &str = "sim://grbl";
+
+/// Usable planner blocks (GRBL reports 15 free blocks when idle)
+pub const PLANNER_BLOCKS: usize = 15;
+
+/// Startup banner printed after power-up and every reset
+const BANNER: &str = "Grbl 1.1h ['$' for help]";
+
+/// How often a blocked reader re-checks the machine
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// GRBL 1.1h default settings
+const DEFAULT_SETTINGS: &[(u16, f64)] = &[
+    (0, 10.0),
+    (1, 25.0),
+    (2, 0.0),
+    (3, 0.0),
+    (4, 0.0),
+    (5, 0.0),
+    (6, 0.0),
+    (10, 1.0),
+    (11, 0.010),
+    (12, 0.002),
+    (13, 0.0),
+    (20, 0.0),
+    (21, 0.0),
+    (22, 0.0),
+    (23, 0.0),
+    (24, 25.0),
+    (25, 500.0),
+    (26, 250.0),
+    (27, 1.0),
+    (30, 1000.0),
+    (31, 0.0),
+    (32, 0.0),
+    (100, 250.0),
+    (101, 250.0),
+    (102, 250.0),
+    (110, 500.0),
+    (111, 500.0),
+    (112, 500.0),
+    (120, 10.0),
+    (121, 10.0),
+    (122, 10.0),
+    (130, 200.0),
+    (131, 200.0),
+    (132, 200.0),
+];
+
+/// Simulator configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatorConfig {
+    /// Motion speed multiplier (1.0 = real time)
+    pub time_scale: f64,
+    /// Enable homing (`$22=1`) so the machine powers up locked in Alarm
+    pub homing_enabled: bool,
+    /// Regions that close the probe input (touch plate, stock, tool setter)
+    pub probe_zones: Vec<ProbeZone>,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        SimulatorConfig {
+            time_scale: 1.0,
+            homing_enabled: false,
+            probe_zones: Vec::new(),
+        }
+    }
+}
+
+/// Box in machine coordinates where the probe input is closed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProbeZone {
+    pub min: Position,
+    pub max: Position,
+}
+
+impl ProbeZone {
+    fn contains(&self, point: &[f64; 3]) -> bool {
+        let (min, max) = (to_array(&self.min), to_array(&self.max));
+        (0..3).all(|axis| point[axis] >= min[axis] - 1e-9 && point[axis] <= max[axis] + 1e-9)
+    }
+
+    /// Fraction of the way from `start` to `end` where the segment enters
+    /// the box
+    fn entry(&self, start: &[f64; 3], end: &[f64; 3]) -> Option<f64> {
+        let (t_enter, t_exit) = self.clip(start, end)?;
+        (t_enter <= t_exit && t_enter <= 1.0 && t_exit >= 0.0).then_some(t_enter.max(0.0))
+    }
+
+    /// Fraction of the way from `start` (inside the box) to `end` where the
+    /// segment leaves it
+    fn exit(&self, start: &[f64; 3], end: &[f64; 3]) -> Option<f64> {
+        let (_, t_exit) = self.clip(start, end)?;
+        (t_exit < 1.0).then_some(t_exit.max(0.0))
+    }
+
+    /// Slab intersection of the line through `start` and `end` with the box
+    fn clip(&self, start: &[f64; 3], end: &[f64; 3]) -> Option<(f64, f64)> {
+        let (min, max) = (to_array(&self.min), to_array(&self.max));
+        let (mut t_enter, mut t_exit) = (f64::NEG_INFINITY, f64::INFINITY);
+        for axis in 0..3 {
+            let delta = end[axis] - start[axis];
+            if delta.abs() < 1e-12 {
+                if start[axis] < min[axis] || start[axis] > max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (min[axis] - start[axis]) / delta;
+            let t2 = (max[axis] - start[axis]) / delta;
+            t_enter = t_enter.max(t1.min(t2));
+            t_exit = t_exit.min(t1.max(t2));
+        }
+        Some((t_enter, t_exit))
+    }
+}
+
+/// Modal motion mode (group 1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MotionMode {
+    Rapid,
+    Linear,
+    ArcCw,
+    ArcCcw,
+    /// G38.2-G38.5
+    Probe { toward: bool, error: bool },
+    Cancel,
+}
+
+/// Spindle state (M3/M4/M5)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Spindle {
+    Off,
+    Cw,
+    Ccw,
+}
+
+/// G-code parser modal state
+#[derive(Debug, Clone)]
+struct Modal {
+    motion: MotionMode,
+    /// Active work coordinate system, 0 = G54
+    wcs: usize,
+    /// 17, 18 or 19
+    plane: u8,
+    inches: bool,
+    incremental: bool,
+    inverse_time: bool,
+    spindle: Spindle,
+    flood: bool,
+    mist: bool,
+    /// Feed rate in mm/min (or 1/min in inverse time mode)
+    feed: f64,
+    spindle_speed: f64,
+    tool: u32,
+}
+
+impl Default for Modal {
+    fn default() -> Self {
+        Modal {
+            motion: MotionMode::Rapid,
+            wcs: 0,
+            plane: 17,
+            inches: false,
+            incremental: false,
+            inverse_time: false,
+            spindle: Spindle::Off,
+            flood: false,
+            mist: false,
+            feed: 0.0,
+            spindle_speed: 0.0,
+            tool: 0,
+        }
+    }
+}
+
+/// What a planner block does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Rapid,
+    Feed,
+    Jog,
+    Dwell,
+    Home,
+    /// G38 probe move; `success` if it ends on contact
+    Probe { success: bool, error: bool },
+}
+
+/// A planned motion
+#[derive(Debug, Clone)]
+struct Block {
+    kind: BlockKind,
+    start: [f64; 3],
+    end: [f64; 3],
+    /// Nominal duration in seconds at 100% override
+    duration: f64,
+    /// Programmed rate in mm/min, reported in `FS:`
+    feed: f64,
+    line_number: Option<u32>,
+    /// Send `ok` for the line once this block completes
+    ack: bool,
+}
+
+/// Result of trying to execute a received line
+enum Outcome {
+    /// Planner full or a sync command waiting for motion to finish
+    Wait,
+    /// Line executed; reply `ok` or `error:N` now
+    Reply(std::result::Result<(), GrblError>),
+    /// Line executed; `ok` is sent when its last block completes
+    Deferred,
+    /// Line triggered an alarm; no reply
+    Alarm,
+    /// Reply `ok`, then soft reset (leaving check mode)
+    Reset,
+}
+
+/// The virtual machine behind `SimulatedGrbl`
+#[derive(Debug)]
+struct SimMachine {
+    config: SimulatorConfig,
+    connected: bool,
+    settings: BTreeMap<u16, f64>,
+    state: MachineState,
+    sub_state: Option<u8>,
+    alarm: Option<GrblAlarm>,
+    /// Machine position in mm
+    mpos: [f64; 3],
+    /// Parser position: end point of the last planned move
+    target: [f64; 3],
+    modal: Modal,
+    /// G54-G59
+    coord_systems: [[f64; 3]; 6],
+    g28: [f64; 3],
+    g30: [f64; 3],
+    g92: [f64; 3],
+    tool_length_offset: f64,
+    /// Last probe position and whether it made contact
+    probe: ([f64; 3], bool),
+    overrides: OverrideValues,
+    planner: VecDeque<Block>,
+    /// Nominal seconds already spent on the front block
+    block_elapsed: f64,
+    last_tick: Instant,
+    rx_partial: String,
+    rx_lines: VecDeque<String>,
+    rx_peak: usize,
+    awaiting_ack: bool,
+    /// Hard and soft limit alarms ignore input until a soft reset
+    locked_until_reset: bool,
+    output: String,
+    line_number: Option<u32>,
+    wco_counter: u32,
+    ovr_counter: u32,
+}
+
+impl SimMachine {
+    fn new(config: SimulatorConfig) -> Self {
+        let mut machine = SimMachine {
+            config,
+            connected: false,
+            settings: BTreeMap::new(),
+            state: MachineState::Idle,
+            sub_state: None,
+            alarm: None,
+            mpos: [0.0; 3],
+            target: [0.0; 3],
+            modal: Modal::default(),
+            coord_systems: [[0.0; 3]; 6],
+            g28: [0.0; 3],
+            g30: [0.0; 3],
+            g92: [0.0; 3],
+            tool_length_offset: 0.0,
+            probe: ([0.0; 3], false),
+            overrides: OverrideValues::default(),
+            planner: VecDeque::new(),
+            block_elapsed: 0.0,
+            last_tick: Instant::now(),
+            rx_partial: String::new(),
+            rx_lines: VecDeque::new(),
+            rx_peak: 0,
+            awaiting_ack: false,
+            locked_until_reset: false,
+            output: String::new(),
+            line_number: None,
+            wco_counter: 0,
+            ovr_counter: 1,
+        };
+        machine.restore_settings();
+        machine
+    }
+
+    fn restore_settings(&mut self) {
+        self.settings = DEFAULT_SETTINGS.iter().copied().collect();
+        if self.config.homing_enabled {
+            self.settings.insert(22, 1.0);
+        }
+    }
+
+    fn setting(&self, number: u16) -> f64 {
+        self.settings.get(&number).copied().unwrap_or(0.0)
+    }
+
+    fn emit(&mut self, line: &str) {
+        self.output.push_str(line);
+        self.output.push_str("\r\n");
+    }
+
+    /// Power the board up: position is lost, settings and offsets are kept
+    fn power_on(&mut self, now: Instant) {
+        self.mpos = [0.0; 3];
+        self.alarm = None;
+        self.state = if self.setting(22) != 0.0 {
+            MachineState::Alarm
+        } else {
+            MachineState::Idle
+        };
+        self.output.clear();
+        self.rx_peak = 0;
+        self.reset(now);
+    }
+
+    /// Soft reset (Ctrl-X): abort motion, flush buffers and reinitialise the parser
+    fn soft_reset(&mut self, now: Instant) {
+        match self.state {
+            MachineState::Home => self.alarm(GrblAlarm::HomingFailReset),
+            MachineState::Run | MachineState::Jog | MachineState::Hold
+                if !self.planner.is_empty() =>
+            {
+                self.alarm(GrblAlarm::AbortCycle)
+            }
+            _ => {}
+        }
+        if self.state != MachineState::Alarm {
+            self.state = MachineState::Idle;
+        }
+        self.reset(now);
+    }
+
+    fn reset(&mut self, now: Instant) {
+        self.planner.clear();
+        self.block_elapsed = 0.0;
+        self.last_tick = now;
+        self.rx_partial.clear();
+        self.rx_lines.clear();
+        self.awaiting_ack = false;
+        self.locked_until_reset = false;
+        self.sub_state = None;
+        self.target = self.mpos;
+        self.modal = Modal::default();
+        self.g92 = [0.0; 3];
+        self.tool_length_offset = 0.0;
+        self.overrides = OverrideValues::default();
+        self.line_number = None;
+        self.wco_counter = 0;
+        self.ovr_counter = 1;
+
+        self.output.push_str("\r\n");
+        self.emit(BANNER);
+        if self.state == MachineState::Alarm {
+            self.emit("[MSG:'$H'|'$X' to unlock]");
+        }
+    }
+
+    /// Enter the alarm state, discarding planned motion
+    fn alarm(&mut self, alarm: GrblAlarm) {
+        self.planner.clear();
+        self.block_elapsed = 0.0;
+        self.target = self.mpos;
+        self.awaiting_ack = false;
+        self.state = MachineState::Alarm;
+        self.sub_state = None;
+        self.alarm = Some(alarm);
+        self.emit(&format!("ALARM:{}", alarm.code()));
+
+        if matches!(alarm, GrblAlarm::HardLimit | GrblAlarm::SoftLimit) {
+            self.locked_until_reset = true;
+            self.emit("[MSG:Reset to continue]");
+        }
+    }
+
+    /// Current work coordinate offset (active WCS + G92 + tool length)
+    fn wco(&self) -> [f64; 3] {
+        let wcs = self.coord_systems[self.modal.wcs];
+        [
+            wcs[0] + self.g92[0],
+            wcs[1] + self.g92[1],
+            wcs[2] + self.g92[2] + self.tool_length_offset,
+        ]
+    }
+
+    /// Accept bytes from the host
+    fn receive(&mut self, data: &[u8], now: Instant) {
+        for &byte in data {
+            if let Some(command) = RealtimeCommand::from_byte(byte) {
+                self.realtime(command, now);
+                continue;
+            }
+            match byte {
+                b'\n' => {
+                    let line = std::mem::take(&mut self.rx_partial);
+                    self.rx_lines.push_back(line);
+                }
+                b'\r' => {}
+                0x20..=0x7E => self.rx_partial.push(byte as char),
+                _ => {}
+            }
+        }
+        self.rx_peak = self.rx_peak.max(self.rx_used());
+    }
+
+    /// Bytes waiting in the RX buffer
+    fn rx_used(&self) -> usize {
+        self.rx_partial.len() + self.rx_lines.iter().map(|l| l.len() + 1).sum::<usize>()
+    }
+
+    fn realtime(&mut self, command: RealtimeCommand, now: Instant) {
+        self.tick(now);
+        match command {
+            RealtimeCommand::StatusQuery => {
+                let report = self.status_report();
+                self.emit(&report);
+            }
+            RealtimeCommand::FeedHold => match self.state {
+                MachineState::Jog => self.cancel_jog(),
+                MachineState::Run => {
+                    self.state = MachineState::Hold;
+                    self.sub_state = Some(0);
+                }
+                _ => {}
+            },
+            RealtimeCommand::CycleStart => {
+                if matches!(self.state, MachineState::Hold | MachineState::Door) {
+                    self.state = MachineState::Idle;
+                    self.sub_state = None;
+                    self.update_state();
+                }
+            }
+            RealtimeCommand::SoftReset => self.soft_reset(now),
+            RealtimeCommand::SafetyDoor => match self.state {
+                MachineState::Home => self.alarm(GrblAlarm::HomingFailDoor),
+                MachineState::Jog => {
+                    self.cancel_jog();
+                    self.state = MachineState::Door;
+                    self.sub_state = Some(0);
+                }
+                MachineState::Idle | MachineState::Run | MachineState::Hold => {
+                    self.state = MachineState::Door;
+                    self.sub_state = Some(0);
+                }
+                _ => {}
+            },
+            RealtimeCommand::JogCancel => {
+                if self.state == MachineState::Jog {
+                    self.cancel_jog();
+                }
+            }
+            RealtimeCommand::FeedOverrideReset => self.set_feed_override(100),
+            RealtimeCommand::FeedOverrideCoarsePlus => {
+                self.set_feed_override(self.overrides.feed + 10)
+            }
+            RealtimeCommand::FeedOverrideCoarseMinus => {
+                self.set_feed_override(self.overrides.feed.saturating_sub(10))
+            }
+            RealtimeCommand::FeedOverrideFinePlus => self.set_feed_override(self.overrides.feed + 1),
+            RealtimeCommand::FeedOverrideFineMinus => {
+                self.set_feed_override(self.overrides.feed.saturating_sub(1))
+            }
+            RealtimeCommand::RapidOverrideFull => self.set_rapid_override(100),
+            RealtimeCommand::RapidOverrideMedium => self.set_rapid_override(50),
+            RealtimeCommand::RapidOverrideLow => self.set_rapid_override(25),
+            RealtimeCommand::SpindleOverrideReset => self.set_spindle_override(100),
+            RealtimeCommand::SpindleOverrideCoarsePlus => {
+                self.set_spindle_override(self.overrides.spindle + 10)
+            }
+            RealtimeCommand::SpindleOverrideCoarseMinus => {
+                self.set_spindle_override(self.overrides.spindle.saturating_sub(10))
+            }
+            RealtimeCommand::SpindleOverrideFinePlus => {
+                self.set_spindle_override(self.overrides.spindle + 1)
+            }
+            RealtimeCommand::SpindleOverrideFineMinus => {
+                self.set_spindle_override(self.overrides.spindle.saturating_sub(1))
+            }
+            RealtimeCommand::SpindleStopToggle => {}
+            RealtimeCommand::FloodCoolantToggle => {
+                self.modal.flood = !self.modal.flood;
+                self.ovr_counter = 0;
+            }
+            RealtimeCommand::MistCoolantToggle => {
+                self.modal.mist = !self.modal.mist;
+                self.ovr_counter = 0;
+            }
+        }
+    }
+
+    fn set_feed_override(&mut self, percent: u32) {
+        self.overrides.feed = percent.clamp(MIN_OVERRIDE, MAX_OVERRIDE);
+        self.ovr_counter = 0;
+    }
+
+    fn set_rapid_override(&mut self, percent: u32) {
+        self.overrides.rapid = percent;
+        self.ovr_counter = 0;
+    }
+
+    fn set_spindle_override(&mut self, percent: u32) {
+        self.overrides.spindle = percent.clamp(MIN_OVERRIDE, MAX_OVERRIDE);
+        self.ovr_counter = 0;
+    }
+
+    /// Stop the active jog where it is and flush the remaining jog motions
+    fn cancel_jog(&mut self) {
+        self.planner.clear();
+        self.block_elapsed = 0.0;
+        self.target = self.mpos;
+        self.state = MachineState::Idle;
+        self.sub_state = None;
+    }
+
+    /// Advance motion to `now`, then execute any lines that can now run
+    fn tick(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_tick).as_secs_f64();
+        self.last_tick = now;
+        if !matches!(self.state, MachineState::Hold | MachineState::Door) {
+            self.advance(elapsed * self.config.time_scale);
+        }
+        self.update_state();
+        self.process_lines();
+        self.update_state();
+    }
+
+    /// Speed factor applied to a block by the overrides
+    fn rate_scale(&self, kind: BlockKind) -> f64 {
+        match kind {
+            BlockKind::Feed | BlockKind::Probe { .. } => self.overrides.feed as f64 / 100.0,
+            BlockKind::Rapid => self.overrides.rapid as f64 / 100.0,
+            _ => 1.0,
+        }
+    }
+
+    fn advance(&mut self, mut seconds: f64) {
+        while let Some(block) = self.planner.front() {
+            let scale = self.rate_scale(block.kind).max(0.01);
+            let remaining = block.duration - self.block_elapsed;
+            let available = seconds * scale;
+
+            if available < remaining {
+                self.block_elapsed += available;
+                let t = self.block_elapsed / block.duration;
+                for axis in 0..3 {
+                    self.mpos[axis] = block.start[axis] + (block.end[axis] - block.start[axis]) * t;
+                }
+                return;
+            }
+
+            seconds -= remaining.max(0.0) / scale;
+            self.block_elapsed = 0.0;
+            if let Some(block) = self.planner.pop_front() {
+                self.complete_block(block);
+            }
+            // Freed planner space lets waiting lines in before time runs on
+            self.process_lines();
+        }
+    }
+
+    fn complete_block(&mut self, block: Block) {
+        self.mpos = block.end;
+        if block.kind == BlockKind::Home && self.planner.is_empty() {
+            self.target = self.mpos;
+            self.alarm = None;
+            self.state = MachineState::Idle;
+        }
+        if let BlockKind::Probe { success, error } = block.kind {
+            // GRBL reports the alarm and the probe position, then still acks
+            self.probe = (block.end, success);
+            if !success && error {
+                self.alarm(GrblAlarm::ProbeFailContact);
+            }
+            let report = format!("[PRB:{}:{}]", format_xyz(&block.end), success as u8);
+            self.emit(&report);
+        }
+        if block.ack {
+            self.awaiting_ack = false;
+            self.emit("ok");
+        }
+    }
+
+    /// Derive Run/Jog/Home/Idle from the planner
+    fn update_state(&mut self) {
+        if !matches!(
+            self.state,
+            MachineState::Idle | MachineState::Run | MachineState::Jog | MachineState::Home
+        ) {
+            return;
+        }
+        self.state = match self.planner.front().map(|b| b.kind) {
+            None => MachineState::Idle,
+            Some(BlockKind::Jog) => MachineState::Jog,
+            Some(BlockKind::Home) => MachineState::Home,
+            Some(_) => MachineState::Run,
+        };
+    }
+
+    /// Execute buffered lines in order until one has to wait
+    fn process_lines(&mut self) {
+        while !self.awaiting_ack && !self.locked_until_reset {
+            let line = match self.rx_lines.pop_front() {
+                Some(line) => line,
+                None => return,
+            };
+
+            match self.execute_line(&line) {
+                Outcome::Wait => {
+                    self.rx_lines.push_front(line);
+                    return;
+                }
+                Outcome::Reply(result) => {
+                    match result {
+                        Ok(()) => self.emit("ok"),
+                        Err(error) => {
+                            let code = error.code().unwrap_or(0);
+                            self.emit(&format!("error:{}", code));
+                        }
+                    }
+                }
+                Outcome::Deferred => self.awaiting_ack = true,
+                Outcome::Alarm => return,
+                Outcome::Reset => {
+                    self.emit("ok");
+                    self.state = MachineState::Idle;
+                    self.reset(self.last_tick);
+                    return;
+                }
+            }
+            self.update_state();
+        }
+    }
+
+    fn execute_line(&mut self, line: &str) -> Outcome {
+        let cleaned = match streaming::prepare_line(line) {
+            Some(cleaned) => cleaned,
+            None => return Outcome::Reply(Ok(())),
+        };
+        let block: String = cleaned
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase();
+
+        if let Some(command) = block.strip_prefix('$') {
+            self.system_command(command)
+        } else {
+            self.gcode_block(&block)
+        }
+    }
+
+    /// Handle a `$` system command
+    fn system_command(&mut self, command: &str) -> Outcome {
+        if let Some(jog) = command.strip_prefix("J=") {
+            return self.jog(jog);
+        }
+
+        let moving = matches!(self.state, MachineState::Run | MachineState::Hold);
+        match command {
+            "" => {
+                self.emit("[HLP:$$ $# $G $I $N $x=val $Nx=line $J=line $SLP $C $X $H ~ ! ? ctrl-x]");
+                return Outcome::Reply(Ok(()));
+            }
+            "$" => {
+                if moving {
+                    return Outcome::Reply(Err(GrblError::IdleError));
+                }
+                self.report_settings();
+                return Outcome::Reply(Ok(()));
+            }
+            "G" => {
+                let modes = self.parser_state();
+                self.emit(&modes);
+                return Outcome::Reply(Ok(()));
+            }
+            "C" => return self.toggle_check_mode(),
+            "X" => {
+                if self.state == MachineState::Alarm {
+                    self.state = MachineState::Idle;
+                    self.alarm = None;
+                    self.emit("[MSG:Caution: Unlocked]");
+                }
+                return Outcome::Reply(Ok(()));
+            }
+            _ => {}
+        }
+
+        if !matches!(self.state, MachineState::Idle | MachineState::Alarm) {
+            return Outcome::Reply(Err(GrblError::IdleError));
+        }
+
+        match command {
+            "#" => {
+                self.report_offsets();
+                Outcome::Reply(Ok(()))
+            }
+            "I" => {
+                self.emit("[VER:1.1h.20190830:]");
+                self.emit(&format!("[OPT:V,{},{}]", PLANNER_BLOCKS, GRBL_RX_BUFFER_SIZE));
+                Outcome::Reply(Ok(()))
+            }
+            "N" => {
+                self.emit("$N0=");
+                self.emit("$N1=");
+                Outcome::Reply(Ok(()))
+            }
+            "H" => self.home(&[0, 1, 2]),
+            "HX" => self.home(&[0]),
+            "HY" => self.home(&[1]),
+            "HZ" => self.home(&[2]),
+            "RST=$" => {
+                self.restore_settings();
+                self.emit("[MSG:Restoring defaults]");
+                Outcome::Reply(Ok(()))
+            }
+            "RST=#" => {
+                self.clear_offsets();
+                self.emit("[MSG:Restoring defaults]");
+                Outcome::Reply(Ok(()))
+            }
+            "RST=*" => {
+                self.restore_settings();
+                self.clear_offsets();
+                self.emit("[MSG:Restoring defaults]");
+                Outcome::Reply(Ok(()))
+            }
+            _ => Outcome::Reply(self.write_setting(command)),
+        }
+    }
+
+    fn clear_offsets(&mut self) {
+        self.coord_systems = [[0.0; 3]; 6];
+        self.g28 = [0.0; 3];
+        self.g30 = [0.0; 3];
+        self.wco_counter = 0;
+    }
+
+    /// Handle `$n=value`
+    fn write_setting(&mut self, command: &str) -> std::result::Result<(), GrblError> {
+        let (number, value) = command
+            .split_once('=')
+            .ok_or(GrblError::InvalidStatement)?;
+        let number: u16 = number.parse().map_err(|_| GrblError::InvalidStatement)?;
+        if !self.settings.contains_key(&number) {
+            return Err(GrblError::InvalidStatement);
+        }
+        let value: f64 = value.parse().map_err(|_| GrblError::BadNumberFormat)?;
+        if value < 0.0 {
+            return Err(GrblError::NegativeValue);
+        }
+        if number == 20 && value != 0.0 && self.setting(22) == 0.0 {
+            return Err(GrblError::SoftLimitError);
+        }
+        self.settings.insert(number, value);
+        Ok(())
+    }
+
+    fn report_settings(&mut self) {
+        let lines: Vec<String> = self
+            .settings
+            .iter()
+            .map(|(number, value)| format!("${}={}", number, settings::format_value(*number, *value)))
+            .collect();
+        for line in lines {
+            self.emit(&line);
+        }
+    }
+
+    fn report_offsets(&mut self) {
+        let mut lines = Vec::new();
+        for (i, offset) in self.coord_systems.iter().enumerate() {
+            lines.push(format!("[G{}:{}]", 54 + i, format_xyz(offset)));
+        }
+        lines.push(format!("[G28:{}]", format_xyz(&self.g28)));
+        lines.push(format!("[G30:{}]", format_xyz(&self.g30)));
+        lines.push(format!("[G92:{}]", format_xyz(&self.g92)));
+        lines.push(format!("[TLO:{:.3}]", self.tool_length_offset));
+        lines.push(format!("[PRB:{}:{}]", format_xyz(&self.probe.0), self.probe.1 as u8));
+        for line in lines {
+            self.emit(&line);
+        }
+    }
+
+    /// Build the `$G` parser state line
+    fn parser_state(&self) -> String {
+        let motion = match self.modal.motion {
+            MotionMode::Rapid => "G0",
+            MotionMode::Linear => "G1",
+            MotionMode::ArcCw => "G2",
+            MotionMode::ArcCcw => "G3",
+            MotionMode::Probe { toward, error } => match (toward, error) {
+                (true, true) => "G38.2",
+                (true, false) => "G38.3",
+                (false, true) => "G38.4",
+                (false, false) => "G38.5",
+            },
+            MotionMode::Cancel => "G80",
+        };
+        let spindle = match self.modal.spindle {
+            Spindle::Off => "M5",
+            Spindle::Cw => "M3",
+            Spindle::Ccw => "M4",
+        };
+        let coolant = match (self.modal.mist, self.modal.flood) {
+            (false, false) => "M9",
+            (true, false) => "M7",
+            (false, true) => "M8",
+            (true, true) => "M7 M8",
+        };
+        let unit_scale = if self.modal.inches { 25.4 } else { 1.0 };
+        format!(
+            "[GC:{} G{} G{} {} {} {} {} {} T{} F{} S{}]",
+            motion,
+            54 + self.modal.wcs,
+            self.modal.plane,
+            if self.modal.inches { "G20" } else { "G21" },
+            if self.modal.incremental { "G91" } else { "G90" },
+            if self.modal.inverse_time { "G93" } else { "G94" },
+            spindle,
+            coolant,
+            self.modal.tool,
+            (self.modal.feed / unit_scale).round(),
+            self.modal.spindle_speed.round(),
+        )
+    }
+
+    fn toggle_check_mode(&mut self) -> Outcome {
+        match self.state {
+            MachineState::Check => {
+                // Leaving check mode resets GRBL once the ok has been sent
+                self.emit("[MSG:Disabled]");
+                Outcome::Reset
+            }
+            MachineState::Idle => {
+                self.state = MachineState::Check;
+                self.emit("[MSG:Enabled]");
+                Outcome::Reply(Ok(()))
+            }
+            _ => Outcome::Reply(Err(GrblError::IdleError)),
+        }
+    }
+
+    /// Run a homing cycle: Z first, then X and Y together
+    fn home(&mut self, axes: &[usize]) -> Outcome {
+        if self.setting(22) == 0.0 {
+            return Outcome::Reply(Err(GrblError::SettingDisabled));
+        }
+
+        let pulloff = self.setting(27);
+        let seek_rate = self.setting(25).max(1.0);
+        let phases: Vec<Vec<usize>> = if axes.len() == 3 {
+            vec![vec![2], vec![0, 1]]
+        } else {
+            vec![axes.to_vec()]
+        };
+
+        let mut position = self.mpos;
+        for (i, phase) in phases.iter().enumerate() {
+            let start = position;
+            for &axis in phase {
+                position[axis] = -pulloff;
+            }
+            let distance = distance(&start, &position);
+            self.planner.push_back(Block {
+                kind: BlockKind::Home,
+                start,
+                end: position,
+                duration: distance / seek_rate * 60.0,
+                feed: seek_rate,
+                line_number: None,
+                ack: i + 1 == phases.len(),
+            });
+        }
+        self.state = MachineState::Home;
+        self.sub_state = None;
+        Outcome::Deferred
+    }
+
+    /// Handle `$J=` jog lines
+    fn jog(&mut self, line: &str) -> Outcome {
+        if !matches!(self.state, MachineState::Idle | MachineState::Jog) {
+            return Outcome::Reply(Err(GrblError::IdleError));
+        }
+        let words = match parse_words(line) {
+            Ok(words) => words,
+            Err(e) => return Outcome::Reply(Err(e)),
+        };
+
+        let mut inches = self.modal.inches;
+        let mut incremental = self.modal.incremental;
+        let mut machine_coords = false;
+        let mut axes = [None; 3];
+        let mut feed = None;
+        for (letter, value) in words {
+            match (letter, (value * 10.0).round() as i32) {
+                ('G', 200) => inches = true,
+                ('G', 210) => inches = false,
+                ('G', 900) => incremental = false,
+                ('G', 910) => incremental = true,
+                ('G', 530) => machine_coords = true,
+                ('X', _) => axes[0] = Some(value),
+                ('Y', _) => axes[1] = Some(value),
+                ('Z', _) => axes[2] = Some(value),
+                ('F', _) => feed = Some(value),
+                _ => return Outcome::Reply(Err(GrblError::InvalidJogCommand)),
+            }
+        }
+
+        let scale = if inches { 25.4 } else { 1.0 };
+        let feed = match feed {
+            Some(feed) if feed > 0.0 => feed * scale,
+            _ => return Outcome::Reply(Err(GrblError::UndefinedFeedRate)),
+        };
+        if axes.iter().all(Option::is_none) {
+            return Outcome::Reply(Err(GrblError::NoAxisWords));
+        }
+
+        let end = self.resolve_target(&axes, scale, incremental, machine_coords);
+        if self.setting(20) != 0.0 && !self.within_travel(&end) {
+            return Outcome::Reply(Err(GrblError::TravelExceeded));
+        }
+        if self.planner.len() >= PLANNER_BLOCKS {
+            return Outcome::Wait;
+        }
+
+        let start = self.target;
+        self.plan(BlockKind::Jog, start, end, distance(&start, &end) / feed * 60.0, feed, false);
+        self.target = end;
+        Outcome::Reply(Ok(()))
+    }
+
+    /// Parse and execute a G-code block
+    fn gcode_block(&mut self, line: &str) -> Outcome {
+        if matches!(self.state, MachineState::Alarm | MachineState::Jog) {
+            return Outcome::Reply(Err(GrblError::SystemGcLock));
+        }
+        let words = match parse_words(line) {
+            Ok(words) => words,
+            Err(e) => return Outcome::Reply(Err(e)),
+        };
+
+        let mut block = ParsedBlock::default();
+        for (letter, value) in words {
+            if let Err(e) = block.add_word(letter, value) {
+                return Outcome::Reply(Err(e));
+            }
+        }
+        if block.dynamic_tool_length {
+            // The Z word is the offset, not a move
+            match block.axes[2].take() {
+                Some(z) => block.tool_length = Some(z),
+                None => return Outcome::Reply(Err(GrblError::G43DynamicAxisError)),
+            }
+        }
+
+        let check = self.state == MachineState::Check;
+        let axis_words = block.axes.iter().any(Option::is_some);
+        let motion = block.motion.unwrap_or(self.modal.motion);
+        let moves = match block.non_modal {
+            Some(NonModal::Home28) | Some(NonModal::Home30) => 1 + axis_words as usize,
+            Some(_) => 0,
+            None if axis_words && motion != MotionMode::Cancel => 1,
+            None => 0,
+        };
+        let probing = axis_words && matches!(motion, MotionMode::Probe { .. });
+        let sync = block.dwell || block.stop.is_some() || probing;
+
+        // Wait for the planner before touching any state
+        if !check {
+            if sync && (!self.planner.is_empty() || self.state == MachineState::Hold) {
+                return Outcome::Wait;
+            }
+            if self.planner.len() + moves > PLANNER_BLOCKS {
+                return Outcome::Wait;
+            }
+        }
+
+        match self.execute_block(&block, motion, axis_words, check) {
+            Ok(outcome) => outcome,
+            Err(e) => Outcome::Reply(Err(e)),
+        }
+    }
+
+    fn execute_block(
+        &mut self,
+        block: &ParsedBlock,
+        motion: MotionMode,
+        axis_words: bool,
+        check: bool,
+    ) -> std::result::Result<Outcome, GrblError> {
+        if let Some(n) = block.line_number {
+            self.line_number = Some(n);
+        }
+        if let Some(inches) = block.inches {
+            self.modal.inches = inches;
+        }
+        let scale = if self.modal.inches { 25.4 } else { 1.0 };
+
+        if let Some(inverse_time) = block.inverse_time {
+            self.modal.inverse_time = inverse_time;
+        }
+        if let Some(feed) = block.feed {
+            if feed < 0.0 {
+                return Err(GrblError::NegativeValue);
+            }
+            self.modal.feed = if self.modal.inverse_time { feed } else { feed * scale };
+        }
+        if let Some(speed) = block.spindle_speed {
+            self.modal.spindle_speed = speed;
+        }
+        if let Some(tool) = block.tool {
+            self.modal.tool = tool;
+        }
+        if let Some(spindle) = block.spindle {
+            self.modal.spindle = spindle;
+        }
+        if let Some((mist, flood)) = block.coolant {
+            self.modal.mist = mist;
+            self.modal.flood = flood;
+        }
+        if let Some(plane) = block.plane {
+            self.modal.plane = plane;
+        }
+        if let Some(tlo) = block.tool_length {
+            self.tool_length_offset = tlo * scale;
+            self.wco_counter = 0;
+        }
+        if let Some(wcs) = block.wcs {
+            self.modal.wcs = wcs;
+            self.wco_counter = 0;
+        }
+        if let Some(incremental) = block.incremental {
+            self.modal.incremental = incremental;
+        }
+        if let Some(motion) = block.motion {
+            self.modal.motion = motion;
+        }
+
+        let mut outcome = Outcome::Reply(Ok(()));
+        if block.dwell && !check {
+            let seconds = block.p.unwrap_or(0.0);
+            if seconds < 0.0 {
+                return Err(GrblError::NegativeValue);
+            }
+            let here = self.target;
+            self.plan(BlockKind::Dwell, here, here, seconds, 0.0, true);
+            outcome = Outcome::Deferred;
+        }
+
+        match block.non_modal {
+            Some(NonModal::SetCoordinates) => self.set_coordinates(block, scale)?,
+            Some(NonModal::Home28) | Some(NonModal::Home30) => {
+                let stored = if block.non_modal == Some(NonModal::Home28) {
+                    self.g28
+                } else {
+                    self.g30
+                };
+                if axis_words {
+                    let via = self.resolve_target(&block.axes, scale, self.modal.incremental, false);
+                    self.rapid_to(via, check);
+                }
+                self.rapid_to(stored, check);
+            }
+            Some(NonModal::StoreHome28) => self.g28 = self.target,
+            Some(NonModal::StoreHome30) => self.g30 = self.target,
+            Some(NonModal::SetG92) => {
+                if !axis_words {
+                    return Err(GrblError::NoAxisWords);
+                }
+                let wcs = self.coord_systems[self.modal.wcs];
+                for (axis, word) in block.axes.iter().enumerate() {
+                    if let Some(value) = word {
+                        let tlo = if axis == 2 { self.tool_length_offset } else { 0.0 };
+                        self.g92[axis] = self.target[axis] - wcs[axis] - value * scale - tlo;
+                    }
+                }
+                self.wco_counter = 0;
+            }
+            Some(NonModal::ClearG92) => {
+                self.g92 = [0.0; 3];
+                self.wco_counter = 0;
+            }
+            None if axis_words => {
+                let end =
+                    self.resolve_target(&block.axes, scale, self.modal.incremental, block.machine_coords);
+                if let MotionMode::Probe { toward, error } = motion {
+                    return self.probe(toward, error, end, check);
+                }
+                if let Some(alarm) = self.motion(motion, block, end, scale, check)? {
+                    self.alarm(alarm);
+                    return Ok(Outcome::Alarm);
+                }
+            }
+            None => {}
+        }
+
+        if let Some(stop) = block.stop {
+            match stop {
+                ProgramStop::Pause if !check => {
+                    self.state = MachineState::Hold;
+                    self.sub_state = Some(0);
+                }
+                ProgramStop::Pause => {}
+                ProgramStop::End => {
+                    let wcs_changed = self.modal.wcs != 0;
+                    self.modal = Modal {
+                        motion: MotionMode::Linear,
+                        tool: self.modal.tool,
+                        feed: self.modal.feed,
+                        spindle_speed: self.modal.spindle_speed,
+                        ..Modal::default()
+                    };
+                    self.overrides = OverrideValues::default();
+                    if wcs_changed {
+                        self.wco_counter = 0;
+                    }
+                    self.emit("[MSG:Pgm End]");
+                }
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// Handle G10 L2 / L20
+    fn set_coordinates(
+        &mut self,
+        block: &ParsedBlock,
+        scale: f64,
+    ) -> std::result::Result<(), GrblError> {
+        let l = block.l.ok_or(GrblError::ValueWordMissing)?;
+        let p = block.p.ok_or(GrblError::ValueWordMissing)?;
+        if p.fract() != 0.0 || !(0.0..=6.0).contains(&p) {
+            return Err(GrblError::UnsupportedCoordSys);
+        }
+        let index = if p == 0.0 {
+            self.modal.wcs
+        } else {
+            p as usize - 1
+        };
+
+        for axis in 0..3 {
+            if let Some(value) = block.axes[axis] {
+                let value = value * scale;
+                self.coord_systems[index][axis] = match l {
+                    2 => value,
+                    20 => {
+                        let tlo = if axis == 2 { self.tool_length_offset } else { 0.0 };
+                        self.target[axis] - self.g92[axis] - value - tlo
+                    }
+                    _ => return Err(GrblError::UnsupportedCommand),
+                };
+            }
+        }
+        self.wco_counter = 0;
+        Ok(())
+    }
+
+    /// Resolve axis words to a machine-coordinate target
+    fn resolve_target(
+        &self,
+        axes: &[Option<f64>; 3],
+        scale: f64,
+        incremental: bool,
+        machine_coords: bool,
+    ) -> [f64; 3] {
+        let wco = self.wco();
+        let mut target = self.target;
+        for axis in 0..3 {
+            if let Some(value) = axes[axis] {
+                let value = value * scale;
+                target[axis] = if machine_coords {
+                    value
+                } else if incremental {
+                    self.target[axis] + value
+                } else {
+                    value + wco[axis]
+                };
+            }
+        }
+        target
+    }
+
+    /// Check a machine-coordinate target against `$130`-`$132`
+    fn within_travel(&self, target: &[f64; 3]) -> bool {
+        (0..3).all(|axis| {
+            let travel = self.setting(130 + axis as u16);
+            target[axis] <= 1e-6 && target[axis] >= -travel - 1e-6
+        })
+    }
+
+    /// Slowest configured rapid rate among the axes that move
+    fn rapid_rate(&self, start: &[f64; 3], end: &[f64; 3]) -> f64 {
+        (0..3)
+            .filter(|&axis| (end[axis] - start[axis]).abs() > 1e-9)
+            .map(|axis| self.setting(110 + axis as u16))
+            .fold(f64::INFINITY, f64::min)
+            .clamp(1.0, f64::MAX)
+    }
+
+    fn rapid_to(&mut self, end: [f64; 3], check: bool) {
+        let start = self.target;
+        self.target = end;
+        if !check {
+            let rate = self.rapid_rate(&start, &end);
+            self.plan(BlockKind::Rapid, start, end, distance(&start, &end) / rate * 60.0, rate, false);
+        }
+    }
+
+    /// Plan a G0/G1/G2/G3 move
+    ///
+    /// # Returns
+    /// An alarm if the move violates soft limits
+    fn motion(
+        &mut self,
+        motion: MotionMode,
+        block: &ParsedBlock,
+        end: [f64; 3],
+        scale: f64,
+        check: bool,
+    ) -> std::result::Result<Option<GrblAlarm>, GrblError> {
+        let start = self.target;
+        let (kind, length) = match motion {
+            MotionMode::Rapid => (BlockKind::Rapid, distance(&start, &end)),
+            MotionMode::Linear => (BlockKind::Feed, distance(&start, &end)),
+            MotionMode::ArcCw | MotionMode::ArcCcw => {
+                let length =
+                    self.arc_length(motion == MotionMode::ArcCw, block, &start, &end, scale)?;
+                (BlockKind::Feed, length)
+            }
+            MotionMode::Probe { .. } | MotionMode::Cancel => return Err(GrblError::AxisWordsExist),
+        };
+
+        let (duration, feed) = if kind == BlockKind::Rapid {
+            let rate = self.rapid_rate(&start, &end);
+            (length / rate * 60.0, rate)
+        } else if self.modal.inverse_time {
+            let f = block.feed.ok_or(GrblError::UndefinedFeedRate)?;
+            if f <= 0.0 {
+                return Err(GrblError::UndefinedFeedRate);
+            }
+            (60.0 / f, length * f)
+        } else {
+            if self.modal.feed <= 0.0 {
+                return Err(GrblError::UndefinedFeedRate);
+            }
+            (length / self.modal.feed * 60.0, self.modal.feed)
+        };
+
+        if self.setting(20) != 0.0 && !self.within_travel(&end) {
+            return Ok(Some(GrblAlarm::SoftLimit));
+        }
+
+        self.target = end;
+        if !check {
+            self.plan(kind, start, end, duration, feed, false);
+        }
+        Ok(None)
+    }
+
+    /// Plan a G38 probe move that stops where the probe input changes
+    ///
+    /// Like GRBL, the line is acknowledged once the probe move is over; a
+    /// failed G38.2/G38.4 raises an alarm first.
+    fn probe(
+        &mut self,
+        toward: bool,
+        error: bool,
+        end: [f64; 3],
+        check: bool,
+    ) -> std::result::Result<Outcome, GrblError> {
+        if self.modal.inverse_time || self.modal.feed <= 0.0 {
+            return Err(GrblError::UndefinedFeedRate);
+        }
+        let start = self.target;
+        if distance(&start, &end) < 1e-9 {
+            return Err(GrblError::InvalidTarget);
+        }
+        if check {
+            self.target = end;
+            return Ok(Outcome::Reply(Ok(())));
+        }
+
+        let zones = &self.config.probe_zones;
+        if zones.iter().any(|zone| zone.contains(&start)) == toward {
+            self.alarm(GrblAlarm::ProbeFailInitial);
+            return Ok(Outcome::Reply(Ok(())));
+        }
+        let trigger = if toward {
+            zones
+                .iter()
+                .filter_map(|zone| zone.entry(&start, &end))
+                .fold(None, |first: Option<f64>, t| Some(first.map_or(t, |f| f.min(t))))
+        } else {
+            // Contact opens once the tip is outside every zone it started in
+            zones
+                .iter()
+                .filter(|zone| zone.contains(&start))
+                .map(|zone| zone.exit(&start, &end))
+                .try_fold(0.0f64, |last, t| t.map(|t| last.max(t)))
+        };
+
+        let contact = match trigger {
+            Some(t) => [0, 1, 2].map(|axis| start[axis] + (end[axis] - start[axis]) * t),
+            None => end,
+        };
+        let feed = self.modal.feed;
+        self.planner.push_back(Block {
+            kind: BlockKind::Probe {
+                success: trigger.is_some(),
+                error,
+            },
+            start,
+            end: contact,
+            duration: (distance(&start, &contact) / feed * 60.0).max(1e-6),
+            feed,
+            line_number: self.line_number,
+            ack: true,
+        });
+        self.target = contact;
+        Ok(Outcome::Deferred)
+    }
+
+    /// Length of a G2/G3 arc given by IJK offsets or R
+    fn arc_length(
+        &self,
+        clockwise: bool,
+        block: &ParsedBlock,
+        start: &[f64; 3],
+        end: &[f64; 3],
+        scale: f64,
+    ) -> std::result::Result<f64, GrblError> {
+        let (a, b, linear) = match self.modal.plane {
+            18 => (2, 0, 1),
+            19 => (1, 2, 0),
+            _ => (0, 1, 2),
+        };
+        let helix = end[linear] - start[linear];
+        let (da, db) = (end[a] - start[a], end[b] - start[b]);
+
+        let (radius, sweep) = if let Some(r) = block.r {
+            let r = r * scale;
+            let chord = (da * da + db * db).sqrt();
+            if chord < 1e-9 {
+                return Err(GrblError::InvalidTarget);
+            }
+            if chord > 2.0 * r.abs() + 1e-6 {
+                return Err(GrblError::ArcRadiusError);
+            }
+            let half = (chord / (2.0 * r.abs())).min(1.0).asin();
+            let sweep = if r < 0.0 { TAU - 2.0 * half } else { 2.0 * half };
+            (r.abs(), sweep)
+        } else {
+            let offsets = [block.ijk[0], block.ijk[1], block.ijk[2]];
+            let (oa, ob) = (offsets[a], offsets[b]);
+            if oa.is_none() && ob.is_none() {
+                return Err(GrblError::NoOffsetsInPlane);
+            }
+            let (ca, cb) = (
+                start[a] + oa.unwrap_or(0.0) * scale,
+                start[b] + ob.unwrap_or(0.0) * scale,
+            );
+            let radius = ((start[a] - ca).powi(2) + (start[b] - cb).powi(2)).sqrt();
+            let end_radius = ((end[a] - ca).powi(2) + (end[b] - cb).powi(2)).sqrt();
+            let error = (end_radius - radius).abs();
+            if error > 0.005 && error > 0.001 * radius {
+                return Err(GrblError::InvalidTarget);
+            }
+            let start_angle = (start[b] - cb).atan2(start[a] - ca);
+            let end_angle = (end[b] - cb).atan2(end[a] - ca);
+            let mut sweep = if clockwise {
+                start_angle - end_angle
+            } else {
+                end_angle - start_angle
+            };
+            sweep = sweep.rem_euclid(TAU);
+            if sweep < 1e-9 {
+                sweep = TAU;
+            }
+            (radius, sweep)
+        };
+
+        Ok(((radius * sweep).powi(2) + helix * helix).sqrt())
+    }
+
+    fn plan(
+        &mut self,
+        kind: BlockKind,
+        start: [f64; 3],
+        end: [f64; 3],
+        duration: f64,
+        feed: f64,
+        ack: bool,
+    ) {
+        if kind != BlockKind::Dwell && distance(&start, &end) < 1e-9 {
+            return;
+        }
+        self.planner.push_back(Block {
+            kind,
+            start,
+            end,
+            duration,
+            feed,
+            line_number: self.line_number,
+            ack,
+        });
+    }
+
+    /// Build a `<...>` status report as configured by `$10`
+    fn status_report(&mut self) -> String {
+        let state = match (self.state, self.sub_state) {
+            (MachineState::Hold, Some(sub)) => format!("Hold:{}", sub),
+            (MachineState::Door, Some(sub)) => format!("Door:{}", sub),
+            (state, _) => format!("{:?}", state),
+        };
+
+        let mask = self.setting(10) as u32;
+        let wco = self.wco();
+        let mut report = if mask & 1 != 0 {
+            format!("<{}|MPos:{}", state, format_xyz(&self.mpos))
+        } else {
+            let wpos = [
+                self.mpos[0] - wco[0],
+                self.mpos[1] - wco[1],
+                self.mpos[2] - wco[2],
+            ];
+            format!("<{}|WPos:{}", state, format_xyz(&wpos))
+        };
+
+        if mask & 2 != 0 {
+            report.push_str(&format!(
+                "|Bf:{},{}",
+                PLANNER_BLOCKS - self.planner.len().min(PLANNER_BLOCKS),
+                GRBL_RX_BUFFER_SIZE.saturating_sub(self.rx_used())
+            ));
+        }
+
+        let active = self.planner.front().filter(|_| {
+            matches!(
+                self.state,
+                MachineState::Run | MachineState::Jog | MachineState::Home
+            )
+        });
+        if let Some(n) = self.planner.front().and_then(|b| b.line_number) {
+            report.push_str(&format!("|Ln:{}", n));
+        }
+        let feed = active
+            .map(|b| b.feed * self.rate_scale(b.kind))
+            .unwrap_or(0.0);
+        let speed = if self.modal.spindle == Spindle::Off {
+            0.0
+        } else {
+            self.modal.spindle_speed * self.overrides.spindle as f64 / 100.0
+        };
+        report.push_str(&format!("|FS:{},{}", feed.round(), speed.round()));
+
+        if self.state == MachineState::Door {
+            report.push_str("|Pn:D");
+        }
+
+        if self.wco_counter > 0 {
+            self.wco_counter -= 1;
+        } else {
+            report.push_str(&format!("|WCO:{}", format_xyz(&wco)));
+            self.wco_counter = 9;
+            // GRBL never sends WCO and Ov in the same report
+            if self.ovr_counter == 0 {
+                self.ovr_counter = 1;
+            }
+        }
+
+        if self.ovr_counter > 0 {
+            self.ovr_counter -= 1;
+        } else {
+            report.push_str(&format!(
+                "|Ov:{},{},{}",
+                self.overrides.feed, self.overrides.rapid, self.overrides.spindle
+            ));
+            let mut accessories = String::new();
+            match self.modal.spindle {
+                Spindle::Cw => accessories.push('S'),
+                Spindle::Ccw => accessories.push('C'),
+                Spindle::Off => {}
+            }
+            if self.modal.flood {
+                accessories.push('F');
+            }
+            if self.modal.mist {
+                accessories.push('M');
+            }
+            if !accessories.is_empty() {
+                report.push_str(&format!("|A:{}", accessories));
+            }
+            self.ovr_counter = 9;
+        }
+
+        report.push('>');
+        report
+    }
+}
+
+/// Program flow stop words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProgramStop {
+    /// M0/M1
+    Pause,
+    /// M2/M30
+    End,
+}
+
+/// Non-modal commands that use axis words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NonModal {
+    SetCoordinates,
+    Home28,
+    StoreHome28,
+    Home30,
+    StoreHome30,
+    SetG92,
+    ClearG92,
+}
+
+/// Words collected from a single G-code block
+#[derive(Debug, Default)]
+struct ParsedBlock {
+    motion: Option<MotionMode>,
+    non_modal: Option<NonModal>,
+    dwell: bool,
+    machine_coords: bool,
+    plane: Option<u8>,
+    inches: Option<bool>,
+    incremental: Option<bool>,
+    inverse_time: Option<bool>,
+    wcs: Option<usize>,
+    /// G43.1 takes its offset from the Z word
+    dynamic_tool_length: bool,
+    /// Some(offset) for G43.1 and G49 (zero offset)
+    tool_length: Option<f64>,
+    spindle: Option<Spindle>,
+    /// (mist, flood)
+    coolant: Option<(bool, bool)>,
+    stop: Option<ProgramStop>,
+    axes: [Option<f64>; 3],
+    ijk: [Option<f64>; 3],
+    r: Option<f64>,
+    feed: Option<f64>,
+    spindle_speed: Option<f64>,
+    tool: Option<u32>,
+    line_number: Option<u32>,
+    p: Option<f64>,
+    l: Option<u32>,
+}
+
+impl ParsedBlock {
+    fn add_word(&mut self, letter: char, value: f64) -> std::result::Result<(), GrblError> {
+        match letter {
+            'G' => self.add_g(value)?,
+            'M' => self.add_m(value)?,
+            'X' => self.axes[0] = Some(value),
+            'Y' => self.axes[1] = Some(value),
+            'Z' => self.axes[2] = Some(value),
+            'I' => self.ijk[0] = Some(value),
+            'J' => self.ijk[1] = Some(value),
+            'K' => self.ijk[2] = Some(value),
+            'R' => self.r = Some(value),
+            'F' => self.feed = Some(value),
+            'S' => {
+                if value < 0.0 {
+                    return Err(GrblError::NegativeValue);
+                }
+                self.spindle_speed = Some(value)
+            }
+            'T' => {
+                if value.fract() != 0.0 {
+                    return Err(GrblError::CommandValueNotInteger);
+                }
+                if !(0.0..=255.0).contains(&value) {
+                    return Err(GrblError::MaxValueExceeded);
+                }
+                self.tool = Some(value as u32)
+            }
+            'N' => {
+                if value.fract() != 0.0 || !(0.0..=10_000_000.0).contains(&value) {
+                    return Err(GrblError::InvalidLineNumber);
+                }
+                self.line_number = Some(value as u32)
+            }
+            'P' => self.p = Some(value),
+            'L' => {
+                if value.fract() != 0.0 {
+                    return Err(GrblError::CommandValueNotInteger);
+                }
+                self.l = Some(value as u32)
+            }
+            _ => return Err(GrblError::UnsupportedCommand),
+        }
+        Ok(())
+    }
+
+    fn add_g(&mut self, value: f64) -> std::result::Result<(), GrblError> {
+        let code = (value * 10.0).round() as i32;
+        let set_motion = |block: &mut Self, mode| {
+            if block.motion.is_some() {
+                return Err(GrblError::ModalGroupViolation);
+            }
+            block.motion = Some(mode);
+            Ok(())
+        };
+        let set_non_modal = |block: &mut Self, command| {
+            if block.non_modal.is_some() || block.dwell {
+                return Err(GrblError::ModalGroupViolation);
+            }
+            block.non_modal = Some(command);
+            Ok(())
+        };
+
+        match code {
+            0 => set_motion(self, MotionMode::Rapid)?,
+            10 => set_motion(self, MotionMode::Linear)?,
+            20 => set_motion(self, MotionMode::ArcCw)?,
+            30 => set_motion(self, MotionMode::ArcCcw)?,
+            382 => set_motion(self, MotionMode::Probe { toward: true, error: true })?,
+            383 => set_motion(self, MotionMode::Probe { toward: true, error: false })?,
+            384 => set_motion(self, MotionMode::Probe { toward: false, error: true })?,
+            385 => set_motion(self, MotionMode::Probe { toward: false, error: false })?,
+            800 => set_motion(self, MotionMode::Cancel)?,
+            40 => {
+                if self.non_modal.is_some() {
+                    return Err(GrblError::ModalGroupViolation);
+                }
+                self.dwell = true
+            }
+            100 => set_non_modal(self, NonModal::SetCoordinates)?,
+            280 => set_non_modal(self, NonModal::Home28)?,
+            281 => set_non_modal(self, NonModal::StoreHome28)?,
+            300 => set_non_modal(self, NonModal::Home30)?,
+            301 => set_non_modal(self, NonModal::StoreHome30)?,
+            920 => set_non_modal(self, NonModal::SetG92)?,
+            921 => set_non_modal(self, NonModal::ClearG92)?,
+            170 => self.plane = Some(17),
+            180 => self.plane = Some(18),
+            190 => self.plane = Some(19),
+            200 => self.inches = Some(true),
+            210 => self.inches = Some(false),
+            431 => self.dynamic_tool_length = true,
+            490 => self.tool_length = Some(0.0),
+            530 => self.machine_coords = true,
+            540 | 550 | 560 | 570 | 580 | 590 => self.wcs = Some(((code - 540) / 10) as usize),
+            900 => self.incremental = Some(false),
+            910 => self.incremental = Some(true),
+            911 => {}
+            930 => self.inverse_time = Some(true),
+            940 => self.inverse_time = Some(false),
+            _ => return Err(GrblError::UnsupportedCommand),
+        }
+        Ok(())
+    }
+
+    fn add_m(&mut self, value: f64) -> std::result::Result<(), GrblError> {
+        match (value * 10.0).round() as i32 {
+            0 | 10 => self.stop = Some(ProgramStop::Pause),
+            20 | 300 => self.stop = Some(ProgramStop::End),
+            30 => self.spindle = Some(Spindle::Cw),
+            40 => self.spindle = Some(Spindle::Ccw),
+            50 => self.spindle = Some(Spindle::Off),
+            70 => {
+                let flood = self.coolant.map(|(_, flood)| flood).unwrap_or(false);
+                self.coolant = Some((true, flood))
+            }
+            80 => {
+                let mist = self.coolant.map(|(mist, _)| mist).unwrap_or(false);
+                self.coolant = Some((mist, true))
+            }
+            90 => self.coolant = Some((false, false)),
+            _ => return Err(GrblError::UnsupportedCommand),
+        }
+        Ok(())
+    }
+}
+
+/// Split a block such as `G1X10.5F300` into letter/value words
+fn parse_words(line: &str) -> std::result::Result<Vec<(char, f64)>, GrblError> {
+    let mut words = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(letter) = chars.next() {
+        if !letter.is_ascii_alphabetic() {
+            return Err(GrblError::ExpectedCommandLetter);
+        }
+        let mut number = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_ascii_digit() || c == '.' || c == '-' || c == '+' {
+                number.push(c);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        let value: f64 = number.parse().map_err(|_| GrblError::BadNumberFormat)?;
+        words.push((letter, value));
+    }
+    Ok(words)
+}
+
+fn format_xyz(values: &[f64; 3]) -> String {
+    format!("{:.3},{:.3},{:.3}", values[0], values[1], values[2])
+}
+
+fn distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2) + (b[2] - a[2]).powi(2)).sqrt()
+}
+
+fn to_array(position: &Position) -> [f64; 3] {
+    [position.x, position.y, position.z]
+}
+
+fn to_position(values: &[f64; 3]) -> Position {
+    Position {
+        x: values[0],
+        y: values[1],
+        z: values[2],
+    }
+}
+
+/// Virtual GRBL 1.1 machine reachable as a loopback transport
+///
+/// Settings and coordinate offsets survive reconnects like EEPROM does; the
+/// machine position is lost on every connect, as on a real power-up.
+pub struct SimulatedGrbl {
+    machine: Arc<Mutex<SimMachine>>,
+}
+
+impl SimulatedGrbl {
+    /// Create a simulator with default settings running in real time
+    pub fn new() -> Self {
+        Self::with_config(SimulatorConfig::default())
+    }
+
+    /// Create a simulator with a custom configuration
+    pub fn with_config(config: SimulatorConfig) -> Self {
+        SimulatedGrbl {
+            machine: Arc::new(Mutex::new(SimMachine::new(config))),
+        }
+    }
+
+    /// Get the simulated machine state
+    pub async fn state(&self) -> MachineState {
+        let mut machine = self.machine.lock().await;
+        machine.tick(Instant::now());
+        machine.state
+    }
+
+    /// Get the simulated machine position
+    pub async fn machine_position(&self) -> Position {
+        let mut machine = self.machine.lock().await;
+        machine.tick(Instant::now());
+        to_position(&machine.mpos)
+    }
+
+    /// Get a setting value
+    pub async fn setting(&self, number: u16) -> Option<f64> {
+        let machine = self.machine.lock().await;
+        machine.settings.get(&number).copied()
+    }
+
+    /// Raise an alarm as if the hardware had triggered it (e.g. a hard limit)
+    pub async fn trigger_alarm(&self, alarm: GrblAlarm) {
+        let mut machine = self.machine.lock().await;
+        machine.tick(Instant::now());
+        machine.alarm(alarm);
+    }
+
+    /// Drop the link as a cable glitch or USB reset would
+    ///
+    /// The next `connect` powers the board up again, like an Arduino that
+    /// resets when the port reopens.
+    pub async fn drop_link(&self) {
+        let mut machine = self.machine.lock().await;
+        machine.connected = false;
+    }
+
+    /// Get the most bytes that were ever waiting in the RX buffer
+    ///
+    /// A value above `GRBL_RX_BUFFER_SIZE` means the host overflowed the buffer.
+    pub async fn rx_peak(&self) -> usize {
+        let machine = self.machine.lock().await;
+        machine.rx_peak
+    }
+}
+
+impl Default for SimulatedGrbl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Transport for SimulatedGrbl {
+    fn kind(&self) -> TransportKind {
+        TransportKind::Simulator
+    }
+
+    async fn connect(&self, _address: &str) -> Result<()> {
+        let mut machine = self.machine.lock().await;
+        machine.connected = true;
+        machine.power_on(Instant::now());
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        let mut machine = self.machine.lock().await;
+        machine.connected = false;
+        Ok(())
+    }
+
+    async fn send_bytes(&self, data: &[u8]) -> Result<usize> {
+        let mut machine = self.machine.lock().await;
+        if !machine.connected {
+            return Err(anyhow!("Simulator not connected"));
+        }
+        let now = Instant::now();
+        machine.tick(now);
+        machine.receive(data, now);
+        machine.tick(now);
+        Ok(data.len())
+    }
+
+    async fn read_response_timeout(&self, max_size: usize, timeout: Duration) -> Result<String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            {
+                let mut machine = self.machine.lock().await;
+                if !machine.connected {
+                    return Err(anyhow!("Simulator not connected"));
+                }
+                machine.tick(Instant::now());
+                if !machine.output.is_empty() {
+                    let n = machine.output.len().min(max_size);
+                    return Ok(machine.output.drain(..n).collect());
+                }
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(anyhow!("Read operation timed out"));
+            }
+            tokio::time::sleep((deadline - now).min(POLL_INTERVAL)).await;
+        }
+    }
+
+    async fn is_connected(&self) -> bool {
+        let machine = self.machine.lock().await;
+        machine.connected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine() -> (SimMachine, Instant) {
+        let mut machine = SimMachine::new(SimulatorConfig::default());
+        let now = Instant::now();
+        machine.connected = true;
+        machine.power_on(now);
+        machine.output.clear();
+        (machine, now)
+    }
+
+    fn send(machine: &mut SimMachine, now: Instant, line: &str) -> String {
+        machine.receive(format!("{}\n", line).as_bytes(), now);
+        machine.tick(now);
+        std::mem::take(&mut machine.output)
+    }
+
+    #[test]
+    fn test_banner_on_power_up() {
+        let mut machine = SimMachine::new(SimulatorConfig::default());
+        machine.power_on(Instant::now());
+        assert!(machine.output.contains(BANNER));
+        assert_eq!(machine.state, MachineState::Idle);
+
+        let mut homing = SimMachine::new(SimulatorConfig {
+            homing_enabled: true,
+            ..Default::default()
+        });
+        homing.power_on(Instant::now());
+        assert_eq!(homing.state, MachineState::Alarm);
+        assert!(homing.output.contains("'$X' to unlock"));
+    }
+
+    #[test]
+    fn test_motion_timing() {
+        let (mut machine, now) = machine();
+        assert_eq!(send(&mut machine, now, "G1 X10 F600"), "ok\r\n");
+        assert_eq!(machine.state, MachineState::Run);
+
+        // 10 mm at 600 mm/min takes one second
+        machine.tick(now + Duration::from_millis(500));
+        assert!((machine.mpos[0] - 5.0).abs() < 1e-6);
+        machine.tick(now + Duration::from_millis(1001));
+        assert_eq!(machine.mpos[0], 10.0);
+        assert_eq!(machine.state, MachineState::Idle);
+    }
+
+    #[test]
+    fn test_planner_full_delays_ok() {
+        let (mut machine, now) = machine();
+        let mut oks = 0;
+        for i in 1..=20 {
+            oks += send(&mut machine, now, &format!("G1 X{} F6000", i))
+                .matches("ok")
+                .count();
+        }
+        assert_eq!(oks, PLANNER_BLOCKS);
+        assert_eq!(machine.rx_lines.len(), 20 - PLANNER_BLOCKS);
+
+        machine.tick(now + Duration::from_secs(5));
+        assert_eq!(machine.output.matches("ok").count(), 20 - PLANNER_BLOCKS);
+        assert_eq!(machine.mpos[0], 20.0);
+    }
+
+    #[test]
+    fn test_errors_and_alarm_lock() {
+        let (mut machine, now) = machine();
+        assert_eq!(send(&mut machine, now, "G1 X1"), "error:22\r\n");
+        assert_eq!(send(&mut machine, now, "G99"), "error:20\r\n");
+        assert_eq!(send(&mut machine, now, "$99=1"), "error:3\r\n");
+        assert_eq!(send(&mut machine, now, "$H"), "error:5\r\n");
+
+        machine.alarm(GrblAlarm::HardLimit);
+        machine.output.clear();
+        assert_eq!(send(&mut machine, now, "$X"), "");
+        machine.soft_reset(now);
+        machine.output.clear();
+        assert_eq!(send(&mut machine, now, "G0 X1"), "error:9\r\n");
+        assert!(send(&mut machine, now, "$X").contains("Unlocked"));
+        assert_eq!(machine.state, MachineState::Idle);
+    }
+
+    #[test]
+    fn test_work_offsets() {
+        let (mut machine, now) = machine();
+        send(&mut machine, now, "G0 X10 Y5");
+        machine.tick(now + Duration::from_secs(5));
+        send(&mut machine, now + Duration::from_secs(5), "G10 L20 P1 X0 Y0");
+        assert_eq!(machine.coord_systems[0], [10.0, 5.0, 0.0]);
+
+        let offsets = send(&mut machine, now + Duration::from_secs(5), "$#");
+        assert!(offsets.contains("[G54:10.000,5.000,0.000]"));
+
+        send(&mut machine, now + Duration::from_secs(5), "G92 Z2");
+        assert_eq!(machine.wco(), [10.0, 5.0, -2.0]);
+
+        let modes = send(&mut machine, now + Duration::from_secs(5), "$G");
+        assert!(modes.starts_with("[GC:G0 G54 G17 G21 G90 G94 M5 M9 T0 F0 S0]"));
+    }
+
+    #[test]
+    fn test_status_report_fields() {
+        let (mut machine, now) = machine();
+        machine.receive(b"?", now);
+        let report = std::mem::take(&mut machine.output);
+        assert!(report.starts_with("<Idle|MPos:0.000,0.000,0.000|FS:0,0|WCO:"));
+
+        machine.receive(b"?", now);
+        assert!(machine.output.contains("|Ov:100,100,100"));
+    }
+
+    #[test]
+    fn test_feed_hold_and_resume() {
+        let (mut machine, now) = machine();
+        send(&mut machine, now, "G1 X10 F600");
+        machine.receive(b"!", now + Duration::from_millis(500));
+        assert_eq!(machine.state, MachineState::Hold);
+
+        machine.tick(now + Duration::from_secs(5));
+        assert!((machine.mpos[0] - 5.0).abs() < 1e-6);
+
+        machine.receive(b"~", now + Duration::from_secs(5));
+        machine.tick(now + Duration::from_millis(5600));
+        assert_eq!(machine.mpos[0], 10.0);
+    }
+
+    #[test]
+    fn test_reset_during_motion_raises_alarm() {
+        let (mut machine, now) = machine();
+        send(&mut machine, now, "G1 X10 F600");
+        machine.receive(&[RealtimeCommand::SoftReset.byte()], now);
+        assert!(machine.output.starts_with("ALARM:3"));
+        assert_eq!(machine.state, MachineState::Alarm);
+    }
+
+    #[test]
+    fn test_homing_cycle() {
+        let (mut machine, now) = machine();
+        send(&mut machine, now, "$22=1");
+        assert_eq!(send(&mut machine, now, "$H"), "");
+        assert_eq!(machine.state, MachineState::Home);
+
+        machine.tick(now + Duration::from_secs(1));
+        assert_eq!(machine.output, "ok\r\n");
+        assert_eq!(machine.state, MachineState::Idle);
+        assert_eq!(machine.mpos, [-1.0, -1.0, -1.0]);
+    }
+
+    #[test]
+    fn test_check_mode() {
+        let (mut machine, now) = machine();
+        assert!(send(&mut machine, now, "$C").contains("[MSG:Enabled]"));
+        assert_eq!(send(&mut machine, now, "G1 X10 F100"), "ok\r\n");
+        assert!(machine.planner.is_empty());
+        assert_eq!(send(&mut machine, now, "G2 X1"), "error:35\r\n");
+
+        let exit = send(&mut machine, now, "$C");
+        assert!(exit.starts_with("[MSG:Disabled]\r\nok\r\n"));
+        assert!(exit.contains(BANNER));
+        assert_eq!(machine.state, MachineState::Idle);
+    }
+
+    #[test]
+    fn test_soft_limits() {
+        let (mut machine, now) = machine();
+        send(&mut machine, now, "$22=1");
+        send(&mut machine, now, "$20=1");
+        assert_eq!(send(&mut machine, now, "$J=G91 X10 F500"), "error:15\r\n");
+        assert!(send(&mut machine, now, "G0 X10").starts_with("ALARM:2\r\n"));
+        assert_eq!(machine.state, MachineState::Alarm);
+    }
+
+    #[test]
+    fn test_arc_length() {
+        let (machine, _) = machine();
+        let mut block = ParsedBlock::default();
+        block.ijk[0] = Some(5.0);
+        let length = machine
+            .arc_length(true, &block, &[0.0; 3], &[10.0, 0.0, 0.0], 1.0)
+            .unwrap();
+        assert!((length - 5.0 * std::f64::consts::PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_probe_cycle() {
+        let mut machine = SimMachine::new(SimulatorConfig {
+            probe_zones: vec![ProbeZone {
+                min: Position { x: -20.0, y: -20.0, z: -50.0 },
+                max: Position { x: 0.0, y: 0.0, z: -10.0 },
+            }],
+            ..Default::default()
+        });
+        let now = Instant::now();
+        machine.power_on(now);
+        machine.output.clear();
+
+        // The ok waits for the probe move and follows the PRB report
+        assert_eq!(send(&mut machine, now, "G38.2 Z-20 F600"), "");
+        machine.tick(now + Duration::from_secs(1));
+        assert_eq!(machine.output, "[PRB:0.000,0.000,-10.000:1]\r\nok\r\n");
+        assert_eq!(machine.mpos[2], -10.0);
+        machine.output.clear();
+
+        // Already touching: G38.2 cannot start
+        assert_eq!(send(&mut machine, now, "G38.2 Z-15"), "ALARM:4\r\nok\r\n");
+        send(&mut machine, now, "$X");
+
+        // Probe away until contact opens
+        send(&mut machine, now, "G38.4 Z0");
+        machine.tick(now + Duration::from_secs(3));
+        assert!(machine.output.contains("[PRB:0.000,0.000,-10.000:1]"));
+        machine.output.clear();
+
+        // Missing the plate: G38.3 reports failure, G38.2 alarms
+        send(&mut machine, now, "G0 Z0");
+        send(&mut machine, now, "G38.3 Z-5");
+        machine.tick(now + Duration::from_secs(4));
+        assert_eq!(machine.output, "[PRB:0.000,0.000,-5.000:0]\r\nok\r\n");
+        assert_eq!(machine.state, MachineState::Idle);
+        machine.output.clear();
+        send(&mut machine, now, "G38.2 X10");
+        machine.tick(now + Duration::from_secs(6));
+        assert!(machine.output.starts_with("ALARM:5\r\n[PRB:10.000,0.000,-5.000:0]"));
+        assert_eq!(machine.state, MachineState::Alarm);
+    }
+}
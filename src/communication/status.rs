@@ -0,0 +1,302 @@
+//! GRBL real-time status report parsing
+//!
+//! Parses `<Idle|MPos:0.000,0.000,0.000|FS:0,0|...>` reports into typed fields.
+//! Fields that GRBL only reports intermittently (WCO, Ov, A) are kept optional so
+//! they can be merged into the last known `GrblStatus`.
+
+use super::{MachineState, Position};
+use serde::{Deserialize, Serialize};
+
+/// Planner and serial RX buffer availability (`Bf:`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BufferState {
+    /// Free blocks in the planner buffer
+    pub planner_blocks: u32,
+    /// Free bytes in the serial RX buffer
+    pub rx_bytes: u32,
+}
+
+/// Override values in percent (`Ov:`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OverrideValues {
+    pub feed: u32,
+    pub rapid: u32,
+    pub spindle: u32,
+}
+
+impl Default for OverrideValues {
+    fn default() -> Self {
+        OverrideValues {
+            feed: 100,
+            rapid: 100,
+            spindle: 100,
+        }
+    }
+}
+
+/// Input pin states (`Pn:`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinState {
+    pub limit_x: bool,
+    pub limit_y: bool,
+    pub limit_z: bool,
+    pub probe: bool,
+    pub door: bool,
+    pub hold: bool,
+    pub soft_reset: bool,
+    pub cycle_start: bool,
+}
+
+impl PinState {
+    /// Parse pin letters such as `XYZPDHRS`
+    pub fn parse(pins: &str) -> Self {
+        let mut state = PinState::default();
+        for ch in pins.chars() {
+            match ch {
+                'X' => state.limit_x = true,
+                'Y' => state.limit_y = true,
+                'Z' => state.limit_z = true,
+                'P' => state.probe = true,
+                'D' => state.door = true,
+                'H' => state.hold = true,
+                'R' => state.soft_reset = true,
+                'S' => state.cycle_start = true,
+                _ => {}
+            }
+        }
+        state
+    }
+
+    /// Check if any limit switch is triggered
+    pub fn any_limit(&self) -> bool {
+        self.limit_x || self.limit_y || self.limit_z
+    }
+}
+
+/// Spindle and coolant accessory states (`A:`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessoryState {
+    pub spindle_cw: bool,
+    pub spindle_ccw: bool,
+    pub flood: bool,
+    pub mist: bool,
+}
+
+impl AccessoryState {
+    /// Parse accessory letters such as `SFM`
+    pub fn parse(accessories: &str) -> Self {
+        let mut state = AccessoryState::default();
+        for ch in accessories.chars() {
+            match ch {
+                'S' => state.spindle_cw = true,
+                'C' => state.spindle_ccw = true,
+                'F' => state.flood = true,
+                'M' => state.mist = true,
+                _ => {}
+            }
+        }
+        state
+    }
+}
+
+/// A single parsed status report
+///
+/// Every field except `state` is optional because GRBL only includes the fields
+/// enabled by `$10` and refreshes WCO/Ov on their own schedule.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatusReport {
+    pub state: MachineState,
+    /// Sub-state code for Hold and Door states (e.g. `Hold:1`)
+    pub sub_state: Option<u8>,
+    pub mpos: Option<Position>,
+    pub wpos: Option<Position>,
+    pub wco: Option<Position>,
+    pub feed_rate: Option<f64>,
+    pub spindle_speed: Option<f64>,
+    pub buffer: Option<BufferState>,
+    pub overrides: Option<OverrideValues>,
+    pub pins: Option<PinState>,
+    pub line_number: Option<u32>,
+    pub accessories: Option<AccessoryState>,
+}
+
+impl StatusReport {
+    /// Parse a status report line
+    ///
+    /// # Returns
+    /// None if the line is not a well-formed `<...>` report
+    pub fn parse(line: &str) -> Option<Self> {
+        let body = line.trim().strip_prefix('<')?.strip_suffix('>')?;
+        let mut fields = body.split('|');
+
+        let state_field = fields.next()?;
+        let (state_name, sub_state) = match state_field.split_once(':') {
+            Some((name, code)) => (name, code.parse().ok()),
+            None => (state_field, None),
+        };
+
+        let mut report = StatusReport {
+            state: MachineState::from_str(state_name),
+            sub_state,
+            ..Default::default()
+        };
+
+        for field in fields {
+            let (key, value) = match field.split_once(':') {
+                Some(pair) => pair,
+                None => continue,
+            };
+
+            match key {
+                "MPos" => report.mpos = parse_position(value),
+                "WPos" => report.wpos = parse_position(value),
+                "WCO" => report.wco = parse_position(value),
+                "FS" => {
+                    let values = parse_numbers(value);
+                    report.feed_rate = values.first().copied();
+                    report.spindle_speed = values.get(1).copied();
+                }
+                "F" => report.feed_rate = value.trim().parse().ok(),
+                "Bf" => {
+                    let values = parse_numbers(value);
+                    if values.len() >= 2 {
+                        report.buffer = Some(BufferState {
+                            planner_blocks: values[0] as u32,
+                            rx_bytes: values[1] as u32,
+                        });
+                    }
+                }
+                "Ov" => {
+                    let values = parse_numbers(value);
+                    if values.len() >= 3 {
+                        report.overrides = Some(OverrideValues {
+                            feed: values[0] as u32,
+                            rapid: values[1] as u32,
+                            spindle: values[2] as u32,
+                        });
+                    }
+                }
+                "Pn" => report.pins = Some(PinState::parse(value)),
+                "Ln" => report.line_number = value.trim().parse().ok(),
+                "A" => report.accessories = Some(AccessoryState::parse(value)),
+                _ => {}
+            }
+        }
+
+        Some(report)
+    }
+
+    /// Parse a GRBL 0.9 style report such as
+    /// `<Idle,MPos:0.000,0.000,0.000,WPos:0.000,0.000,0.000>`
+    ///
+    /// Smoothieware still prints this format unless its new status format
+    /// is enabled.
+    pub fn parse_legacy(line: &str) -> Option<Self> {
+        let body = line.trim().strip_prefix('<')?.strip_suffix('>')?;
+        if body.contains('|') {
+            return None;
+        }
+        let mut tokens = body.split(',');
+        let mut report = StatusReport {
+            state: MachineState::from_str(tokens.next()?),
+            ..Default::default()
+        };
+
+        // Keyed fields start a new group; the remaining values follow as plain tokens
+        let mut groups: Vec<(String, Vec<&str>)> = Vec::new();
+        for token in tokens {
+            match token.split_once(':') {
+                Some((key, value)) => groups.push((key.trim().to_string(), vec![value])),
+                None => {
+                    if let Some((_, values)) = groups.last_mut() {
+                        values.push(token);
+                    }
+                }
+            }
+        }
+
+        for (key, values) in groups {
+            let value = values.join(",");
+            match key.as_str() {
+                "MPos" => report.mpos = parse_position(&value),
+                "WPos" => report.wpos = parse_position(&value),
+                "F" => report.feed_rate = parse_numbers(&value).first().copied(),
+                "Ln" => report.line_number = value.trim().parse().ok(),
+                _ => {}
+            }
+        }
+
+        Some(report)
+    }
+}
+
+/// Parse a comma-separated coordinate triple
+fn parse_position(value: &str) -> Option<Position> {
+    let values = parse_numbers(value);
+    if values.len() < 3 {
+        return None;
+    }
+    Some(Position {
+        x: values[0],
+        y: values[1],
+        z: values[2],
+    })
+}
+
+/// Parse a comma-separated list of numbers, skipping malformed entries
+fn parse_numbers(value: &str) -> Vec<f64> {
+    value
+        .split(',')
+        .filter_map(|v| v.trim().parse::<f64>().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_minimal_report() {
+        let report = StatusReport::parse("<Idle|MPos:1.000,2.000,3.000|FS:0,0>").unwrap();
+        assert_eq!(report.state, MachineState::Idle);
+        assert_eq!(report.mpos, Some(Position { x: 1.0, y: 2.0, z: 3.0 }));
+        assert_eq!(report.feed_rate, Some(0.0));
+        assert!(report.wco.is_none());
+    }
+
+    #[test]
+    fn test_parse_full_report() {
+        let line = "<Hold:1|WPos:10.000,0.000,-1.500|Bf:15,128|Ln:42|FS:500,12000|WCO:5.000,0.000,0.000|Ov:120,100,80|Pn:XP|A:SF>";
+        let report = StatusReport::parse(line).unwrap();
+        assert_eq!(report.state, MachineState::Hold);
+        assert_eq!(report.sub_state, Some(1));
+        assert_eq!(report.wpos.unwrap().z, -1.5);
+        assert_eq!(
+            report.buffer,
+            Some(BufferState { planner_blocks: 15, rx_bytes: 128 })
+        );
+        assert_eq!(report.line_number, Some(42));
+        assert_eq!(report.spindle_speed, Some(12000.0));
+        assert_eq!(report.overrides.unwrap().feed, 120);
+        let pins = report.pins.unwrap();
+        assert!(pins.limit_x && pins.probe && !pins.door);
+        let accessories = report.accessories.unwrap();
+        assert!(accessories.spindle_cw && accessories.flood && !accessories.mist);
+    }
+
+    #[test]
+    fn test_parse_legacy_report() {
+        let line = "<Run,MPos:5.0000,1.0000,-2.0000,WPos:4.0000,1.0000,-2.0000>";
+        let report = StatusReport::parse_legacy(line).unwrap();
+        assert_eq!(report.state, MachineState::Run);
+        assert_eq!(report.mpos, Some(Position { x: 5.0, y: 1.0, z: -2.0 }));
+        assert_eq!(report.wpos.unwrap().x, 4.0);
+        assert!(StatusReport::parse_legacy("<Idle|MPos:0,0,0>").is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_non_report() {
+        assert!(StatusReport::parse("ok").is_none());
+        assert!(StatusReport::parse("<Idle|MPos:0,0,0").is_none());
+    }
+}
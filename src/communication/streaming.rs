@@ -0,0 +1,382 @@
+//! Character-counting streaming protocol for GRBL
+//!
+//! Tracks how many bytes are occupying GRBL's serial RX buffer so several lines
+//! can be kept in flight at once, and matches every `ok`/`error:N` response back
+//! to the line that produced it. A simple send-response mode is kept as a fallback
+//! for controllers that misbehave with a full buffer.
+
+use super::GrblResponse;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Size of the GRBL serial RX buffer in bytes
+pub const GRBL_RX_BUFFER_SIZE: usize = 128;
+
+/// Protocol used to feed G-code lines to the controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum StreamingMode {
+    /// Keep as many lines in flight as fit in the RX buffer
+    #[default]
+    CharacterCounting,
+    /// Send one line and wait for its response before sending the next
+    SendResponse,
+}
+
+/// Streaming configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamingConfig {
+    pub mode: StreamingMode,
+    pub rx_buffer_size: usize,
+    /// Maximum time to wait without any acknowledgement before giving up
+    pub response_timeout_ms: u64,
+}
+
+impl StreamingConfig {
+    /// Get response timeout as a duration
+    pub fn response_timeout(&self) -> Duration {
+        Duration::from_millis(self.response_timeout_ms)
+    }
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        StreamingConfig {
+            mode: StreamingMode::CharacterCounting,
+            rx_buffer_size: GRBL_RX_BUFFER_SIZE,
+            response_timeout_ms: 30000,
+        }
+    }
+}
+
+/// A line waiting in the command queue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedLine {
+    pub command: String,
+    /// Index of the program line the command came from, for a job or a
+    /// check-mode run; None for commands outside a program (jogs, queries,
+    /// resume preambles)
+    pub job_line: Option<usize>,
+}
+
+impl QueuedLine {
+    /// Create a line that is not part of a job
+    pub fn new(command: impl Into<String>) -> Self {
+        QueuedLine {
+            command: command.into(),
+            job_line: None,
+        }
+    }
+
+    /// Create a line tagged with its index in a program
+    pub fn job(command: impl Into<String>, job_line: usize) -> Self {
+        QueuedLine {
+            command: command.into(),
+            job_line: Some(job_line),
+        }
+    }
+}
+
+/// A line that has been sent and is awaiting acknowledgement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingLine {
+    /// Sequence number of the line since the counter was created (0-based)
+    pub sequence: usize,
+    pub command: String,
+    /// Bytes the line occupies in the RX buffer, including the newline
+    pub bytes: usize,
+    /// Index of the job program line, if the line belongs to a job
+    pub job_line: Option<usize>,
+}
+
+/// Response matched to the line that caused it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineAck {
+    pub sequence: usize,
+    pub command: String,
+    pub response: GrblResponse,
+    /// Index of the job program line, if the line belongs to a job
+    #[serde(default)]
+    pub job_line: Option<usize>,
+}
+
+impl LineAck {
+    /// Check if the line was accepted by the controller
+    pub fn is_ok(&self) -> bool {
+        matches!(self.response, GrblResponse::Ok)
+    }
+}
+
+/// RX buffer accounting for the character-counting protocol
+#[derive(Debug)]
+pub struct CharacterCounter {
+    mode: StreamingMode,
+    capacity: usize,
+    used: usize,
+    next_sequence: usize,
+    in_flight: VecDeque<PendingLine>,
+    completed: VecDeque<LineAck>,
+    /// Callers waiting for the acknowledgement of one line, by sequence
+    waiters: HashMap<usize, oneshot::Sender<LineAck>>,
+}
+
+impl CharacterCounter {
+    /// Create a new counter from a streaming configuration
+    pub fn new(config: &StreamingConfig) -> Self {
+        CharacterCounter {
+            mode: config.mode,
+            capacity: config.rx_buffer_size,
+            used: 0,
+            next_sequence: 0,
+            in_flight: VecDeque::new(),
+            completed: VecDeque::new(),
+            waiters: HashMap::new(),
+        }
+    }
+
+    /// Apply a new configuration, keeping lines already in flight
+    pub fn configure(&mut self, config: &StreamingConfig) {
+        self.mode = config.mode;
+        self.capacity = config.rx_buffer_size;
+    }
+
+    /// Check whether a line can be sent without overflowing the RX buffer
+    pub fn can_send(&self, command: &str) -> bool {
+        if self.in_flight.is_empty() {
+            // An oversized line is still sent on its own; GRBL reports it
+            return true;
+        }
+
+        match self.mode {
+            StreamingMode::SendResponse => false,
+            // Line plus its newline must fit in the remaining space
+            StreamingMode::CharacterCounting => self.used + command.len() < self.capacity,
+        }
+    }
+
+    /// Record a line as sent
+    pub fn register(&mut self, command: &str) -> PendingLine {
+        self.register_line(&QueuedLine::new(command))
+    }
+
+    /// Record a queued line as sent, keeping its job tag
+    pub fn register_line(&mut self, line: &QueuedLine) -> PendingLine {
+        let pending = PendingLine {
+            sequence: self.next_sequence,
+            command: line.command.clone(),
+            bytes: line.command.len() + 1,
+            job_line: line.job_line,
+        };
+        self.next_sequence += 1;
+        self.used += pending.bytes;
+        self.in_flight.push_back(pending.clone());
+        pending
+    }
+
+    /// Record a line as sent and deliver its acknowledgement to the caller
+    ///
+    /// The acknowledgement goes to the returned channel instead of
+    /// `take_completed`, so a stream running at the same time cannot take
+    /// it. The channel closes without a value if the counter is reset first.
+    pub fn register_awaited(&mut self, command: &str) -> (PendingLine, oneshot::Receiver<LineAck>) {
+        let pending = self.register(command);
+        let (tx, rx) = oneshot::channel();
+        self.waiters.insert(pending.sequence, tx);
+        (pending, rx)
+    }
+
+    /// Match an `ok`/`error` response to the oldest line in flight
+    ///
+    /// # Returns
+    /// The acknowledged line, or None if the response is not an acknowledgement
+    /// or nothing is in flight
+    pub fn acknowledge(&mut self, response: &GrblResponse) -> Option<LineAck> {
+        if !matches!(response, GrblResponse::Ok | GrblResponse::Error(_)) {
+            return None;
+        }
+
+        let pending = self.in_flight.pop_front()?;
+        self.used = self.used.saturating_sub(pending.bytes);
+
+        let ack = LineAck {
+            sequence: pending.sequence,
+            command: pending.command,
+            response: response.clone(),
+            job_line: pending.job_line,
+        };
+        match self.waiters.remove(&ack.sequence) {
+            // A caller that gave up has dropped the receiver; the ack is spent
+            Some(waiter) => {
+                let _ = waiter.send(ack.clone());
+            }
+            None => self.completed.push_back(ack.clone()),
+        }
+        Some(ack)
+    }
+
+    /// Take all acknowledgements collected since the last call
+    pub fn take_completed(&mut self) -> Vec<LineAck> {
+        self.completed.drain(..).collect()
+    }
+
+    /// Get lines currently awaiting acknowledgement
+    pub fn in_flight(&self) -> impl Iterator<Item = &PendingLine> {
+        self.in_flight.iter()
+    }
+
+    /// Get number of lines awaiting acknowledgement
+    pub fn in_flight_count(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Get bytes currently occupying the RX buffer
+    pub fn buffer_used(&self) -> usize {
+        self.used
+    }
+
+    /// Get bytes still free in the RX buffer
+    pub fn buffer_available(&self) -> usize {
+        self.capacity.saturating_sub(self.used)
+    }
+
+    /// Check if no lines are awaiting acknowledgement
+    pub fn is_idle(&self) -> bool {
+        self.in_flight.is_empty()
+    }
+
+    /// Forget all lines in flight (after a soft reset or a lost connection)
+    pub fn reset(&mut self) {
+        self.in_flight.clear();
+        self.completed.clear();
+        self.waiters.clear();
+        self.used = 0;
+    }
+}
+
+impl Default for CharacterCounter {
+    fn default() -> Self {
+        Self::new(&StreamingConfig::default())
+    }
+}
+
+/// Strip comments and whitespace from a G-code line before streaming
+///
+/// # Returns
+/// The cleaned line, or None if nothing remains to send
+pub fn prepare_line(line: &str) -> Option<String> {
+    let mut cleaned = String::with_capacity(line.len());
+    let mut in_paren = false;
+
+    for ch in line.chars() {
+        match ch {
+            ';' if !in_paren => break,
+            '(' => in_paren = true,
+            ')' if in_paren => in_paren = false,
+            '\r' | '\n' => {}
+            _ if !in_paren => cleaned.push(ch),
+            _ => {}
+        }
+    }
+
+    let trimmed = cleaned.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::communication::GrblError;
+
+    #[test]
+    fn test_counter_fills_buffer() {
+        let mut counter = CharacterCounter::default();
+        let line = "G1 X10.000 Y10.000 F1000"; // 24 chars + newline
+        let mut sent = 0;
+        while counter.can_send(line) {
+            counter.register(line);
+            sent += 1;
+        }
+        assert_eq!(sent, GRBL_RX_BUFFER_SIZE / (line.len() + 1));
+        assert!(counter.buffer_used() <= GRBL_RX_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn test_counter_matches_acks_in_order() {
+        let mut counter = CharacterCounter::default();
+        counter.register("G0 X1");
+        counter.register("G0 X2");
+
+        let first = counter.acknowledge(&GrblResponse::Ok).unwrap();
+        assert_eq!(first.command, "G0 X1");
+        assert_eq!(first.sequence, 0);
+
+        let second = counter
+            .acknowledge(&GrblResponse::Error(GrblError::UnsupportedCommand))
+            .unwrap();
+        assert_eq!(second.command, "G0 X2");
+        assert!(!second.is_ok());
+
+        assert!(counter.is_idle());
+        assert_eq!(counter.buffer_used(), 0);
+        assert_eq!(counter.take_completed().len(), 2);
+    }
+
+    #[test]
+    fn test_counter_ignores_non_ack_responses() {
+        let mut counter = CharacterCounter::default();
+        counter.register("G0 X1");
+        assert!(counter
+            .acknowledge(&GrblResponse::Message("[MSG:Pgm End]".to_string()))
+            .is_none());
+        assert_eq!(counter.in_flight_count(), 1);
+    }
+
+    #[test]
+    fn test_awaited_line_skips_completed() {
+        let mut counter = CharacterCounter::default();
+        counter.register("G1 X1");
+        let (pending, mut ack) = counter.register_awaited("$X");
+        assert_eq!(pending.sequence, 1);
+
+        counter.acknowledge(&GrblResponse::Ok).unwrap();
+        counter
+            .acknowledge(&GrblResponse::Error(GrblError::UnsupportedCommand))
+            .unwrap();
+        let ack = ack.try_recv().unwrap();
+        assert_eq!(ack.command, "$X");
+        assert!(!ack.is_ok());
+        let completed = counter.take_completed();
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].command, "G1 X1");
+
+        // A reset drops the line, and the waiter hears nothing
+        let (_, mut ack) = counter.register_awaited("$H");
+        counter.reset();
+        assert!(ack.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_send_response_mode_single_line() {
+        let config = StreamingConfig {
+            mode: StreamingMode::SendResponse,
+            ..Default::default()
+        };
+        let mut counter = CharacterCounter::new(&config);
+        assert!(counter.can_send("G0 X1"));
+        counter.register("G0 X1");
+        assert!(!counter.can_send("G0 X2"));
+    }
+
+    #[test]
+    fn test_prepare_line() {
+        assert_eq!(prepare_line("G1 X10 ; cut"), Some("G1 X10".to_string()));
+        assert_eq!(prepare_line("(setup) G21"), Some("G21".to_string()));
+        assert_eq!(prepare_line("; comment only"), None);
+        assert_eq!(prepare_line("   \r\n"), None);
+    }
+}
@@ -0,0 +1,176 @@
+//! Connection supervision and state restore
+//!
+//! When the link drops (a USB reset, a WiFi hiccup) the controller reconnects
+//! with exponential backoff, then replays the work offsets and modal state
+//! captured before the drop. A GRBL board that reset on reconnect has lost
+//! its position, so jobs can only be resumed after homing.
+
+use super::offsets::{format_coordinate, Axis, CoordinateSystem, WorkOffsets};
+use super::parser_state::ParserState;
+use super::{Position, RecoveryConfig};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Longest wait between reconnect attempts
+pub const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Offsets closer than this are considered equal (GRBL reports 3 decimals)
+const OFFSET_TOLERANCE: f64 = 0.0005;
+
+/// Connection lifecycle events published by the supervisor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ConnectionEvent {
+    /// The link dropped without the user disconnecting
+    Lost { reason: String },
+    /// Waiting `delay_ms` before reconnect attempt `attempt`
+    Reconnecting { attempt: u32, delay_ms: u64 },
+    /// The link is back; `reset` is true if the controller rebooted and lost
+    /// its position
+    Reconnected { attempts: u32, reset: bool },
+    /// Offsets and modal state were replayed
+    Restored { commands: Vec<String> },
+    /// State could not be replayed yet (e.g. the controller waits for homing)
+    RestoreFailed { reason: String },
+}
+
+/// Machine state captured before a connection drop
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RestorePoint {
+    pub parser_state: ParserState,
+    pub offsets: WorkOffsets,
+}
+
+impl RestorePoint {
+    /// Build the commands that bring a controller back to this state
+    ///
+    /// Coordinate systems are only rewritten when they differ, since they
+    /// survive a reset in EEPROM. G92 and the tool length offset are lost on
+    /// reset; G92 can only be replayed when the current machine position is
+    /// known because GRBL sets it relative to that position.
+    ///
+    /// # Arguments
+    /// * `current` - Offsets the controller reports now
+    /// * `machine_position` - Current machine position, if known
+    pub fn restore_commands(
+        &self,
+        current: &WorkOffsets,
+        machine_position: Option<Position>,
+    ) -> Vec<String> {
+        let mut commands = Vec::new();
+
+        for wcs in CoordinateSystem::all() {
+            let wanted = self.offsets.get(wcs);
+            if !same_position(&wanted, &current.get(wcs)) {
+                commands.push(format!("G10 L2 P{}{}", wcs.p_number(), axis_words(&wanted)));
+            }
+        }
+
+        let tool_length = self.offsets.tool_length;
+        if (tool_length - current.tool_length).abs() > OFFSET_TOLERANCE {
+            if tool_length == 0.0 {
+                commands.push("G49".to_string());
+            } else {
+                commands.push(format!("G43.1 Z{}", format_coordinate(tool_length)));
+            }
+        }
+
+        if let Some(mpos) = machine_position {
+            if !same_position(&self.offsets.g92, &current.g92) {
+                // G92 makes the current position read the given values
+                let wcs = self.offsets.get(self.parser_state.coordinate_system);
+                let g92 = self.offsets.g92;
+                let values = Position {
+                    x: mpos.x - wcs.x - g92.x,
+                    y: mpos.y - wcs.y - g92.y,
+                    z: mpos.z - wcs.z - g92.z - tool_length,
+                };
+                commands.push(format!(
+                    "{} G92{}",
+                    self.parser_state.coordinate_system.code(),
+                    axis_words(&values)
+                ));
+            }
+        }
+
+        // Offsets are reported in mm, so write them in mm before the modal state
+        if !commands.is_empty() {
+            commands.insert(0, "G21".to_string());
+        }
+        commands.push(self.parser_state.to_gcode());
+        commands
+    }
+}
+
+/// Get the wait before a reconnect attempt
+///
+/// Starts at `reconnect_delay_ms` and doubles per attempt up to
+/// `MAX_RECONNECT_DELAY`.
+///
+/// # Arguments
+/// * `attempt` - Attempt number, starting at 1
+pub fn reconnect_delay(config: &RecoveryConfig, attempt: u32) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(16);
+    Duration::from_millis(config.reconnect_delay_ms.saturating_mul(factor)).min(MAX_RECONNECT_DELAY)
+}
+
+/// Format ` X.. Y.. Z..` for a position
+fn axis_words(position: &Position) -> String {
+    Axis::all()
+        .iter()
+        .map(|axis| format!(" {}{}", axis.letter(), format_coordinate(axis.of(position))))
+        .collect()
+}
+
+fn same_position(a: &Position, b: &Position) -> bool {
+    Axis::all()
+        .iter()
+        .all(|axis| (axis.of(a) - axis.of(b)).abs() <= OFFSET_TOLERANCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_delay_backs_off() {
+        let config = RecoveryConfig {
+            reconnect_delay_ms: 1000,
+            ..Default::default()
+        };
+        assert_eq!(reconnect_delay(&config, 1), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(&config, 3), Duration::from_secs(4));
+        assert_eq!(reconnect_delay(&config, 40), MAX_RECONNECT_DELAY);
+    }
+
+    #[test]
+    fn test_restore_commands_after_reset() {
+        let mut point = RestorePoint::default();
+        point.parser_state.apply_line("G55 G20");
+        point.offsets.coordinate_systems[1] = Position { x: 10.0, y: 5.0, z: -20.0 };
+        point.offsets.g92 = Position { x: 1.0, y: 0.0, z: 0.0 };
+        point.offsets.tool_length = 2.5;
+
+        // After a reset EEPROM offsets remain but G92 and TLO are gone
+        let mut current = WorkOffsets::default();
+        current.coordinate_systems[1] = point.offsets.coordinate_systems[1];
+        let mpos = Position { x: 30.0, y: 5.0, z: -10.0 };
+
+        let commands = point.restore_commands(&current, Some(mpos));
+        assert_eq!(
+            commands,
+            vec![
+                "G21",
+                "G43.1 Z2.5",
+                "G55 G92 X19 Y0 Z7.5",
+                "G20 G90 G17 G55 G94",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_restore_commands_when_unchanged() {
+        let point = RestorePoint::default();
+        let commands = point.restore_commands(&WorkOffsets::default(), None);
+        assert_eq!(commands, vec!["G21 G90 G17 G54 G94"]);
+    }
+}
@@ -0,0 +1,154 @@
+//! Raw TCP (telnet) transport for WiFi GRBL boards
+//!
+//! The socket is split into read and write halves so real-time bytes and
+//! streamed lines can be written while a read is pending.
+
+use super::transport::{parse_tcp_address, Transport, TransportKind};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+/// TCP connection handler
+pub struct TcpConnection {
+    reader: Arc<Mutex<Option<OwnedReadHalf>>>,
+    writer: Arc<Mutex<Option<OwnedWriteHalf>>>,
+    connect_timeout: Duration,
+    address: Arc<Mutex<String>>,
+}
+
+impl TcpConnection {
+    /// Create a new TCP connection handler
+    pub fn new() -> Self {
+        TcpConnection {
+            reader: Arc::new(Mutex::new(None)),
+            writer: Arc::new(Mutex::new(None)),
+            connect_timeout: Duration::from_secs(5),
+            address: Arc::new(Mutex::new(String::new())),
+        }
+    }
+
+    /// Get the connected socket address
+    pub async fn get_address(&self) -> String {
+        let address = self.address.lock().await;
+        address.clone()
+    }
+}
+
+impl Default for TcpConnection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Transport for TcpConnection {
+    fn kind(&self) -> TransportKind {
+        TransportKind::Tcp
+    }
+
+    async fn connect(&self, address: &str) -> Result<()> {
+        let socket_addr = parse_tcp_address(address);
+        let stream = tokio::time::timeout(self.connect_timeout, TcpStream::connect(&socket_addr))
+            .await
+            .map_err(|_| anyhow!("Timed out connecting to {}", socket_addr))?
+            .context(format!("Failed to connect to {}", socket_addr))?;
+        stream.set_nodelay(true).ok();
+
+        let (read_half, write_half) = stream.into_split();
+        *self.reader.lock().await = Some(read_half);
+        *self.writer.lock().await = Some(write_half);
+        *self.address.lock().await = socket_addr;
+
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        if let Some(mut writer) = self.writer.lock().await.take() {
+            let _ = writer.shutdown().await;
+        }
+        *self.reader.lock().await = None;
+        Ok(())
+    }
+
+    async fn send_bytes(&self, data: &[u8]) -> Result<usize> {
+        let mut writer = self.writer.lock().await;
+        let stream = writer
+            .as_mut()
+            .ok_or_else(|| anyhow!("TCP socket not connected"))?;
+
+        stream
+            .write_all(data)
+            .await
+            .context("Failed to write to TCP socket")?;
+
+        Ok(data.len())
+    }
+
+    async fn read_response_timeout(&self, max_size: usize, timeout: Duration) -> Result<String> {
+        let mut reader = self.reader.lock().await;
+        let stream = reader
+            .as_mut()
+            .ok_or_else(|| anyhow!("TCP socket not connected"))?;
+
+        let mut buffer = vec![0u8; max_size];
+        match tokio::time::timeout(timeout, stream.read(&mut buffer)).await {
+            Ok(Ok(0)) => {
+                // Peer closed the connection
+                *reader = None;
+                drop(reader);
+                *self.writer.lock().await = None;
+                Err(anyhow!("TCP connection closed by device"))
+            }
+            Ok(Ok(n)) => Ok(String::from_utf8_lossy(&buffer[..n]).to_string()),
+            Ok(Err(e)) => Err(anyhow!("TCP read error: {}", e)),
+            Err(_) => Err(anyhow!("Read operation timed out")),
+        }
+    }
+
+    async fn is_connected(&self) -> bool {
+        let writer = self.writer.lock().await;
+        writer.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_tcp_round_trip() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 64];
+            let n = socket.read(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], b"$I\n");
+            socket.write_all(b"ok\r\n").await.unwrap();
+        });
+
+        let conn = TcpConnection::new();
+        conn.connect(&format!("tcp://127.0.0.1:{}", port)).await.unwrap();
+        assert!(conn.is_connected().await);
+        conn.send_command("$I").await.unwrap();
+        let response = conn
+            .read_response_timeout(64, Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(response, "ok\r\n");
+        conn.disconnect().await.unwrap();
+        assert!(!conn.is_connected().await);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_not_connected() {
+        let conn = TcpConnection::new();
+        assert!(conn.send_bytes(b"?").await.is_err());
+    }
+}
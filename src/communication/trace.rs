@@ -0,0 +1,588 @@
+//! Communication trace recording and replay
+//!
+//! `TraceRecorder` keeps a timestamped record of every line sent to the
+//! device, every line received and every real-time byte, and exports it as
+//! JSON Lines. `ReplayTransport` plays a captured trace back to
+//! `GrblController`, so a session from an operator's machine can be
+//! reproduced offline. Connect to `replay://<path to trace.jsonl>` to load one.
+
+use super::transport::{Transport, TransportKind};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Records kept before the oldest are dropped (a few hours of status polling)
+pub const DEFAULT_TRACE_CAPACITY: usize = 100_000;
+
+/// Address scheme that selects `ReplayTransport`
+pub const REPLAY_SCHEME: &str = "replay://";
+
+/// How often a replay read checks for due lines
+const REPLAY_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Which way a traced message travelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceDirection {
+    /// Line sent to the device
+    Tx,
+    /// Line received from the device
+    Rx,
+    /// Real-time bytes sent to the device
+    Realtime,
+}
+
+/// One traced message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    /// Microseconds since the trace started
+    pub elapsed_us: u64,
+    pub direction: TraceDirection,
+    /// Line without its terminator, or real-time bytes as hex (`0x85 0x18`)
+    pub data: String,
+}
+
+impl TraceRecord {
+    /// Get the time since the trace started
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_micros(self.elapsed_us)
+    }
+
+    /// Decode the bytes of a real-time record
+    pub fn realtime_bytes(&self) -> Option<Vec<u8>> {
+        if self.direction != TraceDirection::Realtime {
+            return None;
+        }
+        self.data
+            .split_whitespace()
+            .map(|byte| u8::from_str_radix(byte.trim_start_matches("0x"), 16).ok())
+            .collect()
+    }
+}
+
+/// Format real-time bytes the way they are stored in a trace
+pub fn format_realtime_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("0x{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Bounded, timestamped log of the traffic with the device
+#[derive(Debug, Clone)]
+pub struct TraceRecorder {
+    records: VecDeque<TraceRecord>,
+    capacity: usize,
+    enabled: bool,
+    started: Instant,
+    started_at: DateTime<Utc>,
+    /// Records discarded because the trace was full
+    dropped: usize,
+}
+
+impl TraceRecorder {
+    /// Create a recorder keeping at most `capacity` records
+    pub fn new(capacity: usize) -> Self {
+        TraceRecorder {
+            records: VecDeque::new(),
+            capacity: capacity.max(1),
+            enabled: true,
+            started: Instant::now(),
+            started_at: Utc::now(),
+            dropped: 0,
+        }
+    }
+
+    /// Enable or disable recording
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Check if recording is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Get the wall-clock time the trace started
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    /// Get the number of records discarded because the trace was full
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Append a record stamped with the current time
+    pub fn record(&mut self, direction: TraceDirection, data: impl Into<String>) {
+        if !self.enabled {
+            return;
+        }
+        if self.records.len() >= self.capacity {
+            self.records.pop_front();
+            self.dropped += 1;
+        }
+        self.records.push_back(TraceRecord {
+            elapsed_us: self.started.elapsed().as_micros() as u64,
+            direction,
+            data: data.into(),
+        });
+    }
+
+    /// Record a line sent to the device
+    pub fn record_tx(&mut self, line: &str) {
+        self.record(TraceDirection::Tx, line.trim_end());
+    }
+
+    /// Record a line received from the device
+    pub fn record_rx(&mut self, line: &str) {
+        self.record(TraceDirection::Rx, line.trim_end());
+    }
+
+    /// Record real-time bytes sent to the device
+    pub fn record_realtime(&mut self, bytes: &[u8]) {
+        self.record(TraceDirection::Realtime, format_realtime_bytes(bytes));
+    }
+
+    /// Get the recorded messages, oldest first
+    pub fn records(&self) -> Vec<TraceRecord> {
+        self.records.iter().cloned().collect()
+    }
+
+    /// Get the number of records held
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Check if nothing has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Drop every record and restart the clock
+    pub fn clear(&mut self) {
+        self.records.clear();
+        self.dropped = 0;
+        self.started = Instant::now();
+        self.started_at = Utc::now();
+    }
+
+    /// Export the trace as JSON Lines, one record per line
+    pub fn to_jsonl(&self) -> Result<String> {
+        to_jsonl(self.records.iter())
+    }
+
+    /// Write the trace to a JSON Lines file
+    pub fn save_jsonl(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_jsonl()?)
+            .with_context(|| format!("Failed to write trace to {}", path.display()))
+    }
+}
+
+impl Default for TraceRecorder {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRACE_CAPACITY)
+    }
+}
+
+/// Serialize records as JSON Lines
+pub fn to_jsonl<'a>(records: impl IntoIterator<Item = &'a TraceRecord>) -> Result<String> {
+    let mut out = String::new();
+    for record in records {
+        out.push_str(&serde_json::to_string(record)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Parse a JSON Lines trace, skipping blank lines
+pub fn parse_jsonl(text: &str) -> Result<Vec<TraceRecord>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Invalid trace record on line {}", index + 1))
+        })
+        .collect()
+}
+
+/// Load a JSON Lines trace file
+pub fn load_jsonl(path: &Path) -> Result<Vec<TraceRecord>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read trace {}", path.display()))?;
+    parse_jsonl(&text)
+}
+
+/// A line the controller sent during replay that differs from the trace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayDivergence {
+    /// Index of the trace record that was expected
+    pub record: usize,
+    pub expected: String,
+    pub actual: String,
+}
+
+#[derive(Debug, Default)]
+struct ReplayState {
+    records: Vec<TraceRecord>,
+    connected: bool,
+    /// Next TX record the controller is expected to send
+    next_tx: usize,
+    /// Next RX record to deliver
+    next_rx: usize,
+    /// Trace time and replay instant of the last matched TX line
+    anchor: Option<(u64, tokio::time::Instant)>,
+    /// Partial line sent without its newline yet
+    pending_tx: String,
+    divergences: Vec<ReplayDivergence>,
+    /// Everything the controller sent, recorded as during capture
+    sent: TraceRecorder,
+}
+
+impl ReplayState {
+    fn advance_tx(&mut self, from: usize) {
+        self.next_tx = (from..self.records.len())
+            .find(|&i| self.records[i].direction == TraceDirection::Tx)
+            .unwrap_or(self.records.len());
+    }
+
+    fn rewind(&mut self) {
+        self.next_rx = 0;
+        self.anchor = None;
+        self.pending_tx.clear();
+        self.divergences.clear();
+        self.sent.clear();
+        self.advance_tx(0);
+    }
+
+    /// Match a line sent by the controller against the trace
+    fn send_line(&mut self, line: &str) {
+        self.sent.record_tx(line);
+        let Some(record) = self.records.get(self.next_tx) else {
+            self.divergences.push(ReplayDivergence {
+                record: self.records.len(),
+                expected: String::new(),
+                actual: line.to_string(),
+            });
+            return;
+        };
+        if record.data != line {
+            self.divergences.push(ReplayDivergence {
+                record: self.next_tx,
+                expected: record.data.clone(),
+                actual: line.to_string(),
+            });
+        }
+        self.anchor = Some((record.elapsed_us, tokio::time::Instant::now()));
+        let next = self.next_tx + 1;
+        self.advance_tx(next);
+    }
+
+    /// Index of the next RX record that may be delivered at `speed`
+    ///
+    /// Replies are held back until the lines sent before them in the trace
+    /// have been sent again, and paced by the original gaps when a speed is
+    /// set. Real-time records never gate replies: status polling timing
+    /// differs from run to run.
+    fn due_rx(&mut self, speed: Option<f64>) -> Option<usize> {
+        while self.next_rx < self.records.len()
+            && self.records[self.next_rx].direction != TraceDirection::Rx
+        {
+            self.next_rx += 1;
+        }
+        if self.next_rx >= self.records.len() || self.next_rx > self.next_tx {
+            return None;
+        }
+        if let (Some(speed), Some((anchor_us, anchor_at))) = (speed, self.anchor) {
+            let gap = self.records[self.next_rx]
+                .elapsed_us
+                .saturating_sub(anchor_us);
+            let due = anchor_at + Duration::from_micros(gap).div_f64(speed.max(1e-3));
+            if tokio::time::Instant::now() < due {
+                return None;
+            }
+        }
+        Some(self.next_rx)
+    }
+}
+
+/// Transport that replays a captured trace
+///
+/// Received lines are delivered in their original order once the controller
+/// has sent the lines that preceded them. Lines the controller sends that do
+/// not match the trace are collected as divergences, which is usually where
+/// a reproduced bug shows itself.
+pub struct ReplayTransport {
+    state: Mutex<ReplayState>,
+    /// Replay speed relative to the capture; None delivers replies at once
+    speed: Option<f64>,
+}
+
+impl ReplayTransport {
+    /// Create an empty replay that loads its trace on connect
+    pub fn new() -> Self {
+        ReplayTransport {
+            state: Mutex::new(ReplayState::default()),
+            speed: None,
+        }
+    }
+
+    /// Create a replay of the given records
+    pub fn from_records(records: Vec<TraceRecord>) -> Self {
+        let mut state = ReplayState {
+            records,
+            ..Default::default()
+        };
+        state.rewind();
+        ReplayTransport {
+            state: Mutex::new(state),
+            speed: None,
+        }
+    }
+
+    /// Pace replies by the gaps in the capture, scaled by `speed`
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = Some(speed);
+        self
+    }
+
+    /// Get the lines sent by the controller that differ from the trace
+    pub async fn divergences(&self) -> Vec<ReplayDivergence> {
+        self.state.lock().await.divergences.clone()
+    }
+
+    /// Get what the controller sent during replay
+    pub async fn sent(&self) -> Vec<TraceRecord> {
+        self.state.lock().await.sent.records()
+    }
+
+    /// Check if every line of the trace has been sent and received
+    pub async fn is_finished(&self) -> bool {
+        let state = self.state.lock().await;
+        let unread = &state.records[state.next_rx.min(state.records.len())..];
+        state.next_tx >= state.records.len()
+            && unread
+                .iter()
+                .all(|record| record.direction != TraceDirection::Rx)
+    }
+}
+
+impl Default for ReplayTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Transport for ReplayTransport {
+    fn kind(&self) -> TransportKind {
+        TransportKind::Replay
+    }
+
+    async fn connect(&self, address: &str) -> Result<()> {
+        let address = address.trim();
+        // Match the scheme the way `TransportKind::from_address` does
+        let path = address
+            .get(..REPLAY_SCHEME.len())
+            .filter(|scheme| scheme.eq_ignore_ascii_case(REPLAY_SCHEME))
+            .map(|_| &address[REPLAY_SCHEME.len()..])
+            .unwrap_or("");
+        let mut state = self.state.lock().await;
+        if !path.is_empty() {
+            state.records = load_jsonl(Path::new(path))?;
+        } else if state.records.is_empty() {
+            return Err(anyhow!("No trace to replay"));
+        }
+        state.rewind();
+        state.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        self.state.lock().await.connected = false;
+        Ok(())
+    }
+
+    async fn send_bytes(&self, data: &[u8]) -> Result<usize> {
+        let mut state = self.state.lock().await;
+        if !state.connected {
+            return Err(anyhow!("Replay not connected"));
+        }
+        if !data.ends_with(b"\n") && state.pending_tx.is_empty() {
+            state.sent.record_realtime(data);
+            return Ok(data.len());
+        }
+
+        state.pending_tx.push_str(&String::from_utf8_lossy(data));
+        while let Some(end) = state.pending_tx.find('\n') {
+            let line: String = state.pending_tx.drain(..=end).collect();
+            state.send_line(line.trim_end());
+        }
+        Ok(data.len())
+    }
+
+    async fn read_response_timeout(&self, max_size: usize, timeout: Duration) -> Result<String> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            {
+                let mut state = self.state.lock().await;
+                if !state.connected {
+                    return Err(anyhow!("Replay not connected"));
+                }
+                let mut out = String::new();
+                while let Some(index) = state.due_rx(self.speed) {
+                    let line = &state.records[index].data;
+                    if !out.is_empty() && out.len() + line.len() + 2 > max_size {
+                        break;
+                    }
+                    out.push_str(line);
+                    out.push_str("\r\n");
+                    state.next_rx += 1;
+                }
+                if !out.is_empty() {
+                    return Ok(out);
+                }
+            }
+
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return Err(anyhow!("Read operation timed out"));
+            }
+            tokio::time::sleep((deadline - now).min(REPLAY_POLL_INTERVAL)).await;
+        }
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.state.lock().await.connected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(elapsed_us: u64, direction: TraceDirection, data: &str) -> TraceRecord {
+        TraceRecord {
+            elapsed_us,
+            direction,
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_recorder_capacity_and_jsonl() {
+        let mut recorder = TraceRecorder::new(3);
+        recorder.record_tx("G0 X1\n");
+        recorder.record_realtime(b"?");
+        recorder.record_rx("ok\r\n");
+        recorder.record_rx("<Idle|MPos:1.000,0.000,0.000|FS:0,0>");
+        assert_eq!(recorder.len(), 3);
+        assert_eq!(recorder.dropped(), 1);
+
+        let records = recorder.records();
+        assert_eq!(records[0].data, "0x3F");
+        assert_eq!(records[0].realtime_bytes(), Some(vec![b'?']));
+        assert_eq!(records[1].data, "ok");
+        assert!(records
+            .windows(2)
+            .all(|w| w[0].elapsed_us <= w[1].elapsed_us));
+
+        let jsonl = recorder.to_jsonl().unwrap();
+        assert_eq!(jsonl.lines().count(), 3);
+        assert!(jsonl.contains("\"direction\":\"realtime\""));
+        assert_eq!(parse_jsonl(&jsonl).unwrap(), records);
+
+        recorder.set_enabled(false);
+        recorder.record_tx("G0 X2");
+        assert_eq!(recorder.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_jsonl_reports_bad_line() {
+        let error = parse_jsonl("\n{\"nope\":1}\n").unwrap_err();
+        assert!(error.to_string().contains("line 2"));
+    }
+
+    #[tokio::test]
+    async fn test_replay_gates_replies_on_sent_lines() {
+        let replay = ReplayTransport::from_records(vec![
+            record(0, TraceDirection::Rx, "Grbl 1.1h ['$' for help]"),
+            record(10, TraceDirection::Tx, "G0 X1"),
+            record(12, TraceDirection::Realtime, "0x3F"),
+            record(20, TraceDirection::Rx, "ok"),
+        ]);
+        replay.connect("replay://").await.unwrap();
+
+        let banner = replay
+            .read_response_timeout(256, Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(banner, "Grbl 1.1h ['$' for help]\r\n");
+        // The ok is held until the line before it is sent
+        assert!(replay
+            .read_response_timeout(256, Duration::from_millis(10))
+            .await
+            .is_err());
+
+        replay.send_bytes(b"G0 X2\n").await.unwrap();
+        let reply = replay
+            .read_response_timeout(256, Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(reply, "ok\r\n");
+        assert!(replay.is_finished().await);
+
+        let divergences = replay.divergences().await;
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].record, 1);
+        assert_eq!(divergences[0].expected, "G0 X1");
+        assert_eq!(divergences[0].actual, "G0 X2");
+    }
+
+    #[tokio::test]
+    async fn test_replay_scheme_is_case_insensitive() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("trace.jsonl");
+        let jsonl = to_jsonl(&[record(0, TraceDirection::Rx, "ok")]).unwrap();
+        std::fs::write(&path, jsonl).unwrap();
+
+        let replay = ReplayTransport::new();
+        replay
+            .connect(&format!("REPLAY://{}", path.display()))
+            .await
+            .unwrap();
+        let reply = replay
+            .read_response_timeout(256, Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(reply, "ok\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_replay_paces_replies() {
+        let replay = ReplayTransport::from_records(vec![
+            record(0, TraceDirection::Tx, "G4 P0.05"),
+            record(50_000, TraceDirection::Rx, "ok"),
+        ])
+        .with_speed(1.0);
+        replay.connect("replay://").await.unwrap();
+        replay.send_bytes(b"G4 P0.05\n").await.unwrap();
+
+        assert!(replay
+            .read_response_timeout(256, Duration::from_millis(10))
+            .await
+            .is_err());
+        let reply = replay
+            .read_response_timeout(256, Duration::from_millis(200))
+            .await
+            .unwrap();
+        assert_eq!(reply, "ok\r\n");
+    }
+}
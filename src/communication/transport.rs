@@ -0,0 +1,118 @@
+//! Transport abstraction for GRBL device links
+//!
+//! `GrblController` talks to the device through a `Transport`, so streaming,
+//! status polling and real-time commands work the same over a USB serial port
+//! or a network socket (ESP32 grblHAL/FluidNC boards expose raw TCP on port 23,
+//! and FluidNC also offers a WebSocket on its web port).
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Default telnet port used by WiFi GRBL boards
+pub const DEFAULT_TCP_PORT: u16 = 23;
+
+/// Kind of link used to reach the device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TransportKind {
+    Serial,
+    Tcp,
+    WebSocket,
+    Simulator,
+    /// Playback of a captured trace
+    Replay,
+}
+
+impl TransportKind {
+    /// Determine the transport kind from a connection address
+    ///
+    /// Addresses with a `tcp://` or `telnet://` scheme use TCP, `ws://` and
+    /// `wss://` use WebSocket, `sim://` selects the built-in simulator and
+    /// `replay://` plays back a trace file; anything else is treated as a
+    /// serial port name.
+    pub fn from_address(address: &str) -> Self {
+        let lower = address.trim().to_ascii_lowercase();
+        if lower.starts_with("tcp://") || lower.starts_with("telnet://") {
+            TransportKind::Tcp
+        } else if lower.starts_with("ws://") || lower.starts_with("wss://") {
+            TransportKind::WebSocket
+        } else if lower.starts_with("sim://") {
+            TransportKind::Simulator
+        } else if lower.starts_with("replay://") {
+            TransportKind::Replay
+        } else {
+            TransportKind::Serial
+        }
+    }
+}
+
+/// Bidirectional byte link to a GRBL device
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Get the kind of link this transport provides
+    fn kind(&self) -> TransportKind;
+
+    /// Open the link to the given address
+    async fn connect(&self, address: &str) -> Result<()>;
+
+    /// Close the link
+    async fn disconnect(&self) -> Result<()>;
+
+    /// Send raw bytes to the device
+    async fn send_bytes(&self, data: &[u8]) -> Result<usize>;
+
+    /// Read whatever the device has sent, waiting up to `timeout`
+    async fn read_response_timeout(&self, max_size: usize, timeout: Duration) -> Result<String>;
+
+    /// Check if the link is open
+    async fn is_connected(&self) -> bool;
+
+    /// Send a string command (with newline)
+    async fn send_command(&self, command: &str) -> Result<()> {
+        let command_with_newline = format!("{}\n", command);
+        self.send_bytes(command_with_newline.as_bytes()).await?;
+        Ok(())
+    }
+}
+
+/// Split a `tcp://host:port` address into a socket address string
+///
+/// The port defaults to 23 when omitted.
+pub fn parse_tcp_address(address: &str) -> String {
+    let trimmed = address.trim();
+    let host = trimmed
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(trimmed)
+        .trim_end_matches('/');
+
+    if host.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok()) {
+        host.to_string()
+    } else {
+        format!("{}:{}", host, DEFAULT_TCP_PORT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transport_kind_from_address() {
+        assert_eq!(TransportKind::from_address("/dev/ttyUSB0"), TransportKind::Serial);
+        assert_eq!(TransportKind::from_address("COM3"), TransportKind::Serial);
+        assert_eq!(TransportKind::from_address("tcp://192.168.1.50:23"), TransportKind::Tcp);
+        assert_eq!(TransportKind::from_address("Telnet://grbl.local"), TransportKind::Tcp);
+        assert_eq!(TransportKind::from_address("ws://fluidnc.local:81"), TransportKind::WebSocket);
+        assert_eq!(TransportKind::from_address("sim://grbl"), TransportKind::Simulator);
+        assert_eq!(TransportKind::from_address("replay:///tmp/trace.jsonl"), TransportKind::Replay);
+    }
+
+    #[test]
+    fn test_parse_tcp_address() {
+        assert_eq!(parse_tcp_address("tcp://192.168.1.50:8023"), "192.168.1.50:8023");
+        assert_eq!(parse_tcp_address("telnet://fluidnc.local"), "fluidnc.local:23");
+        assert_eq!(parse_tcp_address("tcp://10.0.0.2/"), "10.0.0.2:23");
+    }
+}
@@ -0,0 +1,261 @@
+//! WebSocket transport for FluidNC controllers
+//!
+//! FluidNC exposes its GRBL channel over a WebSocket on the web port
+//! (e.g. `ws://fluidnc.local:81`). Commands are sent as binary frames so
+//! real-time bytes above 0x7F survive; replies may arrive as binary or text.
+//! `wss://` addresses are secured with rustls against the bundled web PKI
+//! roots.
+
+use super::transport::{Transport, TransportKind};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// FluidNC web UI housekeeping messages that are not part of the GRBL stream
+const FLUIDNC_HOUSEKEEPING: &[&str] = &["CURRENT_ID:", "ACTIVE_ID:", "PING:", "DHT:"];
+
+/// WebSocket connection handler
+pub struct WebSocketConnection {
+    sink: Arc<Mutex<Option<SplitSink<WsStream, Message>>>>,
+    stream: Arc<Mutex<Option<SplitStream<WsStream>>>>,
+    /// Received bytes not yet handed to the reader
+    pending: Arc<Mutex<Vec<u8>>>,
+    connect_timeout: Duration,
+    url: Arc<Mutex<String>>,
+}
+
+impl WebSocketConnection {
+    /// Create a new WebSocket connection handler
+    pub fn new() -> Self {
+        WebSocketConnection {
+            sink: Arc::new(Mutex::new(None)),
+            stream: Arc::new(Mutex::new(None)),
+            pending: Arc::new(Mutex::new(Vec::new())),
+            connect_timeout: Duration::from_secs(5),
+            url: Arc::new(Mutex::new(String::new())),
+        }
+    }
+
+    /// Get the connected URL
+    pub async fn get_url(&self) -> String {
+        let url = self.url.lock().await;
+        url.clone()
+    }
+
+    /// Extract GRBL data from a WebSocket frame
+    ///
+    /// # Returns
+    /// None for control frames and FluidNC housekeeping text
+    fn frame_payload(message: Message) -> Option<Vec<u8>> {
+        match message {
+            Message::Binary(data) => Some(data),
+            Message::Text(text) => {
+                if FLUIDNC_HOUSEKEEPING.iter().any(|p| text.starts_with(p)) {
+                    None
+                } else if text.ends_with('\n') {
+                    Some(text.into_bytes())
+                } else {
+                    // Text frames usually carry one line without a terminator
+                    Some(format!("{}\n", text).into_bytes())
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Hand out up to `max_size` pending bytes
+    fn take_pending(pending: &mut Vec<u8>, max_size: usize) -> String {
+        let n = pending.len().min(max_size);
+        let chunk: Vec<u8> = pending.drain(..n).collect();
+        String::from_utf8_lossy(&chunk).to_string()
+    }
+
+    /// Drop both halves of the socket
+    async fn close_halves(&self) {
+        *self.sink.lock().await = None;
+        *self.stream.lock().await = None;
+    }
+}
+
+impl Default for WebSocketConnection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Transport for WebSocketConnection {
+    fn kind(&self) -> TransportKind {
+        TransportKind::WebSocket
+    }
+
+    async fn connect(&self, address: &str) -> Result<()> {
+        let url = address.trim().to_string();
+        let (socket, _) =
+            tokio::time::timeout(self.connect_timeout, tokio_tungstenite::connect_async(&url))
+                .await
+                .map_err(|_| anyhow!("Timed out connecting to {}", url))?
+                .context(format!("Failed to open WebSocket: {}", url))?;
+
+        let (sink, stream) = socket.split();
+        *self.sink.lock().await = Some(sink);
+        *self.stream.lock().await = Some(stream);
+        self.pending.lock().await.clear();
+        *self.url.lock().await = url;
+
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        if let Some(mut sink) = self.sink.lock().await.take() {
+            let _ = sink.close().await;
+        }
+        *self.stream.lock().await = None;
+        self.pending.lock().await.clear();
+        Ok(())
+    }
+
+    async fn send_bytes(&self, data: &[u8]) -> Result<usize> {
+        let mut sink = self.sink.lock().await;
+        let socket = sink
+            .as_mut()
+            .ok_or_else(|| anyhow!("WebSocket not connected"))?;
+
+        socket
+            .send(Message::Binary(data.to_vec()))
+            .await
+            .context("Failed to write to WebSocket")?;
+
+        Ok(data.len())
+    }
+
+    async fn read_response_timeout(&self, max_size: usize, timeout: Duration) -> Result<String> {
+        {
+            let mut pending = self.pending.lock().await;
+            if !pending.is_empty() {
+                return Ok(Self::take_pending(&mut pending, max_size));
+            }
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut stream = self.stream.lock().await;
+        let socket = stream
+            .as_mut()
+            .ok_or_else(|| anyhow!("WebSocket not connected"))?;
+
+        loop {
+            let message = match tokio::time::timeout_at(deadline, socket.next()).await {
+                Err(_) => return Err(anyhow!("Read operation timed out")),
+                Ok(None) | Ok(Some(Ok(Message::Close(_)))) => {
+                    drop(stream);
+                    self.close_halves().await;
+                    return Err(anyhow!("WebSocket closed by device"));
+                }
+                Ok(Some(Err(e))) => {
+                    // The socket is unusable after a protocol or I/O error
+                    drop(stream);
+                    self.close_halves().await;
+                    return Err(anyhow!("WebSocket read error: {}", e));
+                }
+                Ok(Some(Ok(message))) => message,
+            };
+
+            if let Some(payload) = Self::frame_payload(message) {
+                let mut pending = self.pending.lock().await;
+                pending.extend(payload);
+                return Ok(Self::take_pending(&mut pending, max_size));
+            }
+        }
+    }
+
+    async fn is_connected(&self) -> bool {
+        let sink = self.sink.lock().await;
+        sink.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_payload_binary_and_text() {
+        assert_eq!(
+            WebSocketConnection::frame_payload(Message::Binary(b"ok\r\n".to_vec())),
+            Some(b"ok\r\n".to_vec())
+        );
+        assert_eq!(
+            WebSocketConnection::frame_payload(Message::Text("ok".to_string())),
+            Some(b"ok\n".to_vec())
+        );
+        assert_eq!(
+            WebSocketConnection::frame_payload(Message::Text("CURRENT_ID:0".to_string())),
+            None
+        );
+        assert_eq!(
+            WebSocketConnection::frame_payload(Message::Ping(vec![1])),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_websocket_not_connected() {
+        let conn = WebSocketConnection::new();
+        assert!(!conn.is_connected().await);
+        assert!(conn.send_bytes(b"?").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wss_attempts_tls_handshake() {
+        // A plain TCP listener makes the handshake fail, but only after TLS
+        // was attempted
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            drop(socket);
+        });
+
+        let conn = WebSocketConnection::new();
+        let error = conn
+            .connect(&format!("wss://127.0.0.1:{}", port))
+            .await
+            .unwrap_err();
+        assert!(!format!("{:#}", error).contains("TLS support not compiled in"));
+        assert!(!conn.is_connected().await);
+    }
+
+    #[tokio::test]
+    async fn test_read_error_closes_socket() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            // Dropping without a close handshake is a read error on the client
+            drop(ws);
+        });
+
+        let conn = WebSocketConnection::new();
+        conn.connect(&format!("ws://127.0.0.1:{}", port))
+            .await
+            .unwrap();
+        assert!(conn.is_connected().await);
+
+        let error = conn
+            .read_response_timeout(64, Duration::from_secs(2))
+            .await
+            .unwrap_err();
+        assert!(format!("{}", error).contains("WebSocket"));
+        assert!(!conn.is_connected().await);
+    }
+}
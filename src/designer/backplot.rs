@@ -3,8 +3,25 @@
 //! Provides step-through execution of G-code with real-time position tracking,
 //! visualization support, and full simulation control (forward, backward, jump, pause/resume).
 
+use super::estimator::dwell_seconds;
+use super::interpreter::{plane_axes, GcodeInterpreter};
+use crate::communication::parser_state::Plane;
 use anyhow::{anyhow, Result};
 use std::collections::VecDeque;
+use std::f64::consts::FRAC_PI_2;
+use std::time::Duration;
+
+/// Smallest sampling tolerance in mm, so a zero tolerance stays finite
+const MIN_TOLERANCE: f32 = 1e-4;
+
+/// Slowest playback speed multiplier
+pub const MIN_PLAYBACK_SPEED: f32 = 0.1;
+
+/// Fastest playback speed multiplier
+pub const MAX_PLAYBACK_SPEED: f32 = 5.0;
+
+/// Rate in mm/min used for rapids and moves without a feed rate
+pub const DEFAULT_RAPID_RATE: f32 = 5000.0;
 
 /// Represents a single step in G-code execution
 #[derive(Clone, Debug)]
@@ -23,6 +40,8 @@ pub struct BackPlotStep {
     pub spindle_speed: f32,
     /// Move type indicator
     pub move_type: MoveType,
+    /// Arc geometry for G2/G3 steps; without it an arc is drawn as a chord
+    pub arc: Option<ArcGeometry>,
 }
 
 /// Type of G-code move
@@ -42,6 +61,139 @@ pub enum MoveType {
     Other,
 }
 
+/// Geometry of an arc step
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub struct ArcGeometry {
+    /// Centre of the arc; the coordinate along the plane normal is the start's
+    pub center: [f32; 3],
+    /// Radius in mm
+    pub radius: f32,
+    /// Plane the arc turns in (G17, G18 or G19)
+    pub plane: Plane,
+    /// Angle swept in radians, positive counter-clockwise, negative clockwise
+    pub sweep: f32,
+}
+
+impl ArcGeometry {
+    /// Number of chords needed to stay within `tolerance` of the arc
+    ///
+    /// # Arguments
+    /// * `tolerance` - Largest distance in mm between a chord and the arc
+    pub fn segments(&self, tolerance: f32) -> usize {
+        let ratio = (tolerance.max(MIN_TOLERANCE) / self.radius.max(f32::EPSILON)).min(1.0);
+        let max_angle = 2.0 * (1.0 - ratio).acos();
+        ((self.sweep.abs() / max_angle).ceil() as usize).max(1)
+    }
+
+    /// Length along the arc, including travel along the plane normal
+    fn length(&self, start: &[f32; 3], end: &[f32; 3]) -> f32 {
+        let (a, b) = plane_axes(self.plane);
+        let linear = 3 - a - b;
+        (self.radius * self.sweep.abs()).hypot(end[linear] - start[linear])
+    }
+
+    /// Angle of the start point around the centre
+    fn start_angle(&self, start: &[f32; 3]) -> f64 {
+        let (a, b) = plane_axes(self.plane);
+        f64::from(start[b] - self.center[b]).atan2(f64::from(start[a] - self.center[a]))
+    }
+
+    /// Point on the arc at `angle`, `fraction` of the way along the normal
+    fn point(&self, start: &[f32; 3], end: &[f32; 3], angle: f64, fraction: f32) -> [f32; 3] {
+        let (a, b) = plane_axes(self.plane);
+        let linear = 3 - a - b;
+        let radius = f64::from(self.radius);
+        let mut point = [0.0; 3];
+        point[a] = (f64::from(self.center[a]) + radius * angle.cos()) as f32;
+        point[b] = (f64::from(self.center[b]) + radius * angle.sin()) as f32;
+        point[linear] = start[linear] + (end[linear] - start[linear]) * fraction;
+        point
+    }
+}
+
+impl BackPlotStep {
+    /// Get the length of the path in mm, along the arc for arc steps
+    pub fn length(&self) -> f32 {
+        match &self.arc {
+            Some(arc) => arc.length(&self.start_pos, &self.end_pos),
+            None => distance(&self.start_pos, &self.end_pos),
+        }
+    }
+
+    /// Get the position `fraction` (0.0 to 1.0) of the way along the path
+    pub fn point_at(&self, fraction: f32) -> [f32; 3] {
+        let fraction = fraction.clamp(0.0, 1.0);
+        if fraction >= 1.0 {
+            return self.end_pos;
+        }
+        match &self.arc {
+            Some(arc) => {
+                let angle =
+                    arc.start_angle(&self.start_pos) + f64::from(arc.sweep) * f64::from(fraction);
+                arc.point(&self.start_pos, &self.end_pos, angle, fraction)
+            }
+            None => {
+                let mut point = self.start_pos;
+                for (axis, value) in point.iter_mut().enumerate() {
+                    *value += (self.end_pos[axis] - self.start_pos[axis]) * fraction;
+                }
+                point
+            }
+        }
+    }
+
+    /// Sample the path into a polyline
+    ///
+    /// # Arguments
+    /// * `tolerance` - Largest distance in mm between the polyline and an arc
+    ///
+    /// # Returns
+    /// Points from start to end; straight moves give just the two end points
+    pub fn sample(&self, tolerance: f32) -> Vec<[f32; 3]> {
+        let segments = self.arc.map_or(1, |arc| arc.segments(tolerance));
+        (0..=segments)
+            .map(|i| self.point_at(i as f32 / segments as f32))
+            .collect()
+    }
+
+    /// Get the axis-aligned bounding box of the path
+    ///
+    /// # Returns
+    /// (min, max) corners; arcs include the points where they cross an axis
+    pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        let mut bounds = (self.start_pos, self.start_pos);
+        extend_bounds(&mut bounds, &self.end_pos);
+        if let Some(arc) = &self.arc {
+            let start = arc.start_angle(&self.start_pos);
+            let end = start + f64::from(arc.sweep);
+            let (low, high) = (start.min(end), start.max(end));
+            let mut quadrant = (low / FRAC_PI_2).ceil();
+            while quadrant * FRAC_PI_2 <= high {
+                let angle = quadrant * FRAC_PI_2;
+                let fraction = ((angle - start) / f64::from(arc.sweep)) as f32;
+                extend_bounds(
+                    &mut bounds,
+                    &arc.point(&self.start_pos, &self.end_pos, angle, fraction),
+                );
+                quadrant += 1.0;
+            }
+        }
+        bounds
+    }
+}
+
+fn distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    let (dx, dy, dz) = (b[0] - a[0], b[1] - a[1], b[2] - a[2]);
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+fn extend_bounds(bounds: &mut ([f32; 3], [f32; 3]), point: &[f32; 3]) {
+    for (axis, value) in point.iter().enumerate() {
+        bounds.0[axis] = bounds.0[axis].min(*value);
+        bounds.1[axis] = bounds.1[axis].max(*value);
+    }
+}
+
 /// Simulation state
 #[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum BackPlotState {
@@ -55,6 +207,15 @@ pub enum BackPlotState {
     Completed,
 }
 
+/// Event raised while playing back over time
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+pub enum PlaybackEvent {
+    /// A step started executing; the editor highlights its line
+    LineStarted { step: usize, line_number: usize },
+    /// The last step finished
+    Finished,
+}
+
 /// Back-plotter for G-code visualization
 #[derive(Clone, Debug)]
 pub struct BackPlotter {
@@ -70,6 +231,16 @@ pub struct BackPlotter {
     max_history: usize,
     /// Current machine position
     current_pos: [f32; 3],
+    /// Rate in mm/min for rapids and moves without a feed rate
+    rapid_rate: f32,
+    /// Playback time at which each step starts, plus the end time
+    step_starts: Vec<f64>,
+    /// Playback time in seconds
+    clock: f64,
+    /// Playback speed multiplier
+    speed: f32,
+    /// Step whose `LineStarted` event was raised last
+    executing: Option<usize>,
 }
 
 impl BackPlotter {
@@ -91,14 +262,199 @@ impl BackPlotter {
             steps[0].start_pos
         };
 
-        Ok(Self {
+        let mut plotter = Self {
             steps,
             current_step: 0,
             state: BackPlotState::Idle,
             history: VecDeque::new(),
             max_history: 1000,
             current_pos: initial_pos,
-        })
+            rapid_rate: DEFAULT_RAPID_RATE,
+            step_starts: Vec::new(),
+            clock: 0.0,
+            speed: 1.0,
+            executing: None,
+        };
+        plotter.build_timeline();
+        Ok(plotter)
+    }
+
+    /// Create a BackPlotter by interpreting a G-code program
+    ///
+    /// # Arguments
+    /// * `gcode` - Program text
+    ///
+    /// # Returns
+    /// New BackPlotter, or an error for malformed lines or a program without moves
+    pub fn from_gcode(gcode: &str) -> Result<Self> {
+        let steps = GcodeInterpreter::new().interpret(gcode)?;
+        Self::new(steps)
+    }
+
+    /// Work out when each step starts from its length and feed rate
+    fn build_timeline(&mut self) {
+        let mut time = 0.0;
+        self.step_starts = Vec::with_capacity(self.steps.len() + 1);
+        for step in &self.steps {
+            self.step_starts.push(time);
+            time += if step.move_type == MoveType::Dwell {
+                dwell_seconds(&step.gcode_command)
+            } else {
+                let rate = if step.move_type == MoveType::Rapid || step.feed_rate <= 0.0 {
+                    self.rapid_rate
+                } else {
+                    step.feed_rate
+                };
+                f64::from(step.length()) * 60.0 / f64::from(rate)
+            };
+        }
+        self.step_starts.push(time);
+    }
+
+    /// Set the rate used for rapids and moves without a feed rate
+    ///
+    /// # Arguments
+    /// * `rate` - Rate in mm/min, e.g. the machine's `$110` max rate
+    pub fn set_rapid_rate(&mut self, rate: f32) -> Result<()> {
+        if rate <= 0.0 {
+            return Err(anyhow!("Rapid rate must be positive, got {}", rate));
+        }
+        let fraction = self.clock / self.get_duration().max(f64::EPSILON);
+        self.rapid_rate = rate;
+        self.build_timeline();
+        self.clock = fraction * self.get_duration();
+        Ok(())
+    }
+
+    /// Set the playback speed multiplier
+    ///
+    /// # Arguments
+    /// * `speed` - Multiplier from 0.1 (slow motion) to 5.0
+    pub fn set_speed(&mut self, speed: f32) -> Result<()> {
+        if !(MIN_PLAYBACK_SPEED..=MAX_PLAYBACK_SPEED).contains(&speed) {
+            return Err(anyhow!(
+                "Playback speed {}x outside {}x to {}x",
+                speed,
+                MIN_PLAYBACK_SPEED,
+                MAX_PLAYBACK_SPEED
+            ));
+        }
+        self.speed = speed;
+        Ok(())
+    }
+
+    /// Get the playback speed multiplier
+    pub fn get_speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Get the program run time in seconds at 1x
+    pub fn get_duration(&self) -> f64 {
+        self.step_starts.last().copied().unwrap_or(0.0)
+    }
+
+    /// Get the playback time in seconds
+    pub fn get_elapsed(&self) -> f64 {
+        self.clock
+    }
+
+    /// Start or continue playback over time
+    ///
+    /// A completed simulation starts again from the beginning.
+    pub fn play(&mut self) {
+        if self.state == BackPlotState::Completed {
+            self.reset();
+        }
+        self.state = BackPlotState::Running;
+    }
+
+    /// Advance playback by wall-clock time
+    ///
+    /// # Arguments
+    /// * `elapsed` - Real time since the last call; scaled by the speed
+    ///
+    /// # Returns
+    /// Line events for every step reached, in order; nothing unless running
+    pub fn advance(&mut self, elapsed: Duration) -> Vec<PlaybackEvent> {
+        if self.state != BackPlotState::Running {
+            return Vec::new();
+        }
+        let time = self.clock + elapsed.as_secs_f64() * f64::from(self.speed);
+        self.move_clock(time)
+    }
+
+    /// Move playback to a point in time
+    ///
+    /// Seeking while idle or completed pauses the simulation at that point.
+    ///
+    /// # Arguments
+    /// * `seconds` - Program time at 1x, clamped to the program duration
+    ///
+    /// # Returns
+    /// A line event for the step now executing, if it changed
+    pub fn seek(&mut self, seconds: f64) -> Vec<PlaybackEvent> {
+        if self.state != BackPlotState::Running {
+            self.state = BackPlotState::Paused;
+        }
+        // Steps passed over are not replayed as events
+        let target = self.step_at(seconds.max(0.0)).min(self.steps.len() - 1);
+        if self.executing != Some(target) {
+            self.executing = target.checked_sub(1);
+        }
+        self.move_clock(seconds)
+    }
+
+    /// Index of the step executing at `time`, or the step count at the end
+    fn step_at(&self, time: f64) -> usize {
+        if time >= self.get_duration() {
+            return self.steps.len();
+        }
+        // The last step starting at or before `time`
+        self.step_starts[..self.steps.len()]
+            .partition_point(|start| *start <= time)
+            .saturating_sub(1)
+    }
+
+    /// Set the clock, update the position and collect line events
+    fn move_clock(&mut self, time: f64) -> Vec<PlaybackEvent> {
+        let duration = self.get_duration();
+        self.clock = time.clamp(0.0, duration);
+        let index = self.step_at(self.clock);
+
+        let mut events = Vec::new();
+        let first_new = self.executing.map_or(0, |step| step + 1);
+        for step in first_new..=index.min(self.steps.len() - 1) {
+            events.push(PlaybackEvent::LineStarted {
+                step,
+                line_number: self.steps[step].line_number,
+            });
+            self.executing = Some(step);
+        }
+
+        self.current_step = index;
+        if index >= self.steps.len() {
+            self.current_pos = self.steps[self.steps.len() - 1].end_pos;
+            if self.state != BackPlotState::Completed {
+                self.state = BackPlotState::Completed;
+                events.push(PlaybackEvent::Finished);
+            }
+        } else {
+            let start = self.step_starts[index];
+            let length = self.step_starts[index + 1] - start;
+            let fraction = if length > 0.0 {
+                ((self.clock - start) / length) as f32
+            } else {
+                0.0
+            };
+            self.current_pos = self.steps[index].point_at(fraction);
+        }
+        events
+    }
+
+    /// Keep the playback clock at the boundary of the current step
+    fn sync_clock(&mut self) {
+        self.clock = self.step_starts[self.current_step];
+        self.executing = self.current_step.checked_sub(1);
     }
 
     /// Step forward one command
@@ -119,7 +475,8 @@ impl BackPlotter {
         }
         
         self.current_step += 1;
-        
+        self.sync_clock();
+
         // Check if we've reached the end
         if self.current_step >= self.steps.len() {
             self.state = BackPlotState::Completed;
@@ -141,6 +498,7 @@ impl BackPlotter {
         }
 
         self.current_step -= 1;
+        self.sync_clock();
         let step = &self.steps[self.current_step];
         self.current_pos = step.start_pos;
 
@@ -173,6 +531,7 @@ impl BackPlotter {
         self.current_step = step_number;
         self.current_pos = current_pos;
         self.state = BackPlotState::Running;
+        self.sync_clock();
 
         Ok(&self.steps[step_number])
     }
@@ -197,6 +556,7 @@ impl BackPlotter {
         self.current_pos = self.steps[0].start_pos;
         self.state = BackPlotState::Idle;
         self.history.clear();
+        self.sync_clock();
     }
 
     /// Get current simulation state
@@ -246,12 +606,32 @@ impl BackPlotter {
         self.steps.get(index)
     }
 
+    /// Get the length of the whole program path in mm
+    pub fn total_length(&self) -> f32 {
+        self.steps.iter().map(BackPlotStep::length).sum()
+    }
+
+    /// Get the axis-aligned bounding box of the whole program
+    ///
+    /// # Returns
+    /// (min, max) corners, including rapid moves
+    pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        let mut bounds = self.steps[0].bounds();
+        for step in &self.steps[1..] {
+            let (min, max) = step.bounds();
+            extend_bounds(&mut bounds, &min);
+            extend_bounds(&mut bounds, &max);
+        }
+        bounds
+    }
+
     /// Reset to beginning
     pub fn reset(&mut self) {
         self.current_step = 0;
         self.current_pos = self.steps[0].start_pos;
         self.state = BackPlotState::Idle;
         self.history.clear();
+        self.sync_clock();
     }
 }
 
@@ -269,6 +649,7 @@ mod tests {
                 feed_rate: 0.0,
                 spindle_speed: 0.0,
                 move_type: MoveType::Rapid,
+                arc: None,
             },
             BackPlotStep {
                 line_number: 2,
@@ -278,6 +659,7 @@ mod tests {
                 feed_rate: 1000.0,
                 spindle_speed: 5000.0,
                 move_type: MoveType::Linear,
+                arc: None,
             },
             BackPlotStep {
                 line_number: 3,
@@ -287,6 +669,7 @@ mod tests {
                 feed_rate: 1000.0,
                 spindle_speed: 5000.0,
                 move_type: MoveType::Linear,
+                arc: None,
             },
         ]
     }
@@ -487,6 +870,7 @@ mod tests {
                 feed_rate: 0.0,
                 spindle_speed: 0.0,
                 move_type: MoveType::Rapid,
+                arc: None,
             },
             BackPlotStep {
                 line_number: 2,
@@ -496,6 +880,7 @@ mod tests {
                 feed_rate: 1000.0,
                 spindle_speed: 5000.0,
                 move_type: MoveType::Linear,
+                arc: None,
             },
             BackPlotStep {
                 line_number: 3,
@@ -505,6 +890,7 @@ mod tests {
                 feed_rate: 1000.0,
                 spindle_speed: 5000.0,
                 move_type: MoveType::ArcCW,
+                arc: None,
             },
         ];
 
@@ -515,4 +901,18 @@ mod tests {
         let arc_step = bp.get_current_step_ref().unwrap();
         assert_eq!(arc_step.move_type, MoveType::ArcCW);
     }
+
+    #[test]
+    fn test_arc_segments_for_tolerance() {
+        let arc = ArcGeometry {
+            center: [0.0; 3],
+            radius: 10.0,
+            plane: Plane::XY,
+            sweep: std::f32::consts::PI,
+        };
+        assert!(arc.segments(0.01) > arc.segments(0.1));
+        // A tolerance past the radius still needs a chord per half turn
+        assert_eq!(arc.segments(100.0), 1);
+        assert!(arc.segments(0.0) < 10_000);
+    }
 }
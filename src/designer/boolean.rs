@@ -0,0 +1,539 @@
+//! Polygon boolean operations
+//!
+//! A `Region` is an area bounded by closed contours: outer boundaries run
+//! counter-clockwise and holes clockwise. Combining two regions splits every
+//! edge of both where it meets any other edge, including edges of its own
+//! region, so holes, overlaps and self-intersecting contours need no special
+//! cases. Each piece is kept when the result is filled on exactly one side
+//! of it, judged from the operands' winding numbers just either side of its
+//! midpoint, and the kept pieces are chained back into contours with the
+//! filled side on their left.
+
+use super::offset::{offset, JoinType};
+use super::shapes::Shape;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+type Point = (f64, f64);
+
+/// Snapping distance relative to the size of the operands
+const SNAP_RATIO: f64 = 1e-9;
+
+/// How far either side of an edge the fill is sampled, in snapping distances
+const SAMPLE_OFFSET: f64 = 100.0;
+
+/// Boolean operation on two regions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BooleanOp {
+    /// Area in either region
+    Union,
+    /// Area of the first region outside the second
+    Difference,
+    /// Area in both regions
+    Intersection,
+    /// Area in exactly one region
+    Xor,
+}
+
+/// Which winding numbers count as filled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FillRule {
+    /// Any non-zero winding, so holes must run against their outer contour
+    #[default]
+    NonZero,
+    /// Odd winding, so overlaps and self-overlaps become holes
+    EvenOdd,
+    /// Positive winding, so clockwise contours only cut holes
+    Positive,
+}
+
+impl FillRule {
+    fn is_filled(self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+            FillRule::Positive => winding > 0,
+        }
+    }
+}
+
+/// Area bounded by closed contours, possibly with holes and separate parts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+    /// Contours without a repeated end point; holes run clockwise
+    pub contours: Vec<Vec<Point>>,
+}
+
+impl Region {
+    /// Create a region from contours
+    pub fn new(contours: Vec<Vec<Point>>) -> Self {
+        Region { contours }
+    }
+
+    /// Create a region from the outline of a closed shape
+    ///
+    /// # Arguments
+    /// * `shape` - Shape to fill; open shapes give an empty region
+    /// * `tolerance` - Largest distance in mm between curves and the chords
+    ///   replacing them
+    pub fn from_shape(shape: &Shape, tolerance: f64) -> Self {
+        if matches!(shape, Shape::Line { .. } | Shape::Polyline { .. })
+            || matches!(shape, Shape::Path { closed: false, .. })
+        {
+            return Region::default();
+        }
+        if let Shape::Region { contours } = shape {
+            return Region::new(contours.clone());
+        }
+        let mut outline = shape.outline(tolerance);
+        if signed_area(&outline) < 0.0 {
+            outline.reverse();
+        }
+        Region::new(vec![outline])
+    }
+
+    /// Check whether the region has no contours
+    pub fn is_empty(&self) -> bool {
+        self.contours.is_empty()
+    }
+
+    /// Filled area, with holes taken away
+    pub fn area(&self) -> f64 {
+        self.contours
+            .iter()
+            .map(|contour| signed_area(contour))
+            .sum()
+    }
+
+    /// Calculate bounding box (min_x, min_y, max_x, max_y), None when empty
+    pub fn bounds(&self) -> Option<(f64, f64, f64, f64)> {
+        let mut points = self.contours.iter().flatten();
+        let first = points.next()?;
+        Some(points.fold(
+            (first.0, first.1, first.0, first.1),
+            |(min_x, min_y, max_x, max_y), (x, y)| {
+                (min_x.min(*x), min_y.min(*y), max_x.max(*x), max_y.max(*y))
+            },
+        ))
+    }
+
+    /// Check if a point is inside the region, using the non-zero rule
+    pub fn contains_point(&self, px: f64, py: f64) -> bool {
+        FillRule::NonZero.is_filled(winding(&self.contours, (px, py)))
+    }
+
+    /// Combine with another region
+    ///
+    /// # Arguments
+    /// * `other` - Second operand
+    /// * `op` - Operation
+    /// * `fill_rule` - How both operands' contours are filled
+    ///
+    /// # Returns
+    /// Outer contours counter-clockwise and holes clockwise
+    pub fn boolean(&self, other: &Region, op: BooleanOp, fill_rule: FillRule) -> Region {
+        overlay(&self.contours, &other.contours, |subject, clip| {
+            let subject = fill_rule.is_filled(subject);
+            let clip = fill_rule.is_filled(clip);
+            match op {
+                BooleanOp::Union => subject || clip,
+                BooleanOp::Difference => subject && !clip,
+                BooleanOp::Intersection => subject && clip,
+                BooleanOp::Xor => subject != clip,
+            }
+        })
+    }
+
+    /// Area in either region
+    pub fn union(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Union, FillRule::NonZero)
+    }
+
+    /// Area of this region outside the other
+    pub fn difference(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Difference, FillRule::NonZero)
+    }
+
+    /// Area in both regions
+    pub fn intersection(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Intersection, FillRule::NonZero)
+    }
+
+    /// Area in exactly one region
+    pub fn xor(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Xor, FillRule::NonZero)
+    }
+
+    /// Resolve self-intersections and overlaps into clean contours
+    pub fn simplify(&self, fill_rule: FillRule) -> Region {
+        self.boolean(&Region::default(), BooleanOp::Union, fill_rule)
+    }
+
+    /// Grow or shrink the region, see `offset::offset`
+    pub fn offset(&self, distance: f64, join: JoinType, tolerance: f64) -> Region {
+        offset(self, distance, join, tolerance)
+    }
+
+    /// One shape per separate part
+    ///
+    /// Each outer contour takes the holes it encloses; parts without holes
+    /// become polygons and the rest `Shape::Region`s.
+    pub fn to_shapes(&self) -> Vec<Shape> {
+        let (outers, holes): (Vec<&Vec<Point>>, Vec<&Vec<Point>>) = self
+            .contours
+            .iter()
+            .filter(|contour| !contour.is_empty())
+            .partition(|contour| signed_area(contour) > 0.0);
+
+        let mut parts: Vec<Vec<Vec<Point>>> =
+            outers.iter().map(|outer| vec![(*outer).clone()]).collect();
+        for hole in holes {
+            // Nested parts overlap, so the hole belongs to the smallest
+            let owner = outers
+                .iter()
+                .enumerate()
+                .filter(|(_, outer)| winding(std::slice::from_ref(*outer), hole[0]) != 0)
+                .min_by(|(_, a), (_, b)| signed_area(a).total_cmp(&signed_area(b)))
+                .map(|(index, _)| index);
+            match owner {
+                Some(index) => parts[index].push(hole.clone()),
+                None => parts.push(vec![hole.clone()]),
+            }
+        }
+
+        parts
+            .into_iter()
+            .map(|mut contours| {
+                if contours.len() == 1 {
+                    Shape::polygon(contours.remove(0))
+                } else {
+                    Shape::region(contours)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Signed shoelace area, positive for counter-clockwise contours
+pub(crate) fn signed_area(points: &[Point]) -> f64 {
+    let mut area = 0.0;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        area += a.0 * b.1 - b.0 * a.1;
+    }
+    area / 2.0
+}
+
+/// Winding number of the contours around a point
+pub(crate) fn winding(contours: &[Vec<Point>], p: Point) -> i32 {
+    let mut winding = 0;
+    for contour in contours {
+        for (i, a) in contour.iter().enumerate() {
+            let b = contour[(i + 1) % contour.len()];
+            let side = (b.0 - a.0) * (p.1 - a.1) - (p.0 - a.0) * (b.1 - a.1);
+            if a.1 <= p.1 {
+                if b.1 > p.1 && side > 0.0 {
+                    winding += 1;
+                }
+            } else if b.1 <= p.1 && side < 0.0 {
+                winding -= 1;
+            }
+        }
+    }
+    winding
+}
+
+fn cross(a: Point, b: Point) -> f64 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+/// Points merged when closer than the snapping distance
+struct VertexPool {
+    cell: f64,
+    grid: HashMap<(i64, i64), Vec<usize>>,
+    points: Vec<Point>,
+}
+
+impl VertexPool {
+    fn new(cell: f64) -> Self {
+        VertexPool {
+            cell,
+            grid: HashMap::new(),
+            points: Vec::new(),
+        }
+    }
+
+    fn id(&mut self, p: Point) -> usize {
+        let key = (
+            (p.0 / self.cell).floor() as i64,
+            (p.1 / self.cell).floor() as i64,
+        );
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if let Some(ids) = self.grid.get(&(key.0 + dx, key.1 + dy)) {
+                    for &id in ids {
+                        let q = self.points[id];
+                        if (q.0 - p.0).hypot(q.1 - p.1) <= self.cell {
+                            return id;
+                        }
+                    }
+                }
+            }
+        }
+        self.points.push(p);
+        let id = self.points.len() - 1;
+        self.grid.entry(key).or_default().push(id);
+        id
+    }
+}
+
+/// Overlay two sets of contours and trace the boundary of the area where
+/// `filled(subject winding, clip winding)` holds
+fn overlay<F>(subject: &[Vec<Point>], clip: &[Vec<Point>], filled: F) -> Region
+where
+    F: Fn(i32, i32) -> bool,
+{
+    let edges: Vec<(Point, Point)> = subject
+        .iter()
+        .chain(clip)
+        .filter(|contour| contour.len() > 1)
+        .flat_map(|contour| {
+            (0..contour.len()).map(move |i| (contour[i], contour[(i + 1) % contour.len()]))
+        })
+        .filter(|(a, b)| a != b)
+        .collect();
+    let Some(bounds) = Region::new(subject.iter().chain(clip).cloned().collect()).bounds() else {
+        return Region::default();
+    };
+    let extent = (bounds.2 - bounds.0).hypot(bounds.3 - bounds.1).max(1.0);
+    let snap = SNAP_RATIO * extent;
+
+    // Split points of every edge, as (parameter, point)
+    let mut splits: Vec<Vec<(f64, Point)>> = edges
+        .iter()
+        .map(|&(a, b)| vec![(0.0, a), (1.0, b)])
+        .collect();
+    let mut order: Vec<usize> = (0..edges.len()).collect();
+    let min_x = |i: usize| edges[i].0 .0.min(edges[i].1 .0);
+    order.sort_by(|&i, &j| min_x(i).total_cmp(&min_x(j)));
+    for (n, &i) in order.iter().enumerate() {
+        let (a, b) = edges[i];
+        let max_x = a.0.max(b.0) + snap;
+        for &j in &order[n + 1..] {
+            if min_x(j) > max_x {
+                break;
+            }
+            let (c, d) = edges[j];
+            if a.1.max(b.1) + snap < c.1.min(d.1) || c.1.max(d.1) + snap < a.1.min(b.1) {
+                continue;
+            }
+            for (t, u, p) in intersections((a, b), (c, d), snap) {
+                splits[i].push((t, p));
+                splits[j].push((u, p));
+            }
+        }
+    }
+
+    // Pieces between consecutive split points, each kept once
+    let mut pool = VertexPool::new(snap);
+    let mut seen = HashSet::new();
+    let mut pieces = Vec::new();
+    for mut points in splits {
+        points.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let ids: Vec<usize> = points.iter().map(|&(_, p)| pool.id(p)).collect();
+        for pair in ids.windows(2) {
+            let (u, v) = (pair[0], pair[1]);
+            if u != v && seen.insert((u.min(v), u.max(v))) {
+                pieces.push((u, v));
+            }
+        }
+    }
+    let vertices = pool.points;
+
+    // Orient kept pieces so the filled side is on their left
+    let offset = SAMPLE_OFFSET * snap;
+    let mut kept = Vec::new();
+    for (u, v) in pieces {
+        let (a, b) = (vertices[u], vertices[v]);
+        let direction = sub(b, a);
+        let length = direction.0.hypot(direction.1);
+        let normal = (
+            -direction.1 / length * offset,
+            direction.0 / length * offset,
+        );
+        let middle = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+        let left = (middle.0 + normal.0, middle.1 + normal.1);
+        let right = (middle.0 - normal.0, middle.1 - normal.1);
+        let left_filled = filled(winding(subject, left), winding(clip, left));
+        let right_filled = filled(winding(subject, right), winding(clip, right));
+        match (left_filled, right_filled) {
+            (true, false) => kept.push((u, v)),
+            (false, true) => kept.push((v, u)),
+            _ => {}
+        }
+    }
+
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, &(u, _)) in kept.iter().enumerate() {
+        outgoing.entry(u).or_default().push(index);
+    }
+    let mut used = vec![false; kept.len()];
+    let mut contours = Vec::new();
+    for first in 0..kept.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let (start, mut current) = kept[first];
+        let mut direction = sub(vertices[current], vertices[start]);
+        let mut contour = vec![vertices[start]];
+        let mut closed = false;
+        loop {
+            if current == start {
+                closed = true;
+                break;
+            }
+            // Sharpest left turn, so regions touching at a vertex stay apart
+            let next = outgoing
+                .get(&current)
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|&index| !used[index])
+                .max_by(|&x, &y| {
+                    let turn = |index: usize| {
+                        let (from, to) = kept[index];
+                        let d = sub(vertices[to], vertices[from]);
+                        cross(direction, d).atan2(direction.0 * d.0 + direction.1 * d.1)
+                    };
+                    turn(x).total_cmp(&turn(y))
+                });
+            let Some(next) = next else { break };
+            used[next] = true;
+            contour.push(vertices[current]);
+            let (from, to) = kept[next];
+            direction = sub(vertices[to], vertices[from]);
+            current = to;
+        }
+        if closed {
+            let contour = remove_collinear(contour, snap);
+            if contour.len() >= 3 && signed_area(&contour).abs() > snap * snap {
+                contours.push(contour);
+            }
+        }
+    }
+    Region::new(contours)
+}
+
+/// Points where two edges meet, as (parameter on first, parameter on
+/// second, point); overlapping collinear edges meet at their end points
+fn intersections(
+    first: (Point, Point),
+    second: (Point, Point),
+    snap: f64,
+) -> Vec<(f64, f64, Point)> {
+    let (a, b) = first;
+    let (c, d) = second;
+    let r = sub(b, a);
+    let s = sub(d, c);
+    let (r_length, s_length) = (r.0.hypot(r.1), s.0.hypot(s.1));
+    let denominator = cross(r, s);
+    if denominator.abs() > 1e-12 * r_length * s_length {
+        let t = cross(sub(c, a), s) / denominator;
+        let u = cross(sub(c, a), r) / denominator;
+        let (t_slack, u_slack) = (snap / r_length, snap / s_length);
+        if t < -t_slack || t > 1.0 + t_slack || u < -u_slack || u > 1.0 + u_slack {
+            return Vec::new();
+        }
+        let (t, u) = (t.clamp(0.0, 1.0), u.clamp(0.0, 1.0));
+        return vec![(t, u, (a.0 + t * r.0, a.1 + t * r.1))];
+    }
+
+    // Parallel: only collinear edges touch
+    if (cross(r, sub(c, a)) / r_length).abs() > snap {
+        return Vec::new();
+    }
+    let along = |p: Point, origin: Point, v: Point, length: f64| {
+        (sub(p, origin).0 * v.0 + sub(p, origin).1 * v.1) / (length * length)
+    };
+    let mut points = Vec::new();
+    for p in [c, d] {
+        let t = along(p, a, r, r_length);
+        if (0.0..=1.0).contains(&t) {
+            points.push((t, along(p, c, s, s_length), p));
+        }
+    }
+    for p in [a, b] {
+        let u = along(p, c, s, s_length);
+        if (0.0..=1.0).contains(&u) {
+            points.push((along(p, a, r, r_length), u, p));
+        }
+    }
+    points
+}
+
+/// Drop points lying on the straight line between their neighbours
+fn remove_collinear(mut points: Vec<Point>, snap: f64) -> Vec<Point> {
+    let mut i = 0;
+    while points.len() >= 3 && i < points.len() {
+        let previous = points[(i + points.len() - 1) % points.len()];
+        let next = points[(i + 1) % points.len()];
+        let (before, after) = (sub(points[i], previous), sub(next, points[i]));
+        let length = before.0.hypot(before.1) + after.0.hypot(after.1);
+        let straight = before.0 * after.0 + before.1 * after.1 > 0.0;
+        if straight && cross(before, after).abs() <= snap * length {
+            points.remove(i);
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intersections_crossing_and_collinear() {
+        let crossing = intersections(((0.0, 0.0), (2.0, 2.0)), ((0.0, 2.0), (2.0, 0.0)), 1e-9);
+        assert_eq!(crossing, vec![(0.5, 0.5, (1.0, 1.0))]);
+
+        let overlap = intersections(((0.0, 0.0), (4.0, 0.0)), ((2.0, 0.0), (6.0, 0.0)), 1e-9);
+        let points: Vec<Point> = overlap.iter().map(|&(_, _, p)| p).collect();
+        assert_eq!(points, vec![(2.0, 0.0), (4.0, 0.0)]);
+
+        assert!(intersections(((0.0, 0.0), (1.0, 0.0)), ((0.0, 1.0), (1.0, 1.0)), 1e-9).is_empty());
+    }
+
+    #[test]
+    fn test_winding_and_fill_rules() {
+        let square = vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+        let twice = vec![square.clone(), square.clone()];
+        assert_eq!(winding(&twice, (1.0, 1.0)), 2);
+        assert!(FillRule::NonZero.is_filled(2));
+        assert!(!FillRule::EvenOdd.is_filled(2));
+        assert!(!FillRule::Positive.is_filled(-1));
+    }
+
+    #[test]
+    fn test_remove_collinear() {
+        let points = vec![
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 2.0),
+            (0.0, 2.0),
+            (0.0, 1.0),
+        ];
+        assert_eq!(
+            remove_collinear(points, 1e-9),
+            vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]
+        );
+    }
+}
@@ -0,0 +1,459 @@
+//! Job time estimation
+//!
+//! Estimates how long a program takes to run by planning its moves the way
+//! GRBL does: every move accelerates and decelerates within the per-axis
+//! limits (`$110`-`$112` max rate, `$120`-`$122` acceleration), and corners
+//! are taken at the speed the junction deviation (`$11`) allows. Moves are
+//! parsed with `GcodeInterpreter`, so modal state, units and arcs are
+//! handled the same way as in the back plot.
+
+use super::backplot::{ArcGeometry, BackPlotStep, MoveType};
+use super::interpreter::{plane_axes, GcodeInterpreter};
+use crate::communication::parser_state::{split_words, FeedRateMode};
+use crate::communication::GrblSettings;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// GRBL's compiled-in default max rate in mm/min
+const DEFAULT_MAX_RATE: f64 = 500.0;
+
+/// GRBL's compiled-in default acceleration in mm/sec²
+const DEFAULT_ACCELERATION: f64 = 10.0;
+
+/// GRBL's compiled-in default junction deviation in mm
+const DEFAULT_JUNCTION_DEVIATION: f64 = 0.01;
+
+/// Direction changes smaller than this are treated as straight through
+const STRAIGHT_JUNCTION_COS: f64 = 0.999999;
+
+/// Motion limits of a machine
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MachineLimits {
+    /// Max rate per axis (X, Y, Z) in mm/min
+    pub max_rate: [f64; 3],
+    /// Acceleration per axis (X, Y, Z) in mm/sec²
+    pub acceleration: [f64; 3],
+    /// Junction deviation in mm
+    pub junction_deviation: f64,
+}
+
+impl Default for MachineLimits {
+    fn default() -> Self {
+        MachineLimits {
+            max_rate: [DEFAULT_MAX_RATE; 3],
+            acceleration: [DEFAULT_ACCELERATION; 3],
+            junction_deviation: DEFAULT_JUNCTION_DEVIATION,
+        }
+    }
+}
+
+impl MachineLimits {
+    /// Read the limits from GRBL settings
+    ///
+    /// Settings the controller did not report keep GRBL's defaults.
+    pub fn from_settings(settings: &GrblSettings) -> Self {
+        let defaults = MachineLimits::default();
+        MachineLimits {
+            max_rate: [0, 1, 2].map(|axis| {
+                settings
+                    .max_rate(axis)
+                    .filter(|rate| *rate > 0.0)
+                    .unwrap_or(defaults.max_rate[axis])
+            }),
+            acceleration: [0, 1, 2].map(|axis| {
+                settings
+                    .acceleration(axis)
+                    .filter(|accel| *accel > 0.0)
+                    .unwrap_or(defaults.acceleration[axis])
+            }),
+            junction_deviation: settings
+                .junction_deviation()
+                .filter(|deviation| *deviation >= 0.0)
+                .unwrap_or(defaults.junction_deviation),
+        }
+    }
+
+    /// Scale a per-axis limit onto a direction
+    ///
+    /// The move is limited by whichever axis reaches its own limit first,
+    /// as GRBL's planner does.
+    fn along(limits: &[f64; 3], direction: &[f64; 3]) -> f64 {
+        direction
+            .iter()
+            .zip(limits)
+            .filter(|(component, _)| component.abs() > 1e-12)
+            .map(|(component, limit)| limit / component.abs())
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// Estimated run time of a program, in seconds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimeEstimate {
+    /// Time spent on feed moves (G1, G2, G3 and probing)
+    pub cutting: f64,
+    /// Time spent on rapid moves (G0)
+    pub rapid: f64,
+    /// Time spent in dwells (G4)
+    pub dwell: f64,
+    /// Time spent on each program line, indexed from line 1
+    pub line_times: Vec<f64>,
+}
+
+impl TimeEstimate {
+    /// Get the total run time in seconds
+    pub fn total(&self) -> f64 {
+        self.cutting + self.rapid + self.dwell
+    }
+
+    /// Get the time left once the first `lines_done` lines have run
+    pub fn remaining_after(&self, lines_done: usize) -> f64 {
+        self.line_times.iter().skip(lines_done).sum()
+    }
+}
+
+/// One move ready for planning, in mm and mm/sec
+#[derive(Debug, Clone)]
+struct PlannedMove {
+    line_number: usize,
+    move_type: MoveType,
+    length: f64,
+    /// Unit direction leaving the previous move
+    entry_direction: [f64; 3],
+    /// Unit direction into the next move
+    exit_direction: [f64; 3],
+    nominal_speed: f64,
+    acceleration: f64,
+    /// Highest speed allowed at the start of the move
+    max_entry_speed: f64,
+    entry_speed: f64,
+    /// Seconds of dwell; dwells have no length and stop the machine
+    dwell: f64,
+}
+
+/// Estimates program run time from machine limits
+#[derive(Debug, Clone, Default)]
+pub struct TimeEstimator {
+    limits: MachineLimits,
+    default_feed: Option<f64>,
+}
+
+impl TimeEstimator {
+    /// Create an estimator for a machine
+    pub fn new(limits: MachineLimits) -> Self {
+        TimeEstimator {
+            limits,
+            default_feed: None,
+        }
+    }
+
+    /// Use `feed_rate` (mm/min) for feed moves before the program sets one
+    ///
+    /// Without it such moves run at the axis max rate.
+    pub fn with_default_feed(mut self, feed_rate: f64) -> Self {
+        self.default_feed = Some(feed_rate).filter(|feed| *feed > 0.0);
+        self
+    }
+
+    /// Get the machine limits used for planning
+    pub fn limits(&self) -> &MachineLimits {
+        &self.limits
+    }
+
+    /// Estimate how long a program takes
+    ///
+    /// # Arguments
+    /// * `gcode` - Program to estimate
+    ///
+    /// # Returns
+    /// Time split into cutting, rapid and dwell, or an error for lines the
+    /// interpreter rejects
+    pub fn estimate(&self, gcode: &str) -> Result<TimeEstimate> {
+        let mut interpreter = GcodeInterpreter::new();
+        let mut moves: Vec<PlannedMove> = Vec::new();
+        let mut line_count = 0;
+
+        for (index, line) in gcode.lines().enumerate() {
+            line_count = index + 1;
+            let Some(step) = interpreter.interpret_line(index + 1, line)? else {
+                continue;
+            };
+            if step.move_type == MoveType::Dwell {
+                moves.push(PlannedMove::dwell(step.line_number, dwell_seconds(line)));
+                continue;
+            }
+            if let Some(planned) = self.plan_move(&interpreter, &step) {
+                moves.push(planned);
+            }
+        }
+
+        self.limit_junctions(&mut moves);
+        plan_speeds(&mut moves);
+
+        let mut estimate = TimeEstimate {
+            line_times: vec![0.0; line_count],
+            ..Default::default()
+        };
+        for (index, planned) in moves.iter().enumerate() {
+            let exit_speed = moves.get(index + 1).map_or(0.0, |next| next.entry_speed);
+            let time = planned.duration(exit_speed);
+            match planned.move_type {
+                MoveType::Rapid => estimate.rapid += time,
+                MoveType::Dwell => {}
+                _ => estimate.cutting += time,
+            }
+            estimate.dwell += planned.dwell;
+            estimate.line_times[planned.line_number - 1] += time + planned.dwell;
+        }
+        Ok(estimate)
+    }
+
+    /// Work out length, direction and speed limits of an interpreted move
+    fn plan_move(
+        &self,
+        interpreter: &GcodeInterpreter,
+        step: &BackPlotStep,
+    ) -> Option<PlannedMove> {
+        let start = step.start_pos.map(f64::from);
+        let end = step.end_pos.map(f64::from);
+        let delta = [end[0] - start[0], end[1] - start[1], end[2] - start[2]];
+
+        let arc = step.arc;
+        let (length, entry_direction, exit_direction) = match &arc {
+            Some(arc) => arc_path(arc, &start, &end),
+            None => {
+                let length = norm(&delta);
+                let direction = delta.map(|d| d / length);
+                (length, direction, direction)
+            }
+        };
+        if length < 1e-9 {
+            return None;
+        }
+
+        // Arcs turn through every direction in their plane, so the slowest
+        // axis taking part limits the whole arc
+        let limit_direction = match &arc {
+            Some(arc) => {
+                let (a, b) = plane_axes(arc.plane);
+                let mut direction = delta.map(|d| if d.abs() > 1e-12 { 1.0 } else { 0.0 });
+                direction[a] = 1.0;
+                direction[b] = 1.0;
+                direction
+            }
+            None => entry_direction,
+        };
+        let max_speed = MachineLimits::along(&self.limits.max_rate, &limit_direction) / 60.0;
+        let acceleration = MachineLimits::along(&self.limits.acceleration, &limit_direction);
+
+        let state = interpreter.state();
+        let mut nominal_speed = if step.move_type == MoveType::Rapid {
+            max_speed
+        } else if state.feed_mode == FeedRateMode::InverseTime && state.feed_rate > 0.0 {
+            // F is the inverse of the move's duration in minutes
+            length * state.feed_rate / 60.0
+        } else if step.feed_rate > 0.0 {
+            f64::from(step.feed_rate) / 60.0
+        } else {
+            self.default_feed.map_or(max_speed, |feed| feed / 60.0)
+        }
+        .min(max_speed);
+        if let Some(arc) = &arc {
+            // GRBL splits arcs into short segments whose junctions hold the
+            // speed near the centripetal limit
+            nominal_speed = nominal_speed.min((acceleration * f64::from(arc.radius)).sqrt());
+        }
+
+        Some(PlannedMove {
+            line_number: step.line_number,
+            move_type: step.move_type,
+            length,
+            entry_direction,
+            exit_direction,
+            nominal_speed,
+            acceleration,
+            max_entry_speed: 0.0,
+            entry_speed: 0.0,
+            dwell: 0.0,
+        })
+    }
+
+    /// Set the highest entry speed of every move from junction deviation
+    fn limit_junctions(&self, moves: &mut [PlannedMove]) {
+        for index in 1..moves.len() {
+            let previous = &moves[index - 1];
+            let current = &moves[index];
+            if previous.length == 0.0 || current.length == 0.0 {
+                // Dwells bring the machine to a stop
+                continue;
+            }
+            let cos_theta = -dot(&previous.exit_direction, &current.entry_direction);
+            let junction_speed = if cos_theta > STRAIGHT_JUNCTION_COS {
+                // Full reversal
+                0.0
+            } else if cos_theta < -STRAIGHT_JUNCTION_COS {
+                f64::INFINITY
+            } else {
+                // Largest circle that fits the corner within the deviation
+                let sin_theta_d2 = (0.5 * (1.0 - cos_theta)).sqrt();
+                let acceleration = previous.acceleration.min(current.acceleration);
+                (acceleration * self.limits.junction_deviation * sin_theta_d2
+                    / (1.0 - sin_theta_d2))
+                    .sqrt()
+            };
+            let max_entry = junction_speed
+                .min(previous.nominal_speed)
+                .min(current.nominal_speed);
+            moves[index].max_entry_speed = max_entry;
+        }
+    }
+}
+
+impl PlannedMove {
+    /// A dwell, which waits with the machine stopped
+    fn dwell(line_number: usize, seconds: f64) -> Self {
+        PlannedMove {
+            line_number,
+            move_type: MoveType::Dwell,
+            length: 0.0,
+            entry_direction: [0.0; 3],
+            exit_direction: [0.0; 3],
+            nominal_speed: 0.0,
+            acceleration: 0.0,
+            max_entry_speed: 0.0,
+            entry_speed: 0.0,
+            dwell: seconds,
+        }
+    }
+
+    /// Seconds to run the move between its entry and exit speeds
+    ///
+    /// The speed follows a trapezoid: accelerate to the nominal speed,
+    /// cruise, and decelerate. Moves too short to reach the nominal speed
+    /// follow a triangle instead.
+    fn duration(&self, exit_speed: f64) -> f64 {
+        if self.length == 0.0 {
+            return 0.0;
+        }
+        let (v_in, v_out, v_max, a) = (
+            self.entry_speed,
+            exit_speed,
+            self.nominal_speed,
+            self.acceleration,
+        );
+        let accelerate = (v_max * v_max - v_in * v_in) / (2.0 * a);
+        let decelerate = (v_max * v_max - v_out * v_out) / (2.0 * a);
+        if accelerate + decelerate <= self.length {
+            (v_max - v_in) / a
+                + (v_max - v_out) / a
+                + (self.length - accelerate - decelerate) / v_max
+        } else {
+            let peak = ((2.0 * a * self.length + v_in * v_in + v_out * v_out) / 2.0).sqrt();
+            (peak - v_in).max(0.0) / a + (peak - v_out).max(0.0) / a
+        }
+    }
+}
+
+/// Pick entry speeds that every move can reach and stop from in time
+///
+/// The backward pass makes sure each move can slow down to what the next
+/// one allows; the forward pass makes sure it can speed up to it.
+fn plan_speeds(moves: &mut [PlannedMove]) {
+    let mut next_entry = 0.0;
+    for planned in moves.iter_mut().rev() {
+        let reachable =
+            (next_entry * next_entry + 2.0 * planned.acceleration * planned.length).sqrt();
+        planned.entry_speed = planned.max_entry_speed.min(reachable);
+        next_entry = planned.entry_speed;
+    }
+
+    for index in 1..moves.len() {
+        let previous = &moves[index - 1];
+        let reachable = (previous.entry_speed * previous.entry_speed
+            + 2.0 * previous.acceleration * previous.length)
+            .sqrt();
+        if moves[index].entry_speed > reachable {
+            moves[index].entry_speed = reachable;
+        }
+    }
+}
+
+/// Length and start and end tangents of an arc, including any helix travel
+fn arc_path(arc: &ArcGeometry, start: &[f64; 3], end: &[f64; 3]) -> (f64, [f64; 3], [f64; 3]) {
+    let (a, b) = plane_axes(arc.plane);
+    let linear = 3 - a - b;
+    let planar = f64::from(arc.radius * arc.sweep.abs());
+    let rise = end[linear] - start[linear];
+    let length = planar.hypot(rise);
+
+    let tangent = |point: &[f64; 3]| {
+        let center = arc.center.map(f64::from);
+        let (ra, rb) = (point[a] - center[a], point[b] - center[b]);
+        // Perpendicular to the radius in the turning direction
+        let (ta, tb) = if arc.sweep > 0.0 {
+            (-rb, ra)
+        } else {
+            (rb, -ra)
+        };
+        let scale = planar / length / ra.hypot(rb).max(1e-12);
+        let mut direction = [0.0; 3];
+        direction[a] = ta * scale;
+        direction[b] = tb * scale;
+        direction[linear] = rise / length;
+        direction
+    };
+    (length, tangent(start), tangent(end))
+}
+
+/// Read the dwell time of a `G4` line in seconds
+pub(crate) fn dwell_seconds(line: &str) -> f64 {
+    split_words(line)
+        .iter()
+        .find_map(|word| word.strip_prefix(['P', 'p']))
+        .and_then(|value| value.parse::<f64>().ok())
+        .unwrap_or(0.0)
+        .max(0.0)
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn norm(v: &[f64; 3]) -> f64 {
+    dot(v, v).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_along_direction() {
+        let limits = [1000.0, 500.0, 100.0];
+        assert_eq!(MachineLimits::along(&limits, &[1.0, 0.0, 0.0]), 1000.0);
+        let diagonal = std::f64::consts::FRAC_1_SQRT_2;
+        let rate = MachineLimits::along(&limits, &[diagonal, diagonal, 0.0]);
+        assert!((rate - 500.0 / diagonal).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_trapezoid_and_triangle_profiles() {
+        let mut planned = PlannedMove::dwell(1, 0.0);
+        planned.length = 100.0;
+        planned.nominal_speed = 10.0;
+        planned.acceleration = 10.0;
+        // 5 mm to reach 10 mm/s, 5 mm to stop, 90 mm cruising
+        assert!((planned.duration(0.0) - 11.0).abs() < 1e-9);
+
+        planned.length = 2.5;
+        // Peaks at 5 mm/s halfway
+        assert!((planned.duration(0.0) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_dwell_seconds() {
+        assert_eq!(dwell_seconds("G4 P1.5"), 1.5);
+        assert_eq!(dwell_seconds("g4p2"), 2.0);
+        assert_eq!(dwell_seconds("G4"), 0.0);
+    }
+}
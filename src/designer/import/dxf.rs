@@ -0,0 +1,744 @@
+//! DXF import
+//!
+//! Reads LINE, LWPOLYLINE and POLYLINE (with bulges), ARC, CIRCLE, ELLIPSE,
+//! SPLINE and INSERT entities, flattens curves into points and scales them
+//! from the drawing's `$INSUNITS` to millimetres. CAD parts are often drawn
+//! as separate lines and arcs, so open pieces on the same layer whose ends
+//! meet are joined into contours, which close when they come back to their
+//! start. Shapes keep their DXF layer, with the layer's colour.
+
+use super::{contour_shape, hex_color, DEFAULT_TOLERANCE};
+use crate::designer::shapes::arc_segments;
+use crate::designer::Design;
+use ::dxf::entities::{Entity, EntityType};
+use ::dxf::enums::Units;
+use ::dxf::{Drawing, Point};
+use anyhow::{anyhow, Context, Result};
+use std::f64::consts::TAU;
+use std::path::Path;
+
+/// Deepest nesting of block references followed, to stop self-references
+const MAX_INSERT_DEPTH: usize = 16;
+
+/// POLYLINE flags for polygon and polyface meshes, which are 3D surfaces
+const POLYLINE_MESH_FLAGS: i32 = 16 | 64;
+
+/// Deepest subdivision of a spline span
+const MAX_SPLINE_DEPTH: usize = 12;
+
+/// DXF import configuration
+#[derive(Clone, Debug)]
+pub struct DxfImportOptions {
+    /// Largest distance in mm between a flattened curve and the original
+    pub tolerance: f64,
+    /// Largest gap in mm between piece ends that are joined
+    pub join_tolerance: f64,
+    /// Millimetres per drawing unit when the drawing does not say
+    pub unitless_scale: f64,
+}
+
+impl Default for DxfImportOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: DEFAULT_TOLERANCE,
+            join_tolerance: 0.01,
+            unitless_scale: 1.0,
+        }
+    }
+}
+
+/// Outcome of a DXF import
+#[derive(Clone, Debug, Default)]
+pub struct DxfImportReport {
+    /// IDs of the shapes added to the design
+    pub shape_ids: Vec<String>,
+    /// Shapes that form closed contours
+    pub closed: usize,
+    /// Shapes left open after joining
+    pub open: usize,
+    /// Entities of unsupported types, such as text, hatches and rational splines
+    pub skipped: usize,
+    /// Millimetres per drawing unit used
+    pub scale: f64,
+}
+
+/// Imports DXF drawings into designs
+#[derive(Clone, Debug)]
+pub struct DxfImporter {
+    options: DxfImportOptions,
+}
+
+/// 2D affine transform: `x' = xx*x + xy*y + tx`, `y' = yx*x + yy*y + ty`
+#[derive(Clone, Copy, Debug)]
+struct Affine {
+    xx: f64,
+    xy: f64,
+    yx: f64,
+    yy: f64,
+    tx: f64,
+    ty: f64,
+}
+
+impl Affine {
+    fn scale(sx: f64, sy: f64) -> Self {
+        Affine {
+            xx: sx,
+            xy: 0.0,
+            yx: 0.0,
+            yy: sy,
+            tx: 0.0,
+            ty: 0.0,
+        }
+    }
+
+    fn translate(tx: f64, ty: f64) -> Self {
+        Affine {
+            tx,
+            ty,
+            ..Affine::scale(1.0, 1.0)
+        }
+    }
+
+    fn rotate(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Affine {
+            xx: cos,
+            xy: -sin,
+            yx: sin,
+            yy: cos,
+            tx: 0.0,
+            ty: 0.0,
+        }
+    }
+
+    /// Transform that applies `inner` first, then `self`
+    fn then(&self, inner: &Affine) -> Self {
+        Affine {
+            xx: self.xx * inner.xx + self.xy * inner.yx,
+            xy: self.xx * inner.xy + self.xy * inner.yy,
+            yx: self.yx * inner.xx + self.yy * inner.yx,
+            yy: self.yx * inner.xy + self.yy * inner.yy,
+            tx: self.xx * inner.tx + self.xy * inner.ty + self.tx,
+            ty: self.yx * inner.tx + self.yy * inner.ty + self.ty,
+        }
+    }
+
+    fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            self.xx * x + self.xy * y + self.tx,
+            self.yx * x + self.yy * y + self.ty,
+        )
+    }
+
+    /// Average length scaling, for turning tolerances into local units
+    fn scale_factor(&self) -> f64 {
+        (self.xx * self.yy - self.xy * self.yx)
+            .abs()
+            .sqrt()
+            .max(1e-12)
+    }
+}
+
+/// Flattened piece of an entity, in millimetres
+#[derive(Debug)]
+struct Piece {
+    layer: String,
+    points: Vec<(f64, f64)>,
+    closed: bool,
+}
+
+impl DxfImporter {
+    /// Create an importer with default options
+    pub fn new() -> Self {
+        Self {
+            options: DxfImportOptions::default(),
+        }
+    }
+
+    /// Create an importer with custom options
+    pub fn with_options(options: DxfImportOptions) -> Self {
+        Self { options }
+    }
+
+    /// Get importer options
+    pub fn options(&self) -> &DxfImportOptions {
+        &self.options
+    }
+
+    /// Import a DXF file
+    ///
+    /// # Arguments
+    /// * `path` - DXF file
+    /// * `design` - Design the shapes are added to
+    pub fn import_file(&self, path: &Path, design: &mut Design) -> Result<DxfImportReport> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read DXF from {}", path.display()))?;
+        self.import(&data, design)
+    }
+
+    /// Import DXF data
+    ///
+    /// # Arguments
+    /// * `data` - ASCII or binary DXF
+    /// * `design` - Design the shapes are added to
+    pub fn import(&self, data: &[u8], design: &mut Design) -> Result<DxfImportReport> {
+        let drawing = Drawing::load(&mut &data[..]).map_err(|e| anyhow!("Invalid DXF: {}", e))?;
+        self.import_drawing(&drawing, design)
+    }
+
+    /// Import a loaded drawing's model space
+    ///
+    /// # Arguments
+    /// * `drawing` - Drawing to import
+    /// * `design` - Design the shapes are added to
+    ///
+    /// # Returns
+    /// The shapes added, with how many are closed
+    pub fn import_drawing(
+        &self,
+        drawing: &Drawing,
+        design: &mut Design,
+    ) -> Result<DxfImportReport> {
+        let scale = units_to_mm(drawing.header.default_drawing_units)
+            .unwrap_or(self.options.unitless_scale);
+        let mut report = DxfImportReport {
+            scale,
+            ..Default::default()
+        };
+
+        let mut pieces = Vec::new();
+        self.collect(
+            drawing,
+            drawing.entities.iter(),
+            &Affine::scale(scale, scale),
+            None,
+            0,
+            &mut pieces,
+            &mut report.skipped,
+        );
+
+        // Layers in the order they first appear
+        let mut layers: Vec<String> = Vec::new();
+        for piece in &pieces {
+            if !layers.contains(&piece.layer) {
+                layers.push(piece.layer.clone());
+            }
+        }
+        for layer in layers {
+            let color = layer_color(drawing, &layer);
+            let (closed, open): (Vec<_>, Vec<_>) = pieces
+                .iter()
+                .filter(|piece| piece.layer == layer)
+                .partition(|piece| piece.closed);
+            let mut contours: Vec<(Vec<(f64, f64)>, bool)> = closed
+                .into_iter()
+                .map(|piece| (piece.points.clone(), true))
+                .collect();
+            contours.extend(join_pieces(
+                open.into_iter().map(|piece| piece.points.clone()).collect(),
+                self.options.join_tolerance,
+            ));
+
+            for (points, closed) in contours {
+                if let Some(shape) = contour_shape(points, closed) {
+                    if closed {
+                        report.closed += 1;
+                    } else {
+                        report.open += 1;
+                    }
+                    let id = design.add_shape_to_layer(shape, &layer, color.as_deref());
+                    report.shape_ids.push(id);
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Flatten entities into pieces, following block references
+    #[allow(clippy::too_many_arguments)]
+    fn collect<'a>(
+        &self,
+        drawing: &Drawing,
+        entities: impl Iterator<Item = &'a Entity>,
+        transform: &Affine,
+        insert_layer: Option<&str>,
+        depth: usize,
+        pieces: &mut Vec<Piece>,
+        skipped: &mut usize,
+    ) {
+        let tolerance = self.options.tolerance / transform.scale_factor();
+        for entity in entities {
+            // Block entities on layer 0 take the layer of the reference
+            let layer = match insert_layer {
+                Some(layer) if entity.common.layer == "0" => layer.to_string(),
+                _ => entity.common.layer.clone(),
+            };
+            let (points, closed, normal_z) = match &entity.specific {
+                EntityType::Line(line) => (vec![xy(&line.p1), xy(&line.p2)], false, 1.0),
+                EntityType::LwPolyline(polyline) => {
+                    let closed = polyline.get_is_closed();
+                    let vertices: Vec<_> = polyline
+                        .vertices
+                        .iter()
+                        .map(|v| ((v.x, v.y), v.bulge))
+                        .collect();
+                    (
+                        bulge_points(&vertices, closed, tolerance),
+                        closed,
+                        polyline.extrusion_direction.z,
+                    )
+                }
+                EntityType::Polyline(polyline) if polyline.flags & POLYLINE_MESH_FLAGS == 0 => {
+                    let closed = polyline.get_is_closed();
+                    let vertices: Vec<_> = polyline
+                        .vertices
+                        .iter()
+                        .map(|v| (xy(&v.location), v.bulge))
+                        .collect();
+                    (
+                        bulge_points(&vertices, closed, tolerance),
+                        closed,
+                        polyline.normal.z,
+                    )
+                }
+                EntityType::Arc(arc) => {
+                    let start = arc.start_angle.to_radians();
+                    let mut sweep = arc.end_angle.to_radians() - start;
+                    if sweep <= 0.0 {
+                        sweep += TAU;
+                    }
+                    let points = arc_points(xy(&arc.center), arc.radius, start, sweep, tolerance);
+                    (points, false, arc.normal.z)
+                }
+                EntityType::Circle(circle) => {
+                    let mut points =
+                        arc_points(xy(&circle.center), circle.radius, 0.0, TAU, tolerance);
+                    points.pop();
+                    (points, true, circle.normal.z)
+                }
+                EntityType::Ellipse(ellipse) => {
+                    let mut sweep = ellipse.end_parameter - ellipse.start_parameter;
+                    if sweep <= 0.0 {
+                        sweep += TAU;
+                    }
+                    let closed = (sweep - TAU).abs() < 1e-9;
+                    let mut points = ellipse_points(
+                        xy(&ellipse.center),
+                        (ellipse.major_axis.x, ellipse.major_axis.y),
+                        ellipse.minor_axis_ratio,
+                        ellipse.start_parameter,
+                        sweep,
+                        tolerance,
+                    );
+                    if closed {
+                        points.pop();
+                    }
+                    (points, closed, 1.0)
+                }
+                // Control point weights are not read, so rational splines
+                // (NURBS arcs and conics) are skipped rather than misdrawn
+                EntityType::Spline(spline) if !spline.get_is_rational() => {
+                    let points = if spline.control_points.len() > spline.degree_of_curve as usize {
+                        spline_points(
+                            spline.degree_of_curve as usize,
+                            &spline.knot_values,
+                            &spline.control_points,
+                            tolerance,
+                        )
+                    } else {
+                        // Splines saved with fit points only
+                        spline.fit_points.iter().map(xy).collect()
+                    };
+                    (points, spline.get_is_closed(), 1.0)
+                }
+                EntityType::Insert(insert) => {
+                    match drawing
+                        .blocks
+                        .iter()
+                        .find(|block| block.name == insert.name)
+                    {
+                        Some(block) if depth < MAX_INSERT_DEPTH => {
+                            let placement = Affine::translate(insert.location.x, insert.location.y)
+                                .then(&Affine::rotate(insert.rotation));
+                            let local = Affine::scale(insert.x_scale_factor, insert.y_scale_factor)
+                                .then(&Affine::translate(-block.base_point.x, -block.base_point.y));
+                            for row in 0..insert.row_count.max(1) {
+                                for column in 0..insert.column_count.max(1) {
+                                    let cell = Affine::translate(
+                                        f64::from(column) * insert.column_spacing,
+                                        f64::from(row) * insert.row_spacing,
+                                    );
+                                    let block_transform =
+                                        transform.then(&placement).then(&cell).then(&local);
+                                    self.collect(
+                                        drawing,
+                                        block.entities.iter(),
+                                        &block_transform,
+                                        Some(&layer),
+                                        depth + 1,
+                                        pieces,
+                                        skipped,
+                                    );
+                                }
+                            }
+                        }
+                        _ => *skipped += 1,
+                    }
+                    continue;
+                }
+                _ => {
+                    *skipped += 1;
+                    continue;
+                }
+            };
+
+            // Entities drawn with a downward normal are mirrored in X
+            let transform = if normal_z < 0.0 {
+                transform.then(&Affine::scale(-1.0, 1.0))
+            } else {
+                *transform
+            };
+            pieces.push(Piece {
+                layer,
+                points: points.into_iter().map(|p| transform.apply(p)).collect(),
+                closed,
+            });
+        }
+    }
+}
+
+impl Default for DxfImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Millimetres per unit for `$INSUNITS`, or None when unitless
+fn units_to_mm(units: Units) -> Option<f64> {
+    let scale = match units {
+        Units::Unitless => return None,
+        Units::Inches | Units::USSurveyInch => 25.4,
+        Units::Feet => 304.8,
+        Units::USSurveyFeet => 1200.0 / 3937.0 * 1000.0,
+        Units::Yards | Units::USSurveyYard => 914.4,
+        Units::Miles | Units::USSurveyMile => 1_609_344.0,
+        Units::Millimeters => 1.0,
+        Units::Centimeters => 10.0,
+        Units::Decimeters => 100.0,
+        Units::Meters => 1000.0,
+        Units::Decameters => 10_000.0,
+        Units::Hectometers => 100_000.0,
+        Units::Kilometers => 1_000_000.0,
+        Units::Microinches => 25.4e-6,
+        Units::Mils => 0.0254,
+        Units::Microns => 1e-3,
+        Units::Nanometers => 1e-6,
+        Units::Angstroms => 1e-7,
+        // Too large to cut
+        Units::Gigameters | Units::AstronomicalUnits | Units::LightYears | Units::Parsecs => {
+            return None
+        }
+    };
+    Some(scale)
+}
+
+/// Colour of a layer from the layer table, for the standard colour indices
+fn layer_color(drawing: &Drawing, name: &str) -> Option<String> {
+    let layer = drawing.layers.iter().find(|layer| layer.name == name)?;
+    let (red, green, blue) = match layer.color.index()? {
+        1 => (255, 0, 0),
+        2 => (255, 255, 0),
+        3 => (0, 255, 0),
+        4 => (0, 255, 255),
+        5 => (0, 0, 255),
+        6 => (255, 0, 255),
+        7 => (255, 255, 255),
+        8 => (128, 128, 128),
+        9 => (192, 192, 192),
+        _ => return None,
+    };
+    Some(hex_color(red, green, blue))
+}
+
+fn xy(point: &Point) -> (f64, f64) {
+    (point.x, point.y)
+}
+
+/// Points along a circular arc, both ends included
+fn arc_points(
+    center: (f64, f64),
+    radius: f64,
+    start: f64,
+    sweep: f64,
+    tolerance: f64,
+) -> Vec<(f64, f64)> {
+    let segments = arc_segments(radius, sweep, tolerance);
+    (0..=segments)
+        .map(|i| {
+            let angle = start + sweep * i as f64 / segments as f64;
+            (
+                center.0 + radius * angle.cos(),
+                center.1 + radius * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+/// Points along an elliptical arc, both ends included
+fn ellipse_points(
+    center: (f64, f64),
+    major: (f64, f64),
+    ratio: f64,
+    start: f64,
+    sweep: f64,
+    tolerance: f64,
+) -> Vec<(f64, f64)> {
+    let minor = (-major.1 * ratio, major.0 * ratio);
+    // The major radius bounds the curvature error
+    let segments = arc_segments(major.0.hypot(major.1), sweep, tolerance);
+    (0..=segments)
+        .map(|i| {
+            let (sin, cos) = (start + sweep * i as f64 / segments as f64).sin_cos();
+            (
+                center.0 + major.0 * cos + minor.0 * sin,
+                center.1 + major.1 * cos + minor.1 * sin,
+            )
+        })
+        .collect()
+}
+
+/// Points of a polyline whose segments may bulge into arcs
+///
+/// A vertex's bulge is the tangent of a quarter of the angle swept from it
+/// to the next vertex, positive counter-clockwise; zero is a straight line.
+fn bulge_points(vertices: &[((f64, f64), f64)], closed: bool, tolerance: f64) -> Vec<(f64, f64)> {
+    let mut points = Vec::new();
+    let Some(&(first, _)) = vertices.first() else {
+        return points;
+    };
+    points.push(first);
+    let count = if closed {
+        vertices.len()
+    } else {
+        vertices.len() - 1
+    };
+    for i in 0..count {
+        let (start, bulge) = vertices[i];
+        let (end, _) = vertices[(i + 1) % vertices.len()];
+        if bulge.abs() < 1e-12 {
+            points.push(end);
+            continue;
+        }
+        let sweep = 4.0 * bulge.atan();
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let offset = (1.0 - bulge * bulge) / (4.0 * bulge);
+        let center = (
+            (start.0 + end.0) / 2.0 - offset * dy,
+            (start.1 + end.1) / 2.0 + offset * dx,
+        );
+        let radius = (start.0 - center.0).hypot(start.1 - center.1);
+        let start_angle = (start.1 - center.1).atan2(start.0 - center.0);
+        let mut arc = arc_points(center, radius, start_angle, sweep, tolerance);
+        // Land exactly on the vertex
+        arc.pop();
+        points.extend(arc.into_iter().skip(1));
+        points.push(end);
+    }
+    points
+}
+
+/// Evaluate a B-spline at `t` with de Boor's algorithm
+fn de_boor(degree: usize, knots: &[f64], control: &[(f64, f64)], t: f64) -> (f64, f64) {
+    let n = control.len();
+    // Knot span holding t, kept inside the valid range
+    let span = (degree..n).rev().find(|&k| knots[k] <= t).unwrap_or(degree);
+    let mut d: Vec<(f64, f64)> = (0..=degree).map(|j| control[j + span - degree]).collect();
+    for r in 1..=degree {
+        for j in (r..=degree).rev() {
+            let i = j + span - degree;
+            let denominator = knots[i + degree + 1 - r] - knots[i];
+            let alpha = if denominator.abs() < 1e-12 {
+                0.0
+            } else {
+                (t - knots[i]) / denominator
+            };
+            d[j] = (
+                (1.0 - alpha) * d[j - 1].0 + alpha * d[j].0,
+                (1.0 - alpha) * d[j - 1].1 + alpha * d[j].1,
+            );
+        }
+    }
+    d[degree]
+}
+
+/// Points along a B-spline, subdividing each knot span until the chords
+/// stay within `tolerance`
+fn spline_points(
+    degree: usize,
+    knots: &[f64],
+    control: &[Point],
+    tolerance: f64,
+) -> Vec<(f64, f64)> {
+    let control: Vec<(f64, f64)> = control.iter().map(xy).collect();
+    let n = control.len();
+    let knots: Vec<f64> = if knots.len() == n + degree + 1 {
+        knots.to_vec()
+    } else {
+        // Clamped uniform knots when the file has none
+        (0..n + degree + 1)
+            .map(|i| i.saturating_sub(degree).min(n - degree) as f64)
+            .collect()
+    };
+    let evaluate = |t: f64| de_boor(degree, &knots, &control, t);
+
+    let mut points = vec![evaluate(knots[degree])];
+    for span in degree..n {
+        let (t0, t1) = (knots[span], knots[span + 1]);
+        if t1 <= t0 {
+            continue;
+        }
+        // Split each span up front so S-bends are not taken for straight
+        for quarter in 0..4 {
+            let a = t0 + (t1 - t0) * quarter as f64 / 4.0;
+            let b = t0 + (t1 - t0) * (quarter + 1) as f64 / 4.0;
+            subdivide(&evaluate, a, b, tolerance, 0, &mut points);
+        }
+    }
+    points
+}
+
+/// Add points from `t0` (already added) to `t1`, halving while the curve
+/// strays from the chord
+fn subdivide<F>(
+    evaluate: &F,
+    t0: f64,
+    t1: f64,
+    tolerance: f64,
+    depth: usize,
+    out: &mut Vec<(f64, f64)>,
+) where
+    F: Fn(f64) -> (f64, f64),
+{
+    let (p0, p1) = (evaluate(t0), evaluate(t1));
+    let middle = (t0 + t1) / 2.0;
+    let pm = evaluate(middle);
+    if depth < MAX_SPLINE_DEPTH && distance_to_segment(pm, p0, p1) > tolerance {
+        subdivide(evaluate, t0, middle, tolerance, depth + 1, out);
+        subdivide(evaluate, middle, t1, tolerance, depth + 1, out);
+    } else {
+        out.push(p1);
+    }
+}
+
+fn distance_to_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (p.0 - a.0 - t * dx).hypot(p.1 - a.1 - t * dy)
+}
+
+fn near(a: (f64, f64), b: (f64, f64), tolerance: f64) -> bool {
+    (a.0 - b.0).hypot(a.1 - b.1) <= tolerance
+}
+
+/// Chain open pieces whose ends meet into longer contours
+///
+/// # Returns
+/// Each chain with whether it came back to its start
+fn join_pieces(mut pieces: Vec<Vec<(f64, f64)>>, tolerance: f64) -> Vec<(Vec<(f64, f64)>, bool)> {
+    pieces.retain(|piece| !piece.is_empty());
+    pieces.reverse();
+    let mut contours = Vec::new();
+    while let Some(mut chain) = pieces.pop() {
+        loop {
+            let (start, end) = (chain[0], chain[chain.len() - 1]);
+            if chain.len() > 2 && near(start, end, tolerance) {
+                break;
+            }
+            // Prefer pieces in file order, which is usually drawing order
+            let found = pieces.iter().rposition(|piece| {
+                near(piece[0], end, tolerance)
+                    || near(piece[piece.len() - 1], end, tolerance)
+                    || near(piece[piece.len() - 1], start, tolerance)
+                    || near(piece[0], start, tolerance)
+            });
+            let Some(index) = found else { break };
+            let mut piece = pieces.remove(index);
+            if near(piece[0], end, tolerance) {
+                chain.extend(piece.into_iter().skip(1));
+            } else if near(piece[piece.len() - 1], end, tolerance) {
+                piece.reverse();
+                chain.extend(piece.into_iter().skip(1));
+            } else {
+                if near(piece[0], start, tolerance) {
+                    piece.reverse();
+                }
+                piece.pop();
+                piece.extend(chain);
+                chain = piece;
+            }
+        }
+        let closed = chain.len() > 2 && near(chain[0], chain[chain.len() - 1], tolerance);
+        if closed {
+            // Close exactly on the start point
+            let last = chain.len() - 1;
+            chain[last] = chain[0];
+        }
+        contours.push((chain, closed));
+    }
+    contours
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bulge_half_circle() {
+        // Bulge 1 sweeps 180 degrees counter-clockwise, passing below the chord
+        let points = bulge_points(&[((0.0, 0.0), 1.0), ((10.0, 0.0), 0.0)], false, 0.01);
+        assert_eq!(points.first(), Some(&(0.0, 0.0)));
+        assert_eq!(points.last(), Some(&(10.0, 0.0)));
+        let lowest = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        assert!((lowest + 5.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_join_pieces_closes_square() {
+        let pieces = vec![
+            vec![(0.0, 0.0), (10.0, 0.0)],
+            vec![(10.0, 10.0), (0.0, 10.0)],
+            vec![(10.0, 0.0), (10.0, 10.0)],
+            // Drawn backwards
+            vec![(0.0, 0.0), (0.0, 10.0)],
+            vec![(50.0, 50.0), (60.0, 50.0)],
+        ];
+        let contours = join_pieces(pieces, 0.01);
+        assert_eq!(contours.len(), 2);
+        assert!(contours[0].1);
+        assert_eq!(contours[0].0.len(), 5);
+        assert!(!contours[1].1);
+    }
+
+    #[test]
+    fn test_spline_ends_on_clamped_control_points() {
+        let control = [
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(0.0, 10.0, 0.0),
+            Point::new(10.0, 10.0, 0.0),
+            Point::new(10.0, 0.0, 0.0),
+        ];
+        let knots = [0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0];
+        let points = spline_points(3, &knots, &control, 0.01);
+        assert_eq!(points[0], (0.0, 0.0));
+        let end = points[points.len() - 1];
+        assert!((end.0 - 10.0).abs() < 1e-9 && end.1.abs() < 1e-9);
+        // A single cubic Bézier peaks at three quarters of its control height
+        let top = points.iter().map(|p| p.1).fold(0.0, f64::max);
+        assert!((top - 7.5).abs() < 0.02);
+    }
+}
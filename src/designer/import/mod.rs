@@ -0,0 +1,43 @@
+//! Vector drawing import
+//!
+//! Importers turn drawing files into `Shape`s in a `Design`. Coordinates
+//! are converted to millimetres with Y pointing up, as on the machine, and
+//! shapes are grouped into `Layer`s by the drawing's layers and colours so
+//! each group can be given its own operation.
+
+pub mod dxf;
+pub mod svg;
+
+/// Millimetres per inch
+const MM_PER_INCH: f64 = 25.4;
+
+/// Layer for shapes the drawing does not place in a named layer
+pub const DEFAULT_LAYER: &str = "default";
+
+/// Default largest distance in mm between a flattened curve and the original
+pub const DEFAULT_TOLERANCE: f64 = 0.01;
+
+/// Format an RGB colour as `#rrggbb`
+fn hex_color(red: u8, green: u8, blue: u8) -> String {
+    format!("#{:02x}{:02x}{:02x}", red, green, blue)
+}
+
+/// Turn a flattened contour into a shape
+///
+/// Closed contours become polygons without a repeated end point; open ones
+/// become polylines. Contours too short to draw give nothing.
+fn contour_shape(mut points: Vec<(f64, f64)>, closed: bool) -> Option<super::Shape> {
+    points.dedup_by(|a, b| (a.0 - b.0).hypot(a.1 - b.1) < 1e-9);
+    if closed {
+        if points.len() > 1 {
+            let (first, last) = (points[0], points[points.len() - 1]);
+            if (first.0 - last.0).hypot(first.1 - last.1) < 1e-6 {
+                points.pop();
+            }
+        }
+        if points.len() >= 3 {
+            return Some(super::Shape::polygon(points));
+        }
+    }
+    (points.len() >= 2).then(|| super::Shape::polyline(points))
+}
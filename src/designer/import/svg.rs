@@ -0,0 +1,236 @@
+//! SVG import
+//!
+//! Parses SVG with `usvg`, which resolves styles, `use` references and basic
+//! shapes (`rect`, `circle`, `ellipse`, `polyline`, ...) into plain paths.
+//! Each path is transformed by its element and `viewBox` transforms, scaled
+//! from CSS pixels to millimetres and flattened into polygons or polylines.
+//! Shapes are grouped by the id of their closest named group (usually an
+//! Inkscape layer) and their stroke colour.
+
+use super::{contour_shape, hex_color, DEFAULT_LAYER, DEFAULT_TOLERANCE, MM_PER_INCH};
+use crate::designer::shapes::{flatten_cubic, flatten_quadratic};
+use crate::designer::Design;
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+use usvg::tiny_skia_path::PathSegment;
+use usvg::{NodeExt, NodeKind, Paint, TreeParsing, TreeTextToPath, Visibility};
+
+/// SVG import configuration
+#[derive(Clone, Debug)]
+pub struct SvgImportOptions {
+    /// Largest distance in mm between a flattened curve and the original
+    pub tolerance: f64,
+    /// CSS pixels per inch, used for lengths without a physical unit
+    pub dpi: f64,
+    /// Put the origin at the bottom-left corner with Y up, as on the machine
+    pub flip_y: bool,
+    /// Convert text to paths with the system fonts; text is skipped otherwise
+    pub text_to_paths: bool,
+}
+
+impl Default for SvgImportOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: DEFAULT_TOLERANCE,
+            dpi: 96.0,
+            flip_y: true,
+            text_to_paths: true,
+        }
+    }
+}
+
+/// Outcome of an SVG import
+#[derive(Clone, Debug, Default)]
+pub struct SvgImportReport {
+    /// IDs of the shapes added to the design
+    pub shape_ids: Vec<String>,
+    /// Drawing width in mm
+    pub width: f64,
+    /// Drawing height in mm
+    pub height: f64,
+    /// Images, and text that could not be turned into paths
+    pub skipped: usize,
+}
+
+/// Imports SVG drawings into designs
+#[derive(Clone, Debug)]
+pub struct SvgImporter {
+    options: SvgImportOptions,
+}
+
+impl SvgImporter {
+    /// Create an importer with default options
+    pub fn new() -> Self {
+        Self {
+            options: SvgImportOptions::default(),
+        }
+    }
+
+    /// Create an importer with custom options
+    pub fn with_options(options: SvgImportOptions) -> Self {
+        Self { options }
+    }
+
+    /// Get importer options
+    pub fn options(&self) -> &SvgImportOptions {
+        &self.options
+    }
+
+    /// Import an SVG file
+    ///
+    /// # Arguments
+    /// * `path` - SVG file
+    /// * `design` - Design the shapes are added to
+    pub fn import_file(&self, path: &Path, design: &mut Design) -> Result<SvgImportReport> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read SVG from {}", path.display()))?;
+        self.import(&data, design)
+    }
+
+    /// Import SVG data
+    ///
+    /// # Arguments
+    /// * `data` - SVG document, optionally gzip-compressed
+    /// * `design` - Design the shapes are added to
+    ///
+    /// # Returns
+    /// The shapes added and the drawing size in mm
+    pub fn import(&self, data: &[u8], design: &mut Design) -> Result<SvgImportReport> {
+        let usvg_options = usvg::Options {
+            dpi: self.options.dpi as f32,
+            ..Default::default()
+        };
+        let mut tree = usvg::Tree::from_data(data, &usvg_options)
+            .map_err(|e| anyhow!("Invalid SVG: {}", e))?;
+        if self.options.text_to_paths && tree.has_text_nodes() {
+            let mut fonts = usvg::fontdb::Database::new();
+            fonts.load_system_fonts();
+            tree.convert_text(&fonts);
+        }
+
+        let px_to_mm = MM_PER_INCH / self.options.dpi;
+        let mut report = SvgImportReport {
+            width: f64::from(tree.size.width()) * px_to_mm,
+            height: f64::from(tree.size.height()) * px_to_mm,
+            ..Default::default()
+        };
+        let view_box =
+            usvg::utils::view_box_to_transform(tree.view_box.rect, tree.view_box.aspect, tree.size);
+
+        for node in tree.root.descendants() {
+            let (data, color) = match &*node.borrow() {
+                NodeKind::Path(path) if path.visibility == Visibility::Visible => {
+                    (path.data.clone(), path_color(path))
+                }
+                NodeKind::Image(_) | NodeKind::Text(_) => {
+                    report.skipped += 1;
+                    continue;
+                }
+                _ => continue,
+            };
+            let transform = view_box.pre_concat(node.abs_transform());
+            let to_mm = |x: f32, y: f32| {
+                let (x, y) = (f64::from(x), f64::from(y));
+                let t = &transform;
+                let px = f64::from(t.sx) * x + f64::from(t.kx) * y + f64::from(t.tx);
+                let py = f64::from(t.ky) * x + f64::from(t.sy) * y + f64::from(t.ty);
+                let (mm_x, mm_y) = (px * px_to_mm, py * px_to_mm);
+                if self.options.flip_y {
+                    (mm_x, report.height - mm_y)
+                } else {
+                    (mm_x, mm_y)
+                }
+            };
+
+            let layer = layer_name(&node);
+            for (points, closed) in self.flatten(&data, to_mm) {
+                if let Some(shape) = contour_shape(points, closed) {
+                    let id = design.add_shape_to_layer(shape, &layer, color.as_deref());
+                    report.shape_ids.push(id);
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Split a path into flattened contours, each with its closed flag
+    fn flatten<F>(
+        &self,
+        data: &usvg::tiny_skia_path::Path,
+        to_mm: F,
+    ) -> Vec<(Vec<(f64, f64)>, bool)>
+    where
+        F: Fn(f32, f32) -> (f64, f64),
+    {
+        let tolerance = self.options.tolerance;
+        let mut contours = Vec::new();
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        for segment in data.segments() {
+            let last = points.last().copied().unwrap_or((0.0, 0.0));
+            match segment {
+                PathSegment::MoveTo(p) => {
+                    if points.len() > 1 {
+                        contours.push((std::mem::take(&mut points), false));
+                    }
+                    points = vec![to_mm(p.x, p.y)];
+                }
+                PathSegment::LineTo(p) => points.push(to_mm(p.x, p.y)),
+                PathSegment::QuadTo(p1, p2) => flatten_quadratic(
+                    last,
+                    to_mm(p1.x, p1.y),
+                    to_mm(p2.x, p2.y),
+                    tolerance,
+                    &mut points,
+                ),
+                PathSegment::CubicTo(p1, p2, p3) => flatten_cubic(
+                    last,
+                    to_mm(p1.x, p1.y),
+                    to_mm(p2.x, p2.y),
+                    to_mm(p3.x, p3.y),
+                    tolerance,
+                    &mut points,
+                ),
+                PathSegment::Close => {
+                    // A later segment without a MoveTo starts from the same point
+                    let start = points.first().copied();
+                    contours.push((std::mem::take(&mut points), true));
+                    points.extend(start);
+                }
+            }
+        }
+        if points.len() > 1 {
+            contours.push((points, false));
+        }
+        contours
+    }
+}
+
+impl Default for SvgImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Id of the closest group with one, e.g. an Inkscape layer
+fn layer_name(node: &usvg::Node) -> String {
+    node.ancestors()
+        .skip(1)
+        .find_map(|ancestor| match &*ancestor.borrow() {
+            NodeKind::Group(group) if !group.id.is_empty() => Some(group.id.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| DEFAULT_LAYER.to_string())
+}
+
+/// Stroke colour of a path, or its fill colour when it has no stroke
+fn path_color(path: &usvg::Path) -> Option<String> {
+    let paint = path
+        .stroke
+        .as_ref()
+        .map(|stroke| &stroke.paint)
+        .or(path.fill.as_ref().map(|fill| &fill.paint))?;
+    match paint {
+        Paint::Color(color) => Some(hex_color(color.red, color.green, color.blue)),
+        _ => None,
+    }
+}
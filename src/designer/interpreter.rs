@@ -0,0 +1,383 @@
+//! Modal G-code interpreter for back-plotting
+//!
+//! Turns G-code text into `BackPlotStep`s with absolute start and end
+//! positions in millimetres. Modal state (motion mode, units, distance mode,
+//! plane, feed and spindle) is tracked with the same `ParserState` the
+//! controller uses for acknowledged lines, so the plot and the machine agree
+//! on what each line means.
+
+use super::backplot::{ArcGeometry, BackPlotStep, MoveType};
+use crate::communication::parser_state::{
+    split_words, DistanceMode, MotionMode, ParserState, Plane, SpindleState, Units,
+};
+use anyhow::{anyhow, Result};
+
+/// Millimetres per inch
+const MM_PER_INCH: f64 = 25.4;
+
+/// Largest difference between start and end radius of an IJK arc that GRBL
+/// accepts (`error:33` above 0.005 mm and 0.1% of the radius)
+const ARC_RADIUS_TOLERANCE: f64 = 0.005;
+
+/// GRBL's tolerance for deciding an arc ends where it starts (a full circle)
+const ARC_ANGULAR_TRAVEL_EPSILON: f64 = 5e-7;
+
+/// Words of one block, already split by letter
+#[derive(Debug, Default)]
+struct Block {
+    /// G codes times ten, so G38.2 is 382
+    g_codes: Vec<i32>,
+    axes: [Option<f64>; 3],
+    offsets: [Option<f64>; 3],
+    radius: Option<f64>,
+}
+
+impl Block {
+    fn parse(line: &str) -> Result<Self> {
+        let mut block = Block::default();
+        for word in split_words(line) {
+            let word = word.to_ascii_uppercase();
+            let letter = word.chars().next().unwrap_or_default();
+            let value: f64 = match word[1..].parse() {
+                Ok(value) => value,
+                // Letters without a number, e.g. from `%`, carry nothing
+                Err(_) if word.len() == 1 => continue,
+                Err(_) => return Err(anyhow!("Bad number in word {}", word)),
+            };
+            match letter {
+                'G' => block.g_codes.push((value * 10.0).round() as i32),
+                'X' => block.axes[0] = Some(value),
+                'Y' => block.axes[1] = Some(value),
+                'Z' => block.axes[2] = Some(value),
+                'I' => block.offsets[0] = Some(value),
+                'J' => block.offsets[1] = Some(value),
+                'K' => block.offsets[2] = Some(value),
+                'R' => block.radius = Some(value),
+                _ => {}
+            }
+        }
+        Ok(block)
+    }
+
+    fn has(&self, code: i32) -> bool {
+        self.g_codes.contains(&code)
+    }
+
+    fn has_axes(&self) -> bool {
+        self.axes.iter().any(Option::is_some)
+    }
+}
+
+/// Interprets G-code line by line into back-plot steps
+///
+/// Positions are in millimetres in the coordinate frame the program starts
+/// in; `G92` shifts later coordinates within that frame. Machine-coordinate
+/// moves (`G53`, `G28`, `G30`) cannot be placed without the work offsets and
+/// are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct GcodeInterpreter {
+    state: ParserState,
+    position: [f64; 3],
+    /// Active `G92` offset in millimetres
+    g92: [f64; 3],
+}
+
+impl GcodeInterpreter {
+    /// Create an interpreter at the origin with GRBL's power-on modal state
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an interpreter starting at `position` (millimetres)
+    pub fn with_position(position: [f64; 3]) -> Self {
+        GcodeInterpreter {
+            position,
+            ..Default::default()
+        }
+    }
+
+    /// Get the current tool position in millimetres
+    pub fn position(&self) -> [f64; 3] {
+        self.position
+    }
+
+    /// Get the active modal state
+    pub fn state(&self) -> &ParserState {
+        &self.state
+    }
+
+    /// Get the active `G92` offset in millimetres
+    pub fn g92_offset(&self) -> [f64; 3] {
+        self.g92
+    }
+
+    /// Interpret a whole program
+    ///
+    /// # Returns
+    /// One step per move or dwell, numbered by program line (1-based)
+    pub fn interpret(&mut self, gcode: &str) -> Result<Vec<BackPlotStep>> {
+        let mut steps = Vec::new();
+        for (index, line) in gcode.lines().enumerate() {
+            if let Some(step) = self.interpret_line(index + 1, line)? {
+                steps.push(step);
+            }
+        }
+        Ok(steps)
+    }
+
+    /// Interpret one line
+    ///
+    /// # Arguments
+    /// * `line_number` - Line number recorded in the step
+    /// * `line` - G-code line, comments allowed
+    ///
+    /// # Returns
+    /// The move or dwell the line produces, if any
+    pub fn interpret_line(
+        &mut self,
+        line_number: usize,
+        line: &str,
+    ) -> Result<Option<BackPlotStep>> {
+        if line.trim_start().starts_with('$') {
+            return Ok(None);
+        }
+        let block = Block::parse(line).map_err(|e| anyhow!("Line {}: {}", line_number, e))?;
+        // Modal words take effect before the motion on the same line
+        self.state.apply_line(line);
+
+        let step = if block.has(40) {
+            Some(self.step(line_number, line, self.position, MoveType::Dwell, 0.0, None))
+        } else if block.has(920) {
+            self.set_g92(&block);
+            None
+        } else if block.has(921) {
+            self.g92 = [0.0; 3];
+            None
+        } else if [100, 280, 281, 300, 301, 530]
+            .iter()
+            .any(|code| block.has(*code))
+            || !block.has_axes()
+        {
+            // Axis words belong to the non-modal command, or there is no motion
+            None
+        } else {
+            self.motion(line_number, line, &block)?
+        };
+        Ok(step)
+    }
+
+    /// Execute the modal motion of a block with axis words
+    fn motion(
+        &mut self,
+        line_number: usize,
+        line: &str,
+        block: &Block,
+    ) -> Result<Option<BackPlotStep>> {
+        let target = self.target(block);
+        let mut arc = None;
+        let move_type = match self.state.motion {
+            MotionMode::Rapid => MoveType::Rapid,
+            MotionMode::Linear
+            | MotionMode::ProbeToward
+            | MotionMode::ProbeTowardNoError
+            | MotionMode::ProbeAway
+            | MotionMode::ProbeAwayNoError => MoveType::Linear,
+            MotionMode::ArcCw | MotionMode::ArcCcw => {
+                arc = Some(
+                    self.arc_geometry(block, &target)
+                        .map_err(|e| anyhow!("Line {}: {}", line_number, e))?,
+                );
+                if self.state.motion == MotionMode::ArcCw {
+                    MoveType::ArcCW
+                } else {
+                    MoveType::ArcCCW
+                }
+            }
+            MotionMode::Cancel => return Ok(None),
+        };
+
+        let feed = if move_type == MoveType::Rapid {
+            0.0
+        } else {
+            self.to_mm(self.state.feed_rate)
+        };
+        let start = self.position;
+        self.position = target;
+        Ok(Some(self.step(
+            line_number,
+            line,
+            start,
+            move_type,
+            feed,
+            arc,
+        )))
+    }
+
+    /// Resolve the axis words of a block to an absolute position in mm
+    fn target(&self, block: &Block) -> [f64; 3] {
+        let mut target = self.position;
+        for (axis, value) in block.axes.iter().enumerate() {
+            if let Some(value) = value {
+                let value = self.to_mm(*value);
+                target[axis] = match self.state.distance {
+                    DistanceMode::Absolute => value + self.g92[axis],
+                    DistanceMode::Incremental => self.position[axis] + value,
+                };
+            }
+        }
+        target
+    }
+
+    /// `G92`: make the current position read as the given coordinates
+    fn set_g92(&mut self, block: &Block) {
+        for (axis, value) in block.axes.iter().enumerate() {
+            if let Some(value) = value {
+                self.g92[axis] = self.position[axis] - self.to_mm(*value);
+            }
+        }
+    }
+
+    /// Resolve the centre and sweep of an arc, rejecting arcs GRBL would
+    /// refuse (`error:33` and friends)
+    fn arc_geometry(&self, block: &Block, target: &[f64; 3]) -> Result<ArcGeometry> {
+        let (a, b) = plane_axes(self.state.plane);
+        let (dx, dy) = (target[a] - self.position[a], target[b] - self.position[b]);
+        let clockwise = self.state.motion == MotionMode::ArcCw;
+
+        let (i, j) = if let Some(radius) = block.radius {
+            let radius = self.to_mm(radius);
+            if dx == 0.0 && dy == 0.0 {
+                return Err(anyhow!("R arc needs an end point different from its start"));
+            }
+            // Same test GRBL uses: the chord must fit in the circle
+            let h_x2_div_d = 4.0 * radius * radius - dx * dx - dy * dy;
+            if h_x2_div_d < -1e-6 {
+                return Err(anyhow!(
+                    "Arc radius {:.3} mm too small for the end point",
+                    radius.abs()
+                ));
+            }
+            // Offset of the centre from the start, as GRBL works it out
+            let mut h = -h_x2_div_d.max(0.0).sqrt() / dx.hypot(dy);
+            if !clockwise {
+                h = -h;
+            }
+            // A negative radius picks the arc longer than a half circle
+            if radius < 0.0 {
+                h = -h;
+            }
+            (0.5 * (dx - dy * h), 0.5 * (dy + dx * h))
+        } else {
+            self.arc_offsets(block, dx, dy)?
+        };
+
+        let mut center = self.position;
+        center[a] += i;
+        center[b] += j;
+        // Angle from the start radius to the end radius, swept the way the
+        // arc turns; equal start and end make a full circle
+        let (r0, r1) = (-i, -j);
+        let (rt0, rt1) = (dx - i, dy - j);
+        let mut sweep = (r0 * rt1 - r1 * rt0).atan2(r0 * rt0 + r1 * rt1);
+        if clockwise {
+            if sweep >= -ARC_ANGULAR_TRAVEL_EPSILON {
+                sweep -= 2.0 * std::f64::consts::PI;
+            }
+        } else if sweep <= ARC_ANGULAR_TRAVEL_EPSILON {
+            sweep += 2.0 * std::f64::consts::PI;
+        }
+        Ok(ArcGeometry {
+            center: center.map(|v| v as f32),
+            radius: i.hypot(j) as f32,
+            plane: self.state.plane,
+            sweep: sweep as f32,
+        })
+    }
+
+    /// Read and check the I/J/K offsets of an arc in the active plane
+    fn arc_offsets(&self, block: &Block, dx: f64, dy: f64) -> Result<(f64, f64)> {
+        let (a, b) = plane_axes(self.state.plane);
+        let (i, j) = (
+            block.offsets[a].map(|v| self.to_mm(v)),
+            block.offsets[b].map(|v| self.to_mm(v)),
+        );
+        if i.is_none() && j.is_none() {
+            return Err(anyhow!(
+                "Arc has no I/J/K offsets in the active plane or R word"
+            ));
+        }
+        let (i, j) = (i.unwrap_or(0.0), j.unwrap_or(0.0));
+        let start_radius = i.hypot(j);
+        let end_radius = (dx - i).hypot(dy - j);
+        let error = (start_radius - end_radius).abs();
+        if error > ARC_RADIUS_TOLERANCE && error > 0.001 * start_radius {
+            return Err(anyhow!(
+                "Arc end point is {:.3} mm off the circle through its start",
+                error
+            ));
+        }
+        Ok((i, j))
+    }
+
+    fn step(
+        &self,
+        line_number: usize,
+        line: &str,
+        start: [f64; 3],
+        move_type: MoveType,
+        feed_rate: f64,
+        arc: Option<ArcGeometry>,
+    ) -> BackPlotStep {
+        let spindle_speed = match self.state.spindle {
+            SpindleState::Off => 0.0,
+            SpindleState::Clockwise | SpindleState::CounterClockwise => self.state.spindle_speed,
+        };
+        BackPlotStep {
+            line_number,
+            start_pos: start.map(|v| v as f32),
+            end_pos: self.position.map(|v| v as f32),
+            gcode_command: line.trim().to_string(),
+            feed_rate: feed_rate as f32,
+            spindle_speed: spindle_speed as f32,
+            move_type,
+            arc,
+        }
+    }
+
+    /// Convert a length in the active units to millimetres
+    fn to_mm(&self, value: f64) -> f64 {
+        match self.state.units {
+            Units::Millimeters => value,
+            Units::Inches => value * MM_PER_INCH,
+        }
+    }
+}
+
+/// Indices of the two axes spanning a plane, in the order G2/G3 use them
+pub(crate) fn plane_axes(plane: Plane) -> (usize, usize) {
+    match plane {
+        Plane::XY => (0, 1),
+        Plane::ZX => (2, 0),
+        Plane::YZ => (1, 2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_parse() {
+        let block = Block::parse("G38.2 Z-5 F100 (probe)").unwrap();
+        assert!(block.has(382));
+        assert_eq!(block.axes, [None, None, Some(-5.0)]);
+        assert!(Block::parse("G1 X1..2").is_err());
+    }
+
+    #[test]
+    fn test_plane_axes() {
+        assert_eq!(plane_axes(Plane::XY), (0, 1));
+        assert_eq!(plane_axes(Plane::ZX), (2, 0));
+        assert_eq!(plane_axes(Plane::YZ), (1, 2));
+    }
+}
@@ -4,11 +4,16 @@
 //! for laser engraving and CNC machining operations.
 
 pub mod shapes;
+pub mod boolean;
+pub mod offset;
 pub mod toolpath;
 pub mod imaging;
 pub mod backplot;
+pub mod estimator;
+pub mod interpreter;
 pub mod validator;
 pub mod optimizer;
+pub mod import;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -20,6 +25,18 @@ pub use backplot::{BackPlotter, BackPlotStep, BackPlotState, MoveType};
 pub use validator::{GcodeValidator, GrblVersion, ValidationIssue, Severity};
 pub use optimizer::{GcodeOptimizer, OptimizerOptions, OptimizationStats};
 
+/// Named group of shapes, e.g. an imported layer or stroke colour
+///
+/// Each layer can be mapped to its own operation, such as cutting one
+/// colour and engraving another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Layer {
+    pub name: String,
+    /// Colour as `#rrggbb`, if the source had one
+    pub color: Option<String>,
+    pub shape_ids: Vec<String>,
+}
+
 /// Design document containing shapes and operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Design {
@@ -28,6 +45,9 @@ pub struct Design {
     pub shapes: HashMap<String, Shape>,
     pub toolpaths: Vec<Toolpath>,
     pub notes: String,
+    /// Shape groups, in the order they were created
+    #[serde(default)]
+    pub layers: Vec<Layer>,
 }
 
 impl Design {
@@ -39,6 +59,7 @@ impl Design {
             shapes: HashMap::new(),
             toolpaths: Vec::new(),
             notes: String::new(),
+            layers: Vec::new(),
         }
     }
 
@@ -49,8 +70,47 @@ impl Design {
         id
     }
 
+    /// Add a shape to a layer, creating the layer if needed
+    ///
+    /// # Arguments
+    /// * `shape` - Shape to add
+    /// * `layer` - Layer name
+    /// * `color` - Layer colour as `#rrggbb`; layers with the same name but
+    ///   different colours are kept apart
+    ///
+    /// # Returns
+    /// ID of the new shape
+    pub fn add_shape_to_layer(&mut self, shape: Shape, layer: &str, color: Option<&str>) -> String {
+        let id = self.add_shape(shape);
+        let index = match self
+            .layers
+            .iter()
+            .position(|l| l.name == layer && l.color.as_deref() == color)
+        {
+            Some(index) => index,
+            None => {
+                self.layers.push(Layer {
+                    name: layer.to_string(),
+                    color: color.map(str::to_string),
+                    shape_ids: Vec::new(),
+                });
+                self.layers.len() - 1
+            }
+        };
+        self.layers[index].shape_ids.push(id.clone());
+        id
+    }
+
+    /// Get the first layer with a name
+    pub fn get_layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
     /// Remove a shape from the design
     pub fn remove_shape(&mut self, id: &str) -> Option<Shape> {
+        for layer in &mut self.layers {
+            layer.shape_ids.retain(|shape_id| shape_id != id);
+        }
         self.shapes.remove(id)
     }
 
@@ -79,10 +139,47 @@ impl Design {
         gcode
     }
 
+    /// Replace two shapes with the result of a boolean operation
+    ///
+    /// Each separate part of the result becomes a shape in the layers of the
+    /// `subject` shape; parts with holes become `Shape::Region`s.
+    ///
+    /// # Arguments
+    /// * `subject` - ID of the first operand
+    /// * `clip` - ID of the second operand
+    /// * `op` - Operation
+    ///
+    /// # Returns
+    /// IDs of the new shapes, or None if either shape is missing
+    pub fn combine_shapes(
+        &mut self,
+        subject: &str,
+        clip: &str,
+        op: boolean::BooleanOp,
+    ) -> Option<Vec<String>> {
+        let result = self.get_shape(subject)?.boolean(self.get_shape(clip)?, op);
+        let layers: Vec<usize> = (0..self.layers.len())
+            .filter(|&index| self.layers[index].shape_ids.iter().any(|id| id == subject))
+            .collect();
+        self.remove_shape(subject);
+        self.remove_shape(clip);
+
+        let mut ids = Vec::new();
+        for shape in result.to_shapes() {
+            let id = self.add_shape(shape);
+            for &index in &layers {
+                self.layers[index].shape_ids.push(id.clone());
+            }
+            ids.push(id);
+        }
+        Some(ids)
+    }
+
     /// Clear all shapes
     pub fn clear(&mut self) {
         self.shapes.clear();
         self.toolpaths.clear();
+        self.layers.clear();
     }
 }
 
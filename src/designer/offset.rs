@@ -0,0 +1,391 @@
+//! Polygon offsetting for tool radius compensation
+//!
+//! Every edge of a region is moved sideways by the offset distance. Where
+//! moved edges leave a gap at a corner it is bridged with an arc or a miter;
+//! where they overlap they are joined through the original corner, and the
+//! loops this leaves behind are removed by resolving the raw contours with
+//! the positive fill rule. Profiles use this to keep the tool centre one
+//! radius, plus any stock allowance, away from the part.
+
+use super::boolean::{signed_area, FillRule, Region};
+use super::shapes::{arc_segments, PathSegment, Shape};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+type Point = (f64, f64);
+
+/// How the offset bridges the gap at a corner
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum JoinType {
+    /// Arc around the corner, as the tool would cut it
+    Round,
+    /// Sharp corner, bevelled where it would reach further than `limit`
+    /// times the offset distance
+    Miter { limit: f64 },
+}
+
+/// Which side of the geometry line the tool runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProfileSide {
+    /// Inside closed shapes, e.g. holes and pockets
+    Inside,
+    /// Outside closed shapes, e.g. cutting parts free
+    Outside,
+    /// Tool centre on the line, e.g. engraving
+    OnLine,
+}
+
+/// Direction of travel relative to the cut, for a clockwise spindle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CutDirection {
+    /// Material on the right of the tool; cleaner finish on rigid machines
+    Climb,
+    /// Material on the left of the tool; gentler on flexible machines
+    Conventional,
+}
+
+/// Profile toolpath configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileOptions {
+    /// Tool diameter in mm
+    pub tool_diameter: f64,
+    /// Material in mm left on the part for a finishing pass; ignored on the line
+    pub stock_allowance: f64,
+    pub side: ProfileSide,
+    pub join: JoinType,
+    pub direction: CutDirection,
+    /// Largest distance in mm between curves and the chords replacing them
+    pub tolerance: f64,
+}
+
+impl Default for ProfileOptions {
+    fn default() -> Self {
+        Self {
+            tool_diameter: 3.175,
+            stock_allowance: 0.0,
+            side: ProfileSide::Outside,
+            join: JoinType::Round,
+            direction: CutDirection::Climb,
+            tolerance: 0.01,
+        }
+    }
+}
+
+/// Tool centre path of a profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfilePath {
+    /// Points in cutting order; closed paths do not repeat the first point
+    pub points: Vec<Point>,
+    pub closed: bool,
+}
+
+impl ProfilePath {
+    /// Segments after the first point, with runs of points that follow a
+    /// circle fitted back into arcs
+    ///
+    /// Offsetting flattens curves, but circles, rounded corners and round
+    /// joins come out as chords whose points all lie on one circle.
+    ///
+    /// # Arguments
+    /// * `tolerance` - Largest distance in mm of any point from a fitted
+    ///   arc; chords may bulge from it by twice this
+    ///
+    /// # Returns
+    /// Lines and arcs in cutting order, back to the start for closed paths
+    pub fn segments(&self, tolerance: f64) -> Vec<PathSegment> {
+        let mut points = self.points.clone();
+        if self.closed {
+            points.extend(self.points.first().copied());
+        }
+
+        let mut segments = Vec::new();
+        let mut i = 0;
+        while i + 1 < points.len() {
+            let mut arc = None;
+            let mut end = i + 2;
+            while end < points.len() {
+                match fit_arc(&points[i..=end], tolerance) {
+                    Some(fit) => arc = Some((end, fit)),
+                    None => break,
+                }
+                end += 1;
+            }
+            match arc {
+                Some((end, (center, clockwise, sweep)))
+                    // Nearly straight runs stay as the chords they are
+                    if center_distance(points[i], center) * (1.0 - (sweep.min(PI) / 2.0).cos())
+                        > tolerance =>
+                {
+                    segments.push(PathSegment::Arc {
+                        x: points[end].0,
+                        y: points[end].1,
+                        cx: center.0,
+                        cy: center.1,
+                        clockwise,
+                    });
+                    i = end;
+                }
+                _ => {
+                    segments.push(PathSegment::Line {
+                        x: points[i + 1].0,
+                        y: points[i + 1].1,
+                    });
+                    i += 1;
+                }
+            }
+        }
+        segments
+    }
+}
+
+/// Grow or shrink a region
+///
+/// # Arguments
+/// * `region` - Region with outer contours counter-clockwise and holes
+///   clockwise
+/// * `distance` - Offset in mm; positive grows the region
+/// * `join` - How corners the offset moves away from are bridged
+/// * `tolerance` - Largest distance in mm between round joins and the chords
+///   replacing them
+///
+/// # Returns
+/// Offset region; parts narrower than twice a negative offset disappear
+pub fn offset(region: &Region, distance: f64, join: JoinType, tolerance: f64) -> Region {
+    if distance == 0.0 {
+        return region.clone();
+    }
+    let mut raw = Vec::new();
+    for contour in &region.contours {
+        let mut points = contour.clone();
+        points.dedup();
+        while points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() < 3 {
+            continue;
+        }
+
+        let count = points.len();
+        let mut moved = Vec::new();
+        for i in 0..count {
+            let previous = points[(i + count - 1) % count];
+            let p = points[i];
+            let next = points[(i + 1) % count];
+            let n1 = right_normal(previous, p);
+            let n2 = right_normal(p, next);
+            let sin = n1.0 * n2.1 - n1.1 * n2.0;
+            let cos = n1.0 * n2.0 + n1.1 * n2.1;
+            let start = (p.0 + distance * n1.0, p.1 + distance * n1.1);
+            let end = (p.0 + distance * n2.0, p.1 + distance * n2.1);
+
+            if sin.abs() < 1e-12 && cos > 0.0 {
+                // Straight on
+                moved.push(start);
+            } else if sin * distance > 0.0 {
+                // The moved edges part: bridge the gap
+                match join {
+                    JoinType::Round => {
+                        let sweep = sin.atan2(cos);
+                        let steps = arc_segments(distance, sweep, tolerance);
+                        for step in 0..=steps {
+                            let (s, c) = (sweep * step as f64 / steps as f64).sin_cos();
+                            let n = (n1.0 * c - n1.1 * s, n1.0 * s + n1.1 * c);
+                            moved.push((p.0 + distance * n.0, p.1 + distance * n.1));
+                        }
+                    }
+                    JoinType::Miter { limit } => {
+                        // Miter length over the offset is 1 / cos(half the turn)
+                        if 2.0 / (1.0 + cos) <= limit * limit {
+                            let scale = distance / (1.0 + cos);
+                            let miter = (n1.0 + n2.0, n1.1 + n2.1);
+                            moved.push((p.0 + scale * miter.0, p.1 + scale * miter.1));
+                        } else {
+                            moved.push(start);
+                            moved.push(end);
+                        }
+                    }
+                }
+            } else {
+                // The moved edges cross: go through the corner and let the
+                // fill rule drop the loop
+                moved.push(start);
+                moved.push(p);
+                moved.push(end);
+            }
+        }
+        raw.push(moved);
+    }
+    Region::new(raw).simplify(FillRule::Positive)
+}
+
+/// Unit normal on the right of travel from `a` to `b`, outwards for a
+/// counter-clockwise contour
+fn right_normal(a: Point, b: Point) -> Point {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx.hypot(dy);
+    (dy / length, -dx / length)
+}
+
+/// Circle through a run of points
+///
+/// # Returns
+/// Centre, direction and swept angle, or None unless every point lies within
+/// `tolerance` of the circle, every chord turns the same way around it and
+/// bulges from it by no more than twice `tolerance`
+fn fit_arc(points: &[Point], tolerance: f64) -> Option<(Point, bool, f64)> {
+    let count = points.len();
+    if count < 3 {
+        return None;
+    }
+    // Through both ends, so the controller sees the same radius at each;
+    // a full turn ends where it starts and is sampled at thirds instead
+    let (a, last) = (points[0], points[count - 1]);
+    let (b, c) = if center_distance(a, last) < 1e-9 {
+        (points[count / 3], points[2 * count / 3])
+    } else {
+        (points[count / 2], last)
+    };
+    let d = 2.0 * ((b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0));
+    if d.abs() < 1e-12 {
+        return None;
+    }
+    let (ab, ac) = (
+        (b.0 - a.0).powi(2) + (b.1 - a.1).powi(2),
+        (c.0 - a.0).powi(2) + (c.1 - a.1).powi(2),
+    );
+    let center = (
+        a.0 + ((c.1 - a.1) * ab - (b.1 - a.1) * ac) / d,
+        a.1 + ((b.0 - a.0) * ac - (c.0 - a.0) * ab) / d,
+    );
+    let radius = center_distance(a, center);
+
+    let clockwise = d < 0.0;
+    let mut sweep = 0.0;
+    for pair in points.windows(2) {
+        let (p, q) = (pair[0], pair[1]);
+        if (center_distance(q, center) - radius).abs() > tolerance {
+            return None;
+        }
+        let (u, v) = (
+            (p.0 - center.0, p.1 - center.1),
+            (q.0 - center.0, q.1 - center.1),
+        );
+        let turn = (u.0 * v.1 - u.1 * v.0).atan2(u.0 * v.0 + u.1 * v.1);
+        if turn == 0.0 || (turn < 0.0) != clockwise {
+            return None;
+        }
+        let half_chord = (q.0 - p.0).hypot(q.1 - p.1) / 2.0;
+        if half_chord > radius
+            || radius - (radius * radius - half_chord * half_chord).sqrt() > 2.0 * tolerance
+        {
+            return None;
+        }
+        sweep += turn.abs();
+    }
+    Some((center, clockwise, sweep))
+}
+
+fn center_distance(p: Point, center: Point) -> f64 {
+    (p.0 - center.0).hypot(p.1 - center.1)
+}
+
+/// Tool centre paths for profiling a shape
+///
+/// # Arguments
+/// * `shape` - Shape to profile; inside and outside need a closed shape
+/// * `options` - Tool, side, joins and direction
+///
+/// # Returns
+/// Paths in cutting order, holes in the part first
+pub fn profile(shape: &Shape, options: &ProfileOptions) -> Result<Vec<ProfilePath>> {
+    // Regions keep their holes, so they go through `profile_region` even on the line
+    if options.side == ProfileSide::OnLine && !matches!(shape, Shape::Region { .. }) {
+        let closed = !matches!(
+            shape,
+            Shape::Line { .. } | Shape::Polyline { .. } | Shape::Path { closed: false, .. }
+        );
+        let mut points = shape.outline(options.tolerance);
+        if closed {
+            if signed_area(&points) < 0.0 {
+                points.reverse();
+            }
+            // Cut as the outside of the part; open paths keep their direction
+            if options.direction == CutDirection::Climb {
+                points.reverse();
+            }
+        }
+        return Ok(vec![ProfilePath { points, closed }]);
+    }
+
+    let region = Region::from_shape(shape, options.tolerance);
+    if region.is_empty() {
+        return Err(anyhow!("{:?} profiles need a closed shape", options.side));
+    }
+    profile_region(&region, options)
+}
+
+/// Tool centre paths for profiling a region, e.g. a boolean result
+///
+/// # Arguments
+/// * `region` - Region to profile
+/// * `options` - Tool, side, joins and direction; `OnLine` follows the
+///   contours as they are
+///
+/// # Returns
+/// Paths in cutting order, holes in the part first
+pub fn profile_region(region: &Region, options: &ProfileOptions) -> Result<Vec<ProfilePath>> {
+    if options.tool_diameter < 0.0 {
+        return Err(anyhow!(
+            "Tool diameter must not be negative, got {}",
+            options.tool_diameter
+        ));
+    }
+    let clearance = options.tool_diameter / 2.0 + options.stock_allowance;
+    let (distance, material_on_left) = match options.side {
+        ProfileSide::Outside => (clearance, true),
+        ProfileSide::Inside => (-clearance, false),
+        ProfileSide::OnLine => (0.0, true),
+    };
+    let result = offset(region, distance, options.join, options.tolerance);
+    if result.is_empty() {
+        return Err(anyhow!(
+            "A {} mm tool does not fit inside the shape",
+            options.tool_diameter
+        ));
+    }
+
+    // Contours come back with the offset region on their left
+    let reverse = (options.direction == CutDirection::Climb) == material_on_left;
+    let mut contours = result.contours;
+    contours.sort_by_key(|contour| signed_area(contour) > 0.0);
+    Ok(contours
+        .into_iter()
+        .map(|mut points| {
+            if reverse {
+                points.reverse();
+            }
+            ProfilePath {
+                points,
+                closed: true,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_right_normal_points_out_of_ccw_contour() {
+        // Bottom edge of a counter-clockwise square runs +X; outside is -Y
+        assert_eq!(right_normal((0.0, 0.0), (2.0, 0.0)), (0.0, -1.0));
+    }
+
+    #[test]
+    fn test_offset_zero_keeps_region() {
+        let region = Region::new(vec![vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]]);
+        assert_eq!(offset(&region, 0.0, JoinType::Round, 0.01), region);
+    }
+}
@@ -1,5 +1,6 @@
 //! Shape definition and manipulation for CAM operations
 
+use super::boolean::{self, BooleanOp, FillRule, Region};
 use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
 
@@ -20,14 +21,76 @@ pub enum Shape {
     Polygon {
         points: Vec<(f64, f64)>,
     },
+    /// Open path through the points, e.g. an imported unclosed contour
+    Polyline {
+        points: Vec<(f64, f64)>,
+    },
     Line {
         x1: f64,
         y1: f64,
         x2: f64,
         y2: f64,
     },
+    /// Line, arc and Bézier segments drawn one after another from `start`
+    Path {
+        start: (f64, f64),
+        segments: Vec<PathSegment>,
+        closed: bool,
+    },
+    /// Ellipse centred on (x, y), turned counter-clockwise by `rotation` degrees
+    Ellipse {
+        rx: f64,
+        ry: f64,
+        x: f64,
+        y: f64,
+        rotation: f64,
+    },
+    /// Rectangle whose corners are rounded to `radius`
+    RoundedRectangle {
+        width: f64,
+        height: f64,
+        x: f64,
+        y: f64,
+        radius: f64,
+    },
+    /// Filled area with holes or several parts, e.g. a boolean result;
+    /// outer contours run counter-clockwise and holes clockwise
+    Region {
+        contours: Vec<Vec<(f64, f64)>>,
+    },
+}
+
+/// Segment of a `Shape::Path`, drawn from where the previous one ends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PathSegment {
+    /// Straight line to (x, y)
+    Line { x: f64, y: f64 },
+    /// Circular arc to (x, y) around (cx, cy); a full circle when it ends
+    /// where it starts
+    Arc {
+        x: f64,
+        y: f64,
+        cx: f64,
+        cy: f64,
+        clockwise: bool,
+    },
+    /// Quadratic Bézier to (x, y) with control point (x1, y1)
+    Quadratic { x1: f64, y1: f64, x: f64, y: f64 },
+    /// Cubic Bézier to (x, y) with control points (x1, y1) and (x2, y2)
+    Cubic {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        x: f64,
+        y: f64,
+    },
 }
 
+/// Largest distance in mm between the curve and the chords `contains_point`,
+/// `to_gcode` and `boolean` use in place of curves
+const FLATTEN_TOLERANCE: f64 = 0.01;
+
 impl Shape {
     /// Create a rectangle shape
     pub fn rectangle(width: f64, height: f64, x: f64, y: f64) -> Self {
@@ -49,13 +112,88 @@ impl Shape {
         Shape::Polygon { points }
     }
 
+    /// Create an open polyline from points
+    pub fn polyline(points: Vec<(f64, f64)>) -> Self {
+        Shape::Polyline { points }
+    }
+
+    /// Create a path of segments from a start point
+    pub fn path(start: (f64, f64), segments: Vec<PathSegment>, closed: bool) -> Self {
+        Shape::Path {
+            start,
+            segments,
+            closed,
+        }
+    }
+
+    /// Create an ellipse turned by `rotation` degrees
+    pub fn ellipse(rx: f64, ry: f64, x: f64, y: f64, rotation: f64) -> Self {
+        Shape::Ellipse {
+            rx,
+            ry,
+            x,
+            y,
+            rotation,
+        }
+    }
+
+    /// Create a rectangle with rounded corners
+    pub fn rounded_rectangle(width: f64, height: f64, x: f64, y: f64, radius: f64) -> Self {
+        Shape::RoundedRectangle {
+            width,
+            height,
+            x,
+            y,
+            radius,
+        }
+    }
+
+    /// Create a region from contours, outer ones counter-clockwise and
+    /// holes clockwise
+    pub fn region(contours: Vec<Vec<(f64, f64)>>) -> Self {
+        Shape::Region { contours }
+    }
+
     /// Calculate the area of the shape
     pub fn area(&self) -> f64 {
         match self {
             Shape::Rectangle { width, height, .. } => width * height,
             Shape::Circle { radius, .. } => PI * radius * radius,
             Shape::Polygon { points } => shoelace_area(points),
-            Shape::Line { .. } => 0.0,
+            Shape::Polyline { .. } | Shape::Line { .. } => 0.0,
+            Shape::Path {
+                start,
+                segments,
+                closed,
+            } => {
+                if !closed {
+                    return 0.0;
+                }
+                let mut from = *start;
+                let mut area = 0.0;
+                for segment in segments {
+                    area += segment.area_term(from);
+                    from = segment.end();
+                }
+                // Straight closing edge back to the start
+                area += (from.0 * start.1 - start.0 * from.1) / 2.0;
+                area.abs()
+            }
+            Shape::Ellipse { rx, ry, .. } => PI * rx * ry,
+            Shape::RoundedRectangle {
+                width,
+                height,
+                radius,
+                ..
+            } => {
+                let radius = corner_radius(*width, *height, *radius);
+                width * height - (4.0 - PI) * radius * radius
+            }
+            Shape::Region { contours } => contours
+                .iter()
+                .map(|contour| boolean::signed_area(contour))
+                .sum::<f64>()
+                .abs(),
         }
     }
 
@@ -68,7 +206,7 @@ impl Shape {
             Shape::Circle { radius, x, y } => {
                 (x - radius, y - radius, x + radius, y + radius)
             }
-            Shape::Polygon { points } => {
+            Shape::Polygon { points } | Shape::Polyline { points } => {
                 if points.is_empty() {
                     (0.0, 0.0, 0.0, 0.0)
                 } else {
@@ -89,6 +227,35 @@ impl Shape {
             Shape::Line { x1, y1, x2, y2 } => {
                 (x1.min(*x2), y1.min(*y2), x1.max(*x2), y1.max(*y2))
             }
+            Shape::Path {
+                start, segments, ..
+            } => {
+                let mut bounds = (start.0, start.1, start.0, start.1);
+                let mut from = *start;
+                for segment in segments {
+                    segment.extend_bounds(from, &mut bounds);
+                    from = segment.end();
+                }
+                bounds
+            }
+            Shape::Ellipse {
+                rx,
+                ry,
+                x,
+                y,
+                rotation,
+            } => {
+                let (sin, cos) = rotation.to_radians().sin_cos();
+                let half_width = (rx * cos).hypot(ry * sin);
+                let half_height = (rx * sin).hypot(ry * cos);
+                (x - half_width, y - half_height, x + half_width, y + half_height)
+            }
+            Shape::RoundedRectangle {
+                width, height, x, y, ..
+            } => (*x, *y, x + width, y + height),
+            Shape::Region { contours } => Region::new(contours.clone())
+                .bounds()
+                .unwrap_or((0.0, 0.0, 0.0, 0.0)),
         }
     }
 
@@ -113,21 +280,64 @@ impl Shape {
                     x1, y1, x2, y2
                 )
             }
-            Shape::Polygon { points } => {
+            Shape::Polygon { points } => polygon_gcode(points),
+            Shape::Polyline { points } => {
                 let mut gcode = String::new();
-                if !points.is_empty() {
-                    gcode.push_str(&format!("G0 X{:.2} Y{:.2}\n", points[0].0, points[0].1));
+                if let Some((first, rest)) = points.split_first() {
+                    gcode.push_str(&format!("G0 X{:.2} Y{:.2}\n", first.0, first.1));
                     gcode.push_str("G1 Z-1 F100\n");
-                    for (x, y) in points {
+                    for (x, y) in rest {
                         gcode.push_str(&format!("G1 X{:.2} Y{:.2} F100\n", x, y));
                     }
-                    gcode.push_str(&format!(
-                        "G1 X{:.2} Y{:.2} F100\nG0 Z5",
-                        points[0].0, points[0].1
-                    ));
+                    gcode.push_str("G0 Z5");
                 }
                 gcode
             }
+            Shape::Path {
+                start,
+                segments,
+                closed,
+            } => path_gcode(*start, segments, *closed),
+            Shape::Ellipse { rx, ry, x, y, .. } if (rx - ry).abs() < 1e-9 => {
+                let start = (x + rx, *y);
+                let circle = PathSegment::Arc {
+                    x: start.0,
+                    y: start.1,
+                    cx: *x,
+                    cy: *y,
+                    clockwise: true,
+                };
+                path_gcode(start, &[circle], true)
+            }
+            Shape::Ellipse { .. } => {
+                let points = self.outline(FLATTEN_TOLERANCE);
+                let segments: Vec<_> = points[1..]
+                    .iter()
+                    .map(|&(x, y)| PathSegment::Line { x, y })
+                    .collect();
+                path_gcode(points[0], &segments, true)
+            }
+            Shape::RoundedRectangle {
+                width,
+                height,
+                x,
+                y,
+                radius,
+            } => {
+                let (start, segments) = rounded_rectangle_path(*width, *height, *x, *y, *radius);
+                path_gcode(start, &segments, true)
+            }
+            Shape::Region { contours } => {
+                // Holes first, so the part is still held while they are cut
+                let mut ordered: Vec<_> = contours.iter().collect();
+                ordered.sort_by_key(|contour| boolean::signed_area(contour) > 0.0);
+                ordered
+                    .into_iter()
+                    .map(|contour| polygon_gcode(contour))
+                    .filter(|gcode| !gcode.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
         }
     }
 
@@ -143,9 +353,525 @@ impl Shape {
                 dx * dx + dy * dy <= radius * radius
             }
             Shape::Polygon { points } => point_in_polygon(px, py, points),
-            Shape::Line { .. } => false,
+            Shape::Polyline { .. } | Shape::Line { .. } => false,
+            Shape::Path { closed, .. } => {
+                *closed && point_in_polygon(px, py, &self.outline(FLATTEN_TOLERANCE))
+            }
+            Shape::Ellipse {
+                rx,
+                ry,
+                x,
+                y,
+                rotation,
+            } => {
+                // Turn the point into the ellipse's own axes
+                let (sin, cos) = rotation.to_radians().sin_cos();
+                let (dx, dy) = (px - x, py - y);
+                let u = (dx * cos + dy * sin) / rx;
+                let v = (dy * cos - dx * sin) / ry;
+                u * u + v * v <= 1.0
+            }
+            Shape::RoundedRectangle {
+                width,
+                height,
+                x,
+                y,
+                radius,
+            } => {
+                let radius = corner_radius(*width, *height, *radius);
+                if px < *x || px > x + width || py < *y || py > y + height {
+                    return false;
+                }
+                // Distance past the straight part of the nearest corner
+                let dx = (x + radius - px).max(px - (x + width - radius)).max(0.0);
+                let dy = (y + radius - py).max(py - (y + height - radius)).max(0.0);
+                dx * dx + dy * dy <= radius * radius
+            }
+            Shape::Region { contours } => {
+                boolean::winding(contours, (px, py)) != 0
+            }
+        }
+    }
+
+    /// Combine with another closed shape
+    ///
+    /// Curves are flattened first; the result may have several parts and
+    /// holes, so it is a `Region` rather than a shape.
+    pub fn boolean(&self, other: &Shape, op: BooleanOp) -> Region {
+        Region::from_shape(self, FLATTEN_TOLERANCE).boolean(
+            &Region::from_shape(other, FLATTEN_TOLERANCE),
+            op,
+            FillRule::NonZero,
+        )
+    }
+
+    /// Points along the outline of the shape
+    ///
+    /// # Arguments
+    /// * `tolerance` - Largest distance in mm between curves and the chords
+    ///   replacing them
+    ///
+    /// # Returns
+    /// Outline points; closed shapes do not repeat the first point
+    pub fn outline(&self, tolerance: f64) -> Vec<(f64, f64)> {
+        match self {
+            Shape::Rectangle { width, height, x, y } => vec![
+                (*x, *y),
+                (x + width, *y),
+                (x + width, y + height),
+                (*x, y + height),
+            ],
+            Shape::Circle { radius, x, y } => {
+                let steps = arc_segments(*radius, 2.0 * PI, tolerance);
+                (0..steps)
+                    .map(|i| {
+                        let angle = 2.0 * PI * i as f64 / steps as f64;
+                        (x + radius * angle.cos(), y + radius * angle.sin())
+                    })
+                    .collect()
+            }
+            Shape::Polygon { points } | Shape::Polyline { points } => points.clone(),
+            Shape::Line { x1, y1, x2, y2 } => vec![(*x1, *y1), (*x2, *y2)],
+            Shape::Path {
+                start,
+                segments,
+                closed,
+            } => path_outline(*start, segments, *closed, tolerance),
+            Shape::Ellipse {
+                rx,
+                ry,
+                x,
+                y,
+                rotation,
+            } => {
+                let (sin, cos) = rotation.to_radians().sin_cos();
+                let steps = arc_segments(rx.abs().max(ry.abs()), 2.0 * PI, tolerance);
+                (0..steps)
+                    .map(|i| {
+                        let t = 2.0 * PI * i as f64 / steps as f64;
+                        let (u, v) = (rx * t.cos(), ry * t.sin());
+                        (x + u * cos - v * sin, y + u * sin + v * cos)
+                    })
+                    .collect()
+            }
+            Shape::RoundedRectangle {
+                width,
+                height,
+                x,
+                y,
+                radius,
+            } => {
+                let (start, segments) = rounded_rectangle_path(*width, *height, *x, *y, *radius);
+                path_outline(start, &segments, true, tolerance)
+            }
+            // The largest contour; `Region::from_shape` keeps the holes
+            Shape::Region { contours } => contours
+                .iter()
+                .max_by(|a, b| {
+                    boolean::signed_area(a)
+                        .abs()
+                        .total_cmp(&boolean::signed_area(b).abs())
+                })
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl PathSegment {
+    /// Point the segment ends at
+    pub fn end(&self) -> (f64, f64) {
+        match self {
+            PathSegment::Line { x, y }
+            | PathSegment::Arc { x, y, .. }
+            | PathSegment::Quadratic { x, y, .. }
+            | PathSegment::Cubic { x, y, .. } => (*x, *y),
+        }
+    }
+
+    /// Cubic control points, raising a quadratic to a cubic
+    fn cubic(&self, from: (f64, f64)) -> Option<[(f64, f64); 4]> {
+        match *self {
+            PathSegment::Quadratic { x1, y1, x, y } => Some([
+                from,
+                (from.0 + 2.0 / 3.0 * (x1 - from.0), from.1 + 2.0 / 3.0 * (y1 - from.1)),
+                (x + 2.0 / 3.0 * (x1 - x), y + 2.0 / 3.0 * (y1 - y)),
+                (x, y),
+            ]),
+            PathSegment::Cubic {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => Some([from, (x1, y1), (x2, y2), (x, y)]),
+            _ => None,
+        }
+    }
+
+    /// Signed area between the segment and the origin, `(x dy - y dx) / 2`
+    /// integrated along it
+    fn area_term(&self, from: (f64, f64)) -> f64 {
+        match *self {
+            PathSegment::Line { x, y } => (from.0 * y - x * from.1) / 2.0,
+            PathSegment::Arc {
+                x,
+                y,
+                cx,
+                cy,
+                clockwise,
+            } => {
+                let (radius, start, sweep) = arc_sweep(from, (x, y), (cx, cy), clockwise);
+                let end = start + sweep;
+                (radius * radius * sweep
+                    + radius * (cx * (end.sin() - start.sin()) - cy * (end.cos() - start.cos())))
+                    / 2.0
+            }
+            _ => {
+                let points = self.cubic(from).unwrap_or([from; 4]);
+                // Three-point Gauss-Legendre is exact for the quintic integrand
+                let nodes = [
+                    (0.5 - 0.5 * (0.6f64).sqrt(), 5.0 / 18.0),
+                    (0.5, 8.0 / 18.0),
+                    (0.5 + 0.5 * (0.6f64).sqrt(), 5.0 / 18.0),
+                ];
+                nodes
+                    .iter()
+                    .map(|&(t, weight)| {
+                        let (p, d) = cubic_point(&points, t);
+                        weight * (p.0 * d.1 - p.1 * d.0) / 2.0
+                    })
+                    .sum()
+            }
+        }
+    }
+
+    /// Grow `bounds` (min_x, min_y, max_x, max_y) to hold the segment
+    fn extend_bounds(&self, from: (f64, f64), bounds: &mut (f64, f64, f64, f64)) {
+        let mut extend = |(px, py): (f64, f64)| {
+            bounds.0 = bounds.0.min(px);
+            bounds.1 = bounds.1.min(py);
+            bounds.2 = bounds.2.max(px);
+            bounds.3 = bounds.3.max(py);
+        };
+        extend(self.end());
+        match *self {
+            PathSegment::Line { .. } => {}
+            PathSegment::Arc {
+                x,
+                y,
+                cx,
+                cy,
+                clockwise,
+            } => {
+                let (radius, start, sweep) = arc_sweep(from, (x, y), (cx, cy), clockwise);
+                // Each quarter turn the arc passes is an extreme point
+                for quarter in 0..4 {
+                    let angle = quarter as f64 * PI / 2.0;
+                    let travel = if sweep >= 0.0 {
+                        (angle - start).rem_euclid(2.0 * PI)
+                    } else {
+                        (start - angle).rem_euclid(2.0 * PI)
+                    };
+                    if travel <= sweep.abs() {
+                        extend((cx + radius * angle.cos(), cy + radius * angle.sin()));
+                    }
+                }
+            }
+            _ => {
+                let points = self.cubic(from).unwrap_or([from; 4]);
+                for x_axis in [true, false] {
+                    let [a, b, c, d] = points.map(|p| if x_axis { p.0 } else { p.1 });
+                    // Roots of the derivative's quadratic
+                    let (qa, qb, qc) = (-a + 3.0 * b - 3.0 * c + d, 2.0 * (a - 2.0 * b + c), b - a);
+                    let roots = if qa.abs() < 1e-12 {
+                        vec![-qc / qb]
+                    } else {
+                        let discriminant = qb * qb - 4.0 * qa * qc;
+                        if discriminant < 0.0 {
+                            vec![]
+                        } else {
+                            let root = discriminant.sqrt();
+                            vec![(-qb + root) / (2.0 * qa), (-qb - root) / (2.0 * qa)]
+                        }
+                    };
+                    for t in roots.into_iter().filter(|t| *t > 0.0 && *t < 1.0) {
+                        extend(cubic_point(&points, t).0);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Add points along the segment after `from`
+    fn flatten(&self, from: (f64, f64), tolerance: f64, out: &mut Vec<(f64, f64)>) {
+        match *self {
+            PathSegment::Line { x, y } => out.push((x, y)),
+            PathSegment::Arc {
+                x,
+                y,
+                cx,
+                cy,
+                clockwise,
+            } => {
+                let (radius, start, sweep) = arc_sweep(from, (x, y), (cx, cy), clockwise);
+                let steps = arc_segments(radius, sweep, tolerance);
+                for i in 1..steps {
+                    let angle = start + sweep * i as f64 / steps as f64;
+                    out.push((cx + radius * angle.cos(), cy + radius * angle.sin()));
+                }
+                out.push((x, y));
+            }
+            PathSegment::Quadratic { x1, y1, x, y } => {
+                flatten_quadratic(from, (x1, y1), (x, y), tolerance, out)
+            }
+            PathSegment::Cubic {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => flatten_cubic(from, (x1, y1), (x2, y2), (x, y), tolerance, out),
+        }
+    }
+}
+
+/// Radius of the rounded corners, at most half the shorter side
+fn corner_radius(width: f64, height: f64, radius: f64) -> f64 {
+    radius.clamp(0.0, width.abs().min(height.abs()) / 2.0)
+}
+
+/// Counter-clockwise path around a rounded rectangle, starting at the end of
+/// the bottom-left corner
+fn rounded_rectangle_path(
+    width: f64,
+    height: f64,
+    x: f64,
+    y: f64,
+    radius: f64,
+) -> ((f64, f64), Vec<PathSegment>) {
+    let r = corner_radius(width, height, radius);
+    let (right, top) = (x + width, y + height);
+    let corners = [
+        ((right - r, y), (right, y + r), (right - r, y + r)),
+        ((right, top - r), (right - r, top), (right - r, top - r)),
+        ((x + r, top), (x, top - r), (x + r, top - r)),
+        ((x, y + r), (x + r, y), (x + r, y + r)),
+    ];
+    let mut segments = Vec::new();
+    let mut from = (x + r, y);
+    for (side_end, corner_end, center) in corners {
+        if side_end != from {
+            segments.push(PathSegment::Line {
+                x: side_end.0,
+                y: side_end.1,
+            });
+        }
+        if r > 0.0 {
+            segments.push(PathSegment::Arc {
+                x: corner_end.0,
+                y: corner_end.1,
+                cx: center.0,
+                cy: center.1,
+                clockwise: false,
+            });
+        }
+        from = corner_end;
+    }
+    ((x + r, y), segments)
+}
+
+/// Radius, start angle and signed sweep (positive counter-clockwise) of an
+/// arc, a full turn when it ends where it starts
+fn arc_sweep(
+    from: (f64, f64),
+    to: (f64, f64),
+    center: (f64, f64),
+    clockwise: bool,
+) -> (f64, f64, f64) {
+    let radius = (from.0 - center.0).hypot(from.1 - center.1);
+    let start = (from.1 - center.1).atan2(from.0 - center.0);
+    let end = (to.1 - center.1).atan2(to.0 - center.0);
+    let full_turn = (from.0 - to.0).hypot(from.1 - to.1) < 1e-9;
+    let sweep = if clockwise {
+        let sweep = (start - end).rem_euclid(2.0 * PI);
+        if full_turn || sweep == 0.0 {
+            -2.0 * PI
+        } else {
+            -sweep
+        }
+    } else {
+        let sweep = (end - start).rem_euclid(2.0 * PI);
+        if full_turn || sweep == 0.0 {
+            2.0 * PI
+        } else {
+            sweep
+        }
+    };
+    (radius, start, sweep)
+}
+
+/// Point and derivative of a cubic Bézier at `t`
+fn cubic_point(points: &[(f64, f64); 4], t: f64) -> ((f64, f64), (f64, f64)) {
+    let [p0, p1, p2, p3] = *points;
+    let mt = 1.0 - t;
+    let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+    let point = (
+        a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+        a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+    );
+    let (da, db, dc) = (3.0 * mt * mt, 6.0 * mt * t, 3.0 * t * t);
+    let derivative = (
+        da * (p1.0 - p0.0) + db * (p2.0 - p1.0) + dc * (p3.0 - p2.0),
+        da * (p1.1 - p0.1) + db * (p2.1 - p1.1) + dc * (p3.1 - p2.1),
+    );
+    (point, derivative)
+}
+
+/// Points along a path; closed paths do not repeat the start
+fn path_outline(
+    start: (f64, f64),
+    segments: &[PathSegment],
+    closed: bool,
+    tolerance: f64,
+) -> Vec<(f64, f64)> {
+    let mut points = vec![start];
+    for segment in segments {
+        let from = points[points.len() - 1];
+        segment.flatten(from, tolerance, &mut points);
+    }
+    if closed && points.len() > 1 {
+        let last = points[points.len() - 1];
+        if (last.0 - start.0).hypot(last.1 - start.1) < 1e-9 {
+            points.pop();
+        }
+    }
+    points
+}
+
+/// G-code for a path, with arcs as G2/G3 and Béziers as short lines
+///
+/// Coordinates get three decimals so rounded arc ends stay within GRBL's
+/// 0.005 mm radius check.
+fn path_gcode(start: (f64, f64), segments: &[PathSegment], closed: bool) -> String {
+    let mut gcode = format!("G0 X{:.3} Y{:.3}\nG1 Z-1 F100\n", start.0, start.1);
+    let mut from = start;
+    for segment in segments {
+        match *segment {
+            PathSegment::Line { x, y } => {
+                gcode.push_str(&format!("G1 X{:.3} Y{:.3} F100\n", x, y));
+            }
+            PathSegment::Arc {
+                x,
+                y,
+                cx,
+                cy,
+                clockwise,
+            } => {
+                gcode.push_str(&format!(
+                    "{} X{:.3} Y{:.3} I{:.3} J{:.3} F100\n",
+                    if clockwise { "G2" } else { "G3" },
+                    x,
+                    y,
+                    cx - from.0,
+                    cy - from.1
+                ));
+            }
+            _ => {
+                let mut points = Vec::new();
+                segment.flatten(from, FLATTEN_TOLERANCE, &mut points);
+                for (x, y) in points {
+                    gcode.push_str(&format!("G1 X{:.3} Y{:.3} F100\n", x, y));
+                }
+            }
+        }
+        from = segment.end();
+    }
+    if closed && (from.0 - start.0).hypot(from.1 - start.1) > 1e-9 {
+        gcode.push_str(&format!("G1 X{:.3} Y{:.3} F100\n", start.0, start.1));
+    }
+    gcode.push_str("G0 Z5");
+    gcode
+}
+
+/// Number of equal chords keeping an arc of `radius` within `tolerance`
+pub(crate) fn arc_segments(radius: f64, sweep: f64, tolerance: f64) -> usize {
+    let ratio = (tolerance / radius.abs().max(1e-12)).clamp(1e-9, 1.0);
+    let max_angle = 2.0 * (1.0 - ratio).acos();
+    ((sweep.abs() / max_angle).ceil() as usize).clamp(1, 100_000)
+}
+
+/// Flatten a quadratic Bézier into points after `p0`, within `tolerance`
+///
+/// The segment count comes from the curve's second derivative, which bounds
+/// how far a chord can stray from the curve.
+pub(crate) fn flatten_quadratic(
+    p0: (f64, f64),
+    p1: (f64, f64),
+    p2: (f64, f64),
+    tolerance: f64,
+    out: &mut Vec<(f64, f64)>,
+) {
+    let dd = (p0.0 - 2.0 * p1.0 + p2.0).hypot(p0.1 - 2.0 * p1.1 + p2.1);
+    let segments = bezier_segments(dd / 4.0, tolerance);
+    for i in 1..=segments {
+        let t = i as f64 / segments as f64;
+        let mt = 1.0 - t;
+        out.push((
+            mt * mt * p0.0 + 2.0 * mt * t * p1.0 + t * t * p2.0,
+            mt * mt * p0.1 + 2.0 * mt * t * p1.1 + t * t * p2.1,
+        ));
+    }
+}
+
+/// Flatten a cubic Bézier into points after `p0`, within `tolerance`
+pub(crate) fn flatten_cubic(
+    p0: (f64, f64),
+    p1: (f64, f64),
+    p2: (f64, f64),
+    p3: (f64, f64),
+    tolerance: f64,
+    out: &mut Vec<(f64, f64)>,
+) {
+    let dd = (p0.0 - 2.0 * p1.0 + p2.0)
+        .hypot(p0.1 - 2.0 * p1.1 + p2.1)
+        .max((p1.0 - 2.0 * p2.0 + p3.0).hypot(p1.1 - 2.0 * p2.1 + p3.1));
+    let segments = bezier_segments(0.75 * dd, tolerance);
+    for i in 1..=segments {
+        let t = i as f64 / segments as f64;
+        let mt = 1.0 - t;
+        let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+        out.push((
+            a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+            a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+        ));
+    }
+}
+
+/// Number of equal steps keeping a curve within `tolerance` of its chords
+///
+/// `bend` is an eighth of the largest second derivative along the curve.
+fn bezier_segments(bend: f64, tolerance: f64) -> usize {
+    ((bend / tolerance.max(1e-6)).sqrt().ceil() as usize).clamp(1, 10_000)
+}
+
+/// Closed polygon cut once around at Z-1
+fn polygon_gcode(points: &[(f64, f64)]) -> String {
+    let mut gcode = String::new();
+    if !points.is_empty() {
+        gcode.push_str(&format!("G0 X{:.2} Y{:.2}\n", points[0].0, points[0].1));
+        gcode.push_str("G1 Z-1 F100\n");
+        for (x, y) in points {
+            gcode.push_str(&format!("G1 X{:.2} Y{:.2} F100\n", x, y));
         }
+        gcode.push_str(&format!(
+            "G1 X{:.2} Y{:.2} F100\nG0 Z5",
+            points[0].0, points[0].1
+        ));
     }
+    gcode
 }
 
 /// Calculate area using shoelace formula
@@ -1,5 +1,9 @@
 //! Toolpath generation and G-code optimization
 
+use super::estimator::{MachineLimits, TimeEstimate, TimeEstimator};
+use super::offset::{profile, ProfileOptions};
+use super::shapes::{PathSegment, Shape};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 /// Represents a single G-code toolpath
@@ -30,11 +34,76 @@ impl Toolpath {
         }
     }
 
-    /// Calculate approximate machining time in seconds
-    pub fn estimate_time(&self) -> f64 {
-        // Simple heuristic: count G1 commands and estimate distance
-        let g1_count = self.gcode.matches("G1").count() as f64;
-        (g1_count * 10.0) / self.feed_rate.max(1.0)
+    /// Create a profile toolpath around a shape, compensated for the tool
+    ///
+    /// Parts of the path that follow a circle, such as round joins and the
+    /// offsets of circles and rounded corners, are cut as `G2`/`G3` arcs.
+    ///
+    /// # Arguments
+    /// * `name` - Toolpath name
+    /// * `shape` - Shape to profile
+    /// * `options` - Tool diameter, stock allowance, side, joins and direction
+    /// * `feed_rate` - Cutting feed in mm/min
+    /// * `spindle_speed` - Spindle speed in RPM
+    /// * `cut_depth` - Depth below the surface in mm
+    pub fn profile(
+        name: String,
+        shape: &Shape,
+        options: &ProfileOptions,
+        feed_rate: f64,
+        spindle_speed: u32,
+        cut_depth: f64,
+    ) -> Result<Self> {
+        let mut gcode = String::new();
+        for path in profile(shape, options)? {
+            let Some(&(x, y)) = path.points.first() else {
+                continue;
+            };
+            gcode.push_str(&format!("G0 X{:.3} Y{:.3}\n", x, y));
+            gcode.push_str(&format!("G1 Z{:.3} F{}\n", -cut_depth, feed_rate));
+            let mut from = (x, y);
+            for segment in path.segments(options.tolerance) {
+                match segment {
+                    PathSegment::Arc {
+                        x,
+                        y,
+                        cx,
+                        cy,
+                        clockwise,
+                    } => gcode.push_str(&format!(
+                        "{} X{:.3} Y{:.3} I{:.3} J{:.3} F{}\n",
+                        if clockwise { "G2" } else { "G3" },
+                        x,
+                        y,
+                        cx - from.0,
+                        cy - from.1,
+                        feed_rate
+                    )),
+                    _ => {
+                        let (x, y) = segment.end();
+                        gcode.push_str(&format!("G1 X{:.3} Y{:.3} F{}\n", x, y, feed_rate));
+                    }
+                }
+                from = segment.end();
+            }
+            gcode.push_str("G0 Z5\n");
+        }
+        Ok(Toolpath::new(name, gcode, feed_rate, spindle_speed, cut_depth))
+    }
+
+    /// Estimate machining time on a machine
+    ///
+    /// Moves before the first `F` word run at the toolpath's feed rate.
+    ///
+    /// # Arguments
+    /// * `limits` - Max rates, accelerations and junction deviation
+    ///
+    /// # Returns
+    /// Cutting, rapid and dwell time in seconds
+    pub fn estimate_time(&self, limits: &MachineLimits) -> Result<TimeEstimate> {
+        TimeEstimator::new(*limits)
+            .with_default_feed(self.feed_rate)
+            .estimate(&self.gcode)
     }
 
     /// Optimize G-code by removing comments and extra whitespace
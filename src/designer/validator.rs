@@ -7,6 +7,7 @@
 //! - GRBL version-specific rule validation
 //! - Real-time validation support
 
+use crate::communication::{FirmwareDialect, FirmwareInfo, GrblAlarm, GrblError};
 use anyhow::{anyhow, Result};
 use std::collections::HashMap;
 
@@ -27,6 +28,23 @@ impl Default for GrblVersion {
     }
 }
 
+impl GrblVersion {
+    /// Get the GRBL feature level of detected firmware
+    ///
+    /// Only GRBL itself is versioned here; other dialects are checked against
+    /// their own list of supported commands instead.
+    pub fn from_firmware(info: &FirmwareInfo) -> Self {
+        if info.dialect != FirmwareDialect::Grbl {
+            return GrblVersion::default();
+        }
+        match (info.major, info.minor) {
+            (0, _) | (1, 0) => GrblVersion::V1_0,
+            (1, 1) => GrblVersion::V1_1,
+            _ => GrblVersion::V1_2,
+        }
+    }
+}
+
 /// Validation error severity levels
 #[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Severity {
@@ -66,6 +84,37 @@ pub struct ValidationIssue {
     pub suggestion: Option<String>,
 }
 
+impl ValidationIssue {
+    /// Build an issue from an `error:N` the controller reported for a line
+    ///
+    /// # Arguments
+    /// * `line_number` - Line of the program that was rejected (1-based)
+    /// * `error` - Decoded controller error
+    pub fn from_grbl_error(line_number: usize, error: &GrblError) -> Self {
+        ValidationIssue {
+            line_number,
+            severity: Severity::Error,
+            issue_type: match error.code() {
+                Some(code) => format!("Controller error:{}", code),
+                None => "Controller error".to_string(),
+            },
+            message: error.description().to_string(),
+            suggestion: Some(error.recovery().to_string()),
+        }
+    }
+
+    /// Build an issue from an alarm raised while a line ran
+    pub fn from_grbl_alarm(line_number: usize, alarm: &GrblAlarm) -> Self {
+        ValidationIssue {
+            line_number,
+            severity: Severity::Critical,
+            issue_type: format!("Controller ALARM:{}", alarm.code()),
+            message: alarm.description().to_string(),
+            suggestion: Some(alarm.recovery().to_string()),
+        }
+    }
+}
+
 /// Validation rule for G-code commands
 #[derive(Clone, Debug)]
 struct ValidationRule {
@@ -84,6 +133,8 @@ struct ValidationRule {
 pub struct GcodeValidator {
     /// GRBL version to validate against
     grbl_version: GrblVersion,
+    /// Firmware dialect whose unsupported commands are reported
+    dialect: Option<FirmwareDialect>,
     /// Validation rules
     rules: HashMap<String, ValidationRule>,
     /// Whether to validate syntax
@@ -103,6 +154,7 @@ impl GcodeValidator {
     pub fn new(grbl_version: GrblVersion) -> Self {
         let mut validator = Self {
             grbl_version,
+            dialect: None,
             rules: HashMap::new(),
             validate_syntax: true,
             validate_semantics: true,
@@ -111,6 +163,16 @@ impl GcodeValidator {
         validator
     }
 
+    /// Create a validator for detected controller firmware
+    ///
+    /// In addition to the GRBL version rules, commands the dialect does not
+    /// support (canned cycles on GRBL, `G10` on Marlin, ...) are reported.
+    pub fn for_firmware(info: &FirmwareInfo) -> Self {
+        let mut validator = Self::new(GrblVersion::from_firmware(info));
+        validator.dialect = Some(info.dialect);
+        validator
+    }
+
     /// Initialize default validation rules
     fn init_default_rules(&mut self) {
         // G0 - Rapid move
@@ -350,6 +412,10 @@ impl GcodeValidator {
                 self.rules.contains_key(cmd_type).then(|| cmd_type.clone())
             };
 
+            if let Some(issue) = self.check_dialect_support(cmd_type, cmd_value, line_number) {
+                issues.push(issue);
+            }
+
             if let Some(rule_name) = rule_key {
                 if let Some(rule) = self.rules.get(&rule_name) {
                     if !rule.enabled {
@@ -461,6 +527,31 @@ impl GcodeValidator {
         issues
     }
 
+    /// Report a G- or M-code the target firmware dialect does not support
+    fn check_dialect_support(
+        &self,
+        cmd_type: &str,
+        cmd_value: &str,
+        line_number: usize,
+    ) -> Option<ValidationIssue> {
+        let dialect = self.dialect?;
+        if cmd_type != "G" && cmd_type != "M" {
+            return None;
+        }
+        // Normalize `G01` to `G1`
+        let word = format!("{}{}", cmd_type, cmd_value.parse::<f64>().ok()?);
+        if dialect.supports_gcode(&word) {
+            return None;
+        }
+        Some(ValidationIssue {
+            line_number,
+            severity: Severity::Error,
+            issue_type: format!("Unsupported command: {}", word),
+            message: format!("{} is not supported by {}", word, dialect),
+            suggestion: Some(format!("Remove {} or post-process for {}", word, dialect)),
+        })
+    }
+
     /// Validate semantic consistency across program
     fn validate_semantics_program(&self, _gcode: &str) -> Vec<ValidationIssue> {
         let issues = Vec::new();
@@ -538,6 +629,33 @@ mod tests {
         assert!(validator.validate_semantics);
     }
 
+    #[test]
+    fn test_firmware_dialect_support() {
+        let mut info = FirmwareInfo::detect("Grbl 1.1h ['$' for help]").unwrap();
+        assert_eq!(GrblVersion::from_firmware(&info), GrblVersion::V1_1);
+        let issues = GcodeValidator::for_firmware(&info).validate_program("G81 X0 Y0 Z-2 R1 F100");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].issue_type, "Unsupported command: G81");
+
+        info.dialect = FirmwareDialect::GrblHal;
+        let issues = GcodeValidator::for_firmware(&info).validate_program("G81 X0 Y0 Z-2 R1 F100");
+        assert!(issues.is_empty());
+
+        info.dialect = FirmwareDialect::Marlin;
+        let issues = GcodeValidator::for_firmware(&info).validate_program("G10 L20 P1 X0\nG01 X5 F100");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line_number, 1);
+    }
+
+    #[test]
+    fn test_issue_from_controller_error() {
+        let issue = ValidationIssue::from_grbl_error(12, &GrblError::from_code(20));
+        assert_eq!(issue.line_number, 12);
+        assert_eq!(issue.severity, Severity::Error);
+        assert_eq!(issue.issue_type, "Controller error:20");
+        assert!(issue.suggestion.is_some());
+    }
+
     #[test]
     fn test_simple_program_valid() {
         let validator = GcodeValidator::new(GrblVersion::V1_2);
@@ -8,6 +8,14 @@ use std::collections::BinaryHeap;
 use std::cmp::Ordering;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::communication::offsets::format_coordinate;
+use crate::communication::parser_state::{split_words, DistanceMode, MotionMode, SpindleState};
+use crate::communication::streaming;
+use crate::communication::{
+    GrblAlarm, GrblError, GrblResponse, MachineEvent, MachineState, ParserState, QueuedLine,
+};
+use crate::designer::estimator::{MachineLimits, TimeEstimate, TimeEstimator};
+use anyhow::Result;
 
 /// Priority levels for job scheduling (1-10, where 10 is highest)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -46,6 +54,50 @@ pub enum JobState {
     Cancelled,
 }
 
+/// How to re-enter a program part way through
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ResumeOptions {
+    /// Machine Z (`G53`) to retract to before moving over the resume point
+    pub safe_z: f64,
+    /// Seconds to wait for the spindle to reach speed
+    pub spindle_delay: f64,
+    /// Feed rate for the plunge back to depth; defaults to the program feed
+    pub plunge_feed: Option<f64>,
+}
+
+impl Default for ResumeOptions {
+    fn default() -> Self {
+        ResumeOptions {
+            safe_z: -1.0,
+            spindle_delay: 3.0,
+            plunge_feed: None,
+        }
+    }
+}
+
+/// Lines that resume a job from `current_line`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResumePlan {
+    /// Index of the first program line that is re-sent
+    pub start_line: usize,
+    /// Retract, modal state, spindle/coolant and approach moves
+    pub preamble: Vec<String>,
+    /// Program lines from `start_line` on
+    pub remaining: Vec<String>,
+}
+
+impl ResumePlan {
+    /// Get the preamble followed by the remaining program
+    pub fn to_gcode(&self) -> String {
+        self.preamble
+            .iter()
+            .chain(self.remaining.iter())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
 /// A machining job with G-code
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Job {
@@ -61,6 +113,9 @@ pub struct Job {
     pub started_at: Option<String>,
     pub completed_at: Option<String>,
     pub error_message: Option<String>,
+    /// Run time estimate, by line counted in `current_line`
+    #[serde(skip)]
+    pub estimate: Option<TimeEstimate>,
 }
 
 impl Job {
@@ -81,6 +136,34 @@ impl Job {
             started_at: None,
             completed_at: None,
             error_message: None,
+            estimate: None,
+        }
+    }
+
+    /// Estimate the run time of the job and keep it for `remaining_time`
+    ///
+    /// # Arguments
+    /// * `limits` - Limits of the machine that will run the job
+    ///
+    /// # Returns
+    /// The estimate, or an error if a line cannot be interpreted
+    pub fn estimate_time(&mut self, limits: &MachineLimits) -> Result<&TimeEstimate> {
+        let program = self.program_lines().collect::<Vec<_>>().join("\n");
+        let estimate = TimeEstimator::new(*limits).estimate(&program)?;
+        Ok(self.estimate.insert(estimate))
+    }
+
+    /// Get the estimated seconds left from `current_line` on
+    ///
+    /// # Returns
+    /// None until `estimate_time` has run
+    pub fn remaining_time(&self) -> Option<f64> {
+        match self.state {
+            JobState::Completed => Some(0.0),
+            _ => self
+                .estimate
+                .as_ref()
+                .map(|estimate| estimate.remaining_after(self.current_line)),
         }
     }
 
@@ -114,6 +197,28 @@ impl Job {
         self.completed_at = Some(Utc::now().to_rfc3339());
     }
 
+    /// Mark job as failed because the controller rejected the current line
+    pub fn fail_on_error(&mut self, error: &GrblError) {
+        let message = format!(
+            "Line {}: {} {}",
+            self.current_line + 1,
+            error,
+            error.recovery()
+        );
+        self.fail(message);
+    }
+
+    /// Mark job as failed because the controller raised an alarm
+    pub fn fail_on_alarm(&mut self, alarm: &GrblAlarm) {
+        let message = format!(
+            "Line {}: {} {}",
+            self.current_line + 1,
+            alarm,
+            alarm.recovery()
+        );
+        self.fail(message);
+    }
+
     /// Pause the job
     pub fn pause(&mut self) {
         if self.state == JobState::Running {
@@ -136,6 +241,112 @@ impl Job {
             .nth(self.current_line)
     }
 
+    /// Build the lines that resume this job from `current_line`
+    ///
+    /// The lines before `current_line` are replayed in memory to recover the
+    /// modal state, spindle, coolant, feed and work position at that point.
+    /// The plan retracts to a safe machine Z, restores that state, moves over
+    /// the resume point and plunges back to depth before the remaining lines.
+    pub fn resume_plan(&self, options: &ResumeOptions) -> ResumePlan {
+        let lines: Vec<&str> = self.program_lines().collect();
+        let start_line = self.current_line.min(lines.len());
+
+        let mut state = ParserState::default();
+        let mut position: [Option<f64>; 3] = [None; 3];
+        for line in &lines[..start_line] {
+            state.apply_line(line);
+            track_position(&mut position, line, state.distance);
+        }
+
+        let mut preamble = vec![format!("G21 G53 G0 Z{}", format_coordinate(options.safe_z))];
+        let mut absolute = state.clone();
+        absolute.distance = DistanceMode::Absolute;
+        preamble.push(absolute.to_gcode());
+
+        let spindle = match state.spindle {
+            SpindleState::Off => None,
+            SpindleState::Clockwise => Some("M3"),
+            SpindleState::CounterClockwise => Some("M4"),
+        };
+        if let Some(code) = spindle {
+            preamble.push(format!("{} S{}", code, format_coordinate(state.spindle_speed)));
+            if options.spindle_delay > 0.0 {
+                preamble.push(format!("G4 P{}", format_coordinate(options.spindle_delay)));
+            }
+        }
+        if state.flood {
+            preamble.push("M8".to_string());
+        }
+        if state.mist {
+            preamble.push("M7".to_string());
+        }
+
+        let approach: String = ['X', 'Y']
+            .iter()
+            .zip(position)
+            .filter_map(|(letter, value)| Some(format!(" {}{}", letter, format_coordinate(value?))))
+            .collect();
+        if !approach.is_empty() {
+            preamble.push(format!("G0{}", approach));
+        }
+
+        if let Some(z) = position[2] {
+            let plunge_feed = options.plunge_feed.unwrap_or(state.feed_rate);
+            if plunge_feed > 0.0 {
+                preamble.push(format!(
+                    "G1 Z{} F{}",
+                    format_coordinate(z),
+                    format_coordinate(plunge_feed)
+                ));
+            } else {
+                preamble.push(format!("G0 Z{}", format_coordinate(z)));
+            }
+        }
+        if state.distance == DistanceMode::Incremental {
+            preamble.push("G91".to_string());
+        }
+
+        // Put the motion mode and feed back for lines that rely on them
+        let feed = (state.feed_rate > 0.0).then(|| format!("F{}", format_coordinate(state.feed_rate)));
+        match (state.motion, feed) {
+            (MotionMode::Rapid | MotionMode::Linear, Some(feed)) => {
+                preamble.push(format!("{} {}", state.motion.code(), feed))
+            }
+            (MotionMode::Rapid | MotionMode::Linear, None) => {
+                preamble.push(state.motion.code().to_string())
+            }
+            (_, Some(feed)) => preamble.push(feed),
+            (_, None) => {}
+        }
+
+        ResumePlan {
+            start_line,
+            preamble,
+            remaining: lines[start_line..].iter().map(|l| l.to_string()).collect(),
+        }
+    }
+
+    /// Iterate the lines counted by `total_lines` and `current_line`
+    fn program_lines(&self) -> impl Iterator<Item = &str> {
+        self.gcode
+            .lines()
+            .filter(|l| !l.trim().is_empty() && !l.trim().starts_with(';'))
+    }
+
+    /// Get the program lines from `start_line` on, ready to stream
+    ///
+    /// Each line is tagged with its index so its acknowledgement can advance
+    /// the job; lines holding only a comment are skipped.
+    pub fn queued_lines(&self, start_line: usize) -> Vec<QueuedLine> {
+        self.program_lines()
+            .enumerate()
+            .skip(start_line)
+            .filter_map(|(index, line)| {
+                streaming::prepare_line(line).map(|command| QueuedLine::job(command, index))
+            })
+            .collect()
+    }
+
     /// Get remaining G-code
     pub fn get_remaining_gcode(&self) -> String {
         self.gcode
@@ -147,6 +358,54 @@ impl Job {
     }
 }
 
+/// Follow the work position through one program line
+///
+/// Axes become unknown after moves that end somewhere the program does not
+/// state (G28/G30, probing, G53 machine moves, G92.1).
+fn track_position(position: &mut [Option<f64>; 3], line: &str, distance: DistanceMode) {
+    let words = split_words(line);
+    let g_codes: Vec<i32> = words
+        .iter()
+        .filter_map(|w| w.strip_prefix('G'))
+        .filter_map(|v| v.parse::<f64>().ok())
+        .map(|v| (v * 10.0).round() as i32)
+        .collect();
+
+    let axes = words.iter().filter_map(|word| {
+        let index = match word.chars().next()? {
+            'X' => 0,
+            'Y' => 1,
+            'Z' => 2,
+            _ => return None,
+        };
+        Some((index, word[1..].parse::<f64>().ok()?))
+    });
+
+    if g_codes.iter().any(|g| matches!(g, 280 | 300 | 921)) {
+        *position = [None; 3];
+    } else if g_codes
+        .iter()
+        .any(|g| matches!(g, 40 | 100 | 281 | 301 | 431 | 491))
+    {
+        // Axis words here are not a move
+    } else if g_codes.iter().any(|g| *g == 530 || (382..=385).contains(g)) {
+        for (index, _) in axes {
+            position[index] = None;
+        }
+    } else if g_codes.contains(&920) {
+        for (index, value) in axes {
+            position[index] = Some(value);
+        }
+    } else {
+        for (index, value) in axes {
+            position[index] = match distance {
+                DistanceMode::Absolute => Some(value),
+                DistanceMode::Incremental => position[index].map(|p| p + value),
+            };
+        }
+    }
+}
+
 /// Job wrapper for priority queue
 #[derive(Clone)]
 struct QueuedJob {
@@ -183,6 +442,7 @@ pub struct JobManager {
     sequence: u64,
     active_job: Option<Box<Job>>,
     completed_jobs: Vec<Job>,
+    limits: MachineLimits,
 }
 
 impl JobManager {
@@ -193,11 +453,31 @@ impl JobManager {
             sequence: 0,
             active_job: None,
             completed_jobs: Vec::new(),
+            limits: MachineLimits::default(),
         }
     }
 
-    /// Add a job to the queue
-    pub fn queue_job(&mut self, job: Job) {
+    /// Use the limits of the connected machine for job estimates
+    ///
+    /// Queued jobs and the active job are estimated again with the new limits.
+    pub fn set_machine_limits(&mut self, limits: MachineLimits) {
+        self.limits = limits;
+        let queue = std::mem::take(&mut self.queue);
+        self.queue = queue
+            .into_iter()
+            .map(|mut queued| {
+                estimate_job(&mut queued.job, &limits);
+                queued
+            })
+            .collect();
+        if let Some(job) = self.active_job.as_mut() {
+            estimate_job(job, &limits);
+        }
+    }
+
+    /// Add a job to the queue and estimate its run time
+    pub fn queue_job(&mut self, mut job: Job) {
+        estimate_job(&mut job, &self.limits);
         self.queue.push(QueuedJob {
             priority: job.priority,
             sequence: self.sequence,
@@ -211,8 +491,11 @@ impl JobManager {
         self.queue.pop().map(|qj| *qj.job)
     }
 
-    /// Set active job
-    pub fn set_active_job(&mut self, job: Job) {
+    /// Set active job, estimating it if it was not queued first
+    pub fn set_active_job(&mut self, mut job: Job) {
+        if job.estimate.is_none() {
+            estimate_job(&mut job, &self.limits);
+        }
         self.active_job = Some(Box::new(job));
     }
 
@@ -239,6 +522,24 @@ impl JobManager {
         }
     }
 
+    /// Fail active job with a decoded controller error
+    pub fn fail_active_job_on_error(&mut self, error: &GrblError) {
+        if let Some(job) = self.active_job.take() {
+            let mut j = *job;
+            j.fail_on_error(error);
+            self.completed_jobs.push(j);
+        }
+    }
+
+    /// Fail active job with a decoded controller alarm
+    pub fn fail_active_job_on_alarm(&mut self, alarm: &GrblAlarm) {
+        if let Some(job) = self.active_job.take() {
+            let mut j = *job;
+            j.fail_on_alarm(alarm);
+            self.completed_jobs.push(j);
+        }
+    }
+
     /// Get queue length
     pub fn queue_length(&self) -> usize {
         self.queue.len()
@@ -259,6 +560,51 @@ impl JobManager {
         self.completed_jobs.clear();
     }
 
+    /// Track the active job from a machine event
+    ///
+    /// Acknowledgements of the job's own lines advance its progress, also
+    /// while paused since the controller still works through its buffer
+    /// during a feed hold. An `error:N` on one of those lines or an alarm
+    /// fails it; a feed hold, an opened door or a lost connection pauses it,
+    /// and leaving the hold resumes it.
+    pub fn handle_event(&mut self, event: &MachineEvent) {
+        let Some(job) = self.get_active_job() else {
+            return;
+        };
+        match event {
+            MachineEvent::LineAcknowledged { ack }
+                if matches!(job.state, JobState::Running | JobState::Paused) =>
+            {
+                // Jogs, queries and resume preambles carry no job line
+                let Some(line) = ack.job_line else {
+                    return;
+                };
+                match &ack.response {
+                    GrblResponse::Error(error) => {
+                        let error = error.clone();
+                        self.fail_active_job_on_error(&error);
+                    }
+                    _ => job.update_progress((line + 1).min(job.total_lines)),
+                }
+            }
+            MachineEvent::AlarmRaised { alarm } if job.state != JobState::Pending => match alarm {
+                Some(alarm) => self.fail_active_job_on_alarm(alarm),
+                None => self.fail_active_job("Machine alarm".to_string()),
+            },
+            MachineEvent::StateChanged {
+                to: MachineState::Hold,
+                ..
+            }
+            | MachineEvent::DoorOpened
+            | MachineEvent::ConnectionLost { .. } => job.pause(),
+            MachineEvent::StateChanged {
+                from: MachineState::Hold,
+                to: MachineState::Run,
+            } => job.resume(),
+            _ => {}
+        }
+    }
+
     /// Resume active job from last saved position
     pub fn resume_active_job(&mut self) -> bool {
         if let Some(job) = self.get_active_job() {
@@ -270,6 +616,14 @@ impl JobManager {
     }
 }
 
+/// Estimate a job, leaving it without remaining time if it cannot be read
+fn estimate_job(job: &mut Job, limits: &MachineLimits) {
+    if let Err(e) = job.estimate_time(limits) {
+        job.estimate = None;
+        tracing::warn!("Cannot estimate job '{}': {}", job.name, e);
+    }
+}
+
 impl Default for JobManager {
     fn default() -> Self {
         Self::new()
@@ -7,6 +7,7 @@ mod communication;
 mod designer;
 mod jobs;
 mod materials;
+mod pendant;
 mod theme;
 mod ui_theme;
 mod widgets;
@@ -15,7 +16,10 @@ mod console_logger;
 use theme::ThemeManager;
 use ui_theme::UIThemeProvider;
 use widgets::ConnectionWidget;
-use communication::GrblController;
+use jobs::JobManager;
+use designer::estimator::MachineLimits;
+use pendant::ws::WsConnectionManager;
+use communication::{GrblController, GrblResponse, MachineEvent};
 use console_logger::{init_console_logging, get_console_logs, add_console_message};
 use anyhow::Result;
 use std::sync::Arc;
@@ -70,6 +74,8 @@ async fn setup_ui_handlers(ui: &AppWindow, console_buffer: console_logger::Conso
     // Initialize connection widget and controller
     let connection_widget = Arc::new(Mutex::new(ConnectionWidget::new()));
     let grbl_controller = Arc::new(GrblController::new());
+    let job_manager = Arc::new(Mutex::new(JobManager::new()));
+    let pendant_connections = Arc::new(WsConnectionManager::new());
 
     // Initial port refresh
     {
@@ -135,6 +141,9 @@ async fn setup_ui_handlers(ui: &AppWindow, console_buffer: console_logger::Conso
                     {
                         Ok(_) => {
                             tracing::info!("Connected to device on {}", port_str);
+                            grbl_controller
+                                .start_status_polling(std::time::Duration::from_millis(200))
+                                .await;
                             if let Some(ui) = ui_handle.upgrade() {
                                 ui.set_is_connected(true);
                                 ui.set_selected_port(port_str.clone().into());
@@ -185,6 +194,99 @@ async fn setup_ui_handlers(ui: &AppWindow, console_buffer: console_logger::Conso
         });
     }
 
+    // Log machine events to the device console
+    communication::events::spawn_event_logger(grbl_controller.subscribe_events());
+
+    // Push machine events and status, with the active job's remaining time, to connected pendants
+    pendant_connections.forward_events(grbl_controller.subscribe_events());
+    pendant_connections.forward_status(grbl_controller.subscribe_status(), Arc::clone(&job_manager));
+
+    // Advance, pause and fail the active job from acknowledgements and alarms
+    {
+        let mut event_rx = grbl_controller.subscribe_events();
+        let job_manager = Arc::clone(&job_manager);
+        let grbl_controller = Arc::clone(&grbl_controller);
+        tokio::spawn(async move {
+            loop {
+                let event = match event_rx.recv().await {
+                    Ok(event) => event,
+                    // Progress comes from the line index on each ack, so the
+                    // next one corrects for any that were missed
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                if matches!(event, MachineEvent::Connected) {
+                    // Estimate jobs with the connected machine's rates and accelerations
+                    let grbl_controller = Arc::clone(&grbl_controller);
+                    let job_manager = Arc::clone(&job_manager);
+                    tokio::spawn(async move {
+                        match grbl_controller.read_settings().await {
+                            Ok(settings) => job_manager
+                                .lock()
+                                .await
+                                .set_machine_limits(MachineLimits::from_settings(&settings)),
+                            Err(e) => tracing::warn!("Cannot read machine limits: {}", e),
+                        }
+                    });
+                }
+                job_manager.lock().await.handle_event(&event);
+            }
+        });
+    }
+
+    // Follow state transitions and connection drops in the status bar
+    {
+        let mut event_rx = grbl_controller.subscribe_events();
+        let ui_handle = ui.as_weak();
+        let _ = slint::spawn_local(async move {
+            loop {
+                let event = match event_rx.recv().await {
+                    Ok(event) => event,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(ui) = ui_handle.upgrade() else {
+                    break;
+                };
+                match event {
+                    MachineEvent::StateChanged { to, .. } => {
+                        ui.set_machine_state(format!("{:?}", to).into());
+                    }
+                    MachineEvent::ConnectionLost { reason } => {
+                        ui.set_is_connected(false);
+                        ui.set_connection_status(reason.into());
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    // Mirror machine positions into the status bar
+    {
+        let mut status_rx = grbl_controller.subscribe_status();
+        let ui_handle = ui.as_weak();
+        let _ = slint::spawn_local(async move {
+            loop {
+                let status = match status_rx.recv().await {
+                    Ok(status) => status,
+                    // Only the latest position matters, skip what was missed
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                if let Some(ui) = ui_handle.upgrade() {
+                    ui.set_machine_position(
+                        format!(
+                            "X: {:.2} Y: {:.2} Z: {:.2}",
+                            status.wpos.x, status.wpos.y, status.wpos.z
+                        )
+                        .into(),
+                    );
+                }
+            }
+        });
+    }
+
     // Set up console handlers
     {
         let console_buffer = console_buffer.clone();
@@ -210,6 +312,13 @@ async fn setup_ui_handlers(ui: &AppWindow, console_buffer: console_logger::Conso
                     
                     // Send command to device
                     match grbl_controller.send_command(&cmd_str).await {
+                        Ok(GrblResponse::Error(error)) => {
+                            tracing::warn!("Command '{}' rejected: {}", cmd_str, error);
+                            add_console_message(
+                                &console_buffer,
+                                format!("RX: {} {}", error, error.recovery()),
+                            );
+                        }
                         Ok(_) => {
                             tracing::info!("Command sent successfully: {}", cmd_str);
                             add_console_message(&console_buffer, format!("RX: ok"));
@@ -11,6 +11,8 @@
 //! - `POST /api/connect` - Connect to device
 //! - `POST /api/disconnect` - Disconnect from device
 
+use crate::communication::{GrblStatus, RealtimeCommand};
+use crate::jobs::Job;
 use serde::{Deserialize, Serialize};
 
 /// Machine status response
@@ -32,6 +34,8 @@ pub struct StatusResponse {
     pub spindle_speed: u16,
     /// GRBL firmware version
     pub firmware_version: String,
+    /// Estimated seconds left in the active job
+    pub remaining_time: Option<f64>,
 }
 
 impl Default for StatusResponse {
@@ -45,10 +49,39 @@ impl Default for StatusResponse {
             feed_rate: 0.0,
             spindle_speed: 0,
             firmware_version: String::new(),
+            remaining_time: None,
         }
     }
 }
 
+impl From<&GrblStatus> for StatusResponse {
+    fn from(status: &GrblStatus) -> Self {
+        Self {
+            connected: status.connected,
+            state: if status.connected {
+                format!("{:?}", status.state).to_lowercase()
+            } else {
+                "disconnected".to_string()
+            },
+            pos_x: status.wpos.x,
+            pos_y: status.wpos.y,
+            pos_z: status.wpos.z,
+            feed_rate: status.feed_rate as f64,
+            spindle_speed: status.spindle_speed.min(u16::MAX as u32) as u16,
+            firmware_version: status.version.clone(),
+            remaining_time: None,
+        }
+    }
+}
+
+impl StatusResponse {
+    /// Add the remaining time of the active job
+    pub fn with_job(mut self, job: &Job) -> Self {
+        self.remaining_time = job.remaining_time();
+        self
+    }
+}
+
 /// Jog command request
 #[derive(Clone, Debug, Deserialize)]
 pub struct JogRequest {
@@ -63,12 +96,30 @@ pub struct JogRequest {
 /// Override adjustment request
 #[derive(Clone, Debug, Deserialize)]
 pub struct OverrideRequest {
-    /// Override type (feed_rate, spindle_speed, laser_power)
+    /// Override type (feed_rate, spindle_speed, laser_power, rapid)
     pub override_type: String,
     /// Percentage adjustment (0-200)
     pub value: u8,
 }
 
+impl OverrideRequest {
+    /// Translate the request into GRBL real-time override commands
+    ///
+    /// # Returns
+    /// None if the override type is not recognised
+    pub fn to_realtime_commands(&self) -> Option<Vec<RealtimeCommand>> {
+        let value = self.value as u32;
+        match self.override_type.as_str() {
+            "feed_rate" => Some(RealtimeCommand::feed_override_sequence(value)),
+            "spindle_speed" | "laser_power" => {
+                Some(RealtimeCommand::spindle_override_sequence(value))
+            }
+            "rapid" => Some(vec![RealtimeCommand::rapid_override(value)]),
+            _ => None,
+        }
+    }
+}
+
 /// API error response
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ApiError {
@@ -90,6 +141,44 @@ mod tests {
         assert_eq!(status.pos_x, 0.0);
     }
 
+    #[test]
+    fn test_status_response_from_grbl_status() {
+        use crate::communication::{MachineState, Position};
+
+        let status = GrblStatus {
+            state: MachineState::Run,
+            wpos: Position { x: 1.0, y: 2.0, z: -0.5 },
+            feed_rate: 800,
+            spindle_speed: 12000,
+            connected: true,
+            ..Default::default()
+        };
+        let response = StatusResponse::from(&status);
+        assert_eq!(response.state, "run");
+        assert_eq!(response.pos_z, -0.5);
+        assert_eq!(response.spindle_speed, 12000);
+        assert!(response.remaining_time.is_none());
+    }
+
+    #[test]
+    fn test_status_response_with_job() {
+        use crate::designer::estimator::MachineLimits;
+        use crate::jobs::Priority;
+
+        let mut job = Job::new(
+            "Square".to_string(),
+            "G1 X10 F600\nG1 Y10\nG1 X0\nG1 Y0\n".to_string(),
+            Priority::normal(),
+        );
+        let total = job.estimate_time(&MachineLimits::default()).unwrap().total();
+        job.start();
+        job.update_progress(2);
+
+        let response = StatusResponse::default().with_job(&job);
+        let remaining = response.remaining_time.unwrap();
+        assert!(remaining > 0.0 && remaining < total);
+    }
+
     #[test]
     fn test_jog_request_creation() {
         let req = JogRequest {
@@ -112,6 +201,23 @@ mod tests {
         assert_eq!(req.value, 110);
     }
 
+    #[test]
+    fn test_override_request_to_realtime() {
+        let req = OverrideRequest {
+            override_type: "laser_power".to_string(),
+            value: 90,
+        };
+        let commands = req.to_realtime_commands().unwrap();
+        assert_eq!(commands[0], RealtimeCommand::SpindleOverrideReset);
+        assert_eq!(commands[1], RealtimeCommand::SpindleOverrideCoarseMinus);
+
+        let unknown = OverrideRequest {
+            override_type: "coolant".to_string(),
+            value: 100,
+        };
+        assert!(unknown.to_realtime_commands().is_none());
+    }
+
     #[test]
     fn test_api_error_creation() {
         let error = ApiError {
@@ -7,12 +7,20 @@
 //! - Status updates: `{"type":"status","data":{...}}`
 //! - Command responses: `{"type":"command","data":{...}}`
 //! - Error messages: `{"type":"error","message":"..."}`
+//! - Machine events: `{"type":"event","data":{"event":"alarm_raised",...}}`
 
+use crate::communication::{GrblStatus, MachineEvent};
+use crate::jobs::JobManager;
+use crate::pendant::api::StatusResponse;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tokio::task::JoinHandle;
+
+/// Messages buffered for each connection before a slow client misses some
+const OUTBOUND_CAPACITY: usize = 256;
 
 /// WebSocket message types
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -29,6 +37,9 @@ pub enum MessageType {
     /// Connection acknowledgment
     #[serde(rename = "connected")]
     Connected,
+    /// Machine event forwarded from the controller
+    #[serde(rename = "event")]
+    Event,
 }
 
 /// WebSocket message envelope
@@ -42,6 +53,26 @@ pub struct WsMessage {
     pub data: serde_json::Value,
 }
 
+impl WsMessage {
+    /// Wrap a machine event for the pendant
+    pub fn event(event: &MachineEvent, timestamp: u64) -> Self {
+        Self {
+            msg_type: "event".to_string(),
+            timestamp,
+            data: serde_json::to_value(event).unwrap_or_default(),
+        }
+    }
+
+    /// Wrap a status update for the pendant
+    pub fn status(status: &StatusResponse, timestamp: u64) -> Self {
+        Self {
+            msg_type: "status".to_string(),
+            timestamp,
+            data: serde_json::to_value(status).unwrap_or_default(),
+        }
+    }
+}
+
 /// Connection metadata
 #[derive(Clone, Debug)]
 pub struct ConnectionMetadata {
@@ -95,6 +126,8 @@ pub struct WsConnectionManager {
     connections: Arc<RwLock<HashMap<u64, ConnectionMetadata>>>,
     /// Next connection ID
     next_id: Arc<AtomicU64>,
+    /// Messages pushed to every connected client
+    outbound: broadcast::Sender<WsMessage>,
 }
 
 impl WsConnectionManager {
@@ -103,6 +136,7 @@ impl WsConnectionManager {
         Self {
             connections: Arc::new(RwLock::new(HashMap::new())),
             next_id: Arc::new(AtomicU64::new(1)),
+            outbound: broadcast::channel(OUTBOUND_CAPACITY).0,
         }
     }
 
@@ -138,6 +172,77 @@ impl WsConnectionManager {
         let conns = self.connections.read().await;
         conns.values().cloned().collect()
     }
+
+    /// Subscribe to messages pushed to all clients (one receiver per socket)
+    pub fn subscribe_outbound(&self) -> broadcast::Receiver<WsMessage> {
+        self.outbound.subscribe()
+    }
+
+    /// Push a message to every connected client
+    ///
+    /// # Returns
+    /// Number of connections the message was queued for
+    pub async fn broadcast(&self, message: WsMessage) -> usize {
+        let receivers = self.outbound.send(message).unwrap_or(0);
+        for conn in self.connections.read().await.values() {
+            conn.record_sent();
+        }
+        receivers
+    }
+
+    /// Forward machine events to every connected client
+    ///
+    /// Acknowledged lines are left out; the pendant follows job progress from
+    /// status updates rather than one message per streamed line.
+    pub fn forward_events(
+        self: &Arc<Self>,
+        mut events: broadcast::Receiver<MachineEvent>,
+    ) -> JoinHandle<()> {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if matches!(event, MachineEvent::LineAcknowledged { .. }) {
+                    continue;
+                }
+                let timestamp = chrono::Utc::now().timestamp_millis() as u64;
+                manager.broadcast(WsMessage::event(&event, timestamp)).await;
+            }
+        })
+    }
+
+    /// Forward status updates to every connected client
+    ///
+    /// The remaining time of the active job, if any, is added to each update.
+    pub fn forward_status(
+        self: &Arc<Self>,
+        mut statuses: broadcast::Receiver<GrblStatus>,
+        jobs: Arc<Mutex<JobManager>>,
+    ) -> JoinHandle<()> {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                let status = match statuses.recv().await {
+                    Ok(status) => status,
+                    // Only the latest status matters, skip what was missed
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let mut response = StatusResponse::from(&status);
+                if let Some(job) = jobs.lock().await.get_active_job() {
+                    response = response.with_job(job);
+                }
+                let timestamp = chrono::Utc::now().timestamp_millis() as u64;
+                manager
+                    .broadcast(WsMessage::status(&response, timestamp))
+                    .await;
+            }
+        })
+    }
 }
 
 impl Default for WsConnectionManager {
@@ -194,6 +299,64 @@ mod tests {
         assert_eq!(manager.active_count().await, 0);
     }
 
+    #[tokio::test]
+    async fn test_forward_machine_events() {
+        let manager = Arc::new(WsConnectionManager::new());
+        let id = manager.register("192.168.1.20".to_string(), 1000).await;
+        let mut outbound = manager.subscribe_outbound();
+        let (events, rx) = broadcast::channel(8);
+        let task = manager.forward_events(rx);
+
+        events.send(MachineEvent::DoorOpened).unwrap();
+        let message = outbound.recv().await.unwrap();
+        assert_eq!(message.msg_type, "event");
+        assert_eq!(message.data["event"], "door_opened");
+        let conn = manager.get_connection(id).await.unwrap();
+        assert_eq!(conn.messages_sent.load(Ordering::Relaxed), 1);
+
+        drop(events);
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_forward_status_with_active_job() {
+        use crate::designer::estimator::MachineLimits;
+        use crate::jobs::{Job, Priority};
+
+        let manager = Arc::new(WsConnectionManager::new());
+        let mut outbound = manager.subscribe_outbound();
+        let jobs = Arc::new(Mutex::new(JobManager::new()));
+        let (statuses, rx) = broadcast::channel(8);
+        let task = manager.forward_status(rx, Arc::clone(&jobs));
+
+        let status = GrblStatus {
+            connected: true,
+            ..GrblStatus::default()
+        };
+        statuses.send(status.clone()).unwrap();
+        let message = outbound.recv().await.unwrap();
+        assert_eq!(message.msg_type, "status");
+        assert!(message.data["remaining_time"].is_null());
+
+        let mut job = Job::new(
+            "Line".to_string(),
+            "G1 X100 F600\n".to_string(),
+            Priority::normal(),
+        );
+        let total = job
+            .estimate_time(&MachineLimits::default())
+            .unwrap()
+            .total();
+        job.start();
+        jobs.lock().await.set_active_job(job);
+        statuses.send(status).unwrap();
+        let message = outbound.recv().await.unwrap();
+        assert_eq!(message.data["remaining_time"], total);
+
+        drop(statuses);
+        task.await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_ws_connection_list() {
         let manager = WsConnectionManager::new();
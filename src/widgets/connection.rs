@@ -3,8 +3,10 @@
 //! Manages GRBL device connection through serial ports, providing port detection,
 //! connection/disconnection, and status monitoring integrated with GrblController.
 
-use crate::communication::{GrblController, SerialConnection};
-use anyhow::Result;
+use crate::communication::discovery::{self, BaudProbe, COMMON_BAUD_RATES, PROBE_TIMEOUT};
+use crate::communication::simulator::SIMULATOR_ADDRESS;
+use crate::communication::{GrblController, PortInfo, TransportKind};
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 
 /// Connection widget state synchronized with GrblController
@@ -13,6 +15,8 @@ pub struct ConnectionWidget {
     pub port: String,
     pub connected: bool,
     pub available_ports: Vec<String>,
+    /// USB details for the serial entries of `available_ports`
+    pub port_details: Vec<PortInfo>,
     pub baud_rate: u32,
     pub status_message: String,
 }
@@ -24,6 +28,7 @@ impl ConnectionWidget {
             port: String::new(),
             connected: false,
             available_ports: Vec::new(),
+            port_details: Vec::new(),
             baud_rate: 115200,
             status_message: "Disconnected".to_string(),
         }
@@ -60,25 +65,90 @@ impl ConnectionWidget {
     }
 
     /// Refresh available ports from the system
+    ///
+    /// USB ports are kept even when their name is unusual (macOS lists
+    /// Arduinos as `/dev/cu.usbmodem*`), and recognised GRBL boards are listed
+    /// first. The built-in simulator is always listed last so the app can be
+    /// used without hardware.
     pub fn refresh_ports(&mut self) -> Result<()> {
-        match SerialConnection::list_ports() {
+        match discovery::list_ports() {
             Ok(ports) => {
-                // Filter to only valid GRBL device ports
-                let filtered_ports = Self::filter_valid_ports(ports);
-                self.available_ports = filtered_ports;
+                let valid = Self::filter_valid_ports(ports.iter().map(|p| p.name.clone()).collect());
+                self.port_details = ports
+                    .into_iter()
+                    .filter(|port| port.vid.is_some() || valid.contains(&port.name))
+                    .collect();
+                self.available_ports = self.port_details.iter().map(|p| p.name.clone()).collect();
+                self.available_ports.push(SIMULATOR_ADDRESS.to_string());
                 Ok(())
             }
             Err(e) => {
                 self.available_ports.clear();
+                self.port_details.clear();
                 Err(e)
             }
         }
     }
 
+    /// Get the USB details of a listed port
+    pub fn port_info(&self, port: &str) -> Option<&PortInfo> {
+        self.port_details.iter().find(|info| info.name == port)
+    }
+
+    /// Find a GRBL controller and connect at the baud rate it answers on
+    ///
+    /// Probes the selected port, or every port on a recognised GRBL board if
+    /// none is selected. The configured baud rate is tried first.
+    pub async fn auto_connect(&mut self, controller: &GrblController) -> Result<BaudProbe> {
+        let candidates: Vec<String> =
+            if !self.port.is_empty() && TransportKind::from_address(&self.port) == TransportKind::Serial {
+                vec![self.port.clone()]
+            } else {
+                self.port_details
+                    .iter()
+                    .filter(|info| info.is_likely_grbl())
+                    .map(|info| info.name.clone())
+                    .collect()
+            };
+        if candidates.is_empty() {
+            self.status_message = "No GRBL board found".to_string();
+            return Err(anyhow!("No GRBL board found"));
+        }
+
+        let mut rates = vec![self.baud_rate];
+        rates.extend(COMMON_BAUD_RATES.iter().filter(|&&rate| rate != self.baud_rate));
+
+        for port in candidates {
+            self.status_message = format!("Probing {}...", port);
+            let probe = match discovery::detect_baud_rate(&port, &rates, PROBE_TIMEOUT).await {
+                Ok(probe) => probe,
+                Err(e) => {
+                    tracing::debug!("{:#}", e);
+                    continue;
+                }
+            };
+            self.baud_rate = probe.baud_rate;
+            self.connect(controller, port).await?;
+            self.status_message = format!(
+                "Connected to {} @ {} ({})",
+                self.port,
+                self.baud_rate,
+                probe.firmware.describe()
+            );
+            return Ok(probe);
+        }
+
+        self.status_message = "No GRBL banner found".to_string();
+        Err(anyhow!("No GRBL banner found on any port"))
+    }
+
     /// Connect to selected port using GrblController
     pub async fn connect(&mut self, controller: &GrblController, port: String) -> Result<()> {
         if port.is_empty() {
-            return Err(anyhow::anyhow!("No port selected"));
+            return Err(anyhow!("No port selected"));
+        }
+        if TransportKind::from_address(&port) == TransportKind::Serial {
+            controller.set_baud_rate(self.baud_rate).await?;
         }
 
         match controller.connect(&port).await {
@@ -99,7 +169,7 @@ impl ConnectionWidget {
     /// Disconnect from device using GrblController
     pub async fn disconnect(&mut self, controller: &GrblController) -> Result<()> {
         if self.port.is_empty() {
-            return Err(anyhow::anyhow!("No port connected"));
+            return Err(anyhow!("No port connected"));
         }
 
         match controller.disconnect().await {
@@ -1,5 +1,7 @@
 //! Overrides Widget - Real-time spindle/laser power and feed rate adjustments
 
+use crate::communication::{GrblController, RealtimeCommand};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 /// Overrides widget state
@@ -55,22 +57,24 @@ impl OverridesWidget {
         self.laser_mode = !self.laser_mode;
     }
 
-    /// Get GRBL override command for feed rate
-    pub fn get_feed_rate_command(&self) -> String {
-        match self.feed_rate_override {
-            150..=200 => "0x91".to_string(), // Feed rate + 10%
-            100..=149 => "0x90".to_string(), // Normal
-            _ => "0x8F".to_string(),         // Feed rate - 10%
-        }
+    /// Get GRBL real-time commands that apply the feed rate override
+    pub fn get_feed_rate_commands(&self) -> Vec<RealtimeCommand> {
+        RealtimeCommand::feed_override_sequence(self.feed_rate_override)
     }
 
-    /// Get GRBL override command for spindle power
-    pub fn get_spindle_power_command(&self) -> String {
-        match self.spindle_power_override {
-            76..=100 => "0x99".to_string(), // Spindle + 10%
-            51..=75 => "0x98".to_string(),  // Normal
-            _ => "0x97".to_string(),        // Spindle - 10%
-        }
+    /// Get GRBL real-time commands that apply the spindle/laser power override
+    pub fn get_spindle_power_commands(&self) -> Vec<RealtimeCommand> {
+        RealtimeCommand::spindle_override_sequence(self.spindle_power_override)
+    }
+
+    /// Send both overrides to the controller
+    pub async fn apply(&self, controller: &GrblController) -> Result<()> {
+        controller
+            .send_realtime_sequence(&self.get_feed_rate_commands())
+            .await?;
+        controller
+            .send_realtime_sequence(&self.get_spindle_power_commands())
+            .await
     }
 
     /// Get description of current state
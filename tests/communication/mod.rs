@@ -99,3 +99,1212 @@ fn test_machine_state_colors() {
     assert_eq!(MachineState::Run.color(), "#0000FF");
     assert_eq!(MachineState::Alarm.color(), "#FF0000");
 }
+
+#[test]
+fn test_response_parsing() {
+    use gcodekit2::communication::{GrblAlarm, GrblError, GrblResponse};
+    assert!(matches!(GrblResponse::parse("ok"), GrblResponse::Ok));
+    assert!(matches!(
+        GrblResponse::parse("error:20"),
+        GrblResponse::Error(GrblError::UnsupportedCommand)
+    ));
+    assert!(matches!(
+        GrblResponse::parse("ALARM:2"),
+        GrblResponse::Alarm(GrblAlarm::SoftLimit)
+    ));
+    assert!(matches!(
+        GrblResponse::parse("Grbl 1.1h ['$' for help]"),
+        GrblResponse::Version(_)
+    ));
+    assert!(matches!(GrblResponse::parse("$110=500.000"), GrblResponse::Settings(_)));
+    assert!(matches!(GrblResponse::parse("[MSG:Pgm End]"), GrblResponse::Message(_)));
+}
+
+#[tokio::test]
+async fn test_dispatch_matches_queued_line() {
+    let controller = GrblController::new();
+    assert!(controller.dispatch_line("ok").await.is_none());
+    assert_eq!(controller.lines_in_flight().await, 0);
+}
+
+#[tokio::test]
+async fn test_stream_without_connection_fails() {
+    let controller = GrblController::new();
+    let result = controller.stream_program("G21\nG0 X10 ; move\n").await;
+    assert!(result.is_err());
+    assert_eq!(controller.lines_in_flight().await, 0);
+}
+
+#[tokio::test]
+async fn test_streaming_config() {
+    use gcodekit2::communication::{StreamingConfig, StreamingMode};
+    let controller = GrblController::new();
+    assert_eq!(
+        controller.get_streaming_config().await.mode,
+        StreamingMode::CharacterCounting
+    );
+    controller
+        .set_streaming_config(StreamingConfig {
+            mode: StreamingMode::SendResponse,
+            ..Default::default()
+        })
+        .await;
+    assert_eq!(
+        controller.get_streaming_config().await.mode,
+        StreamingMode::SendResponse
+    );
+}
+
+#[tokio::test]
+async fn test_status_report_updates_status() {
+    let controller = GrblController::new();
+    let mut rx = controller.subscribe_status();
+
+    controller
+        .dispatch_line("<Run|MPos:15.000,5.000,-1.000|FS:1200,8000|WCO:10.000,0.000,0.000>")
+        .await;
+
+    let status = rx.try_recv().unwrap();
+    assert_eq!(status.state, MachineState::Run);
+    assert_eq!(status.wpos.x, 5.0);
+    assert_eq!(status.feed_rate, 1200);
+
+    // An identical report does not publish again
+    controller
+        .dispatch_line("<Run|MPos:15.000,5.000,-1.000|FS:1200,8000>")
+        .await;
+    assert!(rx.try_recv().is_err());
+
+    // Status reports are not added to the response log
+    assert!(controller.get_response_log().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_status_polling_lifecycle() {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let controller = Arc::new(GrblController::new());
+    controller.start_status_polling(Duration::from_millis(50)).await;
+    assert!(controller.is_polling_status().await);
+    controller.stop_status_polling().await;
+    assert!(!controller.is_polling_status().await);
+}
+
+#[tokio::test]
+async fn test_realtime_commands_require_connection() {
+    let controller = GrblController::new();
+    assert!(controller.feed_hold().await.is_err());
+    assert!(controller.set_feed_override(150).await.is_err());
+}
+
+#[tokio::test]
+async fn test_soft_reset_clears_queue() {
+    let controller = GrblController::new();
+    controller.queue_command("G0 X10").await;
+    // Not connected: the reset byte cannot be delivered, queue is untouched
+    assert!(controller.soft_reset().await.is_err());
+    assert_eq!(controller.command_queue.lock().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_error_and_alarm_are_decoded_in_log() {
+    use gcodekit2::communication::GrblAlarm;
+    let controller = GrblController::new();
+    controller.dispatch_line("error:9").await;
+    controller.dispatch_line("ALARM:1").await;
+
+    let log = controller.get_response_log().await;
+    assert!(log[0].starts_with("error:9 - G-code commands are locked out"));
+    assert!(log[1].starts_with("ALARM:1 - Hard limit"));
+
+    let status = controller.get_status().await.unwrap();
+    assert_eq!(status.state, MachineState::Alarm);
+    assert_eq!(controller.get_alarm().await, Some(GrblAlarm::HardLimit));
+
+    // Leaving the alarm state clears the stored alarm
+    controller.dispatch_line("<Idle|MPos:0.000,0.000,0.000|FS:0,0>").await;
+    assert_eq!(controller.get_alarm().await, None);
+}
+
+/// Spawn a minimal GRBL stand-in on a local TCP port
+///
+/// Answers every line with `ok` (or `error:20` for `G99`) and `?` with an Idle report.
+async fn spawn_tcp_grbl() -> u16 {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut line = String::new();
+        let mut buf = [0u8; 256];
+        loop {
+            let n = match socket.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            for &byte in &buf[..n] {
+                match byte {
+                    b'?' => {
+                        let report = "<Idle|MPos:1.000,2.000,3.000|FS:0,0>\r\n";
+                        socket.write_all(report.as_bytes()).await.unwrap();
+                    }
+                    b'\n' => {
+                        let reply = if line.starts_with("G99") { "error:20\r\n" } else { "ok\r\n" };
+                        socket.write_all(reply.as_bytes()).await.unwrap();
+                        line.clear();
+                    }
+                    other => line.push(other as char),
+                }
+            }
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn test_stream_program_over_tcp() {
+    use gcodekit2::communication::{GrblError, GrblResponse, TransportKind};
+
+    let port = spawn_tcp_grbl().await;
+    let controller = GrblController::new();
+    controller
+        .connect(&format!("tcp://127.0.0.1:{}", port))
+        .await
+        .unwrap();
+    assert!(controller.is_connected().await);
+    assert_eq!(controller.transport_kind().await, TransportKind::Tcp);
+
+    let acks = controller
+        .stream_program("G21\nG90 ; absolute\nG0 X10\nG99\nG1 X20 F500\n")
+        .await
+        .unwrap();
+    assert_eq!(acks.len(), 5);
+    assert_eq!(acks[2].command, "G0 X10");
+    assert!(matches!(
+        acks[3].response,
+        GrblResponse::Error(GrblError::UnsupportedCommand)
+    ));
+    assert!(acks[4].is_ok());
+    assert_eq!(controller.lines_in_flight().await, 0);
+
+    controller.disconnect().await.unwrap();
+    assert!(!controller.is_connected().await);
+}
+
+#[tokio::test]
+async fn test_status_polling_over_tcp() {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let port = spawn_tcp_grbl().await;
+    let controller = Arc::new(GrblController::new());
+    controller
+        .connect(&format!("tcp://127.0.0.1:{}", port))
+        .await
+        .unwrap();
+
+    let mut rx = controller.subscribe_status();
+    controller.start_status_polling(Duration::from_millis(20)).await;
+
+    let status = tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            let status = rx.recv().await.unwrap();
+            if status.mpos.z == 3.0 {
+                return status;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(status.state, MachineState::Idle);
+    controller.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stream_program_over_websocket() {
+    use futures_util::{SinkExt, StreamExt};
+    use gcodekit2::communication::TransportKind;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+        // FluidNC greets web clients with housekeeping text frames
+        ws.send(Message::Text("CURRENT_ID:0".to_string())).await.unwrap();
+        while let Some(Ok(message)) = ws.next().await {
+            if let Message::Binary(data) = message {
+                let lines = data.iter().filter(|b| **b == b'\n').count();
+                for i in 0..lines {
+                    // Alternate frame types the way FluidNC does
+                    let reply = if i % 2 == 0 {
+                        Message::Binary(b"ok\r\n".to_vec())
+                    } else {
+                        Message::Text("ok".to_string())
+                    };
+                    ws.send(reply).await.unwrap();
+                }
+            }
+        }
+    });
+
+    let controller = GrblController::new();
+    controller
+        .connect(&format!("ws://127.0.0.1:{}", port))
+        .await
+        .unwrap();
+    assert_eq!(controller.transport_kind().await, TransportKind::WebSocket);
+
+    let acks = controller.stream_program("G21\nG0 X5\nG0 Y5\n").await.unwrap();
+    assert_eq!(acks.len(), 3);
+    assert!(acks.iter().all(|a| a.is_ok()));
+    assert!(!controller
+        .get_response_log()
+        .await
+        .iter()
+        .any(|l| l.starts_with("CURRENT_ID")));
+
+    controller.disconnect().await.unwrap();
+}
+
+/// Connect a controller to a simulator running 100x faster than real time
+async fn connect_simulator() -> (
+    std::sync::Arc<GrblController>,
+    std::sync::Arc<gcodekit2::communication::SimulatedGrbl>,
+) {
+    connect_simulator_with(Default::default()).await
+}
+
+/// Connect a controller to a simulator with the given configuration, running
+/// 100x faster than real time
+async fn connect_simulator_with(
+    config: gcodekit2::communication::simulator::SimulatorConfig,
+) -> (
+    std::sync::Arc<GrblController>,
+    std::sync::Arc<gcodekit2::communication::SimulatedGrbl>,
+) {
+    use gcodekit2::communication::simulator::SimulatorConfig;
+    use gcodekit2::communication::SimulatedGrbl;
+    use std::sync::Arc;
+
+    let simulator = Arc::new(SimulatedGrbl::with_config(SimulatorConfig {
+        time_scale: 100.0,
+        ..config
+    }));
+    let controller = Arc::new(GrblController::with_transport(simulator.clone()));
+    controller.connect("sim://grbl").await.unwrap();
+    (controller, simulator)
+}
+
+#[tokio::test]
+async fn test_stream_program_to_simulator() {
+    use gcodekit2::communication::{simulator::PLANNER_BLOCKS, TransportKind};
+    use gcodekit2::communication::streaming::GRBL_RX_BUFFER_SIZE;
+
+    let (controller, simulator) = connect_simulator().await;
+    assert_eq!(controller.transport_kind().await, TransportKind::Simulator);
+
+    let program: String = (1..=(PLANNER_BLOCKS + 10))
+        .map(|i| format!("G1 X{} Y{} F3000\n", i, i % 3))
+        .collect();
+    let acks = controller.stream_program(&program).await.unwrap();
+    assert_eq!(acks.len(), PLANNER_BLOCKS + 10);
+    assert!(acks.iter().all(|a| a.is_ok()));
+
+    // Character counting must never overflow the device's RX buffer
+    assert!(simulator.rx_peak().await <= GRBL_RX_BUFFER_SIZE);
+
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert_eq!(simulator.state().await, MachineState::Idle);
+    assert_eq!(simulator.machine_position().await.x, 25.0);
+}
+
+#[tokio::test]
+async fn test_simulator_status_polling_tracks_motion() {
+    let (controller, _simulator) = connect_simulator().await;
+    let mut updates = controller.subscribe_status();
+    controller
+        .start_status_polling(std::time::Duration::from_millis(20))
+        .await;
+
+    // 100 mm at 6000 mm/min takes one second, 10 ms at 100x
+    controller.send_command("G1 X100 F6000").await.unwrap();
+
+    let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(2);
+    while let Ok(Ok(status)) = tokio::time::timeout_at(deadline, updates.recv()).await {
+        if status.state == MachineState::Idle && status.mpos.x == 100.0 {
+            break;
+        }
+    }
+    controller.stop_status_polling().await;
+
+    let status = controller.get_status().await.unwrap();
+    assert_eq!(status.mpos.x, 100.0);
+    assert_eq!(status.state, MachineState::Idle);
+}
+
+#[tokio::test]
+async fn test_simulator_errors_and_alarms() {
+    use gcodekit2::communication::{GrblAlarm, GrblError, GrblResponse};
+
+    let (controller, simulator) = connect_simulator().await;
+
+    let response = controller.send_command("G1 X1").await.unwrap();
+    assert!(matches!(
+        response,
+        GrblResponse::Error(GrblError::UndefinedFeedRate)
+    ));
+
+    // A hard limit locks GRBL until it is reset
+    simulator.trigger_alarm(GrblAlarm::HardLimit).await;
+    controller.poll_responses(std::time::Duration::from_millis(50)).await;
+    assert_eq!(controller.get_alarm().await, Some(GrblAlarm::HardLimit));
+    controller.soft_reset().await.unwrap();
+
+    let response = controller.send_command("G0 X1").await.unwrap();
+    assert!(matches!(response, GrblResponse::Error(GrblError::SystemGcLock)));
+
+    assert!(matches!(
+        controller.send_command("$X").await.unwrap(),
+        GrblResponse::Ok
+    ));
+    assert_eq!(simulator.state().await, MachineState::Idle);
+}
+
+#[tokio::test]
+async fn test_send_command_interleaves_with_stream() {
+    use gcodekit2::communication::{GrblError, GrblResponse};
+    use std::time::Duration;
+
+    let (controller, _simulator) = connect_simulator().await;
+    // About 3 s of cutting at 100x
+    let program: String = (1..=30)
+        .map(|i| format!("G1 X{} F600\n", (i % 2) * 50))
+        .collect();
+    let streamer = controller.clone();
+    let stream = tokio::spawn(async move { streamer.stream_program(&program).await });
+    while controller.lines_in_flight().await == 0 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    // Both answers come back to their own caller while the job still runs
+    let (ok, error) = tokio::join!(
+        controller.send_command("G54"),
+        controller.send_command("G5")
+    );
+    assert!(matches!(ok.unwrap(), GrblResponse::Ok));
+    assert!(matches!(
+        error.unwrap(),
+        GrblResponse::Error(GrblError::UnsupportedCommand)
+    ));
+    assert!(!stream.is_finished());
+
+    // The stream gets only its own acknowledgements
+    let acks = stream.await.unwrap().unwrap();
+    assert_eq!(acks.len(), 30);
+    assert!(acks.iter().all(|ack| ack.command.starts_with("G1")));
+}
+
+#[tokio::test]
+async fn test_send_command_without_response_fails() {
+    use std::time::Duration;
+
+    let (controller, _simulator) = connect_simulator().await;
+    // The dwell outlasts the deadline; no `ok` is made up for it
+    let error = controller
+        .send_command_timeout("G4 P60", Duration::from_millis(200))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("No response to G4 P60"));
+    assert_eq!(controller.lines_in_flight().await, 1);
+}
+
+#[tokio::test]
+async fn test_simulator_reports_settings_and_offsets() {
+    let (controller, _simulator) = connect_simulator().await;
+
+    controller.send_command("G10 L2 P1 X5 Y6 Z-1").await.unwrap();
+    controller.send_command("$#").await.unwrap();
+    controller.send_command("$$").await.unwrap();
+    controller.send_command("$G").await.unwrap();
+
+    let log = controller.get_response_log().await;
+    assert!(log.iter().any(|l| l == "[G54:5.000,6.000,-1.000]"));
+    assert!(log.iter().any(|l| l == "$110=500.000"));
+    assert!(log.iter().any(|l| l.starts_with("[GC:G0 G54 G17 G21 G90")));
+}
+
+#[tokio::test]
+async fn test_simulator_check_mode() {
+    use gcodekit2::communication::{GrblError, GrblResponse};
+
+    let (controller, simulator) = connect_simulator().await;
+
+    controller.send_command("$C").await.unwrap();
+    assert_eq!(simulator.state().await, MachineState::Check);
+
+    let acks = controller
+        .stream_program("G21\nG1 X10 F500\nG2 X20\nG0 Z5\n")
+        .await
+        .unwrap();
+    let errors: Vec<_> = acks.iter().filter(|a| !a.is_ok()).collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].command, "G2 X20");
+    assert!(matches!(
+        errors[0].response,
+        GrblResponse::Error(GrblError::NoOffsetsInPlane)
+    ));
+    assert_eq!(simulator.machine_position().await, Position::default());
+
+    controller.send_command("$C").await.unwrap();
+    assert_eq!(simulator.state().await, MachineState::Idle);
+}
+
+#[tokio::test]
+async fn test_read_write_and_diff_settings() {
+    use gcodekit2::communication::GrblSettings;
+
+    let (controller, simulator) = connect_simulator().await;
+
+    let original = controller.read_settings().await.unwrap();
+    assert_eq!(original.get(110), Some(500.0));
+    assert_eq!(original.len(), 34);
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("settings.json");
+    original.save(&path).unwrap();
+
+    let mut edited = original.clone();
+    edited.set(110, 1200.0).unwrap();
+    edited.set(120, 50.0).unwrap();
+    let written = controller.write_settings(&original, &edited).await.unwrap();
+    assert_eq!(written, vec!["$110=1200.000", "$120=50.000"]);
+    assert_eq!(simulator.setting(110).await, Some(1200.0));
+
+    // Invalid edits are rejected before anything is sent
+    let mut invalid = edited.clone();
+    invalid.set(22, 1.0).unwrap();
+    invalid.set(20, 1.0).unwrap();
+    invalid.set(22, 0.0).unwrap();
+    assert!(controller.write_settings(&edited, &invalid).await.is_err());
+    assert_eq!(simulator.setting(22).await, Some(0.0));
+
+    let snapshot = GrblSettings::load(&path).unwrap();
+    let diff = controller.diff_settings(&snapshot).await.unwrap();
+    let changed: Vec<u16> = diff.iter().map(|d| d.number).collect();
+    assert_eq!(changed, vec![110, 120]);
+    assert_eq!(diff[0].current, Some(1200.0));
+    assert_eq!(diff[0].target, Some(500.0));
+}
+
+#[tokio::test]
+async fn test_parser_state_and_offsets() {
+    use gcodekit2::communication::parser_state::{DistanceMode, Units};
+    use gcodekit2::communication::{Axis, CoordinateSystem};
+
+    let (controller, _simulator) = connect_simulator().await;
+
+    let state = controller.refresh_parser_state().await.unwrap();
+    assert_eq!(state.coordinate_system, CoordinateSystem::G54);
+    assert_eq!(state.units, Units::Millimeters);
+
+    // Acknowledged lines keep the cached state current
+    controller.send_command("G20 G91 G55").await.unwrap();
+    let state = controller.get_parser_state().await;
+    assert_eq!(state.units, Units::Inches);
+    assert_eq!(state.distance, DistanceMode::Incremental);
+    assert_eq!(state.coordinate_system, CoordinateSystem::G55);
+    assert_eq!(controller.refresh_parser_state().await.unwrap(), state);
+    controller.send_command("G21 G90 G54").await.unwrap();
+
+    controller.send_command("G0 X12 Y-3 Z4").await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    let offsets = controller
+        .set_work_zero(CoordinateSystem::G56, &[Axis::X, Axis::Y])
+        .await
+        .unwrap();
+    assert_eq!(offsets.get(CoordinateSystem::G56), Position { x: 12.0, y: -3.0, z: 0.0 });
+    assert_eq!(offsets.get(CoordinateSystem::G54), Position::default());
+    assert!(!controller.offsets_stale().await);
+
+    let offsets = controller
+        .set_work_position(CoordinateSystem::G54, &[(Axis::Z, 1.5)])
+        .await
+        .unwrap();
+    assert_eq!(offsets.get(CoordinateSystem::G54).z, 2.5);
+
+    controller.send_command("G92 X0").await.unwrap();
+    assert!(controller.offsets_stale().await);
+    let offsets = controller.refresh_offsets().await.unwrap();
+    assert_eq!(offsets.g92.x, 12.0);
+}
+
+#[tokio::test]
+async fn test_detect_simulator_firmware() {
+    use gcodekit2::communication::FirmwareDialect;
+
+    let (controller, _simulator) = connect_simulator().await;
+    let info = controller.detect_version().await.unwrap();
+    assert_eq!(info.dialect, FirmwareDialect::Grbl);
+    assert_eq!(info.version, "1.1h");
+    assert_eq!(info.build.as_deref(), Some("20190830"));
+    assert_eq!(controller.get_status().await.unwrap().version, "GRBL 1.1h");
+}
+
+#[tokio::test]
+async fn test_marlin_dialect_adapts_protocol() {
+    use gcodekit2::communication::{FirmwareDialect, RealtimeCommand};
+
+    let controller = GrblController::new();
+    controller
+        .dispatch_line("FIRMWARE_NAME:Marlin 2.1.2 (Jun  1 2023) SOURCE_CODE_URL:github.com/MarlinFirmware/Marlin")
+        .await;
+    assert_eq!(controller.get_firmware().await.dialect, FirmwareDialect::Marlin);
+
+    // M114/M154 position reports update the status
+    controller
+        .dispatch_line("X:10.00 Y:2.50 Z:-1.00 E:0.00 Count X:800 Y:200 Z:-400")
+        .await;
+    let status = controller.get_status().await.unwrap();
+    assert_eq!(status.wpos, Position { x: 10.0, y: 2.5, z: -1.0 });
+    assert_eq!(status.state, MachineState::Idle);
+
+    // Marlin has no feed hold byte
+    let error = controller
+        .send_realtime(RealtimeCommand::FeedHold)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("not supported by Marlin"));
+}
+
+#[tokio::test]
+async fn test_smoothieware_writes_one_acceleration() {
+    use gcodekit2::communication::{FirmwareDialect, FirmwareInfo, GrblSettings};
+
+    let port = spawn_tcp_grbl().await;
+    let controller = GrblController::new();
+    controller
+        .connect(&format!("tcp://127.0.0.1:{}", port))
+        .await
+        .unwrap();
+    controller
+        .set_firmware(FirmwareInfo {
+            dialect: FirmwareDialect::Smoothieware,
+            ..Default::default()
+        })
+        .await;
+
+    let original: GrblSettings = [(120, 10.0), (121, 10.0), (122, 10.0)].into_iter().collect();
+    let mut edited = original.clone();
+    edited.set(120, 500.0).unwrap();
+    let error = controller.write_settings(&original, &edited).await.unwrap_err();
+    assert!(error.to_string().contains("must be equal"));
+
+    edited.set(121, 500.0).unwrap();
+    edited.set(122, 500.0).unwrap();
+    let commands = controller.write_settings(&original, &edited).await.unwrap();
+    assert_eq!(commands.iter().filter(|c| c.starts_with("M204")).count(), 1);
+}
+
+#[tokio::test]
+async fn test_marlin_emergency_lines_are_counted() {
+    use gcodekit2::communication::{FirmwareDialect, FirmwareInfo, RealtimeCommand};
+    use gcodekit2::communication::{TcpConnection, Transport};
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    // A device that never replies, so acknowledgements are fed by hand
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (_socket, _) = listener.accept().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    });
+    let transport = Arc::new(TcpConnection::new());
+    transport
+        .connect(&format!("tcp://127.0.0.1:{}", port))
+        .await
+        .unwrap();
+
+    let controller = GrblController::with_transport(transport);
+    controller
+        .set_firmware(FirmwareInfo {
+            dialect: FirmwareDialect::Marlin,
+            ..Default::default()
+        })
+        .await;
+
+    controller
+        .send_realtime(RealtimeCommand::CycleStart)
+        .await
+        .unwrap();
+    assert_eq!(controller.lines_in_flight().await, 1);
+    let ack = controller.dispatch_line("ok").await.unwrap();
+    assert_eq!(ack.command, "M108");
+
+    // Queued lines are dropped by the quick stop, but its own ok is expected
+    controller.queue_command("G1 X10 F100").await;
+    controller.soft_reset().await.unwrap();
+    assert_eq!(controller.lines_in_flight().await, 1);
+    let ack = controller.dispatch_line("ok").await.unwrap();
+    assert_eq!(ack.command, "M410");
+    assert_eq!(controller.lines_in_flight().await, 0);
+
+    // The feed override goes out at once, ahead of the queued job
+    controller.queue_command("G1 X20 F100").await;
+    controller.set_feed_override(150).await.unwrap();
+    assert_eq!(controller.lines_in_flight().await, 1);
+    let ack = controller.dispatch_line("ok").await.unwrap();
+    assert_eq!(ack.command, "M220 S150");
+    assert_eq!(controller.command_queue.lock().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_supervisor_reconnects_and_restores_state() {
+    use gcodekit2::communication::parser_state::DistanceMode;
+    use gcodekit2::communication::{Axis, ConnectionEvent, CoordinateSystem, RecoveryConfig};
+    use gcodekit2::jobs::{Job, Priority, ResumeOptions};
+    use std::time::Duration;
+
+    let (controller, simulator) = connect_simulator().await;
+    controller
+        .set_recovery_config(RecoveryConfig {
+            reconnect_delay_ms: 20,
+            ..Default::default()
+        })
+        .await;
+    controller.start_supervisor(Duration::from_millis(10)).await;
+    assert!(controller.is_supervising().await);
+
+    controller.send_command("G0 X10 Y5").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    controller
+        .set_work_zero(CoordinateSystem::G55, &[Axis::X, Axis::Y])
+        .await
+        .unwrap();
+    controller.send_command("G55 G92 Z2").await.unwrap();
+    controller.send_command("G91").await.unwrap();
+    let before = controller.refresh_offsets().await.unwrap();
+
+    let mut events = controller.subscribe_connection();
+    simulator.drop_link().await;
+
+    let mut seen = Vec::new();
+    let restored = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let event = events.recv().await.unwrap();
+            seen.push(event.clone());
+            match event {
+                ConnectionEvent::Restored { commands } => return commands,
+                ConnectionEvent::RestoreFailed { reason } => panic!("{}", reason),
+                _ => {}
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert!(matches!(seen[0], ConnectionEvent::Lost { .. }));
+    assert!(seen
+        .iter()
+        .any(|e| matches!(e, ConnectionEvent::Reconnected { reset: true, .. })));
+    assert_eq!(restored.last().unwrap(), "G21 G91 G17 G55 G94");
+
+    // The simulator reset, so G92 and the modal state had to be replayed
+    let after = controller.refresh_offsets().await.unwrap();
+    assert_eq!(after.g92, before.g92);
+    assert_eq!(after.get(CoordinateSystem::G55), before.get(CoordinateSystem::G55));
+    let state = controller.refresh_parser_state().await.unwrap();
+    assert_eq!(state.distance, DistanceMode::Incremental);
+    assert_eq!(state.coordinate_system, CoordinateSystem::G55);
+
+    // Resuming needs a known position
+    let mut job = Job::new(
+        "Pocket".to_string(),
+        "G90 G21\nM3 S1000\nG1 Z-1 F200\nG1 X5\nG1 Y5\nM5".to_string(),
+        Priority::normal(),
+    );
+    job.update_progress(3);
+    let options = ResumeOptions {
+        spindle_delay: 0.0,
+        ..Default::default()
+    };
+    assert!(controller.position_lost().await);
+    assert!(controller.resume_job(&job, &options).await.is_err());
+
+    controller.confirm_position().await;
+    let acks = controller.resume_job(&job, &options).await.unwrap();
+    assert!(acks.iter().all(|ack| ack.is_ok()));
+    assert_eq!(acks.last().unwrap().command, "M5");
+    // Only the re-sent program lines belong to the job
+    let job_lines: Vec<usize> = acks.iter().filter_map(|ack| ack.job_line).collect();
+    assert_eq!(job_lines, vec![3, 4, 5]);
+    assert!(acks[0].job_line.is_none());
+
+    controller.stop_supervisor().await;
+    assert!(!controller.is_supervising().await);
+}
+
+#[tokio::test]
+async fn test_stream_job_advances_job_manager() {
+    use gcodekit2::jobs::{Job, JobManager, Priority};
+
+    let (controller, _simulator) = connect_simulator().await;
+    let mut events = controller.subscribe_events();
+
+    let mut job = Job::new(
+        "Square".to_string(),
+        "G21 G90\n; outline\nG1 X5 F1000\n(corner)\nG1 Y5\nG1 X0".to_string(),
+        Priority::normal(),
+    );
+    job.start();
+    let mut manager = JobManager::new();
+    manager.set_active_job(job.clone());
+
+    // A query sent before the job is not part of it
+    controller.send_command("$G").await.unwrap();
+    let acks = controller.stream_job(&job).await.unwrap();
+    let job_lines: Vec<Option<usize>> = acks.iter().map(|ack| ack.job_line).collect();
+    // The comment-only line is counted by the job but never sent
+    assert_eq!(job_lines, vec![Some(0), Some(1), Some(3), Some(4)]);
+
+    while let Ok(event) = events.try_recv() {
+        manager.handle_event(&event);
+    }
+    let active = manager.get_active_job().unwrap();
+    assert_eq!(active.current_line, active.total_lines);
+}
+
+#[tokio::test]
+async fn test_homing_reports_progress() {
+    use gcodekit2::communication::probing::HomingPhase;
+    use gcodekit2::communication::simulator::SimulatorConfig;
+    use std::time::Duration;
+
+    let (controller, simulator) = connect_simulator_with(SimulatorConfig {
+        homing_enabled: true,
+        ..Default::default()
+    })
+    .await;
+
+    let mut updates = Vec::new();
+    let progress = controller
+        .home(Duration::from_secs(5), |progress| updates.push(progress.clone()))
+        .await
+        .unwrap();
+    assert!(progress.is_complete());
+    assert_eq!(updates.last().unwrap().phases, [HomingPhase::Homed; 3]);
+    assert_eq!(simulator.state().await, MachineState::Idle);
+    assert_eq!(simulator.machine_position().await.z, -1.0);
+}
+
+#[tokio::test]
+async fn test_touch_plate_sets_work_z() {
+    use gcodekit2::communication::parser_state::DistanceMode;
+    use gcodekit2::communication::probing::TouchPlateOptions;
+    use gcodekit2::communication::simulator::{ProbeZone, SimulatorConfig};
+    use gcodekit2::communication::CoordinateSystem;
+
+    // A plate whose top is at machine Z-20
+    let (controller, simulator) = connect_simulator_with(SimulatorConfig {
+        probe_zones: vec![ProbeZone {
+            min: Position { x: -50.0, y: -50.0, z: -100.0 },
+            max: Position { x: 50.0, y: 50.0, z: -20.0 },
+        }],
+        ..Default::default()
+    })
+    .await;
+
+    let contact = controller
+        .probe_touch_plate(&TouchPlateOptions {
+            plate_thickness: 10.0,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(contact.success);
+    assert_eq!(contact.position.z, -20.0);
+
+    // The plate top reads 10, so the stock top below it is Z0
+    let offsets = controller.get_offsets().await;
+    assert_eq!(offsets.get(CoordinateSystem::G54).z, -30.0);
+    assert_eq!(simulator.machine_position().await.z, -18.0);
+    assert_eq!(controller.get_parser_state().await.distance, DistanceMode::Absolute);
+}
+
+#[tokio::test]
+async fn test_probe_without_contact_fails() {
+    use gcodekit2::communication::probing::TouchPlateOptions;
+
+    let (controller, _simulator) = connect_simulator().await;
+    let result = controller
+        .probe_touch_plate(&TouchPlateOptions {
+            max_travel: 5.0,
+            ..Default::default()
+        })
+        .await;
+    assert!(result.unwrap_err().to_string().contains("no contact"));
+    assert_eq!(controller.get_status().await.unwrap().state, MachineState::Alarm);
+}
+
+#[tokio::test]
+async fn test_probe_outlasts_response_timeout() {
+    use gcodekit2::communication::probing::TouchPlateOptions;
+    use gcodekit2::communication::simulator::{ProbeZone, SimulatorConfig};
+    use gcodekit2::communication::StreamingConfig;
+    use std::time::Duration;
+
+    let (controller, _simulator) = connect_simulator_with(SimulatorConfig {
+        probe_zones: vec![ProbeZone {
+            min: Position { x: -50.0, y: -50.0, z: -100.0 },
+            max: Position { x: 50.0, y: 50.0, z: -20.0 },
+        }],
+        ..Default::default()
+    })
+    .await;
+    controller
+        .set_streaming_config(StreamingConfig {
+            response_timeout_ms: 100,
+            ..Default::default()
+        })
+        .await;
+
+    // 20 mm at 50 mm/min takes 240 ms at 100x; the probe's own deadline applies
+    let contact = controller
+        .probe_touch_plate(&TouchPlateOptions {
+            seek_feed: 50.0,
+            latch_feed: None,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(contact.position.z, -20.0);
+}
+
+#[tokio::test]
+async fn test_probe_deadline_stops_motion() {
+    use gcodekit2::communication::probing::TouchPlateOptions;
+    use std::time::Duration;
+
+    let (controller, simulator) = connect_simulator().await;
+    let error = controller
+        .probe_touch_plate(&TouchPlateOptions {
+            max_travel: 100.0,
+            seek_feed: 10.0,
+            timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("motion stopped"));
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let stopped = simulator.machine_position().await.z;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(simulator.machine_position().await.z, stopped);
+    assert_ne!(simulator.state().await, MachineState::Run);
+}
+
+#[tokio::test]
+async fn test_find_corner_sets_xy_zero() {
+    use gcodekit2::communication::probing::{Corner, EdgeProbeOptions};
+    use gcodekit2::communication::simulator::{ProbeZone, SimulatorConfig};
+    use gcodekit2::communication::CoordinateSystem;
+
+    // Stock with its front-left corner at machine X-80 Y-80 and top at Z-20
+    let (controller, simulator) = connect_simulator_with(SimulatorConfig {
+        probe_zones: vec![ProbeZone {
+            min: Position { x: -80.0, y: -80.0, z: -100.0 },
+            max: Position { x: -40.0, y: -40.0, z: -20.0 },
+        }],
+        ..Default::default()
+    })
+    .await;
+    controller.send_command("G0 X-75 Y-75 Z-18").await.unwrap();
+
+    // The simulator touches at the tool centre, so use a point probe
+    let corner = controller
+        .find_corner(
+            Corner::FrontLeft,
+            &EdgeProbeOptions {
+                tool_diameter: 0.0,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!((corner.x, corner.y), (-80.0, -80.0));
+
+    let g54 = controller.get_offsets().await.get(CoordinateSystem::G54);
+    assert_eq!((g54.x, g54.y), (-80.0, -80.0));
+    let position = simulator.machine_position().await;
+    assert_eq!((position.x, position.y, position.z), (-75.0, -75.0, -18.0));
+}
+
+#[tokio::test]
+async fn test_measure_tool_length_applies_offset() {
+    use gcodekit2::communication::probing::ToolSetterOptions;
+    use gcodekit2::communication::simulator::{ProbeZone, SimulatorConfig};
+
+    // Tool setter at machine X-10 Y-10 triggering at Z-40
+    let (controller, _simulator) = connect_simulator_with(SimulatorConfig {
+        probe_zones: vec![ProbeZone {
+            min: Position { x: -12.0, y: -12.0, z: -100.0 },
+            max: Position { x: -8.0, y: -8.0, z: -40.0 },
+        }],
+        ..Default::default()
+    })
+    .await;
+    let options = ToolSetterOptions {
+        position: Position { x: -10.0, y: -10.0, z: 0.0 },
+        ..Default::default()
+    };
+
+    let reference = controller.measure_tool_length(&options, None).await.unwrap();
+    assert_eq!(reference.probe.position.z, -40.0);
+    assert_eq!(reference.offset, 0.0);
+
+    // A tool that triggers 5 mm higher than the reference is 5 mm longer
+    let measured = controller
+        .measure_tool_length(&options, Some(-45.0))
+        .await
+        .unwrap();
+    assert_eq!(measured.offset, 5.0);
+    assert_eq!(controller.get_offsets().await.tool_length, 5.0);
+}
+
+#[tokio::test]
+async fn test_check_program_reports_controller_errors() {
+    let (controller, simulator) = connect_simulator().await;
+    let program = "G21 G90\n; comment\nG1 X10 F500\nG2 X1\nG1 Y5\nG99\nM2\n";
+
+    let report = controller.check_program(program).await.unwrap();
+    assert_eq!(report.lines_sent, 6);
+    assert_eq!(report.lines_checked, 6);
+    assert!(report.alarm.is_none());
+    assert!(!report.passed());
+
+    let errors: Vec<(usize, &str)> = report
+        .controller_issues()
+        .map(|issue| (issue.line_number, issue.issue_type.as_str()))
+        .collect();
+    assert_eq!(errors, vec![(4, "Controller error:35"), (6, "Controller error:20")]);
+
+    // Nothing moved and the controller is out of check mode
+    assert_eq!(simulator.machine_position().await.x, 0.0);
+    assert!(matches!(
+        controller.send_command("G0 X1").await.unwrap(),
+        gcodekit2::communication::GrblResponse::Ok
+    ));
+}
+
+#[tokio::test]
+async fn test_check_program_ignores_other_queued_lines() {
+    let (controller, _simulator) = connect_simulator().await;
+    // Queued by someone else ahead of the check; its error is not the program's
+    controller.queue_command("G5").await;
+
+    let report = controller.check_program("G21
+G1 X1 F100
+G99
+").await.unwrap();
+    assert_eq!(report.lines_checked, 3);
+    let errors: Vec<usize> = report
+        .controller_issues()
+        .map(|issue| issue.line_number)
+        .collect();
+    assert_eq!(errors, vec![3]);
+}
+
+#[tokio::test]
+async fn test_check_program_stops_on_alarm() {
+    use gcodekit2::designer::Severity;
+
+    let (controller, _simulator) = connect_simulator().await;
+    controller.send_command("$22=1").await.unwrap();
+    controller.send_command("$20=1").await.unwrap();
+
+    let report = controller.check_program("G1 X-5 F500\nG0 X10\nG0 X-1\n").await.unwrap();
+    assert!(report.alarm.is_some());
+    assert_eq!(report.lines_checked, 1);
+    let alarm = report.controller_issues().last().unwrap();
+    assert_eq!(alarm.line_number, 2);
+    assert_eq!(alarm.severity, Severity::Critical);
+}
+
+#[tokio::test]
+async fn test_trace_records_and_replays_session() {
+    use gcodekit2::communication::trace::{load_jsonl, TraceDirection};
+    use gcodekit2::communication::{GrblResponse, ReplayTransport};
+    use std::sync::Arc;
+
+    let (controller, _simulator) = connect_simulator().await;
+    controller.clear_trace().await;
+    controller.send_command("G21").await.unwrap();
+    controller
+        .send_realtime(gcodekit2::communication::RealtimeCommand::StatusQuery)
+        .await
+        .unwrap();
+    controller.send_command("G99").await.unwrap();
+
+    let trace = controller.get_trace().await;
+    let directions: Vec<TraceDirection> = trace.iter().map(|r| r.direction).collect();
+    assert!(directions.contains(&TraceDirection::Realtime));
+    assert!(trace
+        .iter()
+        .any(|r| r.direction == TraceDirection::Tx && r.data == "G99"));
+    assert!(trace
+        .iter()
+        .any(|r| r.direction == TraceDirection::Rx && r.data == "error:20"));
+
+    let file = tempfile::NamedTempFile::new().unwrap();
+    controller.export_trace(file.path()).await.unwrap();
+    let records = load_jsonl(file.path()).unwrap();
+    assert_eq!(records, trace);
+
+    // Replaying the trace gives the same answers without a machine
+    let replay = Arc::new(ReplayTransport::from_records(records));
+    let offline = GrblController::with_transport(replay.clone());
+    offline.connect("replay://").await.unwrap();
+    assert!(matches!(offline.send_command("G21").await.unwrap(), GrblResponse::Ok));
+    assert!(matches!(
+        offline.send_command("G99").await.unwrap(),
+        GrblResponse::Error(_)
+    ));
+    assert!(replay.divergences().await.is_empty());
+    assert!(replay.is_finished().await);
+}
+
+#[tokio::test]
+async fn test_machine_events_from_simulator() {
+    use gcodekit2::communication::simulator::{ProbeZone, SimulatorConfig};
+    use gcodekit2::communication::MachineEvent;
+    use std::time::Duration;
+
+    let (controller, _simulator) = connect_simulator_with(SimulatorConfig {
+        probe_zones: vec![ProbeZone {
+            min: Position { x: -50.0, y: -50.0, z: -100.0 },
+            max: Position { x: 50.0, y: 50.0, z: -5.0 },
+        }],
+        ..Default::default()
+    })
+    .await;
+    let mut events = controller.subscribe_events();
+    controller.start_status_polling(Duration::from_millis(20)).await;
+
+    controller.send_command("G1 X20 F6000").await.unwrap();
+    controller.send_command("G38.2 Z-20 F3000").await.unwrap();
+    controller.send_command("G0 Z0").await.unwrap();
+    controller.send_command("G4 P0").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    controller.stop_status_polling().await;
+
+    let mut received = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        received.push(event);
+    }
+    let acks = received
+        .iter()
+        .filter(|e| matches!(e, MachineEvent::LineAcknowledged { .. }))
+        .count();
+    assert_eq!(acks, 4);
+    assert!(received.iter().any(|e| matches!(
+        e,
+        MachineEvent::StateChanged { to: MachineState::Run, .. }
+    )));
+    assert!(received.iter().any(|e| matches!(
+        e,
+        MachineEvent::ProbeTriggered { probe } if probe.position.z == -5.0
+    )));
+    // The final report may arrive before or after the dwell's `ok`
+    assert!(matches!(
+        received
+            .iter()
+            .rev()
+            .find(|e| matches!(e, MachineEvent::StateChanged { .. })),
+        Some(MachineEvent::StateChanged { to: MachineState::Idle, .. })
+    ));
+}
+
+/// Transport that hands out fixed chunks, holding early chunks back longer
+/// than later ones so unsynchronised readers would append them out of order
+struct ChunkedTransport {
+    chunks: tokio::sync::Mutex<std::collections::VecDeque<(usize, String)>>,
+}
+
+#[async_trait::async_trait]
+impl gcodekit2::communication::Transport for ChunkedTransport {
+    fn kind(&self) -> gcodekit2::communication::TransportKind {
+        gcodekit2::communication::TransportKind::Replay
+    }
+
+    async fn connect(&self, _address: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn send_bytes(&self, data: &[u8]) -> anyhow::Result<usize> {
+        Ok(data.len())
+    }
+
+    async fn read_response_timeout(
+        &self,
+        _max_size: usize,
+        _timeout: std::time::Duration,
+    ) -> anyhow::Result<String> {
+        let next = self.chunks.lock().await.pop_front();
+        let (index, chunk) = next.ok_or_else(|| anyhow::anyhow!("no data"))?;
+        let delay = 5u64.saturating_sub(index as u64 % 6);
+        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+        Ok(chunk)
+    }
+
+    async fn is_connected(&self) -> bool {
+        true
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_readers_keep_lines_intact() {
+    use std::sync::Arc;
+
+    let expected: Vec<String> = (0..24).map(|n| format!("[MSG:line {}]", n)).collect();
+    let stream: String = expected.iter().map(|line| format!("{}\r\n", line)).collect();
+    let chunks = stream
+        .as_bytes()
+        .chunks(7)
+        .map(|chunk| String::from_utf8_lossy(chunk).to_string())
+        .enumerate()
+        .collect();
+    let transport = Arc::new(ChunkedTransport {
+        chunks: tokio::sync::Mutex::new(chunks),
+    });
+    let controller = Arc::new(GrblController::with_transport(transport.clone()));
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let controller = Arc::clone(&controller);
+            tokio::spawn(async move {
+                for _ in 0..40 {
+                    controller
+                        .poll_responses(std::time::Duration::from_millis(10))
+                        .await;
+                }
+            })
+        })
+        .collect();
+    for reader in readers {
+        reader.await.unwrap();
+    }
+
+    assert!(transport.chunks.lock().await.is_empty());
+    assert_eq!(controller.get_response_log().await, expected);
+}
@@ -2,6 +2,8 @@
 
 use gcodekit2::designer::{BackPlotStep, BackPlotter, BackPlotState, MoveType};
 
+use super::assert_close;
+
 /// Create test steps for integration testing
 fn create_complex_program() -> Vec<BackPlotStep> {
     vec![
@@ -14,6 +16,7 @@ fn create_complex_program() -> Vec<BackPlotStep> {
             feed_rate: 0.0,
             spindle_speed: 0.0,
             move_type: MoveType::Rapid,
+            arc: None,
         },
         // Rapid move to start position
         BackPlotStep {
@@ -24,6 +27,7 @@ fn create_complex_program() -> Vec<BackPlotStep> {
             feed_rate: 0.0,
             spindle_speed: 0.0,
             move_type: MoveType::Rapid,
+            arc: None,
         },
         // Linear feed move
         BackPlotStep {
@@ -34,6 +38,7 @@ fn create_complex_program() -> Vec<BackPlotStep> {
             feed_rate: 1000.0,
             spindle_speed: 5000.0,
             move_type: MoveType::Linear,
+            arc: None,
         },
         // Clockwise arc
         BackPlotStep {
@@ -44,6 +49,7 @@ fn create_complex_program() -> Vec<BackPlotStep> {
             feed_rate: 1000.0,
             spindle_speed: 5000.0,
             move_type: MoveType::ArcCW,
+            arc: None,
         },
         // Counter-clockwise arc
         BackPlotStep {
@@ -54,6 +60,7 @@ fn create_complex_program() -> Vec<BackPlotStep> {
             feed_rate: 1000.0,
             spindle_speed: 5000.0,
             move_type: MoveType::ArcCCW,
+            arc: None,
         },
         // Linear move back to start
         BackPlotStep {
@@ -64,6 +71,7 @@ fn create_complex_program() -> Vec<BackPlotStep> {
             feed_rate: 1000.0,
             spindle_speed: 5000.0,
             move_type: MoveType::Linear,
+            arc: None,
         },
         // Rapid to safe height
         BackPlotStep {
@@ -74,6 +82,7 @@ fn create_complex_program() -> Vec<BackPlotStep> {
             feed_rate: 0.0,
             spindle_speed: 0.0,
             move_type: MoveType::Rapid,
+            arc: None,
         },
     ]
 }
@@ -337,3 +346,175 @@ fn test_gcode_command_preservation() {
     let step3 = bp.get_current_step_ref().unwrap();
     assert_eq!(step3.gcode_command, "G1 X20 F1000 S5000");
 }
+
+#[test]
+fn test_arc_steps_keep_geometry() {
+    use gcodekit2::communication::parser_state::Plane;
+    use std::f32::consts::PI;
+
+    let bp = BackPlotter::from_gcode("G0 X10\nG3 X-10 Y0 R10 F500\nG2 X10 Y0 I10 J0").unwrap();
+    let ccw = bp.get_step(1).unwrap();
+    let arc = ccw.arc.unwrap();
+    assert_eq!(arc.plane, Plane::XY);
+    assert_close(arc.radius, 10.0, 1e-3);
+    assert_close(arc.sweep, PI, 1e-3);
+    assert_eq!(arc.center, [0.0, 0.0, 0.0]);
+    assert_close(ccw.length(), 10.0 * PI, 1e-3);
+
+    // Clockwise from (-10, 0) back over the top to (10, 0)
+    let cw = bp.get_step(2).unwrap();
+    assert_close(cw.arc.unwrap().sweep, -PI, 1e-3);
+    let top = cw.point_at(0.5);
+    assert_close(top[0], 0.0, 1e-3);
+    assert_close(top[1], 10.0, 1e-3);
+    assert!(bp.get_step(0).unwrap().arc.is_none());
+}
+
+#[test]
+fn test_sample_arc_within_tolerance() {
+    let bp = BackPlotter::from_gcode("G0 X10\nG3 X10 Y0 I-10 J0 F500").unwrap();
+    let circle = bp.get_step(1).unwrap();
+
+    let coarse = circle.sample(0.5);
+    let fine = circle.sample(0.01);
+    assert!(fine.len() > coarse.len());
+    assert_eq!(*fine.first().unwrap(), circle.start_pos);
+    assert_eq!(*fine.last().unwrap(), circle.end_pos);
+    for pair in fine.windows(2) {
+        let mid = [(pair[0][0] + pair[1][0]) / 2.0, (pair[0][1] + pair[1][1]) / 2.0];
+        let sagitta = 10.0 - mid[0].hypot(mid[1]);
+        assert!(sagitta <= 0.01 + 1e-4, "chord {} off the arc", sagitta);
+    }
+    // Straight moves need no extra points
+    assert_eq!(bp.get_step(0).unwrap().sample(0.01).len(), 2);
+}
+
+#[test]
+fn test_helix_length_and_bounds() {
+    use std::f32::consts::PI;
+
+    let bp = BackPlotter::from_gcode("G0 X5\nG17 G2 X-5 Y0 Z-3 I-5 J0 F300").unwrap();
+    let helix = bp.get_step(1).unwrap();
+    assert_close(helix.length(), (5.0 * PI).hypot(3.0), 1e-3);
+    let mid = helix.point_at(0.5);
+    assert_close(mid[1], -5.0, 1e-3);
+    assert_close(mid[2], -1.5, 1e-3);
+
+    let (min, max) = helix.bounds();
+    assert_close(min[1], -5.0, 1e-3);
+    assert_close(max[1], 0.0, 1e-3);
+    assert_close(min[2], -3.0, 1e-3);
+    assert_close(max[2], 0.0, 1e-3);
+}
+
+#[test]
+fn test_program_length_and_bounds() {
+    let bp = BackPlotter::from_gcode("G0 X10\nG3 X-10 Y0 R10 F500\nG1 Y-4").unwrap();
+    assert_close(bp.total_length(), 10.0 + 10.0 * std::f32::consts::PI + 4.0, 1e-3);
+
+    let (min, max) = bp.bounds();
+    assert_close(min[0], -10.0, 1e-3);
+    assert_close(min[1], -4.0, 1e-3);
+    assert_close(max[0], 10.0, 1e-3);
+    // The arc's top, not either end point, sets the height
+    assert_close(max[1], 10.0, 1e-3);
+}
+
+#[test]
+fn test_playback_clock_interpolates_position() {
+    use gcodekit2::designer::backplot::PlaybackEvent;
+    use std::time::Duration;
+
+    // 1 s cutting, 2 s dwell, 0.12 s rapid at the default 5000 mm/min
+    let mut bp = BackPlotter::from_gcode("G1 X10 F600\nG4 P2\nG0 X0").unwrap();
+    assert!((bp.get_duration() - 3.12).abs() < 1e-6);
+
+    // Nothing moves until playback starts
+    assert!(bp.advance(Duration::from_millis(500)).is_empty());
+    bp.play();
+    let events = bp.advance(Duration::from_millis(500));
+    assert_eq!(
+        events,
+        vec![PlaybackEvent::LineStarted {
+            step: 0,
+            line_number: 1
+        }]
+    );
+    assert_close(bp.get_position()[0], 5.0, 1e-3);
+    assert_eq!(bp.get_current_step(), 0);
+
+    bp.set_speed(2.0).unwrap();
+    let events = bp.advance(Duration::from_millis(500));
+    assert_eq!(
+        events,
+        vec![PlaybackEvent::LineStarted {
+            step: 1,
+            line_number: 2
+        }]
+    );
+    assert!((bp.get_elapsed() - 1.5).abs() < 1e-6);
+    assert_close(bp.get_position()[0], 10.0, 1e-3);
+
+    let events = bp.advance(Duration::from_secs(5));
+    assert_eq!(
+        events,
+        vec![
+            PlaybackEvent::LineStarted {
+                step: 2,
+                line_number: 3
+            },
+            PlaybackEvent::Finished
+        ]
+    );
+    assert_eq!(bp.get_state(), BackPlotState::Completed);
+    assert_eq!(bp.get_position(), [0.0, 0.0, 0.0]);
+    assert!(bp.advance(Duration::from_secs(1)).is_empty());
+}
+
+#[test]
+fn test_playback_speed_limits() {
+    let mut bp = BackPlotter::from_gcode("G1 X10 F600").unwrap();
+    assert_eq!(bp.get_speed(), 1.0);
+    assert!(bp.set_speed(0.1).is_ok());
+    assert!(bp.set_speed(5.0).is_ok());
+    assert!(bp.set_speed(0.05).is_err());
+    assert!(bp.set_speed(6.0).is_err());
+    assert_eq!(bp.get_speed(), 5.0);
+}
+
+#[test]
+fn test_seek_by_time() {
+    use gcodekit2::designer::backplot::PlaybackEvent;
+
+    let mut bp = BackPlotter::from_gcode("G0 X10\nG3 X-10 Y0 I-10 J0 F600\nG1 Y-5").unwrap();
+    bp.set_rapid_rate(600.0).unwrap();
+    let arc_time = 10.0 * std::f64::consts::PI / 10.0;
+    assert!((bp.get_duration() - (1.0 + arc_time + 0.5)).abs() < 1e-4);
+
+    // Halfway round the arc is its top
+    let events = bp.seek(1.0 + arc_time / 2.0);
+    assert_eq!(
+        events,
+        vec![PlaybackEvent::LineStarted {
+            step: 1,
+            line_number: 2
+        }]
+    );
+    assert_eq!(bp.get_state(), BackPlotState::Paused);
+    assert_close(bp.get_position()[0], 0.0, 1e-3);
+    assert_close(bp.get_position()[1], 10.0, 1e-3);
+    // Seeking within the same step raises no new event
+    assert!(bp.seek(1.5).is_empty());
+
+    bp.seek(0.5);
+    assert_close(bp.get_position()[0], 5.0, 1e-3);
+    let events = bp.seek(100.0);
+    assert_eq!(events.last(), Some(&PlaybackEvent::Finished));
+    assert_eq!(bp.get_current_step(), 3);
+
+    // Stepping keeps the clock on step boundaries
+    bp.reset();
+    assert_eq!(bp.get_elapsed(), 0.0);
+    bp.step_forward();
+    assert!((bp.get_elapsed() - 1.0).abs() < 1e-6);
+}
@@ -0,0 +1,208 @@
+//! Polygon boolean operation tests
+
+use gcodekit2::designer::boolean::{BooleanOp, FillRule, Region};
+use gcodekit2::designer::{Design, Shape};
+
+use super::{assert_close, square};
+
+fn region(contours: Vec<Vec<(f64, f64)>>) -> Region {
+    Region::new(contours)
+}
+
+#[test]
+fn test_overlapping_squares() {
+    let a = region(vec![square(0.0, 0.0, 10.0)]);
+    let b = region(vec![square(5.0, 5.0, 10.0)]);
+
+    let union = a.union(&b);
+    assert_eq!(union.contours.len(), 1);
+    assert_eq!(union.contours[0].len(), 8);
+    assert_close(union.area(), 175.0, 1e-6);
+    assert_eq!(union.bounds(), Some((0.0, 0.0, 15.0, 15.0)));
+
+    let intersection = a.intersection(&b);
+    assert_eq!(intersection.contours.len(), 1);
+    assert_close(intersection.area(), 25.0, 1e-6);
+    assert_eq!(intersection.bounds(), Some((5.0, 5.0, 10.0, 10.0)));
+
+    let difference = a.difference(&b);
+    assert_close(difference.area(), 75.0, 1e-6);
+    assert!(difference.contains_point(2.0, 2.0));
+    assert!(!difference.contains_point(7.0, 7.0));
+
+    // Both L shapes, touching at two corners
+    let xor = a.xor(&b);
+    assert_eq!(xor.contours.len(), 2);
+    assert_close(xor.area(), 150.0, 1e-6);
+}
+
+#[test]
+fn test_disjoint_and_shared_edges() {
+    let a = region(vec![square(0.0, 0.0, 10.0)]);
+    let far = region(vec![square(20.0, 0.0, 10.0)]);
+    assert_eq!(a.union(&far).contours.len(), 2);
+    assert!(a.intersection(&far).is_empty());
+    assert_eq!(a.difference(&far), a);
+
+    // Squares sharing an edge merge into one rectangle without the seam
+    let next = region(vec![square(10.0, 0.0, 10.0)]);
+    let union = a.union(&next);
+    assert_eq!(union.contours.len(), 1);
+    assert_eq!(union.contours[0].len(), 4);
+    assert_close(union.area(), 200.0, 1e-6);
+    assert!(a.intersection(&next).is_empty());
+}
+
+#[test]
+fn test_difference_cuts_holes() {
+    let plate = Shape::rectangle(100.0, 50.0, 0.0, 0.0);
+    let hole = Shape::circle(10.0, 50.0, 25.0);
+    let result = plate.boolean(&hole, BooleanOp::Difference);
+    assert_eq!(result.contours.len(), 2);
+
+    // The outer contour runs counter-clockwise and the hole clockwise
+    let hole_area = Region::from_shape(&hole, 0.01).area();
+    assert!(hole_area < std::f64::consts::PI * 100.0);
+    assert_close(result.area(), 5000.0 - hole_area, 1e-6);
+    let areas: Vec<f64> = result
+        .contours
+        .iter()
+        .map(|contour| Region::new(vec![contour.clone()]).area())
+        .collect();
+    assert!(areas.iter().any(|area| *area > 0.0));
+    assert!(areas.iter().any(|area| *area < 0.0));
+    assert!(!result.contains_point(50.0, 25.0));
+    assert!(result.contains_point(5.0, 5.0));
+
+    // A second hole in the result, then filling the first back in
+    let second = region(vec![square(5.0, 5.0, 10.0)]);
+    let two_holes = result.difference(&second);
+    assert_eq!(two_holes.contours.len(), 3);
+    assert_close(two_holes.area(), 5000.0 - hole_area - 100.0, 1e-6);
+    let filled = two_holes.union(&Region::from_shape(&hole, 0.01));
+    assert_eq!(filled.contours.len(), 2);
+    assert_close(filled.area(), 4900.0, 1e-6);
+}
+
+#[test]
+fn test_multiple_result_contours() {
+    // U shape cut by a bar across both arms
+    let u = region(vec![vec![
+        (0.0, 0.0),
+        (30.0, 0.0),
+        (30.0, 30.0),
+        (20.0, 30.0),
+        (20.0, 10.0),
+        (10.0, 10.0),
+        (10.0, 30.0),
+        (0.0, 30.0),
+    ]]);
+    let bar = region(vec![vec![
+        (-5.0, 20.0),
+        (35.0, 20.0),
+        (35.0, 25.0),
+        (-5.0, 25.0),
+    ]]);
+    let intersection = u.intersection(&bar);
+    assert_eq!(intersection.contours.len(), 2);
+    assert_close(intersection.area(), 100.0, 1e-6);
+
+    let difference = u.difference(&bar);
+    assert_eq!(difference.contours.len(), 3);
+    assert_close(difference.area(), u.area() - 100.0, 1e-6);
+}
+
+#[test]
+fn test_self_intersections_and_fill_rules() {
+    // Bow tie crossing itself at (5, 5)
+    let bow_tie = region(vec![vec![
+        (0.0, 0.0),
+        (10.0, 10.0),
+        (10.0, 0.0),
+        (0.0, 10.0),
+    ]]);
+    let simple = bow_tie.simplify(FillRule::NonZero);
+    assert_eq!(simple.contours.len(), 2);
+    assert_close(simple.area(), 50.0, 1e-6);
+    assert!(simple
+        .contours
+        .iter()
+        .all(|c| Region::new(vec![c.clone()]).area() > 0.0));
+
+    // A pentagram's centre winds twice: filled for non-zero, empty for even-odd
+    let star: Vec<(f64, f64)> = (0..5)
+        .map(|i| {
+            let angle = std::f64::consts::FRAC_PI_2 + i as f64 * 4.0 * std::f64::consts::PI / 5.0;
+            (10.0 * angle.cos(), 10.0 * angle.sin())
+        })
+        .collect();
+    let star = region(vec![star]);
+    let non_zero = star.simplify(FillRule::NonZero);
+    let even_odd = star.simplify(FillRule::EvenOdd);
+    assert_eq!(non_zero.contours.len(), 1);
+    assert_eq!(non_zero.contours[0].len(), 10);
+    assert!(non_zero.contains_point(0.0, 0.0));
+    assert!(!even_odd.contains_point(0.0, 0.0));
+    assert!(even_odd.area() < non_zero.area());
+
+    // Clockwise contours only cut holes with the positive rule
+    let mut clockwise = square(0.0, 0.0, 10.0);
+    clockwise.reverse();
+    assert!(region(vec![clockwise.clone()])
+        .simplify(FillRule::Positive)
+        .is_empty());
+    assert_close(
+        region(vec![clockwise]).simplify(FillRule::NonZero).area(),
+        100.0,
+        1e-6,
+    );
+}
+
+#[test]
+fn test_design_combine_shapes() {
+    let mut design = Design::new("Boolean".to_string());
+    let plate = design.add_shape_to_layer(Shape::rectangle(40.0, 20.0, 0.0, 0.0), "cut", None);
+    let slot = design.add_shape(Shape::rounded_rectangle(20.0, 6.0, 10.0, 7.0, 3.0));
+
+    let ids = design
+        .combine_shapes(&plate, &slot, BooleanOp::Difference)
+        .unwrap();
+    // The slot stays a hole in the plate rather than a second part
+    assert_eq!(ids.len(), 1);
+    assert_eq!(design.shapes.len(), 1);
+    assert!(design.get_shape(&plate).is_none());
+    assert_eq!(design.get_layer("cut").unwrap().shape_ids, ids);
+    assert!(design
+        .combine_shapes(&plate, &ids[0], BooleanOp::Union)
+        .is_none());
+
+    let holed = design.get_shape(&ids[0]).unwrap().clone();
+    assert!(matches!(&holed, Shape::Region { contours } if contours.len() == 2));
+    // Flattened corners leave slightly more plate than the true arcs
+    let slot_area = 20.0 * 6.0 - (4.0 - std::f64::consts::PI) * 9.0;
+    assert_close(holed.area(), 800.0 - slot_area, 0.2);
+    assert!(!holed.contains_point(20.0, 10.0));
+    assert!(holed.contains_point(2.0, 2.0));
+    assert_eq!(holed.bounds(), (0.0, 0.0, 40.0, 20.0));
+
+    // Combining again keeps the hole
+    let tab = design.add_shape(Shape::rectangle(10.0, 4.0, 40.0, 8.0));
+    let ids = design
+        .combine_shapes(&ids[0], &tab, BooleanOp::Union)
+        .unwrap();
+    assert_eq!(ids.len(), 1);
+    let combined = design.get_shape(&ids[0]).unwrap();
+    assert_close(combined.area(), 840.0 - slot_area, 0.2);
+    assert!(!combined.contains_point(20.0, 10.0));
+
+    // Separate parts come back as separate polygons
+    let parts = Region::new(vec![square(0.0, 0.0, 10.0), square(20.0, 0.0, 10.0)]).to_shapes();
+    assert_eq!(parts.len(), 2);
+    assert!(parts.iter().all(|part| matches!(part, Shape::Polygon { .. })));
+
+    // Open shapes have no area to combine
+    let line = Shape::line(0.0, 0.0, 10.0, 10.0);
+    assert!(line
+        .boolean(&Shape::circle(5.0, 0.0, 0.0), BooleanOp::Intersection)
+        .is_empty());
+}
@@ -0,0 +1,110 @@
+//! Job time estimator integration tests
+
+use gcodekit2::communication::GrblSettings;
+use gcodekit2::designer::estimator::{MachineLimits, TimeEstimator};
+
+use super::assert_close;
+
+fn limits(max_rate: f64, acceleration: f64) -> MachineLimits {
+    MachineLimits {
+        max_rate: [max_rate; 3],
+        acceleration: [acceleration; 3],
+        junction_deviation: 0.01,
+    }
+}
+
+#[test]
+fn test_limits_from_settings() {
+    let settings: GrblSettings = [(110, 3000.0), (111, 2000.0), (122, 50.0), (11, 0.02)]
+        .into_iter()
+        .collect();
+    let limits = MachineLimits::from_settings(&settings);
+    assert_eq!(limits.max_rate, [3000.0, 2000.0, 500.0]);
+    assert_eq!(limits.acceleration, [10.0, 10.0, 50.0]);
+    assert_eq!(limits.junction_deviation, 0.02);
+}
+
+#[test]
+fn test_straight_move_with_acceleration() {
+    // 10 mm/s reached after 0.5 mm at 100 mm/s², same to stop
+    let estimate = TimeEstimator::new(limits(1000.0, 100.0))
+        .estimate("G1 X100 F600")
+        .unwrap();
+    assert_close(estimate.cutting, 10.1, 1e-6);
+    assert_eq!(estimate.rapid, 0.0);
+    assert_eq!(estimate.total(), estimate.cutting);
+}
+
+#[test]
+fn test_rapid_cutting_and_dwell_split() {
+    let program = "G0 X50\nG1 X60 F600\nG4 P2.5\nG0 Z5";
+    let estimate = TimeEstimator::new(limits(3000.0, 500.0))
+        .estimate(program)
+        .unwrap();
+    assert!(estimate.rapid > 1.0);
+    assert!(estimate.cutting > 1.0);
+    assert_eq!(estimate.dwell, 2.5);
+    assert_close(
+        estimate.total(),
+        estimate.line_times.iter().sum::<f64>(),
+        1e-9,
+    );
+    assert_eq!(estimate.line_times[2], 2.5);
+}
+
+#[test]
+fn test_slow_axis_limits_the_move() {
+    let mut machine = limits(3000.0, 1000.0);
+    machine.max_rate[2] = 300.0;
+    let estimator = TimeEstimator::new(machine);
+    let across = estimator.estimate("G0 X30").unwrap().total();
+    let down = estimator.estimate("G0 Z-30").unwrap().total();
+    assert_close(down, 6.0, 0.1);
+    assert!(down > 5.0 * across);
+}
+
+#[test]
+fn test_corners_slow_down() {
+    let estimator = TimeEstimator::new(limits(6000.0, 50.0));
+    let straight = estimator.estimate("G1 X10 F3000\nG1 X20").unwrap().total();
+    let single = estimator.estimate("G1 X20 F3000").unwrap().total();
+    let corner = estimator
+        .estimate("G1 X10 F3000\nG1 X10 Y10")
+        .unwrap()
+        .total();
+    let reversal = estimator.estimate("G1 X10 F3000\nG1 X0").unwrap().total();
+    assert_close(straight, single, 1e-9);
+    assert!(corner > straight);
+    assert!(reversal > corner);
+}
+
+#[test]
+fn test_arc_uses_true_length() {
+    // Full circle of radius 10 at 10 mm/s with acceleration barely noticed
+    let estimate = TimeEstimator::new(limits(6000.0, 100_000.0))
+        .estimate("G0 X10\nG2 X10 Y0 I-10 J0 F600")
+        .unwrap();
+    assert_close(estimate.cutting, 2.0 * std::f64::consts::PI, 0.01);
+}
+
+#[test]
+fn test_inches_and_default_feed() {
+    let estimator = TimeEstimator::new(limits(6000.0, 100_000.0)).with_default_feed(600.0);
+    let estimate = estimator.estimate("G20\nG1 X1").unwrap();
+    assert_close(estimate.cutting, 2.54, 0.01);
+}
+
+#[test]
+fn test_remaining_after_lines() {
+    let estimate = TimeEstimator::new(limits(1000.0, 100.0))
+        .estimate("G1 X100 F600\nG4 P1\nG1 X0")
+        .unwrap();
+    assert_close(estimate.remaining_after(0), estimate.total(), 1e-9);
+    assert_close(estimate.remaining_after(2), estimate.line_times[2], 1e-9);
+    assert_eq!(estimate.remaining_after(3), 0.0);
+}
+
+#[test]
+fn test_bad_arc_is_an_error() {
+    assert!(TimeEstimator::default().estimate("G2 X10 R2").is_err());
+}
@@ -0,0 +1,416 @@
+//! Drawing import integration tests
+
+use dxf::entities::{Circle, Ellipse, Entity, EntityType, Insert, Line, LwPolyline, Spline};
+use dxf::enums::{AcadVersion, Units};
+use dxf::tables::Layer;
+use dxf::{Block, Color, Drawing, LwPolylineVertex, Point, Vector};
+use gcodekit2::designer::import::dxf::{DxfImportOptions, DxfImporter};
+use gcodekit2::designer::import::svg::{SvgImportOptions, SvgImporter};
+use gcodekit2::designer::import::DEFAULT_LAYER;
+use gcodekit2::designer::{Design, Shape};
+
+use super::assert_close;
+
+/// 100 x 50 mm drawing whose viewBox has two user units per mm
+const LAYERED_SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg"
+     width="100mm" height="50mm" viewBox="0 0 200 100">
+  <g id="cut">
+    <rect x="20" y="10" width="40" height="20" fill="none" stroke="#ff0000"/>
+    <polyline points="0,100 20,80 40,100" fill="none" stroke="#ff0000"/>
+  </g>
+  <g id="engrave" transform="translate(10 0)">
+    <circle cx="100" cy="50" r="10" fill="none" stroke="#0000ff"/>
+    <ellipse cx="150" cy="50" rx="20" ry="10" fill="none" stroke="#00ff00"/>
+  </g>
+  <path d="M 0 0 C 50 50 150 -50 200 0" fill="none" stroke="black"/>
+</svg>"##;
+
+#[test]
+fn test_svg_import_scales_and_flips_to_mm() {
+    let mut design = Design::new("Import".to_string());
+    let report = SvgImporter::new()
+        .import(LAYERED_SVG.as_bytes(), &mut design)
+        .unwrap();
+    assert_close(report.width, 100.0, 1e-6);
+    assert_close(report.height, 50.0, 1e-6);
+    assert_eq!(report.shape_ids.len(), 5);
+    assert_eq!(design.shapes.len(), 5);
+
+    // SVG y 10..30 is 5..15 mm from the top, so 35..45 mm from the bottom
+    let rect = design.get_shape(&report.shape_ids[0]).unwrap();
+    assert!(matches!(rect, Shape::Polygon { points } if points.len() == 4));
+    let (min_x, min_y, max_x, max_y) = rect.bounds();
+    assert_close(min_x, 10.0, 1e-4);
+    assert_close(max_x, 30.0, 1e-4);
+    assert_close(min_y, 35.0, 1e-4);
+    assert_close(max_y, 45.0, 1e-4);
+    assert_close(rect.area(), 200.0, 1e-3);
+
+    let polyline = design.get_shape(&report.shape_ids[1]).unwrap();
+    assert!(matches!(polyline, Shape::Polyline { points } if points.len() == 3));
+}
+
+#[test]
+fn test_svg_import_applies_transforms_and_flattens_curves() {
+    let mut design = Design::new("Import".to_string());
+    let report = SvgImporter::new()
+        .import(LAYERED_SVG.as_bytes(), &mut design)
+        .unwrap();
+
+    // The group's translate moves the circle 5 mm right
+    let circle = design.get_shape(&report.shape_ids[2]).unwrap();
+    let (min_x, min_y, max_x, max_y) = circle.bounds();
+    assert_close((min_x + max_x) / 2.0, 55.0, 1e-3);
+    assert_close((min_y + max_y) / 2.0, 25.0, 1e-3);
+    assert_close(max_x - min_x, 10.0, 1e-3);
+    // Flattened within 0.01 mm, so the area is just under the true circle's
+    let area = circle.area();
+    let exact = std::f64::consts::PI * 25.0;
+    assert!(area < exact && area > exact - 2.0 * std::f64::consts::PI * 5.0 * 0.01);
+
+    let ellipse = design.get_shape(&report.shape_ids[3]).unwrap();
+    assert_close(ellipse.area(), std::f64::consts::PI * 10.0 * 5.0, 0.2);
+
+    let Shape::Polyline { points } = design.get_shape(&report.shape_ids[4]).unwrap() else {
+        panic!("open path should import as a polyline");
+    };
+    assert!(points.len() > 10);
+    assert_close(points[0].1, 50.0, 1e-6);
+    assert_close(points.last().unwrap().0, 100.0, 1e-4);
+}
+
+#[test]
+fn test_svg_import_keeps_layers_and_colours() {
+    let mut design = Design::new("Import".to_string());
+    let report = SvgImporter::new()
+        .import(LAYERED_SVG.as_bytes(), &mut design)
+        .unwrap();
+
+    let cut = design.get_layer("cut").unwrap();
+    assert_eq!(cut.color.as_deref(), Some("#ff0000"));
+    assert_eq!(cut.shape_ids, report.shape_ids[..2].to_vec());
+
+    // Same layer, different colours stay apart
+    let engrave: Vec<_> = design.layers.iter().filter(|l| l.name == "engrave").collect();
+    assert_eq!(engrave.len(), 2);
+    assert_eq!(engrave[0].color.as_deref(), Some("#0000ff"));
+    assert_eq!(engrave[1].color.as_deref(), Some("#00ff00"));
+
+    let default = design.get_layer(DEFAULT_LAYER).unwrap();
+    assert_eq!(default.color.as_deref(), Some("#000000"));
+
+    design.remove_shape(&report.shape_ids[0]);
+    assert_eq!(design.get_layer("cut").unwrap().shape_ids.len(), 1);
+}
+
+#[test]
+fn test_svg_import_options() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="96" height="96">
+        <line x1="0" y1="0" x2="96" y2="0" stroke="black"/>
+        <text x="10" y="50" font-size="12">Label</text>
+    </svg>"#;
+    let mut design = Design::new("Import".to_string());
+    let report = SvgImporter::with_options(SvgImportOptions {
+        flip_y: false,
+        text_to_paths: false,
+        ..Default::default()
+    })
+    .import(svg.as_bytes(), &mut design)
+    .unwrap();
+
+    // 96 px at 96 dpi is an inch
+    let line = design.get_shape(&report.shape_ids[0]).unwrap();
+    assert_eq!(line.bounds(), (0.0, 0.0, 25.4, 0.0));
+    assert_eq!(report.skipped, 1);
+    assert!(SvgImporter::new().import(b"not svg", &mut design).is_err());
+}
+
+fn dxf_entity(layer: &str, specific: EntityType) -> Entity {
+    let mut entity = Entity::new(specific);
+    entity.common.layer = layer.to_string();
+    entity
+}
+
+fn dxf_line(layer: &str, from: (f64, f64), to: (f64, f64)) -> Entity {
+    dxf_entity(
+        layer,
+        EntityType::Line(Line::new(
+            Point::new(from.0, from.1, 0.0),
+            Point::new(to.0, to.1, 0.0),
+        )),
+    )
+}
+
+/// Save as DXF text, as a file would be
+fn dxf_bytes(drawing: &mut Drawing) -> Vec<u8> {
+    // Units and most curve entities need AutoCAD 2000 or later
+    drawing.header.version = AcadVersion::R2000;
+    let mut data = Vec::new();
+    drawing.save(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn test_dxf_import_joins_lines_into_contours() {
+    let mut drawing = Drawing::default();
+    drawing.header.default_drawing_units = Units::Millimeters;
+    drawing.layers.push(Layer {
+        name: "cut".to_string(),
+        color: Color::from_index(1),
+        ..Default::default()
+    });
+    // A 20 x 10 square drawn as lines in no particular order or direction
+    drawing
+        .entities
+        .push(dxf_line("cut", (0.0, 0.0), (20.0, 0.0)));
+    drawing
+        .entities
+        .push(dxf_line("cut", (0.0, 10.0), (20.0, 10.0)));
+    drawing
+        .entities
+        .push(dxf_line("cut", (20.0, 0.0), (20.0, 10.0)));
+    drawing
+        .entities
+        .push(dxf_line("cut", (0.0, 0.0), (0.0, 10.0)));
+    // An open path on another layer
+    drawing
+        .entities
+        .push(dxf_line("score", (30.0, 0.0), (40.0, 0.0)));
+    drawing
+        .entities
+        .push(dxf_line("score", (40.0, 0.0), (40.0, 5.0)));
+
+    let mut design = Design::new("Import".to_string());
+    let report = DxfImporter::new()
+        .import(&dxf_bytes(&mut drawing), &mut design)
+        .unwrap();
+    assert_eq!(report.shape_ids.len(), 2);
+    assert_eq!((report.closed, report.open), (1, 1));
+    assert_eq!(report.scale, 1.0);
+
+    let square = design.get_shape(&report.shape_ids[0]).unwrap();
+    assert!(matches!(square, Shape::Polygon { points } if points.len() == 4));
+    assert_close(square.area(), 200.0, 1e-9);
+    let score = design.get_shape(&report.shape_ids[1]).unwrap();
+    assert!(matches!(score, Shape::Polyline { points } if points.len() == 3));
+
+    let cut = design.get_layer("cut").unwrap();
+    assert_eq!(cut.color.as_deref(), Some("#ff0000"));
+    assert_eq!(cut.shape_ids, report.shape_ids[..1].to_vec());
+    assert!(design.get_layer("score").is_some());
+}
+
+#[test]
+fn test_dxf_import_curves() {
+    let mut drawing = Drawing::default();
+    drawing.header.default_drawing_units = Units::Millimeters;
+
+    // Closed slot: two straight sides joined by half circles (bulge 1)
+    let mut slot = LwPolyline::default();
+    slot.set_is_closed(true);
+    for (x, y, bulge) in [
+        (0.0, 0.0, 0.0),
+        (20.0, 0.0, 1.0),
+        (20.0, 10.0, 0.0),
+        (0.0, 10.0, 1.0),
+    ] {
+        slot.vertices.push(LwPolylineVertex {
+            x,
+            y,
+            bulge,
+            ..Default::default()
+        });
+    }
+    drawing
+        .entities
+        .push(dxf_entity("0", EntityType::LwPolyline(slot)));
+    drawing.entities.push(dxf_entity(
+        "0",
+        EntityType::Circle(Circle::new(Point::new(50.0, 0.0, 0.0), 5.0)),
+    ));
+    let ellipse = Ellipse {
+        center: Point::new(80.0, 0.0, 0.0),
+        major_axis: Vector::new(0.0, 10.0, 0.0),
+        minor_axis_ratio: 0.5,
+        start_parameter: 0.0,
+        end_parameter: std::f64::consts::TAU,
+        ..Default::default()
+    };
+    drawing
+        .entities
+        .push(dxf_entity("0", EntityType::Ellipse(ellipse)));
+    // Clamped cubic spline closed off by a line
+    let spline = Spline {
+        degree_of_curve: 3,
+        knot_values: vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+        control_points: vec![
+            Point::new(100.0, 0.0, 0.0),
+            Point::new(100.0, 20.0, 0.0),
+            Point::new(120.0, 20.0, 0.0),
+            Point::new(120.0, 0.0, 0.0),
+        ],
+        ..Default::default()
+    };
+    drawing
+        .entities
+        .push(dxf_entity("0", EntityType::Spline(spline)));
+    drawing
+        .entities
+        .push(dxf_line("0", (100.0, 0.0), (120.0, 0.0)));
+
+    let mut design = Design::new("Import".to_string());
+    let report = DxfImporter::new()
+        .import(&dxf_bytes(&mut drawing), &mut design)
+        .unwrap();
+    assert_eq!(report.closed, 4);
+
+    let pi = std::f64::consts::PI;
+    let slot = design.get_shape(&report.shape_ids[0]).unwrap();
+    // Flattening within 0.01 mm loses a little area along the arcs
+    assert_close(slot.area(), 200.0 + pi * 25.0, 0.3);
+    let (min_x, _, max_x, _) = slot.bounds();
+    assert_close(min_x, -5.0, 0.02);
+    assert_close(max_x, 25.0, 0.02);
+
+    let circle = design.get_shape(&report.shape_ids[1]).unwrap();
+    assert_close(circle.area(), pi * 25.0, 0.3);
+    let ellipse = design.get_shape(&report.shape_ids[2]).unwrap();
+    assert_close(ellipse.area(), pi * 10.0 * 5.0, 0.5);
+    assert_eq!(ellipse.bounds().3, 10.0);
+
+    // Area under a cubic Bezier with control heights 0, h, h, 0 is 3/5 h w
+    let spline = design.get_shape(&report.shape_ids[3]).unwrap();
+    assert_close(spline.area(), 0.6 * 20.0 * 20.0, 0.1);
+}
+
+#[test]
+fn test_dxf_import_skips_rational_spline() {
+    let mut drawing = Drawing::default();
+    // Quarter circle as a rational quadratic, which an unweighted curve misses
+    let mut spline = Spline {
+        degree_of_curve: 2,
+        knot_values: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+        control_points: vec![
+            Point::new(10.0, 0.0, 0.0),
+            Point::new(10.0, 10.0, 0.0),
+            Point::new(0.0, 10.0, 0.0),
+        ],
+        weight: std::f64::consts::FRAC_1_SQRT_2,
+        ..Default::default()
+    };
+    spline.set_is_rational(true);
+    drawing
+        .entities
+        .push(dxf_entity("0", EntityType::Spline(spline)));
+    drawing
+        .entities
+        .push(dxf_line("0", (0.0, 0.0), (10.0, 0.0)));
+
+    let mut design = Design::new("Import".to_string());
+    let report = DxfImporter::new()
+        .import(&dxf_bytes(&mut drawing), &mut design)
+        .unwrap();
+    assert_eq!(report.skipped, 1);
+    assert_eq!(report.shape_ids.len(), 1);
+}
+
+#[test]
+fn test_dxf_import_units_and_blocks() {
+    let mut drawing = Drawing::default();
+    drawing.header.default_drawing_units = Units::Inches;
+    // A 1 x 1 inch square block with its base at the centre
+    let mut block = Block {
+        name: "SQUARE".to_string(),
+        base_point: Point::new(0.5, 0.5, 0.0),
+        ..Default::default()
+    };
+    for (from, to) in [
+        ((0.0, 0.0), (1.0, 0.0)),
+        ((1.0, 0.0), (1.0, 1.0)),
+        ((1.0, 1.0), (0.0, 1.0)),
+        ((0.0, 1.0), (0.0, 0.0)),
+    ] {
+        block.entities.push(dxf_line("0", from, to));
+    }
+    drawing.blocks.push(block);
+
+    let insert = Insert {
+        name: "SQUARE".to_string(),
+        location: Point::new(4.0, 2.0, 0.0),
+        rotation: 45.0,
+        x_scale_factor: 2.0,
+        y_scale_factor: 2.0,
+        column_count: 2,
+        column_spacing: 3.0,
+        ..Default::default()
+    };
+    drawing
+        .entities
+        .push(dxf_entity("parts", EntityType::Insert(insert)));
+
+    let mut design = Design::new("Import".to_string());
+    let report = DxfImporter::new()
+        .import(&dxf_bytes(&mut drawing), &mut design)
+        .unwrap();
+    assert_eq!(report.scale, 25.4);
+    assert_eq!(report.shape_ids.len(), 2);
+    assert_eq!(design.get_layer("parts").unwrap().shape_ids.len(), 2);
+
+    // 2 x 2 inch square turned 45 degrees around the insertion point
+    let half_diagonal = 2.0f64.sqrt() * 25.4;
+    let first = design.get_shape(&report.shape_ids[0]).unwrap();
+    assert_close(first.area(), 4.0 * 25.4 * 25.4, 1e-6);
+    let (min_x, min_y, max_x, max_y) = first.bounds();
+    assert_close(min_x, 4.0 * 25.4 - half_diagonal, 1e-6);
+    assert_close(max_x, 4.0 * 25.4 + half_diagonal, 1e-6);
+    assert_close(min_y, 2.0 * 25.4 - half_diagonal, 1e-6);
+    assert_close(max_y, 2.0 * 25.4 + half_diagonal, 1e-6);
+
+    // The second copy is 3 inches along the rotated X axis
+    let step = 3.0 * 25.4 / 2.0f64.sqrt();
+    let (second_min_x, second_min_y, _, _) =
+        design.get_shape(&report.shape_ids[1]).unwrap().bounds();
+    assert_close(second_min_x - min_x, step, 1e-6);
+    assert_close(second_min_y - min_y, step, 1e-6);
+}
+
+#[test]
+fn test_dxf_import_options() {
+    let mut drawing = Drawing::default();
+    drawing.header.default_drawing_units = Units::Unitless;
+    drawing
+        .entities
+        .push(dxf_line("0", (0.0, 0.0), (10.0, 0.0)));
+    // Gap of 0.02 units, 0.04 mm once scaled
+    drawing
+        .entities
+        .push(dxf_line("0", (10.02, 0.0), (10.0, 5.0)));
+    let text = dxf::entities::Text {
+        value: "Label".to_string(),
+        ..Default::default()
+    };
+    drawing
+        .entities
+        .push(dxf_entity("0", EntityType::Text(text)));
+    let data = dxf_bytes(&mut drawing);
+
+    let mut design = Design::new("Import".to_string());
+    let report = DxfImporter::new().import(&data, &mut design).unwrap();
+    assert_eq!(report.shape_ids.len(), 2);
+    assert_eq!(report.skipped, 1);
+
+    let mut design = Design::new("Import".to_string());
+    let report = DxfImporter::with_options(DxfImportOptions {
+        join_tolerance: 0.1,
+        unitless_scale: 2.0,
+        ..Default::default()
+    })
+    .import(&data, &mut design)
+    .unwrap();
+    assert_eq!(report.shape_ids.len(), 1);
+    let joined = design.get_shape(&report.shape_ids[0]).unwrap();
+    assert!(matches!(joined, Shape::Polyline { points } if points.len() == 3));
+    assert_eq!(joined.bounds(), (0.0, 0.0, 20.0, 10.0));
+
+    assert!(DxfImporter::new().import(b"not dxf", &mut design).is_err());
+}
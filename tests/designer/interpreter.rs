@@ -0,0 +1,119 @@
+//! G-code interpreter integration tests
+
+use gcodekit2::designer::interpreter::GcodeInterpreter;
+use gcodekit2::designer::{BackPlotter, MoveType};
+
+fn assert_pos(actual: [f32; 3], expected: [f32; 3]) {
+    for axis in 0..3 {
+        assert!(
+            (actual[axis] - expected[axis]).abs() < 1e-4,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_absolute_and_incremental_moves() {
+    let program = "G21 G90\nG0 X10 Y5\nG1 Z-1 F300\nG91\nG1 X5 Y-5\nG90 G1 X0";
+    let steps = GcodeInterpreter::new().interpret(program).unwrap();
+    assert_eq!(steps.len(), 4);
+
+    assert_eq!(steps[0].line_number, 2);
+    assert_eq!(steps[0].move_type, MoveType::Rapid);
+    assert_eq!(steps[0].feed_rate, 0.0);
+    assert_pos(steps[0].end_pos, [10.0, 5.0, 0.0]);
+
+    assert_eq!(steps[1].feed_rate, 300.0);
+    assert_pos(steps[2].start_pos, [10.0, 5.0, -1.0]);
+    assert_pos(steps[2].end_pos, [15.0, 0.0, -1.0]);
+    // F persists across lines
+    assert_eq!(steps[2].feed_rate, 300.0);
+    assert_pos(steps[3].end_pos, [0.0, 0.0, -1.0]);
+}
+
+#[test]
+fn test_inch_units_convert_to_mm() {
+    let steps = GcodeInterpreter::new()
+        .interpret("G20\nG1 X1 F10\nG21 G1 X30")
+        .unwrap();
+    assert_pos(steps[0].end_pos, [25.4, 0.0, 0.0]);
+    assert!((steps[0].feed_rate - 254.0).abs() < 1e-3);
+    assert_pos(steps[1].end_pos, [30.0, 0.0, 0.0]);
+}
+
+#[test]
+fn test_arcs_in_each_plane() {
+    let program = "\
+G17 G2 X10 Y0 I5 J0 F500
+G3 X0 Y0 R5
+G18 G2 X10 Z0 I5 K0
+G19 G3 Y10 Z0 R-5";
+    let steps = GcodeInterpreter::new().interpret(program).unwrap();
+    assert_eq!(steps.len(), 4);
+    assert_eq!(steps[0].move_type, MoveType::ArcCW);
+    assert_eq!(steps[1].move_type, MoveType::ArcCCW);
+    assert_pos(steps[0].end_pos, [10.0, 0.0, 0.0]);
+    assert_pos(steps[1].end_pos, [0.0, 0.0, 0.0]);
+    assert_pos(steps[2].end_pos, [10.0, 0.0, 0.0]);
+    assert_pos(steps[3].end_pos, [10.0, 10.0, 0.0]);
+}
+
+#[test]
+fn test_invalid_arcs_are_rejected() {
+    let error = GcodeInterpreter::new()
+        .interpret("G1 X0\nG2 X10 Y0 I3 J0")
+        .unwrap_err();
+    assert!(error.to_string().starts_with("Line 2"));
+
+    // Radius shorter than half the chord
+    assert!(GcodeInterpreter::new().interpret("G2 X10 R4").is_err());
+    // Offsets outside the active plane
+    assert!(GcodeInterpreter::new().interpret("G18 G2 X10 I5 J0").is_ok());
+    assert!(GcodeInterpreter::new().interpret("G18 G2 X10 J5").is_err());
+}
+
+#[test]
+fn test_g92_offsets() {
+    let mut interpreter = GcodeInterpreter::new();
+    let steps = interpreter
+        .interpret("G0 X10 Y10\nG92 X0 Y0\nG1 X5 F100\nG92.1\nG1 X5")
+        .unwrap();
+    assert_eq!(steps.len(), 3);
+    assert_pos(steps[1].end_pos, [15.0, 10.0, 0.0]);
+    assert_pos(steps[2].end_pos, [5.0, 10.0, 0.0]);
+    assert_eq!(interpreter.g92_offset(), [0.0; 3]);
+}
+
+#[test]
+fn test_spindle_and_dwell() {
+    let steps = GcodeInterpreter::new()
+        .interpret("S12000 M3\nG1 X1 F100\nG4 P1.5\nM5\nG1 X2\nM4 S500\nG1 X3")
+        .unwrap();
+    assert_eq!(steps[0].spindle_speed, 12000.0);
+    assert_eq!(steps[1].move_type, MoveType::Dwell);
+    assert_eq!(steps[1].start_pos, steps[1].end_pos);
+    assert_eq!(steps[2].spindle_speed, 0.0);
+    assert_eq!(steps[3].spindle_speed, 500.0);
+}
+
+#[test]
+fn test_non_motion_lines_are_skipped() {
+    let program = "%\n(header)\n$H\nG10 L20 P1 X0\nG53 G0 Z-5\nG28\nG90\n; done\nG1 X1 F100\n%";
+    let steps = GcodeInterpreter::new().interpret(program).unwrap();
+    assert_eq!(steps.len(), 1);
+    assert_eq!(steps[0].line_number, 9);
+    assert_pos(steps[0].start_pos, [0.0; 3]);
+}
+
+#[test]
+fn test_backplotter_from_gcode() {
+    let mut plotter = BackPlotter::from_gcode("G0 X5\nG1 Y5 F200").unwrap();
+    assert_eq!(plotter.get_total_steps(), 2);
+    plotter.step_forward();
+    plotter.step_forward();
+    assert_eq!(plotter.get_position(), [5.0, 5.0, 0.0]);
+
+    assert!(BackPlotter::from_gcode("G90\nM5").is_err());
+}
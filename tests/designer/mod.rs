@@ -1,12 +1,34 @@
 //! Designer module tests
 
+use gcodekit2::designer::shapes::PathSegment;
 use gcodekit2::designer::{Design, Designer, Shape};
 
 mod imaging;
 mod backplot;
+mod boolean;
+mod estimator;
+mod import;
+mod interpreter;
+mod offset;
 mod validator;
 mod optimizer;
 
+/// Assert that two values agree within `tolerance`
+fn assert_close(actual: impl Into<f64>, expected: impl Into<f64>, tolerance: f64) {
+    let (actual, expected) = (actual.into(), expected.into());
+    assert!(
+        (actual - expected).abs() < tolerance,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+/// Corners of an axis-aligned square, counter-clockwise from `(x, y)`
+fn square(x: f64, y: f64, size: f64) -> Vec<(f64, f64)> {
+    vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size)]
+}
+
 #[test]
 fn test_design_creation() {
     let design = Design::new("Test Design".to_string());
@@ -129,6 +151,148 @@ fn test_circle_to_gcode() {
     assert!(gcode.contains("G2"));
 }
 
+#[test]
+fn test_polyline_stays_open() {
+    let polyline = Shape::polyline(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 5.0)]);
+    assert_eq!(polyline.area(), 0.0);
+    assert_eq!(polyline.bounds(), (0.0, 0.0, 10.0, 5.0));
+    assert!(!polyline.contains_point(9.0, 1.0));
+    let gcode = polyline.to_gcode();
+    assert!(gcode.contains("G1 X10.00 Y5.00"));
+    // No move back to the start
+    assert_eq!(gcode.matches("X0.00 Y0.00").count(), 1);
+}
+
+#[test]
+fn test_path_with_arcs_and_beziers() {
+    // D shape: flat left side, half circle of radius 10 on the right
+    let d = Shape::path(
+        (0.0, 0.0),
+        vec![
+            PathSegment::Line { x: 10.0, y: 0.0 },
+            PathSegment::Arc {
+                x: 10.0,
+                y: 20.0,
+                cx: 10.0,
+                cy: 10.0,
+                clockwise: false,
+            },
+            PathSegment::Line { x: 0.0, y: 20.0 },
+        ],
+        true,
+    );
+    assert_close(d.area(), 200.0 + std::f64::consts::PI * 50.0, 1e-6);
+    assert_eq!(d.bounds(), (0.0, 0.0, 20.0, 20.0));
+    assert!(d.contains_point(19.0, 10.0));
+    assert!(!d.contains_point(19.0, 1.0));
+    let gcode = d.to_gcode();
+    assert!(gcode.contains("G3 X10.000 Y20.000 I0.000 J10.000"));
+    // Closed back to the start
+    assert!(gcode.contains("G1 X0.000 Y0.000 F100\nG0 Z5"));
+
+    // Cubic arch over a 20 mm base, peaking at 3/4 of its control height
+    let arch = Shape::path(
+        (0.0, 0.0),
+        vec![PathSegment::Cubic {
+            x1: 0.0,
+            y1: 20.0,
+            x2: 20.0,
+            y2: 20.0,
+            x: 20.0,
+            y: 0.0,
+        }],
+        true,
+    );
+    assert_close(arch.area(), 0.6 * 20.0 * 20.0, 1e-6);
+    assert_close(arch.bounds().3, 15.0, 1e-6);
+    assert!(arch.contains_point(10.0, 14.0));
+    assert!(!arch.to_gcode().contains("G2"));
+
+    let quadratic = Shape::path(
+        (0.0, 0.0),
+        vec![PathSegment::Quadratic {
+            x1: 10.0,
+            y1: 20.0,
+            x: 20.0,
+            y: 0.0,
+        }],
+        false,
+    );
+    assert_eq!(quadratic.area(), 0.0);
+    assert_close(quadratic.bounds().3, 10.0, 1e-6);
+    assert!(!quadratic.contains_point(10.0, 5.0));
+}
+
+#[test]
+fn test_path_full_circle_arc() {
+    let circle = Shape::path(
+        (5.0, 0.0),
+        vec![PathSegment::Arc {
+            x: 5.0,
+            y: 0.0,
+            cx: 0.0,
+            cy: 0.0,
+            clockwise: true,
+        }],
+        true,
+    );
+    assert_close(circle.area(), std::f64::consts::PI * 25.0, 1e-6);
+    assert_eq!(circle.bounds(), (-5.0, -5.0, 5.0, 5.0));
+    assert!(circle
+        .to_gcode()
+        .contains("G2 X5.000 Y0.000 I-5.000 J0.000"));
+}
+
+#[test]
+fn test_ellipse() {
+    let ellipse = Shape::ellipse(10.0, 5.0, 0.0, 0.0, 90.0);
+    assert_close(ellipse.area(), std::f64::consts::PI * 50.0, 1e-6);
+    let (min_x, min_y, max_x, max_y) = ellipse.bounds();
+    assert_close(min_x, -5.0, 1e-6);
+    assert_close(max_x, 5.0, 1e-6);
+    assert_close(min_y, -10.0, 1e-6);
+    assert_close(max_y, 10.0, 1e-6);
+    assert!(ellipse.contains_point(0.0, 9.0));
+    assert!(!ellipse.contains_point(9.0, 0.0));
+    assert!(ellipse.to_gcode().contains("G1"));
+
+    let round = Shape::ellipse(5.0, 5.0, 0.0, 0.0, 0.0);
+    assert!(round.to_gcode().contains("G2 X5.000 Y0.000 I-5.000 J0.000"));
+}
+
+#[test]
+fn test_rounded_rectangle() {
+    let rect = Shape::rounded_rectangle(20.0, 10.0, 0.0, 0.0, 2.0);
+    assert_close(rect.area(), 200.0 - (4.0 - std::f64::consts::PI) * 4.0, 1e-6);
+    assert_eq!(rect.bounds(), (0.0, 0.0, 20.0, 10.0));
+    assert!(rect.contains_point(1.0, 5.0));
+    assert!(!rect.contains_point(0.2, 0.2));
+    let gcode = rect.to_gcode();
+    assert_eq!(gcode.matches("G3").count(), 4);
+    assert!(gcode.contains("G3 X20.000 Y2.000 I0.000 J2.000"));
+
+    // The radius is capped at half the short side, making a stadium
+    let stadium = Shape::rounded_rectangle(20.0, 10.0, 0.0, 0.0, 50.0);
+    assert_close(stadium.area(), 100.0 + std::f64::consts::PI * 25.0, 1e-6);
+    let outline = stadium.outline(0.01);
+    assert_close(outline[0].0, 5.0, 1e-6);
+    assert_ne!(outline.first(), outline.last());
+}
+
+#[test]
+fn test_design_layers() {
+    let mut design = Design::new("Layers".to_string());
+    let a = design.add_shape_to_layer(Shape::circle(1.0, 0.0, 0.0), "cut", Some("#ff0000"));
+    let b = design.add_shape_to_layer(Shape::circle(2.0, 0.0, 0.0), "cut", Some("#ff0000"));
+    design.add_shape_to_layer(Shape::circle(3.0, 0.0, 0.0), "engrave", None);
+    assert_eq!(design.layers.len(), 2);
+    assert_eq!(design.get_layer("cut").unwrap().shape_ids, vec![a.clone(), b]);
+    design.remove_shape(&a);
+    assert_eq!(design.get_layer("cut").unwrap().shape_ids.len(), 1);
+    design.clear();
+    assert!(design.layers.is_empty());
+}
+
 // Toolpath tests
 
 #[test]
@@ -148,6 +312,7 @@ fn test_toolpath_creation() {
 
 #[test]
 fn test_toolpath_estimate_time() {
+    use gcodekit2::designer::estimator::MachineLimits;
     use gcodekit2::designer::Toolpath;
     let tp = Toolpath::new(
         "Test".to_string(),
@@ -156,8 +321,10 @@ fn test_toolpath_estimate_time() {
         1000,
         1.0,
     );
-    let time = tp.estimate_time();
-    assert!(time > 0.0);
+    let estimate = tp.estimate_time(&MachineLimits::default()).unwrap();
+    // 30 mm at the toolpath feed of 100 mm/min, plus a little to accelerate
+    assert!(estimate.cutting > 18.0 && estimate.cutting < 18.5);
+    assert_eq!(estimate.total(), estimate.cutting);
 }
 
 #[test]
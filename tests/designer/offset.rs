@@ -0,0 +1,264 @@
+//! Polygon offsetting and profile toolpath tests
+
+use gcodekit2::designer::boolean::{BooleanOp, Region};
+use gcodekit2::designer::estimator::MachineLimits;
+use gcodekit2::designer::offset::{
+    offset, profile, CutDirection, JoinType, ProfileOptions, ProfileSide,
+};
+use gcodekit2::designer::shapes::PathSegment;
+use gcodekit2::designer::{Shape, Toolpath};
+use std::f64::consts::PI;
+
+use super::{assert_close, square};
+
+#[test]
+fn test_offset_square_joins() {
+    let square = Region::new(vec![square(0.0, 0.0, 10.0)]);
+
+    // Rounded corners add a quarter circle each, flattened within 0.01 mm
+    let round = offset(&square, 1.0, JoinType::Round, 0.01);
+    assert_eq!(round.contours.len(), 1);
+    assert_close(round.area(), 100.0 + 40.0 + PI, 0.05);
+    assert_eq!(round.bounds(), Some((-1.0, -1.0, 11.0, 11.0)));
+
+    let miter = offset(&square, 1.0, JoinType::Miter { limit: 2.0 }, 0.01);
+    assert_eq!(miter.contours[0].len(), 4);
+    assert_close(miter.area(), 144.0, 1e-9);
+
+    // A right angle's miter reaches sqrt(2) times the offset, so it is
+    // bevelled below that
+    let bevel = offset(&square, 1.0, JoinType::Miter { limit: 1.2 }, 0.01);
+    assert_eq!(bevel.contours[0].len(), 8);
+    assert_close(bevel.area(), 144.0 - 4.0 * 0.5, 1e-9);
+
+    let inside = offset(&square, -1.0, JoinType::Round, 0.01);
+    assert_close(inside.area(), 64.0, 1e-9);
+    assert!(offset(&square, -5.5, JoinType::Round, 0.01).is_empty());
+    assert_eq!(
+        square.offset(1.0, JoinType::Miter { limit: 2.0 }, 0.01),
+        miter
+    );
+}
+
+#[test]
+fn test_offset_concave_and_holes() {
+    // L shape: the inner corner stays sharp when growing
+    let l = Region::new(vec![vec![
+        (0.0, 0.0),
+        (20.0, 0.0),
+        (20.0, 10.0),
+        (10.0, 10.0),
+        (10.0, 20.0),
+        (0.0, 20.0),
+    ]]);
+    let grown = offset(&l, 1.0, JoinType::Round, 0.01);
+    assert_eq!(grown.contours.len(), 1);
+    assert!(grown.contains_point(10.9, 10.9));
+    assert!(!grown.contains_point(11.1, 11.1));
+
+    // Shrinking a plate widens its hole
+    let plate = Region::new(vec![
+        vec![(0.0, 0.0), (30.0, 0.0), (30.0, 30.0), (0.0, 30.0)],
+        vec![(10.0, 10.0), (10.0, 20.0), (20.0, 20.0), (20.0, 10.0)],
+    ]);
+    let shrunk = offset(&plate, -1.0, JoinType::Round, 0.01);
+    assert_eq!(shrunk.contours.len(), 2);
+    assert_close(shrunk.area(), 28.0 * 28.0 - (144.0 - 4.0 + PI), 0.05);
+
+    // Growing it closes the hole once the offset passes half its width
+    let grown = offset(&plate, 6.0, JoinType::Round, 0.01);
+    assert_eq!(grown.contours.len(), 1);
+}
+
+#[test]
+fn test_profile_sides_and_directions() {
+    let circle = Shape::circle(10.0, 0.0, 0.0);
+    let options = ProfileOptions {
+        tool_diameter: 4.0,
+        stock_allowance: 0.5,
+        ..Default::default()
+    };
+
+    // Outside climb cuts clockwise around the part, 2.5 mm off the line
+    let outside = profile(&circle, &options).unwrap();
+    assert_eq!(outside.len(), 1);
+    assert!(outside[0].closed);
+    assert_close(
+        Region::new(vec![outside[0].points.clone()]).area(),
+        -PI * 12.5 * 12.5,
+        1.0,
+    );
+    let (x, y) = outside[0].points[0];
+    assert_close(x.hypot(y), 12.5, 0.01);
+
+    let conventional = ProfileOptions {
+        direction: CutDirection::Conventional,
+        ..options.clone()
+    };
+    let outside = profile(&circle, &conventional).unwrap();
+    assert!(Region::new(vec![outside[0].points.clone()]).area() > 0.0);
+
+    // Inside climb cuts counter-clockwise, 2.5 mm inside the line
+    let inside = ProfileOptions {
+        side: ProfileSide::Inside,
+        ..options.clone()
+    };
+    let inside = profile(&circle, &inside).unwrap();
+    assert_close(
+        Region::new(vec![inside[0].points.clone()]).area(),
+        PI * 7.5 * 7.5,
+        1.0,
+    );
+
+    let on_line = ProfileOptions {
+        side: ProfileSide::OnLine,
+        ..options.clone()
+    };
+    let on_line = profile(&circle, &on_line).unwrap();
+    let (x, y) = on_line[0].points[0];
+    assert_close(x.hypot(y), 10.0, 1e-9);
+
+    // Open shapes can only be followed on the line
+    let line = Shape::line(0.0, 0.0, 10.0, 0.0);
+    assert!(profile(&line, &options).is_err());
+    let engrave = ProfileOptions {
+        side: ProfileSide::OnLine,
+        direction: CutDirection::Conventional,
+        ..options.clone()
+    };
+    let path = profile(&line, &engrave).unwrap();
+    assert!(!path[0].closed);
+    assert_eq!(path[0].points, vec![(0.0, 0.0), (10.0, 0.0)]);
+
+    // Tool too big for the hole
+    let small = Shape::circle(1.0, 0.0, 0.0);
+    let inside = ProfileOptions {
+        side: ProfileSide::Inside,
+        ..options
+    };
+    assert!(profile(&small, &inside).is_err());
+}
+
+#[test]
+fn test_profile_region_shape_keeps_holes() {
+    let plate = Shape::rectangle(40.0, 40.0, 0.0, 0.0);
+    let hole = Shape::rectangle(20.0, 20.0, 10.0, 10.0);
+    let shapes = plate.boolean(&hole, BooleanOp::Difference).to_shapes();
+    assert_eq!(shapes.len(), 1);
+    let options = ProfileOptions {
+        tool_diameter: 4.0,
+        join: JoinType::Miter { limit: 2.0 },
+        ..Default::default()
+    };
+
+    // Outside the part means inside the hole, cut before the outline
+    let paths = profile(&shapes[0], &options).unwrap();
+    assert_eq!(paths.len(), 2);
+    let hole_path = Region::new(vec![paths[0].points.clone()]);
+    assert_eq!(hole_path.bounds(), Some((12.0, 12.0, 28.0, 28.0)));
+    let outline = Region::new(vec![paths[1].points.clone()]);
+    assert_eq!(outline.bounds(), Some((-2.0, -2.0, 42.0, 42.0)));
+
+    let on_line = ProfileOptions {
+        side: ProfileSide::OnLine,
+        ..options
+    };
+    let paths = profile(&shapes[0], &on_line).unwrap();
+    assert_eq!(paths.len(), 2);
+    assert!(paths.iter().all(|path| path.closed));
+}
+
+#[test]
+fn test_toolpath_profile() {
+    let part = Shape::rectangle(20.0, 10.0, 0.0, 0.0);
+    let options = ProfileOptions {
+        tool_diameter: 6.0,
+        join: JoinType::Miter { limit: 2.0 },
+        ..Default::default()
+    };
+    let toolpath =
+        Toolpath::profile("Outline".to_string(), &part, &options, 800.0, 12000, 2.0).unwrap();
+    assert_eq!(toolpath.name, "Outline");
+    assert_eq!(toolpath.feed_rate, 800.0);
+    assert!(toolpath.gcode.contains("G1 Z-2.000 F800"));
+    // Tool centre 3 mm outside every side
+    assert!(toolpath.gcode.contains("X-3.000 Y-3.000"));
+    assert!(toolpath.gcode.contains("X23.000 Y13.000"));
+    assert!(toolpath.gcode.trim_end().ends_with("G0 Z5"));
+    // 26 x 16 rectangle around, plus the plunge
+    let estimate = toolpath.estimate_time(&MachineLimits::default()).unwrap();
+    assert!(estimate.cutting > 84.0 / 800.0 * 60.0);
+}
+
+#[test]
+fn test_profile_segments_fit_arcs() {
+    let options = ProfileOptions {
+        tool_diameter: 4.0,
+        ..Default::default()
+    };
+    let arcs = |segments: &[PathSegment]| {
+        segments
+            .iter()
+            .filter(|segment| matches!(segment, PathSegment::Arc { .. }))
+            .count()
+    };
+
+    // An offset circle is one clockwise arc back to the start
+    let circle = profile(&Shape::circle(10.0, 0.0, 0.0), &options).unwrap();
+    let segments = circle[0].segments(options.tolerance);
+    assert!(segments.len() <= 2);
+    let PathSegment::Arc { cx, cy, clockwise, .. } = segments[0] else {
+        panic!("expected an arc, got {:?}", segments[0]);
+    };
+    assert!(clockwise);
+    assert_close(cx, 0.0, 0.01);
+    assert_close(cy, 0.0, 0.01);
+    assert_eq!(segments.last().unwrap().end(), circle[0].points[0]);
+
+    // Round joins around a square: four arcs between four straight sides
+    let square = Shape::rectangle(10.0, 10.0, 0.0, 0.0);
+    let segments = profile(&square, &options).unwrap()[0].segments(options.tolerance);
+    assert_eq!(arcs(&segments), 4);
+    assert_eq!(segments.len(), 8);
+
+    let rounded = Shape::rounded_rectangle(30.0, 20.0, 0.0, 0.0, 5.0);
+    let inside = ProfileOptions {
+        side: ProfileSide::Inside,
+        ..options.clone()
+    };
+    let segments = profile(&rounded, &inside).unwrap()[0].segments(options.tolerance);
+    assert_eq!(arcs(&segments), 4);
+    for segment in &segments {
+        if let PathSegment::Arc { x, y, cx, cy, clockwise } = *segment {
+            assert!(!clockwise);
+            assert_close((x - cx).hypot(y - cy), 3.0, 0.02);
+        }
+    }
+
+    // Mitered corners stay sharp
+    let miter = ProfileOptions {
+        join: JoinType::Miter { limit: 2.0 },
+        ..options
+    };
+    let segments = profile(&square, &miter).unwrap()[0].segments(0.01);
+    assert_eq!(arcs(&segments), 0);
+    assert_eq!(segments.len(), 4);
+}
+
+#[test]
+fn test_toolpath_profile_arcs() {
+    let options = ProfileOptions {
+        tool_diameter: 6.0,
+        ..Default::default()
+    };
+    let circle = Shape::circle(10.0, 0.0, 0.0);
+    let toolpath =
+        Toolpath::profile("Disc".to_string(), &circle, &options, 600.0, 12000, 1.0).unwrap();
+    assert!(toolpath.gcode.lines().any(|line| line.starts_with("G2 ")));
+    assert!(toolpath.gcode.lines().filter(|line| line.starts_with("G1 X")).count() <= 1);
+
+    // The arc is 13 mm around the centre, no faster than the feed rate
+    let estimate = toolpath.estimate_time(&MachineLimits::default()).unwrap();
+    assert!(estimate.cutting > 2.0 * PI * 13.0 / 600.0 * 60.0);
+}
+
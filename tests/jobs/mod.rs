@@ -91,3 +91,186 @@ fn test_remaining_gcode() {
     assert!(remaining.contains("Y20"));
     assert!(!remaining.contains("X10"));
 }
+
+#[test]
+fn test_job_fail_on_grbl_error() {
+    use gcodekit2::communication::GrblError;
+    let mut manager = JobManager::new();
+    let mut job = Job::new("Test".to_string(), "G0 X10\nG1 Y20".to_string(), Priority::normal());
+    job.update_progress(1);
+    manager.set_active_job(job);
+    manager.fail_active_job_on_error(&GrblError::UndefinedFeedRate);
+
+    let failed = &manager.get_completed_jobs()[0];
+    assert_eq!(failed.state, JobState::Failed);
+    let message = failed.error_message.as_ref().unwrap();
+    assert!(message.starts_with("Line 2: error:22"));
+    assert!(message.contains("F word"));
+    assert!(!message.contains(".."));
+}
+
+#[test]
+fn test_job_fail_on_alarm() {
+    use gcodekit2::communication::GrblAlarm;
+    let mut job = Job::new("Test".to_string(), "G0 X10".to_string(), Priority::normal());
+    job.fail_on_alarm(&GrblAlarm::HardLimit);
+    let message = job.error_message.unwrap();
+    assert!(message.contains("ALARM:1 - Hard limit"));
+    assert!(!message.contains(".."));
+}
+
+#[test]
+fn test_resume_plan_restores_state() {
+    use gcodekit2::jobs::ResumeOptions;
+
+    let gcode = "G21 G90\n; roughing\nM3 S12000\nM8\nG0 X10 Y5\nG1 Z-2 F300\nG1 X20 F800\nG1 Y15\nM5".to_string();
+    let mut job = Job::new("Resume".to_string(), gcode, Priority::normal());
+    job.update_progress(6);
+
+    let plan = job.resume_plan(&ResumeOptions::default());
+    assert_eq!(plan.start_line, 6);
+    assert_eq!(
+        plan.preamble,
+        vec![
+            "G21 G53 G0 Z-1",
+            "G21 G90 G17 G54 G94",
+            "M3 S12000",
+            "G4 P3",
+            "M8",
+            "G0 X20 Y5",
+            "G1 Z-2 F800",
+            "G1 F800",
+        ]
+    );
+    assert_eq!(plan.remaining, vec!["G1 Y15", "M5"]);
+    assert!(plan.to_gcode().ends_with("G1 Y15\nM5"));
+}
+
+#[test]
+fn test_resume_plan_incremental_program() {
+    use gcodekit2::jobs::ResumeOptions;
+
+    let gcode = "G90 G0 X1 Y1 Z1\nG91\nG1 X2 F100\nG1 Y3\nG1 X1".to_string();
+    let mut job = Job::new("Incremental".to_string(), gcode, Priority::normal());
+    job.update_progress(4);
+
+    let options = ResumeOptions {
+        plunge_feed: Some(50.0),
+        ..Default::default()
+    };
+    let plan = job.resume_plan(&options);
+    assert!(plan.preamble.contains(&"G0 X3 Y4".to_string()));
+    assert!(plan.preamble.contains(&"G1 Z1 F50".to_string()));
+    assert_eq!(&plan.preamble[plan.preamble.len() - 2..], ["G91", "G1 F100"]);
+}
+
+#[test]
+fn test_resume_plan_ignores_tool_length_offset() {
+    use gcodekit2::jobs::ResumeOptions;
+
+    let gcode = "G90 G0 X1 Y1 Z5\nG43.1 Z2.5\nG1 X4 F100\nG49\nG1 Y2".to_string();
+    let mut job = Job::new("TLO".to_string(), gcode, Priority::normal());
+    job.update_progress(4);
+
+    let plan = job.resume_plan(&ResumeOptions::default());
+    assert!(plan.preamble.contains(&"G0 X4 Y1".to_string()));
+    assert!(plan.preamble.iter().any(|l| l.starts_with("G1 Z5")));
+    assert!(!plan.preamble.iter().any(|l| l.contains("Z2.5")));
+}
+
+#[test]
+fn test_job_manager_follows_machine_events() {
+    use gcodekit2::communication::{GrblAlarm, GrblResponse, LineAck, MachineEvent, MachineState};
+
+    let ack = |sequence: usize, job_line: Option<usize>| MachineEvent::LineAcknowledged {
+        ack: LineAck {
+            sequence,
+            command: "G1 X1".to_string(),
+            response: GrblResponse::Ok,
+            job_line,
+        },
+    };
+    let mut job = Job::new("Events".to_string(), "G0 X0\nG1 X1\nG1 X2\nG1 X3".to_string(), Priority::normal());
+    job.start();
+    let mut manager = JobManager::new();
+    manager.set_active_job(job);
+
+    manager.handle_event(&ack(0, Some(0)));
+    manager.handle_event(&ack(1, Some(1)));
+    assert_eq!(manager.get_active_job().unwrap().current_line, 2);
+    // Jogs and queries sent between job lines do not count
+    manager.handle_event(&ack(2, None));
+    assert_eq!(manager.get_active_job().unwrap().current_line, 2);
+
+    manager.handle_event(&MachineEvent::StateChanged { from: MachineState::Run, to: MachineState::Hold });
+    assert_eq!(manager.get_active_job().unwrap().state, JobState::Paused);
+    // Lines already buffered are still acknowledged during the hold
+    manager.handle_event(&ack(3, Some(2)));
+    assert_eq!(manager.get_active_job().unwrap().current_line, 3);
+    manager.handle_event(&MachineEvent::StateChanged { from: MachineState::Hold, to: MachineState::Run });
+    assert_eq!(manager.get_active_job().unwrap().state, JobState::Running);
+
+    manager.handle_event(&MachineEvent::AlarmRaised { alarm: Some(GrblAlarm::HardLimit) });
+    assert!(manager.get_active_job().is_none());
+    let failed = &manager.get_completed_jobs()[0];
+    assert_eq!(failed.state, JobState::Failed);
+    assert!(failed.error_message.as_ref().unwrap().starts_with("Line 4"));
+}
+
+#[test]
+fn test_job_remaining_time() {
+    use gcodekit2::designer::estimator::MachineLimits;
+
+    let mut job = Job::new(
+        "Pocket".to_string(),
+        "; roughing\nG0 X10\n\nG1 X20 F600\nG4 P3\nG1 X30\n".to_string(),
+        Priority::normal(),
+    );
+    assert!(job.remaining_time().is_none());
+    let total = job.estimate_time(&MachineLimits::default()).unwrap().total();
+
+    job.start();
+    assert_eq!(job.remaining_time(), Some(total));
+    // Comment and blank lines are not counted
+    job.update_progress(2);
+    let remaining = job.remaining_time().unwrap();
+    assert!(remaining > 3.0 && remaining < total);
+    job.complete();
+    assert_eq!(job.remaining_time(), Some(0.0));
+}
+
+#[test]
+fn test_job_manager_estimates_jobs() {
+    use gcodekit2::designer::estimator::MachineLimits;
+
+    let program = "G1 X100 F6000\nG1 Y100\nG1 X0\nG1 Y0\n";
+    let mut manager = JobManager::new();
+    manager.queue_job(Job::new(
+        "Square".to_string(),
+        program.to_string(),
+        Priority::normal(),
+    ));
+    let mut job = manager.get_next_job().unwrap();
+    let queued = job.estimate.as_ref().unwrap().total();
+    job.start();
+    manager.set_active_job(job);
+    let active = manager.get_active_job().unwrap();
+    assert_eq!(active.remaining_time(), Some(queued));
+
+    // A slower machine re-estimates the running job
+    manager.set_machine_limits(MachineLimits {
+        acceleration: [5.0; 3],
+        ..MachineLimits::default()
+    });
+    let slower = manager.get_active_job().unwrap().remaining_time().unwrap();
+    assert!(slower > queued);
+
+    // Jobs started without being queued are estimated too
+    let line = Job::new(
+        "Line".to_string(),
+        "G1 X10 F600\n".to_string(),
+        Priority::normal(),
+    );
+    manager.set_active_job(line);
+    assert!(manager.get_active_job().unwrap().estimate.is_some());
+}
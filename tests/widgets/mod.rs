@@ -68,6 +68,15 @@ fn test_connection_widget_refresh_ports() {
     assert!(result.is_ok() || result.is_err());
 }
 
+#[tokio::test]
+async fn test_connection_widget_auto_connect_without_boards() {
+    let mut widget = ConnectionWidget::new();
+    let controller = GrblController::new();
+    assert!(widget.port_info("/dev/ttyUSB0").is_none());
+    assert!(widget.auto_connect(&controller).await.is_err());
+    assert!(!widget.connected);
+}
+
 // Jog widget tests
 
 #[test]
@@ -191,6 +200,41 @@ fn test_increase_feed_rate() {
     assert_eq!(widget.feed_rate_override, 110);
 }
 
+#[test]
+fn test_feed_rate_realtime_commands() {
+    use gcodekit2::communication::RealtimeCommand;
+    let mut widget = OverridesWidget::new();
+    widget.set_feed_rate(120);
+    let commands = widget.get_feed_rate_commands();
+    assert_eq!(
+        commands,
+        vec![
+            RealtimeCommand::FeedOverrideReset,
+            RealtimeCommand::FeedOverrideCoarsePlus,
+            RealtimeCommand::FeedOverrideCoarsePlus,
+        ]
+    );
+}
+
+#[test]
+fn test_spindle_power_realtime_commands() {
+    let mut widget = OverridesWidget::new();
+    widget.set_spindle_power(95);
+    let bytes: Vec<u8> = widget
+        .get_spindle_power_commands()
+        .iter()
+        .map(|c| c.byte())
+        .collect();
+    assert_eq!(bytes, vec![0x99, 0x9D, 0x9D, 0x9D, 0x9D, 0x9D]);
+}
+
+#[tokio::test]
+async fn test_overrides_apply_requires_connection() {
+    let widget = OverridesWidget::new();
+    let controller = GrblController::new();
+    assert!(widget.apply(&controller).await.is_err());
+}
+
 #[test]
 fn test_decrease_feed_rate() {
     let mut widget = OverridesWidget::new();